@@ -15,17 +15,23 @@ use crate::types::IdentityConfig;
 use crate::{
 	api::v1,
 	network::rpc::{self},
-	types::{RuntimeConfig, State},
+	types::{unix_timestamp, BlockVerified, RuntimeConfig, State},
 };
-use color_eyre::eyre::WrapErr;
-use futures::{Future, FutureExt};
+use color_eyre::{
+	eyre::{eyre, WrapErr},
+	Result,
+};
+use dusk_plonk::commitment_scheme::kzg10::PublicParameters;
+use futures::{future::Either, Future, FutureExt};
 use std::{
+	fs,
 	net::SocketAddr,
 	str::FromStr,
 	sync::{Arc, Mutex},
 };
+use tokio::sync::broadcast;
 use tracing::info;
-use warp::{Filter, Reply};
+use warp::{filters::BoxedFilter, http::StatusCode, reply::Response, Filter, Rejection, Reply};
 
 pub struct Server<T: Database> {
 	pub db: T,
@@ -35,24 +41,154 @@ pub struct Server<T: Database> {
 	pub version: String,
 	pub network_version: String,
 	pub node_client: rpc::Client,
+	pub public_parameters: Arc<PublicParameters>,
+	pub block_verified_sender: broadcast::Sender<BlockVerified>,
+	pub rpc_event_sender: broadcast::Sender<rpc::Event>,
 	pub ws_clients: v2::types::WsClients,
+	pub api_metrics: v2::metrics::ApiMetrics,
 	pub shutdown: Controller<String>,
 }
 
+/// Checks if the client has processed a block, and if the latest block is received recently,
+/// which means that the connection to the node is live.
+fn readiness(state: &State, max_block_age: u64, now: u64) -> Result<(), &'static str> {
+	if state.latest == 0 {
+		return Err("No blocks processed");
+	}
+	match state.latest_block_at {
+		Some(latest_block_at) if now.saturating_sub(latest_block_at) <= max_block_age => Ok(()),
+		_ => Err("No recent blocks received from the node"),
+	}
+}
+
 fn health_route() -> impl Filter<Extract = impl Reply, Error = warp::Rejection> + Clone {
 	warp::head()
 		.or(warp::get())
 		.and(warp::path("health"))
-		.map(|_| warp::reply::with_status("", warp::http::StatusCode::OK))
+		.map(|_| warp::reply::with_status("", StatusCode::OK))
+}
+
+fn ready_route(
+	state: Arc<Mutex<State>>,
+	max_block_age: u64,
+) -> impl Filter<Extract = impl Reply, Error = warp::Rejection> + Clone {
+	warp::head()
+		.or(warp::get())
+		.and(warp::path("ready"))
+		.map(move |_| {
+			let state = state.lock().expect("Lock should be acquired");
+			match readiness(&state, max_block_age, unix_timestamp()) {
+				Ok(()) => warp::reply::with_status("", StatusCode::OK),
+				Err(reason) => warp::reply::with_status(reason, StatusCode::SERVICE_UNAVAILABLE),
+			}
+		})
+}
+
+/// Adds CORS headers to the responses for the allowed origins and answers preflight requests.
+/// Cross-origin requests are not allowed if no origins are configured.
+fn with_cors(
+	config: &RuntimeConfig,
+	filter: impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone + Send + Sync + 'static,
+) -> BoxedFilter<(Response,)> {
+	let filter = filter.map(Reply::into_response);
+	if config.cors_allowed_origins.is_empty() {
+		return filter.boxed();
+	}
+
+	let cors = warp::cors()
+		.allow_methods(config.cors_allowed_methods.iter().map(String::as_str))
+		.allow_headers(config.cors_allowed_headers.iter().map(String::as_str));
+	let cors = if config
+		.cors_allowed_origins
+		.iter()
+		.any(|origin| origin == "*")
+	{
+		cors.allow_any_origin()
+	} else {
+		cors.allow_origins(config.cors_allowed_origins.iter().map(String::as_str))
+	};
+	filter.with(cors).map(Reply::into_response).boxed()
+}
+
+/// Binds routes to the configured address and returns bound address with the server future.
+/// Server serves HTTPS (and WSS) if TLS certificate and key are configured, otherwise plain HTTP.
+fn serve(
+	config: &RuntimeConfig,
+	routes: BoxedFilter<(Response,)>,
+	shutdown_signal: impl Future<Output = ()> + Send + 'static,
+) -> Result<(SocketAddr, impl Future<Output = ()>)> {
+	let RuntimeConfig {
+		http_server_host: host,
+		http_server_port: port,
+		..
+	} = config;
+	let addr = SocketAddr::from_str(&format!("{host}:{port}"))
+		.wrap_err("Unable to parse host address from config")?;
+
+	let server = warp::serve(routes);
+	match (&config.http_server_tls_cert, &config.http_server_tls_key) {
+		(None, None) => {
+			let (addr, server) = server
+				.try_bind_with_graceful_shutdown(addr, shutdown_signal)
+				.wrap_err("Unable to bind HTTP server")?;
+			info!("RPC running on http://{addr}");
+			Ok((addr, Either::Left(server)))
+		},
+		(Some(cert), Some(key)) => {
+			// TLS server panics on invalid certificate or unavailable address,
+			// so both are checked before binding
+			let (cert, key) = load_tls(cert, key)?;
+			std::net::TcpListener::bind(addr).wrap_err("Unable to bind HTTPS server")?;
+			let (addr, server) = server
+				.tls()
+				.cert(cert)
+				.key(key)
+				.bind_with_graceful_shutdown(addr, shutdown_signal);
+			info!("RPC running on https://{addr}");
+			Ok((addr, Either::Right(server)))
+		},
+		_ => Err(eyre!(
+			"Both http_server_tls_cert and http_server_tls_key have to be set to enable TLS"
+		)),
+	}
+}
+
+/// Reads PEM encoded TLS certificate chain and private key,
+/// and checks that they contain a certificate and a supported key.
+fn load_tls(cert_path: &str, key_path: &str) -> Result<(Vec<u8>, Vec<u8>)> {
+	let cert = fs::read(cert_path)
+		.wrap_err_with(|| format!("Unable to read TLS certificate from {cert_path}"))?;
+	let key =
+		fs::read(key_path).wrap_err_with(|| format!("Unable to read TLS key from {key_path}"))?;
+
+	let certs = rustls_pemfile::certs(&mut cert.as_slice())
+		.wrap_err_with(|| format!("Unable to parse TLS certificate from {cert_path}"))?;
+	if certs.is_empty() {
+		return Err(eyre!("No TLS certificate found in {cert_path}"));
+	}
+
+	let items = rustls_pemfile::read_all(&mut key.as_slice())
+		.wrap_err_with(|| format!("Unable to parse TLS key from {key_path}"))?;
+	if !items.iter().any(|item| {
+		matches!(
+			item,
+			rustls_pemfile::Item::RSAKey(_)
+				| rustls_pemfile::Item::PKCS8Key(_)
+				| rustls_pemfile::Item::ECKey(_)
+		)
+	}) {
+		return Err(eyre!("No supported TLS private key found in {key_path}"));
+	}
+
+	Ok((cert, key))
 }
 
 impl<T: Database + Clone + Send + Sync + 'static> Server<T> {
 	/// Creates a HTTP server that needs to be spawned into a runtime
-	pub fn bind(self) -> impl Future<Output = ()> {
+	pub fn bind(self) -> Result<impl Future<Output = ()>> {
 		let RuntimeConfig {
-			http_server_host: host,
-			http_server_port: port,
 			app_id,
+			health_max_block_age,
 			..
 		} = self.cfg.clone();
 
@@ -61,28 +197,215 @@ impl<T: Database + Clone + Send + Sync + 'static> Server<T> {
 			self.version.clone(),
 			self.network_version.clone(),
 			self.state.clone(),
-			self.cfg,
+			self.cfg.clone(),
 			self.identity_cfg,
 			self.node_client.clone(),
+			self.public_parameters.clone(),
+			self.block_verified_sender.clone(),
+			self.rpc_event_sender.clone(),
 			self.ws_clients.clone(),
+			self.api_metrics.clone(),
 			self.db.clone(),
 		);
 
-		let cors = warp::cors()
-			.allow_any_origin()
-			.allow_header("content-type")
-			.allow_methods(vec!["GET", "POST", "DELETE"]);
-
-		let routes = health_route().or(v1_api).or(v2_api).with(cors);
+		let routes = health_route()
+			.or(ready_route(self.state.clone(), health_max_block_age))
+			.or(v1_api)
+			.or(v2_api);
+		let routes = with_cors(&self.cfg, routes);
 
-		let addr = SocketAddr::from_str(format!("{host}:{port}").as_str())
-			.wrap_err("Unable to parse host address from config")
-			.unwrap();
-		info!("RPC running on http://{host}:{port}");
 		// warp graceful shutdown expects a signal that is [`Future<Output = ()>`]
 		let shutdown_signal = self.shutdown.triggered_shutdown().map(|_| ());
-		let (_, server) = warp::serve(routes).bind_with_graceful_shutdown(addr, shutdown_signal);
+		let (_, server) = serve(&self.cfg, routes, shutdown_signal)?;
+		Ok(server)
+	}
+}
 
-		server
+#[cfg(test)]
+mod tests {
+	use super::{health_route, ready_route, serve, with_cors};
+	use crate::types::{unix_timestamp, RuntimeConfig, State};
+	use std::{
+		net::{IpAddr, Ipv4Addr},
+		sync::{Arc, Mutex},
+	};
+	use test_case::test_case;
+	use tokio::{
+		io::{AsyncReadExt, AsyncWriteExt},
+		net::TcpStream,
+	};
+	use warp::{http::StatusCode, Filter};
+
+	fn cors_route(
+		allowed_origins: &[&str],
+	) -> warp::filters::BoxedFilter<(warp::reply::Response,)> {
+		let config = RuntimeConfig {
+			cors_allowed_origins: allowed_origins
+				.iter()
+				.map(|origin| origin.to_string())
+				.collect(),
+			..Default::default()
+		};
+		with_cors(&config, warp::path("test").map(warp::reply))
+	}
+
+	#[tokio::test]
+	async fn serve_configured_address() {
+		let config = RuntimeConfig {
+			http_server_host: "127.0.0.1".to_string(),
+			http_server_port: 0,
+			..Default::default()
+		};
+		let routes = warp::path("test")
+			.map(warp::reply)
+			.map(warp::Reply::into_response);
+		let (addr, server) = serve(&config, routes.boxed(), futures::future::pending()).unwrap();
+		assert_eq!(addr.ip(), IpAddr::V4(Ipv4Addr::LOCALHOST));
+		assert_ne!(addr.port(), 0);
+		tokio::spawn(server);
+
+		let mut stream = TcpStream::connect(addr).await.unwrap();
+		stream
+			.write_all(b"GET /test HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+			.await
+			.unwrap();
+		let mut response = String::new();
+		stream.read_to_string(&mut response).await.unwrap();
+		assert!(response.starts_with("HTTP/1.1 200 OK"));
+	}
+
+	#[test_case("127.0.0.1", Some("cert.pem"), None ; "Missing key")]
+	#[test_case("127.0.0.1", None, Some("key.pem") ; "Missing certificate")]
+	#[test_case("invalid host", None, None ; "Invalid host")]
+	#[test_case("127.0.0.1", Some("/nonexistent/cert.pem"), Some("/nonexistent/key.pem") ; "Missing TLS files")]
+	#[tokio::test]
+	async fn serve_invalid_config(host: &str, cert: Option<&str>, key: Option<&str>) {
+		let config = RuntimeConfig {
+			http_server_host: host.to_string(),
+			http_server_port: 0,
+			http_server_tls_cert: cert.map(String::from),
+			http_server_tls_key: key.map(String::from),
+			..Default::default()
+		};
+		let routes = warp::path("test")
+			.map(warp::reply)
+			.map(warp::Reply::into_response);
+		assert!(serve(&config, routes.boxed(), futures::future::pending()).is_err());
+	}
+
+	#[tokio::test]
+	async fn serve_invalid_tls() {
+		let dir = std::env::temp_dir().join("avail-light-invalid-tls");
+		std::fs::create_dir_all(&dir).unwrap();
+		let cert = dir.join("cert.pem");
+		let key = dir.join("key.pem");
+		std::fs::write(&cert, "invalid certificate").unwrap();
+		std::fs::write(&key, "invalid key").unwrap();
+
+		let config = RuntimeConfig {
+			http_server_host: "127.0.0.1".to_string(),
+			http_server_port: 0,
+			http_server_tls_cert: Some(cert.to_string_lossy().to_string()),
+			http_server_tls_key: Some(key.to_string_lossy().to_string()),
+			..Default::default()
+		};
+		let routes = warp::path("test")
+			.map(warp::reply)
+			.map(warp::Reply::into_response);
+		assert!(serve(&config, routes.boxed(), futures::future::pending()).is_err());
+	}
+
+	#[tokio::test]
+	async fn health_route_live() {
+		let response = warp::test::request()
+			.method("GET")
+			.path("/health")
+			.reply(&health_route())
+			.await;
+		assert_eq!(response.status(), StatusCode::OK);
+	}
+
+	#[tokio::test]
+	async fn ready_route_ready() {
+		let mut state = State::default();
+		state.set_latest(10);
+		let route = ready_route(Arc::new(Mutex::new(state)), 120);
+		let response = warp::test::request()
+			.method("GET")
+			.path("/ready")
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::OK);
+	}
+
+	#[test_case(0, Some(unix_timestamp()), "No blocks processed" ; "No blocks")]
+	#[test_case(10, None, "No recent blocks received from the node" ; "No block time")]
+	#[test_case(10, Some(unix_timestamp() - 300), "No recent blocks received from the node" ; "Stale block")]
+	#[tokio::test]
+	async fn ready_route_not_ready(latest: u32, latest_block_at: Option<u64>, reason: &str) {
+		let state = State {
+			latest,
+			latest_block_at,
+			..Default::default()
+		};
+		let route = ready_route(Arc::new(Mutex::new(state)), 120);
+		let response = warp::test::request()
+			.method("GET")
+			.path("/ready")
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+		assert_eq!(response.body(), reason);
+	}
+
+	#[test_case(&["https://example.com"], "https://example.com" ; "Allowed origin")]
+	#[test_case(&["*"], "https://example.com" ; "Any origin")]
+	#[tokio::test]
+	async fn cors_allowed_origin(allowed_origins: &[&str], origin: &str) {
+		let response = warp::test::request()
+			.method("GET")
+			.path("/test")
+			.header("origin", origin)
+			.reply(&cors_route(allowed_origins))
+			.await;
+		assert_eq!(response.status(), StatusCode::OK);
+		assert_eq!(response.headers()["access-control-allow-origin"], origin);
+	}
+
+	#[test_case(&["https://example.com"] ; "Other origin")]
+	#[test_case(&[] ; "Same origin only")]
+	#[tokio::test]
+	async fn cors_disallowed_origin(allowed_origins: &[&str]) {
+		let response = warp::test::request()
+			.method("GET")
+			.path("/test")
+			.header("origin", "https://other.com")
+			.reply(&cors_route(allowed_origins))
+			.await;
+		assert!(!response
+			.headers()
+			.contains_key("access-control-allow-origin"));
+	}
+
+	#[tokio::test]
+	async fn cors_preflight() {
+		let response = warp::test::request()
+			.method("OPTIONS")
+			.path("/test")
+			.header("origin", "https://example.com")
+			.header("access-control-request-method", "POST")
+			.header("access-control-request-headers", "authorization")
+			.reply(&cors_route(&["https://example.com"]))
+			.await;
+		assert_eq!(response.status(), StatusCode::OK);
+		let headers = response.headers();
+		assert_eq!(
+			headers["access-control-allow-origin"],
+			"https://example.com"
+		);
+		let methods = headers["access-control-allow-methods"].to_str().unwrap();
+		assert!(methods.contains("POST"));
+		let allowed_headers = headers["access-control-allow-headers"].to_str().unwrap();
+		assert!(allowed_headers.contains("authorization"));
 	}
 }
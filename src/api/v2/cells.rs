@@ -0,0 +1,84 @@
+use async_trait::async_trait;
+use color_eyre::Result;
+use dusk_plonk::commitment_scheme::kzg10::PublicParameters;
+use kate_recovery::{
+	data::Cell,
+	matrix::{Dimensions, Position},
+};
+use sp_core::H256;
+use std::{collections::HashSet, sync::Arc};
+
+use crate::{
+	network::rpc::{self, DataProof},
+	proof,
+};
+
+#[async_trait]
+pub trait Fetch {
+	/// Fetches cells with their proofs for the given positions of the extended matrix.
+	async fn fetch(&self, block_hash: H256, positions: &[Position]) -> Result<Vec<Cell>>;
+
+	/// Fetches Merkle proof of the data transaction at the given index, under the block data root.
+	async fn fetch_data_proof(&self, block_hash: H256, index: u32) -> Result<DataProof>;
+
+	/// Fetches SCALE encoded extrinsics of the block, in block order.
+	async fn fetch_extrinsics(&self, block_hash: H256) -> Result<Vec<Vec<u8>>>;
+
+	/// Fetches cells for the given positions and verifies their proofs against the commitments.
+	/// Returns the verified cells.
+	async fn fetch_verified(
+		&self,
+		block_number: u32,
+		block_hash: H256,
+		dimensions: Dimensions,
+		commitments: &[[u8; 48]],
+		positions: &[Position],
+	) -> Result<Vec<Cell>>;
+}
+
+/// Fetches cells from the node and verifies them using public parameters.
+pub struct Fetcher {
+	pub rpc_client: rpc::Client,
+	pub public_parameters: Arc<PublicParameters>,
+}
+
+#[async_trait]
+impl Fetch for Fetcher {
+	async fn fetch(&self, block_hash: H256, positions: &[Position]) -> Result<Vec<Cell>> {
+		self.rpc_client
+			.request_kate_proof(block_hash, positions)
+			.await
+	}
+
+	async fn fetch_data_proof(&self, block_hash: H256, index: u32) -> Result<DataProof> {
+		self.rpc_client.request_data_proof(block_hash, index).await
+	}
+
+	async fn fetch_extrinsics(&self, block_hash: H256) -> Result<Vec<Vec<u8>>> {
+		self.rpc_client.get_block_extrinsics(block_hash).await
+	}
+
+	async fn fetch_verified(
+		&self,
+		block_number: u32,
+		block_hash: H256,
+		dimensions: Dimensions,
+		commitments: &[[u8; 48]],
+		positions: &[Position],
+	) -> Result<Vec<Cell>> {
+		let cells = self.fetch(block_hash, positions).await?;
+		let (verified, _) = proof::verify(
+			block_number,
+			dimensions,
+			&cells,
+			commitments,
+			self.public_parameters.clone(),
+		)
+		.await?;
+		let verified = verified.into_iter().collect::<HashSet<_>>();
+		Ok(cells
+			.into_iter()
+			.filter(|cell| verified.contains(&cell.position))
+			.collect())
+	}
+}
@@ -1,64 +1,183 @@
 use super::{
+	cells,
+	metrics::ApiMetrics,
+	rate_limit::{ClientRateLimiter, SubmitQuota},
 	transactions,
 	types::{
-		block_status, filter_fields, Block, BlockStatus, DataQuery, DataResponse, DataTransaction,
-		Error, FieldsQueryParameter, Header, Status, SubmitResponse, Subscription, SubscriptionId,
-		Transaction, Version, WsClients,
+		block_status, check_protocol_version, filter_fields, headers_range, rename_fields,
+		replay_window, Block, BlockStatus, CborEncoder, DataQuery, DataResponse, DataTransaction,
+		Error, FieldsQueryParameter, Header, HeaderMessage, HeaderQuery, HeadersQuery,
+		HeadersResponse, PublishMessage, Status, SubmitBatchResponse, SubmitCache, SubmitRequest,
+		SubmitResponse, SubscriptionId, SubscriptionRequest, Topic, Transaction, Version,
+		WsClients, WsQuery,
 	},
 	ws,
 };
 use crate::{
-	api::v2::types::{ErrorCode, InternalServerError},
+	api::v2::types::{
+		ErrorCode, InternalServerError, RateLimited, Unauthorized, COMPRESSION_THRESHOLD,
+	},
 	data::Database,
 	data::Key,
-	types::{RuntimeConfig, State},
+	network::rpc::Event as RpcEvent,
+	types::{BlockVerified, FieldNaming, RuntimeConfig, State},
 	utils::calculate_confidence,
 };
 use avail_subxt::primitives;
 use color_eyre::{eyre::eyre, Result};
-use hyper::StatusCode;
+use flate2::write::GzEncoder;
+use futures::stream;
+use hyper::{
+	http::{
+		header::{CONTENT_ENCODING, CONTENT_TYPE, ETAG, VARY},
+		HeaderValue,
+	},
+	Body, StatusCode,
+};
 use std::{
 	convert::Infallible,
+	io::Write,
 	sync::{Arc, Mutex},
+	time::{Duration, Instant},
 };
-use tracing::error;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{debug, error, warn};
 use uuid::Uuid;
-use warp::{ws::Ws, Rejection, Reply};
+use warp::{body::BodyDeserializeError, http::Method, path::FullPath, ws::Ws, Rejection, Reply};
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+pub const CBOR_CONTENT_TYPE: &str = "application/cbor";
+pub const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
 
 pub async fn subscriptions(
-	subscription: Subscription,
+	request: SubscriptionRequest,
 	clients: WsClients,
-) -> Result<SubscriptionId, Infallible> {
+	config: RuntimeConfig,
+	state: Arc<Mutex<State>>,
+) -> Result<SubscriptionId, Error> {
+	check_protocol_version(request.protocol_version)
+		.map_err(|message| Error::bad_request_unknown(&message))?;
+
+	// Buffered headers would duplicate the headers replayed from the block
+	if request.replay && request.from_block.is_some() {
+		let message = "Replay cannot be combined with from_block";
+		return Err(Error::bad_request_unknown(message));
+	}
+
 	let subscription_id = Uuid::new_v4().to_string();
-	clients.subscribe(&subscription_id, subscription).await;
-	Ok(SubscriptionId { subscription_id })
+	let subscription = clients
+		.subscribe(&subscription_id, request.subscription)
+		.await;
+	clients
+		.set_replay_from(&subscription_id, request.from_block)
+		.await;
+	clients
+		.set_replay_buffered(&subscription_id, request.replay)
+		.await;
+
+	let replay = request
+		.from_block
+		.filter(|_| subscription.topics.contains(&Topic::HeaderVerified))
+		.and_then(|from_block| {
+			let state = state.lock().expect("Lock should be acquired");
+			replay_window(
+				from_block,
+				state.header_verified.as_ref(),
+				config.ws_replay_max_blocks,
+			)
+		});
+
+	Ok(SubscriptionId {
+		subscription_id,
+		subscription,
+		replay,
+	})
 }
 
 pub async fn submit(
 	submitter: Arc<impl transactions::Submit>,
-	transaction: Transaction,
+	quota: SubmitQuota,
+	submit_cache: SubmitCache,
+	idempotency_key: Option<Uuid>,
+	config: RuntimeConfig,
+	submit_request: SubmitRequest,
 ) -> Result<SubmitResponse, Error> {
-	submitter
-		.submit(transaction)
-		.await
-		.map_err(Error::internal_server_error)
+	let SubmitRequest {
+		transaction,
+		expected_app_id,
+		dry_run,
+	} = submit_request;
+	let transaction = transactions::prepare(transaction, expected_app_id, &config)
+		.map_err(|message| Error::bad_request_unknown(&message))?;
+
+	// Dry run responses are not cached, since the transaction is not submitted
+	if dry_run {
+		return transactions::dry_run(&transaction)
+			.map_err(|message| Error::bad_request_unknown(&message));
+	}
+
+	transactions::submit_idempotent(
+		submitter.as_ref(),
+		&submit_cache,
+		idempotency_key,
+		&quota,
+		transaction,
+	)
+	.await
+}
+
+pub async fn submit_batch(
+	submitter: Arc<impl transactions::Submit>,
+	quota: SubmitQuota,
+	config: RuntimeConfig,
+	transactions: Vec<Transaction>,
+) -> Result<SubmitBatchResponse, Error> {
+	if transactions.is_empty() {
+		return Err(Error::bad_request_unknown("Batch is empty."));
+	}
+	if transactions.len() > config.max_batch_size {
+		let message = transactions::batch_size_exceeded(transactions.len(), config.max_batch_size);
+		return Err(Error::bad_request_unknown(&message));
+	}
+
+	let results =
+		transactions::submit_batch(submitter.as_ref(), &config, &quota, transactions).await;
+	Ok(SubmitBatchResponse(results))
 }
 
 #[allow(clippy::too_many_arguments)]
 pub async fn ws(
 	subscription_id: String,
 	ws: Ws,
+	query: WsQuery,
 	clients: WsClients,
 	version: Version,
 	config: RuntimeConfig,
 	submitter: Option<Arc<impl transactions::Submit + Clone + Send + Sync + 'static>>,
+	submit_cache: SubmitCache,
+	cell_fetcher: Arc<impl cells::Fetch + Send + Sync + 'static>,
+	metrics: ApiMetrics,
+	rate_limiter: ClientRateLimiter,
 	state: Arc<Mutex<State>>,
-) -> Result<impl Reply, Rejection> {
+	db: impl Database + Clone + Send + Sync + 'static,
+) -> Result<warp::reply::Response, Rejection> {
 	if !clients.has_subscription(&subscription_id).await {
 		return Err(warp::reject::not_found());
 	}
+	let max_connections = config.ws_max_connections;
+	let Some(connection) = clients.register_connection(max_connections) else {
+		// Stale connections are closed by heartbeat, so retry is suggested after its interval
+		let retry_after = Duration::from_secs(config.ws_heartbeat_interval);
+		let error = Error::connection_limit_reached(max_connections, retry_after);
+		return Ok(error.into_response());
+	};
+	clients
+		.set_compression(&subscription_id, query.compression)
+		.await;
 	// NOTE: Multiple connections to the same client are currently allowed
-	Ok(ws.on_upgrade(move |web_socket| {
+	let reply = ws.on_upgrade(move |web_socket| async move {
+		// Connection is unregistered when client disconnects
+		let _connection = connection;
 		ws::connect(
 			subscription_id,
 			web_socket,
@@ -66,17 +185,50 @@ pub async fn ws(
 			version,
 			config,
 			submitter.clone(),
+			submit_cache.clone(),
+			cell_fetcher.clone(),
+			metrics.clone(),
+			rate_limiter.clone(),
 			state.clone(),
+			db.clone(),
 		)
-	}))
+		.await
+	});
+	Ok(reply.into_response())
 }
 
-pub fn status(config: RuntimeConfig, state: Arc<Mutex<State>>) -> impl Reply {
-	let state = state.lock().expect("Lock should be acquired");
-	Status::new(&config, &state)
+/// Checks if any of the entity tags from the `If-None-Match` header matches the given entity tag,
+/// using weak comparison.
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+	let etag = etag.trim_start_matches("W/");
+	if_none_match
+		.split(',')
+		.map(str::trim)
+		.any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+pub fn status(
+	if_none_match: Option<String>,
+	config: RuntimeConfig,
+	state: Arc<Mutex<State>>,
+) -> warp::reply::Response {
+	let status = {
+		let state = state.lock().expect("Lock should be acquired");
+		Status::new(&config, &state)
+	};
+
+	let etag = status.etag();
+	if if_none_match.is_some_and(|if_none_match| etag_matches(&if_none_match, &etag)) {
+		return warp::reply::with_header(StatusCode::NOT_MODIFIED, ETAG, etag).into_response();
+	}
+	warp::reply::with_header(status, ETAG, etag).into_response()
 }
 
-pub fn log_internal_server_error(result: Result<impl Reply, Error>) -> Result<impl Reply, Error> {
+/// Logs internal server errors. If enabled, cause chains are exposed in error responses.
+pub fn log_internal_server_error(
+	result: Result<impl Reply, Error>,
+	expose_error_causes: bool,
+) -> Result<impl Reply, Error> {
 	if let Err(Error {
 		error_code: ErrorCode::InternalServerError,
 		cause: Some(error),
@@ -86,7 +238,10 @@ pub fn log_internal_server_error(result: Result<impl Reply, Error>) -> Result<im
 	{
 		error!("{message}: {error:#}");
 	}
-	result
+	match result {
+		Err(error) if expose_error_causes => Err(error.with_cause_chain()),
+		result => result,
+	}
 }
 
 pub async fn block(
@@ -102,15 +257,115 @@ pub async fn block(
 	};
 
 	let confidence = db
-		.get(Key::VerifiedCellCount(block_number))
-		.map_err(Error::internal_server_error)?
+		.get(Key::VerifiedCellCount(block_number))?
 		.map(calculate_confidence);
 
 	Ok(Block::new(block_status, confidence))
 }
 
+/// Returns block if its availability is known, which is when confidence is achieved,
+/// or if the block is before the sync start block.
+fn available_block(
+	config: &RuntimeConfig,
+	state: &Arc<Mutex<State>>,
+	db: &impl Database,
+	block_number: u32,
+) -> Result<Option<Block>, Error> {
+	let block_status = {
+		let state = state.lock().expect("Lock should be acquired");
+		block_status(&config.sync_start_block, &state, block_number)
+	};
+
+	let Some(block_status) = block_status.filter(|status| {
+		matches!(
+			status,
+			BlockStatus::Unavailable | BlockStatus::VerifyingData | BlockStatus::Finished
+		)
+	}) else {
+		return Ok(None);
+	};
+
+	let confidence = db
+		.get(Key::VerifiedCellCount(block_number))?
+		.map(calculate_confidence);
+
+	Ok(Some(Block::new(block_status, confidence)))
+}
+
+/// Waits for the block availability, checking it again on each verified block.
+async fn wait_for_availability(
+	block_number: u32,
+	config: &RuntimeConfig,
+	state: &Arc<Mutex<State>>,
+	db: &impl Database,
+	block_verified: &mut broadcast::Receiver<BlockVerified>,
+) -> Result<Option<Block>, Error> {
+	loop {
+		if let Some(block) = available_block(config, state, db, block_number)? {
+			return Ok(Some(block));
+		}
+		// Lagged receiver only means that the state needs to be checked again
+		if let Err(RecvError::Closed) = block_verified.recv().await {
+			return Ok(None);
+		}
+	}
+}
+
+pub async fn block_availability(
+	block_number: u32,
+	config: RuntimeConfig,
+	state: Arc<Mutex<State>>,
+	db: impl Database,
+	mut block_verified: broadcast::Receiver<BlockVerified>,
+) -> Result<warp::reply::Response, Error> {
+	let timeout = Duration::from_secs(config.block_availability_timeout);
+	let availability =
+		wait_for_availability(block_number, &config, &state, &db, &mut block_verified);
+
+	match tokio::time::timeout(timeout, availability).await {
+		Ok(Ok(Some(block))) => Ok(block.into_response()),
+		Ok(Ok(None)) | Err(_) => Ok(StatusCode::NO_CONTENT.into_response()),
+		Ok(Err(error)) => Err(error),
+	}
+}
+
+/// Streams verified headers as newline delimited JSON, the same way they are published
+/// on the `header-verified` topic. Stream ends when the client disconnects and the body is dropped.
+pub fn header_stream(receiver: broadcast::Receiver<RpcEvent>) -> warp::reply::Response {
+	let lines = stream::unfold(receiver, |mut receiver| async move {
+		loop {
+			let message = match receiver.recv().await {
+				Ok(event) => PublishMessage::try_from(event),
+				Err(RecvError::Lagged(count)) => {
+					warn!("Header stream is lagging, skipped {count} headers");
+					continue;
+				},
+				Err(RecvError::Closed) => return None,
+			};
+			let header = match message {
+				Ok(PublishMessage::HeaderVerified(header)) => header,
+				Ok(_) => continue,
+				Err(error) => {
+					error!("Cannot create header message: {error}");
+					continue;
+				},
+			};
+			match serde_json::to_string(&header) {
+				Ok(line) => return Some((Ok::<_, Infallible>(format!("{line}\n")), receiver)),
+				Err(error) => error!("Cannot serialize header message: {error}"),
+			}
+		}
+	});
+
+	let mut response = warp::reply::Response::new(Body::wrap_stream(lines));
+	let content_type = HeaderValue::from_static(NDJSON_CONTENT_TYPE);
+	response.headers_mut().insert(CONTENT_TYPE, content_type);
+	response
+}
+
 pub async fn block_header(
 	block_number: u32,
+	query: HeaderQuery,
 	config: RuntimeConfig,
 	state: Arc<Mutex<State>>,
 	db: impl Database,
@@ -131,9 +386,60 @@ pub async fn block_header(
 	db.get::<primitives::Header>(Key::BlockHeader(block_number))
 		.and_then(|header| header.ok_or_else(|| eyre!("Header not found")))
 		.and_then(|header| header.try_into())
+		.map(|header: Header| {
+			header.with_commitment_encoding(query.commitments.unwrap_or_default())
+		})
 		.map_err(Error::internal_server_error)
 }
 
+pub async fn block_headers(
+	query: HeadersQuery,
+	state: Arc<Mutex<State>>,
+	db: impl Database,
+) -> Result<HeadersResponse, Error> {
+	if query.first > query.last {
+		return Err(Error::bad_request_unknown(
+			"First block number is greater than last block number",
+		));
+	}
+
+	let range = headers_range(&state.lock().expect("Lock should be acquired"));
+
+	let Some(range) = range else {
+		return Ok(HeadersResponse {
+			headers: vec![],
+			next: None,
+		});
+	};
+
+	let first = query.first.max(range.first);
+	let last = query.last.min(range.last);
+	let limit = query.limit();
+	let encoding = query.commitments.unwrap_or_default();
+
+	let mut headers = vec![];
+	let mut next = None;
+
+	for block_number in first..=last {
+		if headers.len() == limit as usize {
+			next = Some(block_number);
+			break;
+		}
+
+		let header = db.get::<primitives::Header>(Key::BlockHeader(block_number))?;
+
+		// Headers between the synced and the live range may be missing
+		let Some(header) = header else {
+			continue;
+		};
+
+		let header = HeaderMessage::try_from(header)?;
+		headers.push(header.with_commitment_encoding(encoding));
+	}
+
+	Ok(HeadersResponse { headers, next })
+}
+
 pub async fn block_data(
 	block_number: u32,
 	query: DataQuery,
@@ -155,9 +461,7 @@ pub async fn block_data(
 		return Err(Error::bad_request_unknown("Block data is not available"));
 	};
 
-	let data = db
-		.get::<Vec<Vec<u8>>>(Key::AppData(app_id, block_number))
-		.map_err(Error::internal_server_error)?;
+	let data = db.get::<Vec<Vec<u8>>>(Key::AppData(app_id, block_number))?;
 
 	let Some(data) = data else {
 		return Ok(DataResponse {
@@ -169,8 +473,7 @@ pub async fn block_data(
 	let mut data_transactions: Vec<DataTransaction> = data
 		.into_iter()
 		.map(DataTransaction::try_from)
-		.collect::<Result<_>>()
-		.map_err(Error::internal_server_error)?;
+		.collect::<Result<_>>()?;
 
 	if let Some(FieldsQueryParameter(fields)) = &query.fields {
 		filter_fields(&mut data_transactions, fields);
@@ -182,9 +485,217 @@ pub async fn block_data(
 	})
 }
 
+/// Adds request ID header to the response. Request ID is also set to the error response body,
+/// unless error already contains request ID.
+pub async fn request_id_response(request_id: Uuid, reply: impl Reply) -> warp::reply::Response {
+	let (mut parts, mut body) = reply.into_response().into_parts();
+
+	if parts.status.is_client_error() || parts.status.is_server_error() {
+		let bytes = hyper::body::to_bytes(body).await.unwrap_or_default();
+		body = match serde_json::from_slice::<Error>(&bytes) {
+			Ok(error) => {
+				let error = Error {
+					request_id: error.request_id.or(Some(request_id)),
+					..error
+				};
+				let body = serde_json::to_string(&error).expect("Error is serializable");
+				parts.extensions.insert(CborEncoder::new(error));
+				Body::from(body)
+			},
+			Err(_) => Body::from(bytes),
+		};
+	}
+
+	let request_id = HeaderValue::from_str(&request_id.to_string()).expect("Valid header value");
+	parts.headers.insert(REQUEST_ID_HEADER, request_id);
+	warp::reply::Response::from_parts(parts, body)
+}
+
+/// Logs handled request with the response status and the time it took to handle it.
+/// Access is logged at debug level, so it does not flood the logs of the busy nodes.
+pub fn log_response(
+	started: Instant,
+	method: Method,
+	path: FullPath,
+	reply: impl Reply,
+) -> warp::reply::Response {
+	let response = reply.into_response();
+	let request_id = response
+		.headers()
+		.get(REQUEST_ID_HEADER)
+		.and_then(|request_id| request_id.to_str().ok())
+		.unwrap_or_default();
+	let elapsed_ms = started.elapsed().as_millis() as u64;
+	debug!(
+		%method,
+		path = path.as_str(),
+		request_id,
+		status = response.status().as_u16(),
+		elapsed_ms,
+		"Request handled"
+	);
+	response
+}
+
+/// Checks if `application/cbor` is preferred over `application/json` by the `Accept` header value.
+/// CBOR is preferred if it is accepted with at least the same quality as JSON.
+fn prefers_cbor(accept: &str) -> bool {
+	let cbor_quality = quality(accept, CBOR_CONTENT_TYPE);
+	let json_quality = quality(accept, "application/json");
+	cbor_quality.is_some_and(|cbor| cbor > 0.0 && json_quality.map_or(true, |json| cbor >= json))
+}
+
+/// Encodes response value to CBOR, if client prefers `application/cbor` content type.
+/// Responses without the value encoder (e.g. schema) are sent as JSON.
+pub async fn content_negotiation_response(
+	accept: Option<String>,
+	reply: impl Reply,
+) -> warp::reply::Response {
+	let mut response = reply.into_response();
+
+	let is_json = response
+		.headers()
+		.get(CONTENT_TYPE)
+		.is_some_and(|content_type| content_type == "application/json");
+	if !is_json {
+		return response;
+	}
+
+	// Encoding of JSON responses depends on the accepted content type
+	response
+		.headers_mut()
+		.append(VARY, HeaderValue::from_static("accept"));
+	if !accept.is_some_and(|accept| prefers_cbor(&accept)) {
+		return response;
+	}
+
+	let Some(encoder) = response.extensions_mut().remove::<CborEncoder>() else {
+		return response;
+	};
+
+	match encoder.encode() {
+		Ok(cbor) => {
+			let (mut parts, _) = response.into_parts();
+			let content_type = HeaderValue::from_static(CBOR_CONTENT_TYPE);
+			parts.headers.insert(CONTENT_TYPE, content_type);
+			warp::reply::Response::from_parts(parts, Body::from(cbor))
+		},
+		Err(error) => {
+			error!("Cannot encode response to CBOR: {error:#}");
+			response
+		},
+	}
+}
+
+/// Renames fields of the JSON response body, if configured naming differs from the default one.
+pub async fn field_naming_response(
+	naming: FieldNaming,
+	reply: impl Reply,
+) -> warp::reply::Response {
+	let mut response = reply.into_response();
+	// Fields of the CBOR responses are renamed on encoding
+	if let Some(encoder) = response.extensions_mut().get_mut::<CborEncoder>() {
+		encoder.naming = naming;
+	}
+
+	let is_json = response
+		.headers()
+		.get(CONTENT_TYPE)
+		.is_some_and(|content_type| content_type == "application/json");
+	if naming == FieldNaming::SnakeCase || !is_json {
+		return response;
+	}
+
+	let (parts, body) = response.into_parts();
+	let bytes = hyper::body::to_bytes(body).await.unwrap_or_default();
+	let renamed = serde_json::from_slice::<serde_json::Value>(&bytes)
+		.map(|value| rename_fields(value, naming))
+		.and_then(|value| serde_json::to_vec(&value));
+
+	match renamed {
+		Ok(renamed) => warp::reply::Response::from_parts(parts, Body::from(renamed)),
+		Err(error) => {
+			error!("Cannot rename response fields: {error:#}");
+			warp::reply::Response::from_parts(parts, Body::from(bytes))
+		},
+	}
+}
+
+/// Returns quality of the value explicitly listed in the `Accept` or `Accept-Encoding` header value,
+/// which defaults to 1 if quality is not set. Wildcards are not matched.
+fn quality(header: &str, value: &str) -> Option<f32> {
+	header.split(',').find_map(|item| {
+		let mut parameters = item.split(';').map(str::trim);
+		if !parameters
+			.next()
+			.is_some_and(|item| item.eq_ignore_ascii_case(value))
+		{
+			return None;
+		}
+		let quality = parameters
+			.find_map(|parameter| parameter.strip_prefix("q="))
+			.map_or(Some(1.0), |quality| quality.parse::<f32>().ok());
+		Some(quality.unwrap_or(0.0))
+	})
+}
+
+/// Checks if `gzip` content coding is accepted by the `Accept-Encoding` header value.
+fn accepts_gzip(accept_encoding: &str) -> bool {
+	quality(accept_encoding, "gzip").is_some_and(|quality| quality > 0.0)
+}
+
+/// Compresses JSON response body with gzip, if client accepts `gzip` content coding
+/// and the body exceeds compression threshold.
+pub async fn compression_response(
+	accept_encoding: Option<String>,
+	reply: impl Reply,
+) -> warp::reply::Response {
+	let response = reply.into_response();
+
+	let accepts_gzip = accept_encoding.is_some_and(|encoding| accepts_gzip(&encoding));
+	let is_json = response
+		.headers()
+		.get(CONTENT_TYPE)
+		.is_some_and(|content_type| content_type == "application/json");
+	if !accepts_gzip || !is_json || response.headers().contains_key(CONTENT_ENCODING) {
+		return response;
+	}
+
+	let (mut parts, body) = response.into_parts();
+	parts
+		.headers
+		.append(VARY, HeaderValue::from_static("accept-encoding"));
+	let bytes = hyper::body::to_bytes(body).await.unwrap_or_default();
+	if bytes.len() <= COMPRESSION_THRESHOLD {
+		return warp::reply::Response::from_parts(parts, Body::from(bytes));
+	}
+
+	let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+	match encoder.write_all(&bytes).and_then(|_| encoder.finish()) {
+		Ok(gzip) => {
+			let content_encoding = HeaderValue::from_static("gzip");
+			parts.headers.insert(CONTENT_ENCODING, content_encoding);
+			warp::reply::Response::from_parts(parts, Body::from(gzip))
+		},
+		Err(error) => {
+			error!("Cannot compress response: {error:#}");
+			warp::reply::Response::from_parts(parts, Body::from(bytes))
+		},
+	}
+}
+
 pub async fn handle_rejection(error: Rejection) -> Result<impl Reply, Rejection> {
 	if error.find::<InternalServerError>().is_some() {
 		return Ok(StatusCode::INTERNAL_SERVER_ERROR.into_response());
 	}
+	if error.find::<Unauthorized>().is_some() {
+		return Ok(Error::unauthorized(None).into_response());
+	}
+	if let Some(RateLimited(retry_after)) = error.find::<RateLimited>() {
+		return Ok(Error::rate_limited(None, *retry_after).into_response());
+	}
+	if let Some(error) = error.find::<BodyDeserializeError>() {
+		return Ok(Error::bad_request_unknown(&error.to_string()).into_response());
+	}
 	Err(error)
 }
@@ -0,0 +1,221 @@
+use color_eyre::Result;
+use opentelemetry_api::{
+	global,
+	metrics::{noop::NoopMeterProvider, Counter, Histogram, Meter, MeterProvider, Unit},
+	KeyValue,
+};
+use serde::Serialize;
+use std::time::Duration;
+use warp::http::StatusCode;
+
+use super::types::{ErrorCode, Topic, WsClients};
+
+/// Metrics of the API activity, registered with the telemetry meter,
+/// so they are exported together with the other light client metrics.
+#[derive(Clone)]
+pub struct ApiMetrics {
+	requests: Counter<u64>,
+	request_duration: Histogram<f64>,
+	submits: Counter<u64>,
+	published: Counter<u64>,
+	evicted_clients: Counter<u64>,
+	errors: Counter<u64>,
+}
+
+/// Returns serialized name of the enum variant (e.g. `header-verified`).
+fn label(value: &impl Serialize) -> String {
+	serde_json::to_value(value)
+		.ok()
+		.and_then(|value| value.as_str().map(String::from))
+		.unwrap_or_default()
+}
+
+impl Default for ApiMetrics {
+	/// Metrics registered with the global meter, which doesn't export them unless telemetry is initialized.
+	fn default() -> Self {
+		ApiMetrics::new(&global::meter("avail_light_client"))
+	}
+}
+
+impl ApiMetrics {
+	pub fn new(meter: &Meter) -> Self {
+		ApiMetrics {
+			requests: meter
+				.u64_counter("api_requests")
+				.with_description("Number of handled WebSocket requests")
+				.init(),
+			request_duration: meter
+				.f64_histogram("api_request_duration")
+				.with_description("Duration of handled WebSocket requests")
+				.with_unit(Unit::new("s"))
+				.init(),
+			submits: meter
+				.u64_counter("api_submits")
+				.with_description("Number of submitted transactions")
+				.init(),
+			published: meter
+				.u64_counter("api_published_messages")
+				.with_description("Number of messages published to WebSocket clients")
+				.init(),
+			evicted_clients: meter
+				.u64_counter("api_evicted_clients")
+				.with_description(
+					"Number of WebSocket clients evicted for not keeping up with published messages",
+				)
+				.init(),
+			errors: meter
+				.u64_counter("api_errors")
+				.with_description("Number of error responses")
+				.init(),
+		}
+	}
+
+	/// Metrics which are not recorded, used if API metrics are disabled.
+	pub fn disabled() -> Self {
+		ApiMetrics::new(&NoopMeterProvider::new().meter("avail_light_client"))
+	}
+
+	/// Observes number of connected WebSocket clients, each time metrics are exported.
+	pub fn observe_ws_clients(meter: &Meter, clients: WsClients) -> Result<()> {
+		let ws_clients = meter
+			.u64_observable_gauge("api_ws_clients")
+			.with_description("Number of connected WebSocket clients")
+			.try_init()?;
+		meter.register_callback(&[ws_clients.as_any()], move |observer| {
+			observer.observe_u64(&ws_clients, clients.connections() as u64, &[])
+		})?;
+		Ok(())
+	}
+
+	pub fn record_request(&self, request_type: &str, elapsed: Duration) {
+		let attributes = [KeyValue::new("request_type", request_type.to_string())];
+		self.requests.add(1, &attributes);
+		self.request_duration
+			.record(elapsed.as_secs_f64(), &attributes);
+	}
+
+	pub fn record_submit(&self) {
+		self.submits.add(1, &[]);
+	}
+
+	pub fn record_published(&self, topic: &Topic, count: u64) {
+		let attributes = [KeyValue::new("topic", label(topic))];
+		self.published.add(count, &attributes);
+	}
+
+	pub fn record_evicted(&self) {
+		self.evicted_clients.add(1, &[]);
+	}
+
+	pub fn record_error(&self, error_code: &ErrorCode) {
+		let attributes = [KeyValue::new("error_code", error_code.as_str())];
+		self.errors.add(1, &attributes);
+	}
+
+	/// Counts HTTP error responses by the corresponding error code.
+	pub fn record_response_status(&self, status: StatusCode) {
+		let error_code = match status {
+			StatusCode::NOT_FOUND => ErrorCode::NotFound,
+			StatusCode::BAD_REQUEST => ErrorCode::BadRequest,
+			StatusCode::INTERNAL_SERVER_ERROR => ErrorCode::InternalServerError,
+			StatusCode::TOO_MANY_REQUESTS => ErrorCode::TooManyRequests,
+			StatusCode::SERVICE_UNAVAILABLE => ErrorCode::ServiceUnavailable,
+			StatusCode::UNAUTHORIZED => ErrorCode::Unauthorized,
+			_ => return,
+		};
+		self.record_error(&error_code);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use opentelemetry_api::{
+		metrics::{MeterProvider as _, Result},
+		Context,
+	};
+	use opentelemetry_sdk::{
+		metrics::{
+			data::{ResourceMetrics, Sum, Temporality},
+			reader::{AggregationSelector, MetricReader, TemporalitySelector},
+			Aggregation, InstrumentKind, ManualReader, MeterProvider, Pipeline,
+		},
+		Resource,
+	};
+	use std::sync::{Arc, Weak};
+	use warp::http::StatusCode;
+
+	use super::ApiMetrics;
+	use crate::api::v2::types::{ErrorCode, Topic};
+
+	/// Manual reader shared with the meter provider, so metrics can be collected in tests.
+	#[derive(Clone, Debug)]
+	struct SharedReader(Arc<ManualReader>);
+
+	impl TemporalitySelector for SharedReader {
+		fn temporality(&self, kind: InstrumentKind) -> Temporality {
+			self.0.temporality(kind)
+		}
+	}
+
+	impl AggregationSelector for SharedReader {
+		fn aggregation(&self, kind: InstrumentKind) -> Aggregation {
+			self.0.aggregation(kind)
+		}
+	}
+
+	impl MetricReader for SharedReader {
+		fn register_pipeline(&self, pipeline: Weak<Pipeline>) {
+			self.0.register_pipeline(pipeline)
+		}
+
+		fn collect(&self, metrics: &mut ResourceMetrics) -> Result<()> {
+			self.0.collect(metrics)
+		}
+
+		fn force_flush(&self, cx: &Context) -> Result<()> {
+			self.0.force_flush(cx)
+		}
+
+		fn shutdown(&self) -> Result<()> {
+			self.0.shutdown()
+		}
+	}
+
+	/// Returns sum of the counter data points, across all attributes.
+	fn counter(metrics: &ResourceMetrics, name: &str) -> u64 {
+		metrics
+			.scope_metrics
+			.iter()
+			.flat_map(|scope| &scope.metrics)
+			.filter(|metric| metric.name == name)
+			.filter_map(|metric| metric.data.as_any().downcast_ref::<Sum<u64>>())
+			.flat_map(|sum| &sum.data_points)
+			.map(|data_point| data_point.value)
+			.sum()
+	}
+
+	#[test]
+	fn counters() {
+		let reader = SharedReader(Arc::new(ManualReader::builder().build()));
+		let provider = MeterProvider::builder().with_reader(reader.clone()).build();
+		let metrics = ApiMetrics::new(&provider.meter("avail_light_client"));
+
+		metrics.record_submit();
+		metrics.record_submit();
+		metrics.record_published(&Topic::HeaderVerified, 3);
+		metrics.record_published(&Topic::BlockFinalized, 1);
+		metrics.record_error(&ErrorCode::BadRequest);
+		metrics.record_response_status(StatusCode::NOT_FOUND);
+		// Successful responses are not counted as errors
+		metrics.record_response_status(StatusCode::OK);
+
+		let mut collected = ResourceMetrics {
+			resource: Resource::empty(),
+			scope_metrics: vec![],
+		};
+		reader.collect(&mut collected).unwrap();
+		assert_eq!(counter(&collected, "api_submits"), 2);
+		assert_eq!(counter(&collected, "api_published_messages"), 4);
+		assert_eq!(counter(&collected, "api_errors"), 2);
+	}
+}
@@ -1,28 +1,44 @@
 use avail_subxt::AvailConfig;
+use dusk_plonk::commitment_scheme::kzg10::PublicParameters;
+use hyper::body::Bytes;
 use sp_core::sr25519::Pair;
 use std::{
+	collections::HashSet,
 	convert::Infallible,
 	fmt::Display,
+	future::Future,
+	ops::Range,
 	sync::{Arc, Mutex},
+	time::{Duration, Instant},
 };
 use subxt::tx::PairSigner;
 use tokio::sync::broadcast;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
 use warp::{Filter, Rejection, Reply};
 
 use self::{
-	handlers::{handle_rejection, log_internal_server_error},
-	types::{DataQuery, PublishMessage, Version, WsClients},
+	handlers::{handle_rejection, log_internal_server_error, REQUEST_ID_HEADER},
+	metrics::ApiMetrics,
+	rate_limit::{bearer_api_key, ClientKey, RateLimiter, SubmitQuota},
+	types::{
+		Base64, DataQuery, HeaderQuery, HeadersQuery, PublishMessage, Published, RateLimited,
+		Status, SubmitCache, SubmitRequest, SubmittedDataMessage, SyncProgressMessage, Transaction,
+		Unauthorized, Version, WsClients, WsQuery,
+	},
 };
 
 use crate::{
 	api::v2::types::Topic,
-	data::Database,
-	network::rpc::Client,
-	types::{IdentityConfig, RuntimeConfig, State},
+	data::{Database, Key},
+	network::rpc::{Client, Event as RpcEvent},
+	types::{BlockVerified, FieldNaming, IdentityConfig, RuntimeConfig, State},
 };
 
+mod cells;
 mod handlers;
+pub mod metrics;
+mod rate_limit;
 mod transactions;
 pub mod types;
 mod ws;
@@ -46,12 +62,117 @@ fn with_ws_clients(
 	warp::any().map(move || clients.clone())
 }
 
+/// Extracts request ID from the `X-Request-Id` header, or generates a new one if missing or invalid.
+fn request_id() -> impl Filter<Extract = (Uuid,), Error = Rejection> + Clone {
+	warp::header::optional::<String>(REQUEST_ID_HEADER).map(|request_id: Option<String>| {
+		request_id
+			.and_then(|request_id| Uuid::parse_str(&request_id).ok())
+			.unwrap_or_else(Uuid::new_v4)
+	})
+}
+
+/// Echoes request ID in the response headers, and sets it to the error responses.
+fn with_request_id(
+	filter: impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	request_id().and(filter).then(handlers::request_id_response)
+}
+
+/// Extracts key by which client requests are rate limited.
+fn client_key(
+	rate_limiter: RateLimiter,
+) -> impl Filter<Extract = (ClientKey,), Error = Infallible> + Clone {
+	warp::header::optional::<String>("authorization")
+		.and(warp::addr::remote())
+		.map(move |authorization, remote| rate_limiter.client_key(authorization, remote))
+}
+
+/// Rejects requests when client exceeds the configured rate limit.
+fn with_rate_limit(
+	rate_limiter: RateLimiter,
+	filter: impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	client_key(rate_limiter.clone())
+		.and_then(move |key: ClientKey| {
+			let rate_limiter = rate_limiter.clone();
+			async move {
+				rate_limiter
+					.acquire(&key)
+					.await
+					.map_err(|retry_after| warp::reject::custom(RateLimited(retry_after)))
+			}
+		})
+		.untuple_one()
+		.and(filter)
+}
+
+/// Logs handled requests with the elapsed time.
+fn with_request_logging(
+	filter: impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	warp::any()
+		.map(Instant::now)
+		.and(warp::method())
+		.and(warp::path::full())
+		.and(filter)
+		.map(handlers::log_response)
+}
+
+/// Counts error responses in the API metrics.
+fn with_error_metrics(
+	metrics: ApiMetrics,
+	filter: impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	filter.map(move |reply| {
+		let response = Reply::into_response(reply);
+		metrics.record_response_status(response.status());
+		response
+	})
+}
+
+/// Encodes responses to CBOR if client sends `Accept: application/cbor` header.
+fn with_content_negotiation(
+	filter: impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	warp::header::optional::<String>("accept")
+		.and(filter)
+		.then(handlers::content_negotiation_response)
+}
+
+/// Renames fields of the JSON responses according to the configured field naming.
+fn with_field_naming(
+	naming: FieldNaming,
+	filter: impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	warp::any()
+		.map(move || naming)
+		.and(filter)
+		.then(handlers::field_naming_response)
+}
+
+/// Compresses responses with gzip if client sends `Accept-Encoding: gzip` header.
+fn with_compression(
+	filter: impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	warp::header::optional::<String>("accept-encoding")
+		.and(filter)
+		.then(handlers::compression_response)
+}
+
 fn version_route(
 	version: Version,
+	state: Arc<Mutex<State>>,
 ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
-	warp::path!("v2" / "version")
+	warp::path!("v2" / "version").and(warp::get()).map(move || {
+		let state = state.lock().expect("Lock should be acquired");
+		version.clone().with_network(&state)
+	})
+}
+
+fn schema_route() -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	warp::path!("v2" / "schema")
 		.and(warp::get())
-		.map(move || version.clone())
+		.map(|| warp::reply::json(&types::schema()))
 }
 
 fn status_route(
@@ -60,6 +181,7 @@ fn status_route(
 ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
 	warp::path!("v2" / "status")
 		.and(warp::get())
+		.and(warp::header::optional::<String>("if-none-match"))
 		.and(warp::any().map(move || config.clone()))
 		.and(warp::any().map(move || state.clone()))
 		.map(handlers::status)
@@ -70,13 +192,32 @@ fn block_route(
 	state: Arc<Mutex<State>>,
 	db: impl Database + Clone + Send,
 ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	let expose_error_causes = config.expose_error_causes;
 	warp::path!("v2" / "blocks" / u32)
 		.and(warp::get())
 		.and(warp::any().map(move || config.clone()))
 		.and(warp::any().map(move || state.clone()))
 		.and(with_db(db))
 		.then(handlers::block)
-		.map(log_internal_server_error)
+		.map(move |result| log_internal_server_error(result, expose_error_causes))
+}
+
+fn block_availability_route(
+	config: RuntimeConfig,
+	state: Arc<Mutex<State>>,
+	db: impl Database + Clone + Send,
+	block_verified_sender: broadcast::Sender<BlockVerified>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	let expose_error_causes = config.expose_error_causes;
+	warp::path!("v2" / "blocks" / u32 / "availability")
+		.and(warp::get())
+		.and(warp::any().map(move || config.clone()))
+		.and(warp::any().map(move || state.clone()))
+		.and(with_db(db))
+		// Subscribed before the block status is checked, so no notifications are missed
+		.and(warp::any().map(move || block_verified_sender.subscribe()))
+		.then(handlers::block_availability)
+		.map(move |result| log_internal_server_error(result, expose_error_causes))
 }
 
 fn block_header_route(
@@ -84,13 +225,39 @@ fn block_header_route(
 	state: Arc<Mutex<State>>,
 	db: impl Database + Clone + Send,
 ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	let expose_error_causes = config.expose_error_causes;
 	warp::path!("v2" / "blocks" / u32 / "header")
 		.and(warp::get())
+		.and(warp::query::<HeaderQuery>())
 		.and(warp::any().map(move || config.clone()))
 		.and(warp::any().map(move || state.clone()))
 		.and(with_db(db))
 		.then(handlers::block_header)
-		.map(log_internal_server_error)
+		.map(move |result| log_internal_server_error(result, expose_error_causes))
+}
+
+fn block_headers_route(
+	config: RuntimeConfig,
+	state: Arc<Mutex<State>>,
+	db: impl Database + Clone + Send,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	let expose_error_causes = config.expose_error_causes;
+	warp::path!("v2" / "blocks" / "headers")
+		.and(warp::get())
+		.and(warp::query::<HeadersQuery>())
+		.and(warp::any().map(move || state.clone()))
+		.and(with_db(db))
+		.then(handlers::block_headers)
+		.map(move |result| log_internal_server_error(result, expose_error_causes))
+}
+
+fn header_stream_route(
+	rpc_event_sender: broadcast::Sender<RpcEvent>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	warp::path!("v2" / "blocks" / "headers" / "stream")
+		.and(warp::get())
+		.and(warp::any().map(move || rpc_event_sender.subscribe()))
+		.map(handlers::header_stream)
 }
 
 fn block_data_route(
@@ -98,6 +265,7 @@ fn block_data_route(
 	state: Arc<Mutex<State>>,
 	db: impl Database + Clone + Send,
 ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	let expose_error_causes = config.expose_error_causes;
 	warp::path!("v2" / "blocks" / u32 / "data")
 		.and(warp::get())
 		.and(warp::query::<DataQuery>())
@@ -105,54 +273,165 @@ fn block_data_route(
 		.and(warp::any().map(move || state.clone()))
 		.and(with_db(db))
 		.then(handlers::block_data)
-		.map(log_internal_server_error)
+		.map(move |result| log_internal_server_error(result, expose_error_causes))
+}
+
+fn with_submit_cache(
+	submit_cache: SubmitCache,
+) -> impl Filter<Extract = (SubmitCache,), Error = Infallible> + Clone {
+	warp::any().map(move || submit_cache.clone())
+}
+
+/// Rejects request if `Authorization` header does not contain valid API key,
+/// and extracts the submit quota of the API key, charged per submitted transaction.
+fn authorized(
+	config: RuntimeConfig,
+	rate_limiter: RateLimiter,
+) -> impl Filter<Extract = (SubmitQuota,), Error = Rejection> + Clone {
+	warp::header::optional::<String>("authorization").and_then(
+		move |authorization: Option<String>| {
+			let api_key = authorization.as_deref().and_then(bearer_api_key);
+			let result = if config.is_authorized(api_key) {
+				Ok(rate_limiter.submit_quota(api_key))
+			} else {
+				Err(warp::reject::custom(Unauthorized))
+			};
+			async move { result }
+		},
+	)
 }
 
 fn submit_route(
 	submitter: Option<Arc<impl transactions::Submit + Clone + Send + Sync>>,
+	submit_cache: SubmitCache,
+	config: RuntimeConfig,
+	rate_limiter: RateLimiter,
 ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	let expose_error_causes = config.expose_error_causes;
 	warp::path!("v2" / "submit")
 		.and(warp::post())
 		.and_then(move || optionally(submitter.clone()))
+		.and(authorized(config.clone(), rate_limiter))
+		.and(with_submit_cache(submit_cache))
+		.and(warp::header::optional::<Uuid>("idempotency-key"))
+		.and(warp::any().map(move || config.clone()))
 		.and(warp::body::json())
 		.then(handlers::submit)
-		.map(log_internal_server_error)
+		.map(move |result| log_internal_server_error(result, expose_error_causes))
+}
+
+fn submit_raw_route(
+	submitter: Option<Arc<impl transactions::Submit + Clone + Send + Sync>>,
+	submit_cache: SubmitCache,
+	config: RuntimeConfig,
+	rate_limiter: RateLimiter,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	let expose_error_causes = config.expose_error_causes;
+	warp::path!("v2" / "submit")
+		.and(warp::post())
+		.and(warp::header::exact_ignore_case(
+			"content-type",
+			"application/octet-stream",
+		))
+		.and_then(move || optionally(submitter.clone()))
+		.and(authorized(config.clone(), rate_limiter))
+		.and(with_submit_cache(submit_cache))
+		.and(warp::header::optional::<Uuid>("idempotency-key"))
+		.and(warp::any().map(move || config.clone()))
+		.and(warp::body::bytes().map(|body: Bytes| SubmitRequest {
+			transaction: Transaction::Data {
+				data: Base64(body.to_vec()),
+				app_id: None,
+			},
+			expected_app_id: None,
+			dry_run: false,
+		}))
+		.then(handlers::submit)
+		.map(move |result| log_internal_server_error(result, expose_error_causes))
+}
+
+fn submit_batch_route(
+	submitter: Option<Arc<impl transactions::Submit + Clone + Send + Sync>>,
+	config: RuntimeConfig,
+	rate_limiter: RateLimiter,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	let expose_error_causes = config.expose_error_causes;
+	warp::path!("v2" / "submit" / "batch")
+		.and(warp::post())
+		.and_then(move || optionally(submitter.clone()))
+		.and(authorized(config.clone(), rate_limiter))
+		.and(warp::any().map(move || config.clone()))
+		.and(warp::body::json())
+		.then(handlers::submit_batch)
+		.map(move |result| log_internal_server_error(result, expose_error_causes))
 }
 
 fn subscriptions_route(
 	clients: WsClients,
+	config: RuntimeConfig,
+	state: Arc<Mutex<State>>,
 ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	let expose_error_causes = config.expose_error_causes;
 	warp::path!("v2" / "subscriptions")
 		.and(warp::post())
 		.and(warp::body::json())
 		.and(with_ws_clients(clients))
-		.and_then(handlers::subscriptions)
+		.and(warp::any().map(move || config.clone()))
+		.and(warp::any().map(move || state.clone()))
+		.then(handlers::subscriptions)
+		.map(move |result| log_internal_server_error(result, expose_error_causes))
 }
 
+#[allow(clippy::too_many_arguments)]
 fn ws_route(
 	clients: WsClients,
 	version: Version,
 	config: RuntimeConfig,
 	submitter: Option<Arc<impl transactions::Submit + Clone + Send + Sync + 'static>>,
+	submit_cache: SubmitCache,
+	cell_fetcher: Arc<impl cells::Fetch + Send + Sync + 'static>,
+	metrics: ApiMetrics,
+	rate_limiter: RateLimiter,
 	state: Arc<Mutex<State>>,
+	db: impl Database + Clone + Send + Sync + 'static,
 ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
 	warp::path!("v2" / "ws" / String)
 		.and(warp::ws())
+		.and(warp::query::<WsQuery>())
 		.and(with_ws_clients(clients))
 		.and(warp::any().map(move || version.clone()))
 		.and(warp::any().map(move || config.clone()))
 		.and(warp::any().map(move || submitter.clone()))
+		.and(with_submit_cache(submit_cache))
+		.and(warp::any().map(move || cell_fetcher.clone()))
+		.and(warp::any().map(move || metrics.clone()))
+		.and(client_key(rate_limiter.clone()).map(move |key| rate_limiter.client(key)))
 		.and(warp::any().map(move || state.clone()))
+		.and(with_db(db))
 		.and_then(handlers::ws)
 }
 
+/// Publishes messages from the receiver to the clients subscribed to the message topic.
+/// Given topic identifies the receiver in the logs, since receiver can produce messages on multiple topics.
+/// Applications seen in the verified headers are stored, so they are not registered again after restart.
 pub async fn publish<T: Clone + TryInto<PublishMessage>>(
 	topic: Topic,
 	mut receiver: broadcast::Receiver<T>,
 	clients: WsClients,
+	metrics: ApiMetrics,
+	db: impl Database,
 ) where
 	<T as TryInto<PublishMessage>>::Error: Display,
 {
+	let mut seen_app_ids: HashSet<u32> = db
+		.get::<Vec<u32>>(Key::SeenAppIds)
+		.unwrap_or_else(|error| {
+			error!(?topic, "Cannot load seen applications: {error:#}");
+			None
+		})
+		.unwrap_or_default()
+		.into_iter()
+		.collect();
 	loop {
 		let message = match receiver.recv().await {
 			Ok(value) => value,
@@ -170,20 +449,212 @@ pub async fn publish<T: Clone + TryInto<PublishMessage>>(
 			},
 		};
 
-		match clients.publish(&topic, message).await {
-			Ok(results) => {
-				let published = results.iter().filter(|&result| result.is_ok()).count();
-				let failed = results.iter().filter(|&result| result.is_err()).count();
-				info!(?topic, published, failed, "Message published to clients");
-				for error in results.into_iter().filter_map(Result::err) {
-					debug!(?topic, "Cannot publish message to client: {error}")
-				}
+		let registered = message.apps_registered(&mut seen_app_ids);
+		if !registered.is_empty() {
+			let app_ids = seen_app_ids.iter().copied().collect::<Vec<_>>();
+			if let Err(error) = db.put(Key::SeenAppIds, app_ids) {
+				error!(?topic, "Cannot store seen applications: {error:#}");
+			}
+		}
+
+		publish_message(&clients, &metrics, message).await;
+
+		for message in registered {
+			publish_message(&clients, &metrics, message).await;
+		}
+	}
+}
+
+async fn publish_message(clients: &WsClients, metrics: &ApiMetrics, message: PublishMessage) {
+	let topic = message.topic();
+	match clients.publish(&topic, message).await {
+		Ok(Published {
+			results,
+			pruned,
+			lock_held,
+			serializations,
+		}) => {
+			let published = results.iter().filter(|&result| result.is_ok()).count();
+			let failed = results.iter().filter(|&result| result.is_err()).count();
+			let lock_held_ms = lock_held.as_millis() as u64;
+			metrics.record_published(&topic, published as u64);
+			info!(
+				?topic,
+				published,
+				failed,
+				pruned,
+				lock_held_ms,
+				serializations,
+				"Message published to clients"
+			);
+			for error in results.into_iter().filter_map(Result::err) {
+				debug!(?topic, "Cannot publish message to client: {error}")
+			}
+		},
+		Err(error) => error!(?topic, "Cannot publish message: {error}"),
+	}
+}
+
+/// Publishes hashes of the extrinsics included in the blocks with verified headers,
+/// to the clients subscribed to the submitted data inclusion. Block extrinsics are fetched
+/// from the node, so inclusion of transactions of any application is published.
+pub async fn publish_submitted_data<T: Clone + TryInto<PublishMessage>>(
+	receiver: broadcast::Receiver<T>,
+	clients: WsClients,
+	metrics: ApiMetrics,
+	rpc_client: Client,
+	public_parameters: Arc<PublicParameters>,
+) {
+	let fetcher = cells::Fetcher {
+		rpc_client,
+		public_parameters,
+	};
+	publish_included(receiver, clients, metrics, fetcher).await
+}
+
+async fn publish_included<T: Clone + TryInto<PublishMessage>>(
+	mut receiver: broadcast::Receiver<T>,
+	clients: WsClients,
+	metrics: ApiMetrics,
+	fetcher: impl cells::Fetch,
+) {
+	let topic = Topic::SubmittedDataIncluded;
+	loop {
+		let message = match receiver.recv().await {
+			Ok(value) => value,
+			Err(error) => {
+				error!(?topic, "Cannot receive message: {error}");
+				return;
+			},
+		};
+
+		let Some((block_number, block_hash)) = message
+			.try_into()
+			.ok()
+			.and_then(|message| message.verified_block())
+		else {
+			continue;
+		};
+
+		// Extrinsics are not fetched unless some client is interested in them
+		if !clients.has_subscribers(&topic).await {
+			continue;
+		}
+
+		let extrinsics = match fetcher.fetch_extrinsics(block_hash).await {
+			Ok(extrinsics) => extrinsics,
+			Err(error) => {
+				error!(
+					?topic,
+					block_number, "Cannot fetch block extrinsics: {error:#}"
+				);
+				continue;
 			},
-			Err(error) => error!(?topic, "Cannot publish message: {error}"),
+		};
+
+		let message = SubmittedDataMessage::new(block_number, &extrinsics);
+		publish_message(
+			&clients,
+			&metrics,
+			PublishMessage::SubmittedDataIncluded(message),
+		)
+		.await;
+	}
+}
+
+/// Periodically pings connected clients and disconnects unresponsive ones.
+pub async fn heartbeat(clients: WsClients, interval: Duration, max_missed: u32) {
+	let mut interval = tokio::time::interval(interval);
+	loop {
+		interval.tick().await;
+		let disconnected = clients.heartbeat(max_missed).await;
+		if disconnected > 0 {
+			info!(disconnected, "Unresponsive clients disconnected");
+		}
+	}
+}
+
+/// Periodically checks client buffers and evicts clients whose buffer of the given size
+/// stays full longer than the grace period.
+pub async fn evict_lagging(
+	clients: WsClients,
+	interval: Duration,
+	buffer_size: usize,
+	grace_period: Duration,
+	metrics: ApiMetrics,
+) {
+	let mut interval = tokio::time::interval(interval);
+	loop {
+		interval.tick().await;
+		for subscription_id in clients.evict_lagging(buffer_size, grace_period).await {
+			let seconds = grace_period.as_secs();
+			warn!("Client {subscription_id} buffer is full for more than {seconds}s, evicted");
+			metrics.record_evicted();
+		}
+	}
+}
+
+/// Periodically compares the status with the previous one, and publishes changed status fields
+/// to the clients subscribed to the status changes.
+pub async fn publish_status_changes(
+	config: RuntimeConfig,
+	state: Arc<Mutex<State>>,
+	clients: WsClients,
+	metrics: ApiMetrics,
+) {
+	let status = || Status::new(&config, &state.lock().expect("State lock can be acquired"));
+	let mut previous = status();
+	let mut interval =
+		tokio::time::interval(Duration::from_secs(config.ws_status_changed_interval));
+	loop {
+		interval.tick().await;
+		let current = status();
+		if let Some(changes) = current.changes(&previous) {
+			publish_message(&clients, &metrics, PublishMessage::StatusChanged(changes)).await;
+		}
+		previous = current;
+	}
+}
+
+/// Periodically checks the historical sync progress, and publishes it to the clients subscribed
+/// to the sync progress whenever it changes. Publishing stops once the sync is completed.
+pub async fn publish_sync_progress(
+	config: RuntimeConfig,
+	sync_range: Range<u32>,
+	state: Arc<Mutex<State>>,
+	clients: WsClients,
+	metrics: ApiMetrics,
+) {
+	let progress = || {
+		let state = state.lock().expect("State lock can be acquired");
+		SyncProgressMessage::new(&sync_range, &state)
+	};
+	let mut previous = None;
+	let mut interval =
+		tokio::time::interval(Duration::from_secs(config.ws_status_changed_interval));
+	loop {
+		interval.tick().await;
+		let Some(current) = progress() else {
+			continue;
+		};
+		if previous.as_ref() != Some(&current) {
+			let message = PublishMessage::SyncProgress(current.clone());
+			publish_message(&clients, &metrics, message).await;
+		}
+		if current.synced {
+			return;
 		}
+		previous = Some(current);
 	}
 }
 
+/// Closes connections of all WebSocket clients when shutdown is triggered.
+pub async fn shutdown(clients: WsClients, triggered: impl Future, timeout: Duration) {
+	triggered.await;
+	let closed = clients.shutdown(timeout).await;
+	info!(closed, "WebSocket clients closed on shutdown");
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn routes(
 	version: String,
@@ -192,51 +663,127 @@ pub fn routes(
 	config: RuntimeConfig,
 	identity_config: IdentityConfig,
 	rpc_client: Client,
+	public_parameters: Arc<PublicParameters>,
+	block_verified_sender: broadcast::Sender<BlockVerified>,
+	rpc_event_sender: broadcast::Sender<RpcEvent>,
 	ws_clients: WsClients,
-	db: impl Database + Clone + Send,
+	metrics: ApiMetrics,
+	db: impl Database + Clone + Send + Sync + 'static,
 ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
 	let version = Version {
 		version,
 		network_version,
+		network: Default::default(),
 	};
 
-	let app_id = config.app_id.as_ref();
 	let pair_signer = <PairSigner<AvailConfig, Pair>>::new(identity_config.avail_key_pair);
+	let cell_fetcher = Arc::new(cells::Fetcher {
+		rpc_client: rpc_client.clone(),
+		public_parameters,
+	});
 
-	let submitter = app_id.map(|&app_id| {
-		Arc::new(transactions::Submitter {
-			rpc_client,
-			app_id,
-			pair_signer,
+	// Submit is available only if app ID is configured, or if submit app IDs are allowed
+	let submitter = config.is_submit_enabled().then(|| {
+		Arc::new(transactions::MeteredSubmitter {
+			submitter: transactions::Submitter {
+				rpc_client,
+				pair_signer,
+			},
+			metrics: metrics.clone(),
 		})
 	});
 
-	version_route(version.clone())
+	let submit_cache = SubmitCache::new(Duration::from_secs(config.idempotency_key_ttl));
+	let rate_limiter = RateLimiter::from(&config);
+
+	let routes = version_route(version.clone(), state.clone())
 		.or(status_route(config.clone(), state.clone()))
+		.or(schema_route())
 		.or(block_route(config.clone(), state.clone(), db.clone()))
+		.or(block_availability_route(
+			config.clone(),
+			state.clone(),
+			db.clone(),
+			block_verified_sender,
+		))
 		.or(block_header_route(
 			config.clone(),
 			state.clone(),
 			db.clone(),
 		))
+		.or(block_headers_route(
+			config.clone(),
+			state.clone(),
+			db.clone(),
+		))
+		.or(header_stream_route(rpc_event_sender))
 		.or(block_data_route(config.clone(), state.clone(), db.clone()))
-		.or(subscriptions_route(ws_clients.clone()))
-		.or(submit_route(submitter.clone()))
-		.or(ws_route(ws_clients, version, config, submitter, state))
-		.recover(handle_rejection)
+		.or(subscriptions_route(
+			ws_clients.clone(),
+			config.clone(),
+			state.clone(),
+		))
+		.or(submit_raw_route(
+			submitter.clone(),
+			submit_cache.clone(),
+			config.clone(),
+			rate_limiter.clone(),
+		))
+		.or(submit_route(
+			submitter.clone(),
+			submit_cache.clone(),
+			config.clone(),
+			rate_limiter.clone(),
+		))
+		.or(submit_batch_route(
+			submitter.clone(),
+			config.clone(),
+			rate_limiter.clone(),
+		))
+		.or(ws_route(
+			ws_clients,
+			version,
+			config.clone(),
+			submitter,
+			submit_cache,
+			cell_fetcher,
+			metrics.clone(),
+			rate_limiter.clone(),
+			state,
+			db,
+		));
+
+	let routes = with_rate_limit(rate_limiter, routes).recover(handle_rejection);
+
+	let routes = with_request_id(with_error_metrics(metrics, routes));
+	let routes = with_field_naming(config.api_field_naming, routes);
+	with_request_logging(with_compression(with_content_negotiation(routes)))
 }
 
 #[cfg(test)]
 mod tests {
-	use super::{transactions, types::Transaction};
+	use super::{
+		cells,
+		metrics::ApiMetrics,
+		rate_limit::RateLimiter,
+		transactions,
+		types::{self, Base64, SubmitCache, Transaction},
+	};
 	use crate::{
 		api::v2::types::{
-			DataField, ErrorCode, SubmitResponse, Subscription, SubscriptionId, Topic, Version,
-			WsClients, WsError, WsResponse,
+			DataField, Error, ErrorCode, HeaderMessage, PublishMessage, ReplayWindow, Status,
+			SubmitBatchResponse, SubmitResponse, SubmitResult, Subscription, SubscriptionId, Topic,
+			Version, WsClients, WsError, WsResponse,
 		},
 		data::Key,
 		data::{mem_db, Database},
-		types::{BlockRange, OptionBlockRange, RuntimeConfig, State},
+		network::rpc::{BlockEvent, DataProof, Event as RpcEvent},
+		proof,
+		types::{
+			ApiKey, BlockRange, BlockVerified, FieldNaming, OptionBlockRange, RuntimeConfig, State,
+			WsBufferPolicy,
+		},
+		utils::calculate_confidence,
 	};
 	use async_trait::async_trait;
 	use avail_subxt::utils::H256;
@@ -247,22 +794,40 @@ mod tests {
 			kate_commitment::v3::KateCommitment,
 		},
 		primitives::Header as DaHeader,
+		AvailConfig,
 	};
+	use codec::{Decode, Encode};
+	use flate2::read::GzDecoder;
 	use hyper::StatusCode;
-	use kate_recovery::matrix::Partition;
+	use kate_recovery::{
+		data::Cell,
+		matrix::{Dimensions, Partition, Position},
+	};
+	use sp_core::keccak_256;
 	use std::{
 		collections::HashSet,
+		io::Read,
 		str::FromStr,
-		sync::{Arc, Mutex},
+		sync::{
+			atomic::{AtomicUsize, Ordering},
+			Arc, Mutex,
+		},
+		time::Duration,
 	};
 	use subxt::config::substrate::Digest;
 	use test_case::test_case;
+	use tokio::{
+		io::{AsyncReadExt, AsyncWriteExt},
+		net::TcpStream,
+	};
 	use uuid::Uuid;
+	use warp::{Filter, Rejection, Reply};
 
 	fn v1() -> Version {
 		Version {
 			version: "v1.0.0".to_string(),
 			network_version: "nv1.0.0".to_string(),
+			network: Default::default(),
 		}
 	}
 
@@ -270,64 +835,95 @@ mod tests {
 
 	#[tokio::test]
 	async fn version_route() {
-		let route = super::version_route(v1());
+		let state = Arc::new(Mutex::new(State::default()));
+		let route = super::version_route(v1(), state);
 		let response = warp::test::request()
 			.method("GET")
 			.path("/v2/version")
 			.reply(&route)
 			.await;
 
-		assert_eq!(
-			response.body(),
-			r#"{"version":"v1.0.0","network_version":"nv1.0.0"}"#
-		);
+		let expected =
+			format!(r#"{{"version":"v1.0.0","network_version":"nv1.0.0","network":"{NETWORK}"}}"#);
+		assert_eq!(response.body(), &expected);
 	}
 
 	#[tokio::test]
-	async fn status_route_defaults() {
+	async fn schema_route() {
+		let response = warp::test::request()
+			.method("GET")
+			.path("/v2/schema")
+			.reply(&super::schema_route())
+			.await;
+		assert_eq!(response.status(), StatusCode::OK);
+
+		let schema: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+		let status = &schema["definitions"]["Status"];
+		assert_eq!(status["type"], "object");
+		for field in ["modes", "genesis_hash", "network", "blocks", "timestamp"] {
+			assert!(status["properties"].get(field).is_some(), "{field}");
+		}
+		assert!(schema["definitions"].get("HeaderMessage").is_some());
+		assert!(schema["definitions"].get("Error").is_some());
+	}
+
+	#[tokio::test]
+	async fn request_id_round_trip() {
 		let state = Arc::new(Mutex::new(State::default()));
-		let route = super::status_route(RuntimeConfig::default(), state);
+		let route = super::with_request_id(super::version_route(v1(), state));
+		let request_id = "4b2c1c8e-7f4a-4d7e-9a2b-3c1d5e6f7a8b";
 		let response = warp::test::request()
 			.method("GET")
-			.path("/v2/status")
+			.path("/v2/version")
+			.header("x-request-id", request_id)
 			.reply(&route)
 			.await;
 
-		let gen_hash = H256::default();
-		let expected = format!(
-			r#"{{"modes":["light"],"genesis_hash":"{:x?}","network":"{NETWORK}","blocks":{{"latest":0}}}}"#,
-			gen_hash
-		);
-		assert_eq!(response.body(), &expected);
+		assert_eq!(response.status(), StatusCode::OK);
+		assert_eq!(response.headers()["x-request-id"], request_id);
 	}
 
+	#[test_case(None ; "Request ID is missing")]
+	#[test_case(Some("invalid") ; "Request ID is invalid")]
 	#[tokio::test]
-	async fn status_route() {
-		let runtime_config = RuntimeConfig {
-			app_id: Some(1),
-			sync_start_block: Some(10),
-			block_matrix_partition: Some(Partition {
-				number: 1,
-				fraction: 10,
-			}),
+	async fn request_id_generated(request_id: Option<&str>) {
+		let state = Arc::new(Mutex::new(State {
+			latest: 10,
 			..Default::default()
-		};
-		let state = Arc::new(Mutex::new(State::default()));
-		{
-			let mut state = state.lock().unwrap();
-			state.latest = 30;
-			state.confidence_achieved.set(20);
-			state.confidence_achieved.set(29);
-			state.data_verified.set(20);
-			state.data_verified.set(29);
-			state.synced.replace(false);
-			state.sync_confidence_achieved.set(10);
-			state.sync_confidence_achieved.set(19);
-			state.sync_data_verified.set(10);
-			state.sync_data_verified.set(18);
+		}));
+		let db = mem_db::MemoryDB::default();
+		let route = super::with_request_id(super::block_headers_route(
+			RuntimeConfig::default(),
+			state,
+			db,
+		));
+		let mut request = warp::test::request()
+			.method("GET")
+			.path("/v2/blocks/headers?first=5&last=4");
+		if let Some(request_id) = request_id {
+			request = request.header("x-request-id", request_id);
 		}
+		let response = request.reply(&route).await;
 
-		let route = super::status_route(runtime_config, state);
+		assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+		let header = response.headers()["x-request-id"].to_str().unwrap();
+		let generated = Uuid::parse_str(header).unwrap();
+		let error: Error = serde_json::from_slice(response.body()).unwrap();
+		assert_eq!(error.request_id, Some(generated));
+		assert_eq!(error.error_code, ErrorCode::BadRequest);
+	}
+
+	fn without_timestamp(body: &[u8]) -> String {
+		let body = std::str::from_utf8(body).unwrap();
+		let (head, tail) = body.split_once(r#","timestamp":"#).unwrap();
+		let tail = tail.trim_start_matches(|c: char| c.is_ascii_digit());
+		format!("{head}{tail}")
+	}
+
+	#[tokio::test]
+	async fn status_route_defaults() {
+		let state = Arc::new(Mutex::new(State::default()));
+		let route = super::status_route(RuntimeConfig::default(), state);
 		let response = warp::test::request()
 			.method("GET")
 			.path("/v2/status")
@@ -336,17 +932,267 @@ mod tests {
 
 		let gen_hash = H256::default();
 		let expected = format!(
-			r#"{{"modes":["light","app","partition"],"app_id":1,"genesis_hash":"{:#x}","network":"{NETWORK}","blocks":{{"latest":30,"available":{{"first":20,"last":29}},"app_data":{{"first":20,"last":29}},"historical_sync":{{"synced":false,"available":{{"first":10,"last":19}},"app_data":{{"first":10,"last":18}}}}}},"partition":"1/10"}}"#,
+			r#"{{"modes":["light"],"genesis_hash":"{:x?}","network":"{NETWORK}","blocks":{{"latest":0}}}}"#,
 			gen_hash
 		);
-		assert_eq!(response.body(), &expected);
+		assert_eq!(without_timestamp(response.body()), expected);
 	}
 
-	#[test_case(1, 2)]
-	#[test_case(10, 11)]
-	#[test_case(10, 20)]
 	#[tokio::test]
-	async fn block_route_not_found(latest: u32, block_number: u32) {
+	async fn status_route_cbor() {
+		let state = Arc::new(Mutex::new(State::default()));
+		let route =
+			super::with_content_negotiation(super::status_route(RuntimeConfig::default(), state));
+		let response = warp::test::request()
+			.method("GET")
+			.path("/v2/status")
+			.header("accept", "application/cbor")
+			.reply(&route)
+			.await;
+		assert_eq!(response.headers()["content-type"], "application/cbor");
+		assert_eq!(response.headers()["vary"], "accept");
+		let status: Status = ciborium::from_reader(&response.body()[..]).unwrap();
+		let mut cbor = serde_json::to_value(status).unwrap();
+
+		let response = warp::test::request()
+			.method("GET")
+			.path("/v2/status")
+			.reply(&route)
+			.await;
+		assert_eq!(response.headers()["content-type"], "application/json");
+		assert_eq!(response.headers()["vary"], "accept");
+		let mut json: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+
+		cbor.as_object_mut().unwrap().remove("timestamp");
+		json.as_object_mut().unwrap().remove("timestamp");
+		assert_eq!(cbor, json);
+	}
+
+	#[test_case("application/cbor", "application/cbor" ; "CBOR accepted")]
+	#[test_case("application/cbor;q=0", "application/json" ; "CBOR rejected")]
+	#[test_case("application/json, application/cbor;q=0.5", "application/json" ; "JSON preferred")]
+	#[test_case("application/json;q=0.5, application/cbor", "application/cbor" ; "CBOR preferred")]
+	#[test_case("application/json, application/cbor", "application/cbor" ; "CBOR preferred on equal quality")]
+	#[test_case("application/cbor-seq", "application/json" ; "Other content type")]
+	#[tokio::test]
+	async fn content_negotiation(accept: &str, expected_content_type: &str) {
+		let route = super::with_content_negotiation(warp::any().map(|| types::json_reply("test")));
+		let response = warp::test::request()
+			.method("GET")
+			.path("/")
+			.header("accept", accept)
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::OK);
+		assert_eq!(response.headers()["content-type"], expected_content_type);
+		assert_eq!(response.headers()["vary"], "accept");
+	}
+
+	#[test_case(FieldNaming::SnakeCase, "block_number" ; "Snake case")]
+	#[test_case(FieldNaming::CamelCase, "blockNumber" ; "Camel case")]
+	#[tokio::test]
+	async fn content_negotiation_cbor_bytes(naming: FieldNaming, block_number_field: &str) {
+		#[derive(Clone, serde::Serialize)]
+		struct Data {
+			block_number: u32,
+			data: Base64,
+		}
+
+		let data = Data {
+			block_number: 1,
+			data: Base64(vec![1, 2, 3]),
+		};
+		let route = super::with_content_negotiation(super::with_field_naming(
+			naming,
+			warp::any().map(move || types::json_reply(data.clone())),
+		));
+		let response = warp::test::request()
+			.method("GET")
+			.path("/")
+			.header("accept", "application/cbor")
+			.reply(&route)
+			.await;
+		assert_eq!(response.headers()["content-type"], "application/cbor");
+
+		let value: ciborium::Value = ciborium::from_reader(&response.body()[..]).unwrap();
+		let fields = value.as_map().unwrap();
+		let field = |name: &str| {
+			fields
+				.iter()
+				.find(|(field, _)| field.as_text() == Some(name))
+				.map(|(_, value)| value.clone())
+		};
+		// Bytes are encoded as CBOR byte string, instead of base64 string
+		assert_eq!(field("data"), Some(ciborium::Value::Bytes(vec![1, 2, 3])));
+		assert_eq!(field(block_number_field), Some(ciborium::Value::from(1)));
+	}
+
+	#[test_case(types::COMPRESSION_THRESHOLD, Some("gzip"), false ; "Response at threshold")]
+	#[test_case(types::COMPRESSION_THRESHOLD + 1, None, false ; "Gzip not accepted")]
+	#[test_case(types::COMPRESSION_THRESHOLD + 1, Some("gzip;q=0"), false ; "Gzip rejected")]
+	#[test_case(types::COMPRESSION_THRESHOLD + 1, Some("gzip"), true ; "Response above threshold")]
+	#[test_case(types::COMPRESSION_THRESHOLD + 1, Some("deflate, GZIP;q=0.5"), true ; "Gzip accepted with quality")]
+	#[tokio::test]
+	async fn compression(size: usize, accept_encoding: Option<&str>, expected: bool) {
+		// JSON string is enclosed in quotes
+		let value = "a".repeat(size - 2);
+		let expected_body = serde_json::to_vec(&value).unwrap();
+		let route = super::with_compression(warp::any().map(move || warp::reply::json(&value)));
+
+		let mut request = warp::test::request().method("GET").path("/");
+		if let Some(accept_encoding) = accept_encoding {
+			request = request.header("accept-encoding", accept_encoding);
+		}
+		let response = request.reply(&route).await;
+
+		assert_eq!(response.status(), StatusCode::OK);
+		assert_eq!(
+			response.headers().get("content-encoding").is_some(),
+			expected
+		);
+		let body = if expected {
+			assert_eq!(response.headers()["content-encoding"], "gzip");
+			let mut body = vec![];
+			GzDecoder::new(&response.body()[..])
+				.read_to_end(&mut body)
+				.unwrap();
+			body
+		} else {
+			response.body().to_vec()
+		};
+		assert_eq!(body, expected_body);
+	}
+
+	#[tokio::test]
+	async fn status_route_compression() {
+		let state = Arc::new(Mutex::new(State::default()));
+		let route = super::with_compression(super::status_route(RuntimeConfig::default(), state));
+		let response = warp::test::request()
+			.method("GET")
+			.path("/v2/status")
+			.header("accept-encoding", "gzip")
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::OK);
+		assert!(response.headers().get("content-encoding").is_none());
+		assert_eq!(response.headers()["vary"], "accept-encoding");
+		let _: Status = serde_json::from_slice(response.body()).unwrap();
+	}
+
+	#[tokio::test]
+	async fn status_route() {
+		let runtime_config = RuntimeConfig {
+			app_id: Some(1),
+			sync_start_block: Some(10),
+			block_matrix_partition: vec![Partition {
+				number: 1,
+				fraction: 10,
+			}],
+			..Default::default()
+		};
+		let state = Arc::new(Mutex::new(State::default()));
+		{
+			let mut state = state.lock().unwrap();
+			state.latest = 30;
+			state.confidence_achieved.set(20);
+			state.confidence_achieved.set(29);
+			state.data_verified.set(20);
+			state.data_verified.set(29);
+			state.synced.replace(false);
+			state.sync_confidence_achieved.set(10);
+			state.sync_confidence_achieved.set(19);
+			state.sync_data_verified.set(10);
+			state.sync_data_verified.set(18);
+		}
+
+		let route = super::status_route(runtime_config, state);
+		let response = warp::test::request()
+			.method("GET")
+			.path("/v2/status")
+			.reply(&route)
+			.await;
+
+		let gen_hash = H256::default();
+		let expected = format!(
+			r#"{{"modes":["light","app","partition"],"app_id":1,"genesis_hash":"{:#x}","network":"{NETWORK}","blocks":{{"latest":30,"available":{{"first":20,"last":29}},"app_data":{{"first":20,"last":29}},"app_ranges":{{"1":{{"first":20,"last":29}}}},"historical_sync":{{"synced":false,"available":{{"first":10,"last":19}},"app_data":{{"first":10,"last":18}}}}}},"partition":"1/10"}}"#,
+			gen_hash
+		);
+		assert_eq!(without_timestamp(response.body()), expected);
+	}
+
+	#[test_case(FieldNaming::SnakeCase, &["app_id", "genesis_hash", "latest_block_at"], "app_data" ; "Snake case")]
+	#[test_case(FieldNaming::CamelCase, &["appId", "genesisHash", "latestBlockAt"], "appData" ; "Camel case")]
+	#[tokio::test]
+	async fn status_route_field_naming(
+		naming: FieldNaming,
+		expected_fields: &[&str],
+		expected_blocks_field: &str,
+	) {
+		let runtime_config = RuntimeConfig {
+			app_id: Some(1),
+			..Default::default()
+		};
+		let state = Arc::new(Mutex::new(State::default()));
+		{
+			let mut state = state.lock().unwrap();
+			state.set_latest(30);
+			state.data_verified.set(29);
+		}
+		let route = super::with_field_naming(naming, super::status_route(runtime_config, state));
+		let response = warp::test::request()
+			.method("GET")
+			.path("/v2/status")
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::OK);
+
+		let status: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+		for field in expected_fields {
+			assert!(status.get(field).is_some(), "Missing field {field}");
+		}
+		assert!(status["blocks"].get(expected_blocks_field).is_some());
+	}
+
+	#[tokio::test]
+	async fn status_route_not_modified() {
+		let state = Arc::new(Mutex::new(State::default()));
+		let route = super::status_route(RuntimeConfig::default(), state.clone());
+		let response = warp::test::request()
+			.method("GET")
+			.path("/v2/status")
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::OK);
+		let etag = response.headers()["etag"].to_str().unwrap().to_string();
+
+		let response = warp::test::request()
+			.method("GET")
+			.path("/v2/status")
+			.header("if-none-match", &etag)
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+		assert_eq!(response.headers()["etag"], etag.as_str());
+		assert!(response.body().is_empty());
+
+		state.lock().unwrap().latest = 10;
+
+		let response = warp::test::request()
+			.method("GET")
+			.path("/v2/status")
+			.header("if-none-match", &etag)
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::OK);
+		assert_ne!(response.headers()["etag"], etag.as_str());
+		assert!(!response.body().is_empty());
+	}
+
+	#[test_case(1, 2)]
+	#[test_case(10, 11)]
+	#[test_case(10, 20)]
+	#[tokio::test]
+	async fn block_route_not_found(latest: u32, block_number: u32) {
 		let config = RuntimeConfig::default();
 		let state = Arc::new(Mutex::new(State::default()));
 		{
@@ -414,7 +1260,7 @@ mod tests {
 			.reply(&route)
 			.await;
 		assert_eq!(response.status(), StatusCode::BAD_REQUEST);
-		assert_eq!(response.body(), expected);
+		assert_eq!(error_message(response.body()), expected);
 	}
 
 	#[tokio::test]
@@ -451,8 +1297,150 @@ mod tests {
 		}
 	}
 
+	fn block_verified(block_number: u32) -> BlockVerified {
+		let mut header = header();
+		header.number = block_number;
+		header.extension = HeaderExtension::V3(v3::HeaderExtension {
+			commitment: KateCommitment {
+				rows: 1,
+				cols: 4,
+				..Default::default()
+			},
+			app_lookup: CompactDataLookup {
+				size: 0,
+				index: vec![],
+			},
+		});
+		BlockVerified::try_from((header, Some(93.75), RuntimeConfig::default().max_block_rows))
+			.unwrap()
+	}
+
+	fn availability_state() -> Arc<Mutex<State>> {
+		let mut state = State::default();
+		state.latest = 10;
+		state.header_verified.set(10);
+		Arc::new(Mutex::new(state))
+	}
+
+	#[tokio::test]
+	async fn header_stream_route() {
+		use hyper::body::HttpBody;
+		use warp::Reply;
+
+		let (sender, _) = tokio::sync::broadcast::channel(16);
+		let route = super::header_stream_route(sender.clone());
+		let response = warp::test::request()
+			.method("GET")
+			.path("/v2/blocks/headers/stream")
+			.filter(&route)
+			.await
+			.unwrap()
+			.into_response();
+		assert_eq!(response.headers()["content-type"], "application/x-ndjson");
+
+		for number in [1, 2] {
+			let header = DaHeader { number, ..header() };
+			let received_at = std::time::Instant::now();
+			sender
+				.send(RpcEvent::HeaderUpdate {
+					header,
+					received_at,
+				})
+				.unwrap();
+		}
+
+		let mut body = response.into_body();
+		let mut lines = String::new();
+		while lines.lines().count() < 2 {
+			let chunk = body.data().await.unwrap().unwrap();
+			lines.push_str(std::str::from_utf8(&chunk).unwrap());
+		}
+
+		let block_numbers = lines
+			.lines()
+			.map(|line| serde_json::from_str::<HeaderMessage>(line).unwrap())
+			.map(|message| serde_json::to_value(message).unwrap()["block_number"].clone())
+			.collect::<Vec<_>>();
+		assert_eq!(block_numbers, [1, 2]);
+	}
+
+	#[tokio::test]
+	async fn block_availability_route_available() {
+		let state = availability_state();
+		state.lock().unwrap().confidence_achieved.set(10);
+		let db = mem_db::MemoryDB::default();
+		_ = db.put(Key::VerifiedCellCount(10), 4);
+		let (sender, _) = tokio::sync::broadcast::channel(1);
+		let route = super::block_availability_route(RuntimeConfig::default(), state, db, sender);
+		let response = warp::test::request()
+			.method("GET")
+			.path("/v2/blocks/10/availability")
+			.reply(&route)
+			.await;
+
+		assert_eq!(response.status(), StatusCode::OK);
+		assert_eq!(
+			response.body(),
+			r#"{"status":"verifying-data","confidence":93.75}"#
+		);
+	}
+
+	#[tokio::test]
+	async fn block_availability_route_notified() {
+		let state = availability_state();
+		let db = mem_db::MemoryDB::default();
+		let (sender, _) = tokio::sync::broadcast::channel(1);
+		let route = super::block_availability_route(
+			RuntimeConfig::default(),
+			state.clone(),
+			db.clone(),
+			sender.clone(),
+		);
+
+		tokio::spawn(async move {
+			tokio::time::sleep(Duration::from_millis(100)).await;
+			state.lock().unwrap().confidence_achieved.set(10);
+			_ = db.put(Key::VerifiedCellCount(10), 4);
+			_ = sender.send(block_verified(10));
+		});
+
+		let response = warp::test::request()
+			.method("GET")
+			.path("/v2/blocks/10/availability")
+			.reply(&route)
+			.await;
+
+		assert_eq!(response.status(), StatusCode::OK);
+		assert_eq!(
+			response.body(),
+			r#"{"status":"verifying-data","confidence":93.75}"#
+		);
+	}
+
+	#[tokio::test]
+	async fn block_availability_route_timeout() {
+		let config = RuntimeConfig {
+			block_availability_timeout: 1,
+			..Default::default()
+		};
+		let db = mem_db::MemoryDB::default();
+		let (sender, _) = tokio::sync::broadcast::channel(1);
+		let route = super::block_availability_route(config, availability_state(), db, sender);
+		let response = warp::test::request()
+			.method("GET")
+			.path("/v2/blocks/10/availability")
+			.reply(&route)
+			.await;
+
+		assert_eq!(response.status(), StatusCode::NO_CONTENT);
+		assert!(response.body().is_empty());
+	}
+
+	#[test_case("/v2/blocks/1/header" ; "Default commitment encoding")]
+	#[test_case("/v2/blocks/1/header?commitments=hex" ; "Hex commitment encoding")]
+	#[test_case("/v2/blocks/1/header?commitments=base64" ; "Base64 commitment encoding")]
 	#[tokio::test]
-	async fn block_header_route_ok() {
+	async fn block_header_route_ok(path: &str) {
 		let config = RuntimeConfig::default();
 		let state = Arc::new(Mutex::new(State {
 			latest: 1,
@@ -464,12 +1452,83 @@ mod tests {
 		let route = super::block_header_route(config, state, db);
 		let response = warp::test::request()
 			.method("GET")
-			.path("/v2/blocks/1/header")
+			.path(path)
 			.reply(&route)
 			.await;
 		assert_eq!(
 			response.body(),
-			r#"{"hash":"0xb4ab92948e78b5e3115d2ce5ff2207e7d713a7fb33f4a9240e413c00954f244b","parent_hash":"0x0000000000000000000000000000000000000000000000000000000000000000","number":1,"state_root":"0x0000000000000000000000000000000000000000000000000000000000000000","extrinsics_root":"0x0000000000000000000000000000000000000000000000000000000000000000","extension":{"rows":0,"cols":0,"data_root":"0x0000000000000000000000000000000000000000000000000000000000000000","commitments":[],"app_lookup":{"size":0,"index":[]}}}"#
+			r#"{"hash":"0xb4ab92948e78b5e3115d2ce5ff2207e7d713a7fb33f4a9240e413c00954f244b","parent_hash":"0x0000000000000000000000000000000000000000000000000000000000000000","number":1,"state_root":"0x0000000000000000000000000000000000000000000000000000000000000000","extrinsics_root":"0x0000000000000000000000000000000000000000000000000000000000000000","extension":{"extension_version":3,"rows":0,"cols":0,"data_root":"0x0000000000000000000000000000000000000000000000000000000000000000","commitments":[],"app_lookup":{"size":0,"index":[]},"app_cells":[]}}"#
+		);
+	}
+
+	fn headers_setup() -> (Arc<Mutex<State>>, mem_db::MemoryDB) {
+		let state = Arc::new(Mutex::new(State {
+			latest: 6,
+			header_verified: Some(BlockRange { first: 3, last: 6 }),
+			..Default::default()
+		}));
+		let db = mem_db::MemoryDB::default();
+		for number in 3..=6 {
+			_ = db.put(Key::BlockHeader(number), DaHeader { number, ..header() });
+		}
+		(state, db)
+	}
+
+	async fn get_headers(path: &str) -> (Vec<u64>, serde_json::Value) {
+		let (state, db) = headers_setup();
+		let route = super::block_headers_route(RuntimeConfig::default(), state, db);
+		let response = warp::test::request()
+			.method("GET")
+			.path(path)
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::OK);
+		let response: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+		let block_numbers = response["headers"]
+			.as_array()
+			.unwrap()
+			.iter()
+			.map(|header| header["block_number"].as_u64().unwrap())
+			.collect();
+		(block_numbers, response["next"].clone())
+	}
+
+	#[tokio::test]
+	async fn block_headers_route_clamped() {
+		let (block_numbers, next) = get_headers("/v2/blocks/headers?first=1&last=10").await;
+		assert_eq!(block_numbers, vec![3, 4, 5, 6]);
+		assert!(next.is_null());
+
+		let (block_numbers, next) = get_headers("/v2/blocks/headers?first=7&last=10").await;
+		assert!(block_numbers.is_empty());
+		assert!(next.is_null());
+	}
+
+	#[tokio::test]
+	async fn block_headers_route_cursor() {
+		let (block_numbers, next) = get_headers("/v2/blocks/headers?first=1&last=10&limit=3").await;
+		assert_eq!(block_numbers, vec![3, 4, 5]);
+		assert_eq!(next, 6);
+
+		let path = format!("/v2/blocks/headers?first={next}&last=10&limit=3");
+		let (block_numbers, next) = get_headers(&path).await;
+		assert_eq!(block_numbers, vec![6]);
+		assert!(next.is_null());
+	}
+
+	#[tokio::test]
+	async fn block_headers_route_bad_request() {
+		let (state, db) = headers_setup();
+		let route = super::block_headers_route(RuntimeConfig::default(), state, db);
+		let response = warp::test::request()
+			.method("GET")
+			.path("/v2/blocks/headers?first=5&last=4")
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+		assert_eq!(
+			error_message(response.body()),
+			"First block number is greater than last block number"
 		);
 	}
 
@@ -501,7 +1560,7 @@ mod tests {
 			.reply(&route)
 			.await;
 		assert_eq!(response.status(), StatusCode::BAD_REQUEST);
-		assert_eq!(response.body(), expected);
+		assert_eq!(error_message(response.body()), expected);
 	}
 
 	#[tokio::test]
@@ -562,18 +1621,7 @@ mod tests {
 			..Default::default()
 		}));
 		let db = mem_db::MemoryDB::default();
-		_ = db.put(
-			Key::AppData(1, 5),
-			vec![vec![
-				189, 1, 132, 0, 212, 53, 147, 199, 21, 253, 211, 28, 97, 20, 26, 189, 4, 169, 159,
-				214, 130, 44, 133, 88, 133, 76, 205, 227, 154, 86, 132, 231, 165, 109, 162, 125, 1,
-				50, 12, 43, 176, 19, 42, 23, 73, 70, 223, 198, 180, 103, 34, 60, 246, 184, 49, 140,
-				113, 174, 234, 229, 95, 71, 18, 92, 158, 185, 168, 140, 126, 12, 191, 156, 50, 234,
-				8, 4, 68, 137, 5, 156, 94, 209, 7, 169, 105, 62, 63, 1, 122, 253, 195, 112, 173,
-				239, 21, 73, 163, 240, 106, 109, 131, 0, 4, 0, 4, 29, 1, 20, 116, 101, 115, 116,
-				10,
-			]],
-		);
+		_ = db.put(Key::AppData(1, 5), vec![extrinsic()]);
 		let route = super::block_data_route(config, state, db);
 		let response = warp::test::request()
 			.method("GET")
@@ -592,246 +1640,3563 @@ mod tests {
 			Topic::HeaderVerified,
 			Topic::ConfidenceAchieved,
 			Topic::DataVerified,
+			Topic::SubmittedDataIncluded,
 		]
 		.into_iter()
 		.collect()
 	}
 
+	fn extrinsic() -> Vec<u8> {
+		vec![
+			189, 1, 132, 0, 212, 53, 147, 199, 21, 253, 211, 28, 97, 20, 26, 189, 4, 169, 159, 214,
+			130, 44, 133, 88, 133, 76, 205, 227, 154, 86, 132, 231, 165, 109, 162, 125, 1, 50, 12,
+			43, 176, 19, 42, 23, 73, 70, 223, 198, 180, 103, 34, 60, 246, 184, 49, 140, 113, 174,
+			234, 229, 95, 71, 18, 92, 158, 185, 168, 140, 126, 12, 191, 156, 50, 234, 8, 4, 68,
+			137, 5, 156, 94, 209, 7, 169, 105, 62, 63, 1, 122, 253, 195, 112, 173, 239, 21, 73,
+			163, 240, 106, 109, 131, 0, 4, 0, 4, 29, 1, 20, 116, 101, 115, 116, 10,
+		]
+	}
+
 	fn all_data_fields() -> HashSet<DataField> {
 		vec![DataField::Extrinsic, DataField::Data]
 			.into_iter()
 			.collect()
 	}
 
-	#[derive(Clone)]
-	struct MockSubmitter {}
+	fn error_message(body: &[u8]) -> String {
+		let error: Error = serde_json::from_slice(body).unwrap();
+		error.message
+	}
 
-	#[async_trait]
-	impl transactions::Submit for MockSubmitter {
-		async fn submit(&self, _: Transaction) -> color_eyre::Result<SubmitResponse> {
-			Ok(SubmitResponse {
-				block_number: 0,
-				block_hash: H256::random(),
-				hash: H256::random(),
-				index: 0,
-			})
+	/// Configuration with app ID, required to submit data transactions without app ID.
+	fn app_config() -> RuntimeConfig {
+		RuntimeConfig {
+			app_id: Some(1),
+			..Default::default()
 		}
 	}
 
-	#[test_case(r#"{"raw":""}"#, b"Request body deserialize error: unknown variant `raw`" ; "Invalid json schema")]
-	#[test_case(r#"{"data":"dHJhbnooNhY3Rpb24:"}"#, b"Request body deserialize error: Invalid byte" ; "Invalid base64 value")]
-	#[tokio::test]
-	async fn submit_route_bad_request(json: &str, message: &[u8]) {
-		let route = super::submit_route(Some(Arc::new(MockSubmitter {})));
-		let response = warp::test::request()
-			.method("POST")
+	fn submit_cache() -> SubmitCache {
+		SubmitCache::new(Duration::from_secs(60))
+	}
+
+	fn rate_limiter() -> RateLimiter {
+		RateLimiter::new(0.0, 1)
+	}
+
+	struct MockCellFetcher {}
+
+	#[async_trait]
+	impl cells::Fetch for MockCellFetcher {
+		async fn fetch(&self, _: H256, positions: &[Position]) -> color_eyre::Result<Vec<Cell>> {
+			// Proof bytes are zeroes, data bytes are ones
+			let mut content = [0u8; 80];
+			content[48..].fill(1);
+			let cells = positions
+				.iter()
+				.map(|&position| Cell { position, content })
+				.collect();
+			Ok(cells)
+		}
+
+		async fn fetch_data_proof(&self, _: H256, index: u32) -> color_eyre::Result<DataProof> {
+			if index >= 2 {
+				return Err(color_eyre::eyre::eyre!("Data transaction not found"));
+			}
+			Ok(mock_data_proof(index))
+		}
+
+		async fn fetch_extrinsics(&self, _: H256) -> color_eyre::Result<Vec<Vec<u8>>> {
+			Ok(mock_extrinsics())
+		}
+
+		async fn fetch_verified(
+			&self,
+			_: u32,
+			_: H256,
+			_: Dimensions,
+			_: &[[u8; 48]],
+			positions: &[Position],
+		) -> color_eyre::Result<Vec<Cell>> {
+			cells::Fetch::fetch(self, H256::zero(), positions).await
+		}
+	}
+
+	fn mock_extrinsics() -> Vec<Vec<u8>> {
+		vec![b"0".to_vec(), b"1".to_vec(), b"2".to_vec()]
+	}
+
+	/// Proof of the data transaction in the block with two data transactions
+	fn mock_data_proof(index: u32) -> DataProof {
+		let leaves: [H256; 2] = [keccak_256(b"0").into(), keccak_256(b"1").into()];
+		let root = keccak_256(&[leaves[0].as_bytes(), leaves[1].as_bytes()].concat()).into();
+		DataProof {
+			root,
+			proof: vec![leaves[1 - index as usize]],
+			number_of_leaves: 2,
+			leaf_index: index,
+			leaf: leaves[index as usize],
+		}
+	}
+
+	#[derive(Clone)]
+	struct MockSubmitter {}
+
+	#[async_trait]
+	impl transactions::Submit for MockSubmitter {
+		async fn submit(&self, transaction: Transaction) -> color_eyre::Result<SubmitResponse> {
+			let (bytes, app_id) = match transaction {
+				Transaction::Data {
+					data: Base64(bytes),
+					app_id,
+				} => (bytes, app_id),
+				Transaction::Extrinsic(Base64(bytes)) => (bytes, None),
+			};
+			Ok(SubmitResponse {
+				block_number: 0,
+				block_hash: H256::random(),
+				hash: sp_core::blake2_256(&bytes).into(),
+				index: 0,
+				app_id,
+				data_position: None,
+				dry_run: false,
+			})
+		}
+	}
+
+	#[test_case(r#"{"raw":""}"#, "Request body deserialize error: unknown variant `raw`" ; "Invalid json schema")]
+	#[test_case(r#"{"data":"dHJhbnooNhY3Rpb24:"}"#, "Request body deserialize error: Invalid base64 value: Invalid byte" ; "Invalid base64 value")]
+	#[test_case(r#"{"data":"dGVz:A=="}"#, "Request body deserialize error: Invalid base64 value: Invalid byte 58, offset 4." ; "Invalid base64 character")]
+	#[test_case(r#"{"data":"dGVzdA="}"#, "Request body deserialize error: Invalid base64 value: " ; "Invalid base64 padding")]
+	#[tokio::test]
+	async fn submit_route_bad_request(json: &str, message: &str) {
+		let route = super::submit_route(
+			Some(Arc::new(MockSubmitter {})),
+			submit_cache(),
+			RuntimeConfig::default(),
+			rate_limiter(),
+		)
+		.recover(super::handle_rejection);
+		let response = warp::test::request()
+			.method("POST")
 			.path("/v2/submit")
 			.body(json)
 			.reply(&route)
 			.await;
 		assert_eq!(response.status(), StatusCode::BAD_REQUEST);
-		assert!(response.body().starts_with(message));
+		assert!(error_message(response.body()).starts_with(message));
 	}
 
-	#[test_case(r#"{"data":"dHJhbnNhY3Rpb24K"}"# ; "No errors in case of submitted data")]
-	#[test_case(r#"{"extrinsic":"dHJhbnNhY3Rpb24K"}"# ; "No errors in case of submitted extrinsic")]
+	#[test_case(r#"{"data":"dHJhbnNhY3Rpb24K","expected_app_id":1}"#, None ; "Matching app ID")]
+	#[test_case(r#"{"data":"dHJhbnNhY3Rpb24K","expected_app_id":2}"#, Some("App ID mismatch, expected 2, transaction app ID is 1") ; "Mismatching app ID")]
+	#[test_case(r#"{"data":"dHJhbnNhY3Rpb24K"}"#, None ; "No expected app ID")]
+	#[test_case(r#"{"data":"dHJhbnNhY3Rpb24K","app_id":5,"expected_app_id":5}"#, None ; "Matching overridden app ID")]
+	#[test_case(r#"{"data":"dHJhbnNhY3Rpb24K","app_id":5,"expected_app_id":1}"#, Some("App ID mismatch, expected 1, transaction app ID is 5") ; "Mismatching overridden app ID")]
+	#[test_case(r#"{"data":"dHJhbnNhY3Rpb24K","app_id":6}"#, Some("Submitting data of app ID 6 is not allowed") ; "Not allowed app ID")]
 	#[tokio::test]
-	async fn submit_route_extrinsic(body: &str) {
-		let route = super::submit_route(Some(Arc::new(MockSubmitter {})));
+	async fn submit_route_expected_app_id(body: &str, expected_error: Option<&str>) {
+		let config = RuntimeConfig {
+			app_id: Some(1),
+			submit_app_ids: vec![5],
+			..Default::default()
+		};
+		let route = super::submit_route(
+			Some(Arc::new(MockSubmitter {})),
+			submit_cache(),
+			config,
+			rate_limiter(),
+		);
 		let response = warp::test::request()
 			.method("POST")
 			.path("/v2/submit")
 			.body(body)
 			.reply(&route)
 			.await;
-		assert_eq!(response.status(), StatusCode::OK);
-		let response: SubmitResponse = serde_json::from_slice(response.body()).unwrap();
-		let _ = serde_json::to_string(&response).unwrap();
+
+		match expected_error {
+			None => assert_eq!(response.status(), StatusCode::OK),
+			Some(message) => {
+				assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+				assert_eq!(error_message(response.body()), message);
+			},
+		}
 	}
 
+	#[test_case(r#"{"data":"dHJhbnNhY3Rpb24K","app_id":2}"#, Some(1), Ok(2) ; "App ID override")]
+	#[test_case(r#"{"data":"dHJhbnNhY3Rpb24K","app_id":2}"#, None, Ok(2) ; "App ID override without configured app ID")]
+	#[test_case(r#"{"data":"dHJhbnNhY3Rpb24K"}"#, Some(1), Ok(1) ; "Configured app ID fallback")]
+	#[test_case(r#"{"data":"dHJhbnNhY3Rpb24K"}"#, None, Err("App ID is not configured, data transaction requires app_id") ; "Missing app ID")]
+	#[test_case(r#"{"extrinsic":"dHJhbnNhY3Rpb24K","app_id":2}"#, Some(1), Err("Request body deserialize error: App ID can be set only for data transactions") ; "Extrinsic with app ID")]
 	#[tokio::test]
-	async fn subscriptions_route() {
-		let clients = WsClients::default();
-		let route = super::subscriptions_route(clients.clone());
-
-		let body = r#"{"topics":["confidence-achieved","data-verified","header-verified"],"data_fields":["data","extrinsic"]}"#;
+	async fn submit_route_app_id(body: &str, app_id: Option<u32>, expected: Result<u32, &str>) {
+		let config = RuntimeConfig {
+			app_id,
+			submit_app_ids: vec![2],
+			..Default::default()
+		};
+		let route = super::submit_route(
+			Some(Arc::new(MockSubmitter {})),
+			submit_cache(),
+			config,
+			rate_limiter(),
+		)
+		.recover(super::handle_rejection);
 		let response = warp::test::request()
 			.method("POST")
+			.path("/v2/submit")
 			.body(body)
-			.path("/v2/subscriptions")
 			.reply(&route)
 			.await;
 
-		let SubscriptionId { subscription_id } = serde_json::from_slice(response.body()).unwrap();
-		assert!(uuid::Uuid::from_str(&subscription_id).is_ok());
+		match expected {
+			Ok(app_id) => {
+				assert_eq!(response.status(), StatusCode::OK);
+				let response: SubmitResponse = serde_json::from_slice(response.body()).unwrap();
+				assert_eq!(response.app_id, Some(app_id));
+			},
+			Err(message) => {
+				assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+				assert!(error_message(response.body()).starts_with(message));
+			},
+		}
+	}
 
-		let clients = clients.0.read().await;
-		let client = clients.get(&subscription_id).unwrap();
+	/// Fails to submit, so only dry run requests succeed.
+	#[derive(Clone)]
+	struct DryRunSubmitter {}
 
-		let expected = Subscription {
-			topics: all_topics(),
-			data_fields: all_data_fields(),
-		};
-		assert!(client.subscription == expected);
+	#[async_trait]
+	impl transactions::Submit for DryRunSubmitter {
+		async fn submit(&self, _: Transaction) -> color_eyre::Result<SubmitResponse> {
+			Err(color_eyre::eyre::eyre!("Transaction is broadcast"))
+		}
 	}
 
-	struct MockSetup {
-		ws_client: warp::test::WsClient,
-		state: Arc<Mutex<State>>,
+	async fn submit_response(
+		submitter: impl transactions::Submit + Clone + Send + Sync + 'static,
+		body: &str,
+	) -> SubmitResponse {
+		let route = super::submit_route(
+			Some(Arc::new(submitter)),
+			submit_cache(),
+			app_config(),
+			rate_limiter(),
+		);
+		let response = warp::test::request()
+			.method("POST")
+			.path("/v2/submit")
+			.body(body)
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::OK);
+		serde_json::from_slice(response.body()).unwrap()
 	}
 
-	impl MockSetup {
-		async fn new(config: RuntimeConfig, submitter: Option<MockSubmitter>) -> Self {
-			let client_uuid = uuid::Uuid::new_v4().to_string();
-			let clients = WsClients::default();
-			clients
-				.subscribe(&client_uuid, Subscription::default())
-				.await;
+	#[tokio::test]
+	async fn submit_route_dry_run() {
+		let submitted =
+			submit_response(MockSubmitter {}, r#"{"extrinsic":"dHJhbnNhY3Rpb24K"}"#).await;
+		assert!(!submitted.dry_run);
 
-			let state = Arc::new(Mutex::new(State::default()));
-			let route = super::ws_route(
-				clients.clone(),
-				v1(),
-				config.clone(),
-				submitter.map(Arc::new),
-				state.clone(),
-			);
-			let ws_client = warp::test::ws()
-				.path(&format!("/v2/ws/{client_uuid}"))
-				.handshake(route)
-				.await
-				.expect("handshake");
+		// Dry run submitter fails on submit, so the response is returned without broadcast
+		let body = r#"{"extrinsic":"dHJhbnNhY3Rpb24K","dry_run":true}"#;
+		let dry_run = submit_response(DryRunSubmitter {}, body).await;
+		assert!(dry_run.dry_run);
+		assert_eq!(dry_run.hash, submitted.hash);
+		assert_eq!(dry_run.block_hash, H256::zero());
+		assert_eq!(dry_run.block_number, 0);
+		assert_eq!(dry_run.index, 0);
+		assert_eq!(dry_run.app_id, None);
+	}
 
-			MockSetup { ws_client, state }
-		}
+	#[tokio::test]
+	async fn submit_route_dry_run_data() {
+		let route = super::submit_route(
+			Some(Arc::new(DryRunSubmitter {})),
+			submit_cache(),
+			app_config(),
+			rate_limiter(),
+		);
+		let response = warp::test::request()
+			.method("POST")
+			.path("/v2/submit")
+			.body(r#"{"data":"dHJhbnNhY3Rpb24K","dry_run":true}"#)
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+		assert_eq!(
+			error_message(response.body()),
+			"Dry run is supported only for extrinsic transactions"
+		);
+	}
 
-		async fn ws_send_text(&mut self, message: &str) -> String {
-			self.ws_client.send_text(message).await;
-			let message = self.ws_client.recv().await.unwrap();
-			message.to_str().unwrap().to_string()
-		}
+	#[test]
+	fn dry_run_signed_extrinsic_hash() {
+		let transaction = Transaction::Extrinsic(Base64(extrinsic()));
+		let response = transactions::dry_run(&transaction).unwrap();
+
+		// Submitter hashes encoded extrinsic with the hasher of the chain config,
+		// which is the hash reported when the extrinsic is included in a block
+		let expected =
+			<<AvailConfig as subxt::Config>::Hasher as subxt::config::Hasher>::hash(&extrinsic());
+		assert_eq!(response.hash.as_bytes(), expected.as_ref());
 	}
 
+	// Encoded data is 16 bytes long, while decoded data is 12 bytes long
+	#[test_case(12, None ; "Data at the limit")]
+	#[test_case(11, Some("Transaction size 12 exceeds the limit of 11 bytes") ; "Data over the limit")]
 	#[tokio::test]
-	async fn ws_route_version() {
-		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
-		let request = r#"{"type":"version","request_id":"cae63fff-c4b8-4af9-b4fe-0605a5329aa0"}"#;
-		let response = test.ws_send_text(request).await;
-		assert_eq!(
-			r#"{"topic":"version","request_id":"cae63fff-c4b8-4af9-b4fe-0605a5329aa0","message":{"version":"v1.0.0","network_version":"nv1.0.0"}}"#,
-			response
+	async fn submit_route_max_size(max_submit_size: usize, expected_error: Option<&str>) {
+		let config = RuntimeConfig {
+			max_submit_size,
+			..app_config()
+		};
+		let route = super::submit_route(
+			Some(Arc::new(MockSubmitter {})),
+			submit_cache(),
+			config,
+			rate_limiter(),
 		);
+		let response = warp::test::request()
+			.method("POST")
+			.path("/v2/submit")
+			.body(r#"{"data":"dHJhbnNhY3Rpb24K"}"#)
+			.reply(&route)
+			.await;
+
+		match expected_error {
+			None => assert_eq!(response.status(), StatusCode::OK),
+			Some(message) => {
+				assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+				assert_eq!(error_message(response.body()), message);
+			},
+		}
 	}
 
+	#[test_case(None, StatusCode::UNAUTHORIZED ; "No API key")]
+	#[test_case(Some("Bearer invalid"), StatusCode::UNAUTHORIZED ; "Invalid API key")]
+	#[test_case(Some("valid"), StatusCode::UNAUTHORIZED ; "Missing bearer scheme")]
+	#[test_case(Some("Bearer valid"), StatusCode::OK ; "Valid API key")]
 	#[tokio::test]
-	async fn ws_route_status() {
+	async fn submit_route_authorization(authorization: Option<&str>, status: StatusCode) {
 		let config = RuntimeConfig {
 			app_id: Some(1),
-			sync_start_block: Some(10),
-			block_matrix_partition: Some(Partition {
-				number: 1,
-				fraction: 10,
-			}),
+			api_keys: vec!["valid".into()],
 			..Default::default()
 		};
-
-		let mut test = MockSetup::new(config, None).await;
-
-		{
-			let mut state = test.state.lock().unwrap();
-			state.latest = 30;
-			state.confidence_achieved.set(20);
-			state.confidence_achieved.set(29);
-			state.data_verified.set(20);
-			state.data_verified.set(29);
-			state.synced.replace(false);
-			state.sync_confidence_achieved.set(10);
-			state.sync_confidence_achieved.set(19);
-			state.sync_data_verified.set(10);
-			state.sync_data_verified.set(18);
+		let route = super::submit_route(
+			Some(Arc::new(MockSubmitter {})),
+			submit_cache(),
+			config,
+			rate_limiter(),
+		)
+		.recover(super::handle_rejection);
+		let mut request = warp::test::request()
+			.method("POST")
+			.path("/v2/submit")
+			.body(r#"{"data":"dHJhbnNhY3Rpb24K"}"#);
+		if let Some(authorization) = authorization {
+			request = request.header("authorization", authorization);
+		}
+		let response = request.reply(&route).await;
+		assert_eq!(response.status(), status);
+		if status == StatusCode::UNAUTHORIZED {
+			assert_eq!(response.headers()["www-authenticate"], "Bearer");
+			let error: Error = serde_json::from_slice(response.body()).unwrap();
+			assert_eq!(error.error_code, ErrorCode::Unauthorized);
 		}
+	}
 
-		let gen_hash = H256::default();
-		let expected = format!(
-			r#"{{"topic":"status","request_id":"363c71fc-90f7-4276-a5b6-bec688bf01e2","message":{{"modes":["light","app","partition"],"app_id":1,"genesis_hash":"{:x?}","network":"{NETWORK}","blocks":{{"latest":30,"available":{{"first":20,"last":29}},"app_data":{{"first":20,"last":29}},"historical_sync":{{"synced":false,"available":{{"first":10,"last":19}},"app_data":{{"first":10,"last":18}}}}}},"partition":"1/10"}}}}"#,
-			gen_hash
+	fn assert_mixed_batch(results: Vec<SubmitResult>) {
+		assert_eq!(results.len(), 3);
+		assert!(matches!(&results[0], SubmitResult::Submitted(_)));
+		let SubmitResult::Error(error) = &results[1] else {
+			panic!("Empty transaction is submitted");
+		};
+		assert_eq!(error.error_code, ErrorCode::BadRequest);
+		assert_eq!(error.message, "Transaction is empty.");
+		assert!(matches!(&results[2], SubmitResult::Submitted(_)));
+	}
+
+	#[tokio::test]
+	async fn submit_batch_route() {
+		let route = super::submit_batch_route(
+			Some(Arc::new(MockSubmitter {})),
+			app_config(),
+			rate_limiter(),
 		);
+		let response = warp::test::request()
+			.method("POST")
+			.path("/v2/submit/batch")
+			.body(r#"[{"data":"dHJhbnNhY3Rpb24K"},{"data":""},{"extrinsic":"dHJhbnNhY3Rpb24K"}]"#)
+			.reply(&route)
+			.await;
 
-		let status_request =
-			r#"{"type":"status","request_id":"363c71fc-90f7-4276-a5b6-bec688bf01e2"}"#;
-		assert_eq!(expected, test.ws_send_text(status_request).await);
+		assert_eq!(response.status(), StatusCode::OK);
+		let SubmitBatchResponse(results) = serde_json::from_slice(response.body()).unwrap();
+		assert_mixed_batch(results);
 	}
 
-	#[test_case("",  "Failed to parse request" ; "Empty request")]
-	#[test_case("abcd",  "Failed to parse request" ; "Invalid json")]
-	#[test_case("{}",  "Failed to parse request" ; "Empty json")]
-	#[test_case(r#"{"type":"unknown","request_id":"11043443-7e4c-4485-a21c-304b457b6cc7","message":""}"#,  "Failed to parse request: Cannot parse json" ; "Wrong request type")]
 	#[tokio::test]
-	async fn ws_route_bad_request(request: &str, expected: &str) {
-		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
-		let response = test.ws_send_text(request).await;
-		assert!(response.contains(expected));
-	}
+	async fn submit_batch_route_empty() {
+		let route = super::submit_batch_route(
+			Some(Arc::new(MockSubmitter {})),
+			RuntimeConfig::default(),
+			rate_limiter(),
+		);
+		let response = warp::test::request()
+			.method("POST")
+			.path("/v2/submit/batch")
+			.body("[]")
+			.reply(&route)
+			.await;
 
-	fn to_uuid(uuid: &str) -> Uuid {
-		Uuid::try_parse(uuid).unwrap()
+		assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+		assert_eq!(error_message(response.body()), "Batch is empty.");
 	}
 
-	#[test_case(r#"{"type":"submit","request_id":"16b24956-2e01-4ba8-bad5-456c561c87d7","message":{"data":""}}"#, false, Some("16b24956-2e01-4ba8-bad5-456c561c87d7"), "Submit is not configured" ; "No submitter")]
-	#[test_case(r#"{"type":"submit","request_id":"36bc1f28-e093-422f-964b-1cb1b3882baf","message":{"extrinsic":""}}"#, true, Some("36bc1f28-e093-422f-964b-1cb1b3882baf"), "Transaction is empty" ; "Empty extrinsic")]
-	#[test_case(r#"{"type":"submit","request_id":"cc60b2f3-d9ff-4c73-9632-d21d07f7b620","message":{"data":""}}"#, true, Some("cc60b2f3-d9ff-4c73-9632-d21d07f7b620"), "Transaction is empty" ; "Empty data")]
-	#[test_case(r#"{"type":"submit","request_id":"9181df86-22f0-42a1-a965-60adb9fc6bdc","message":{"extrinsic":"bad"}}"#, true, None, "Failed to parse request" ; "Bad extrinsic")]
-	#[test_case(r#"{"type":"submit","request_id":"78cd7b7b-ba70-48e9-a1da-96b370db4d8f","message":{"data":"bad"}}"#, true, None, "Failed to parse request" ; "Bad data")]
 	#[tokio::test]
-	async fn ws_route_submit_bad_requests(
-		request: &str,
-		submitter: bool,
-		expected_request_id: Option<&str>,
-		expected: &str,
-	) {
-		let submitter = submitter.then_some(MockSubmitter {});
-		let expected_request_id = expected_request_id.map(to_uuid);
-		let mut test = MockSetup::new(RuntimeConfig::default(), submitter).await;
-		let response = test.ws_send_text(request).await;
-		let WsError::Error(error) = serde_json::from_str(&response).unwrap();
-		assert_eq!(error.error_code, ErrorCode::BadRequest);
-		assert_eq!(error.request_id, expected_request_id);
-		assert!(error.message.contains(expected));
+	async fn submit_batch_route_too_large() {
+		let config = RuntimeConfig {
+			max_batch_size: 2,
+			..app_config()
+		};
+		let route =
+			super::submit_batch_route(Some(Arc::new(MockSubmitter {})), config, rate_limiter());
+		let response = warp::test::request()
+			.method("POST")
+			.path("/v2/submit/batch")
+			.body(
+				r#"[{"data":"dHJhbnNhY3Rpb24K"},{"data":"dHJhbnNhY3Rpb24K"},{"data":"dHJhbnNhY3Rpb24K"}]"#,
+			)
+			.reply(&route)
+			.await;
+
+		assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+		assert_eq!(
+			error_message(response.body()),
+			"Batch has 3 transactions, exceeding the limit of 2"
+		);
 	}
 
+	#[test_case(r#"{"data":""}"# ; "Empty data")]
+	#[test_case(r#"{"extrinsic":""}"# ; "Empty extrinsic")]
 	#[tokio::test]
-	async fn ws_route_submit_data() {
-		let submitter = Some(MockSubmitter {});
-		let mut test = MockSetup::new(RuntimeConfig::default(), submitter).await;
-
-		let request = r#"{"type":"submit","request_id":"fca2ff0c-7a26-42a2-a6f0-d0aeeaba8a9a","message":{"data":"dHJhbnNhY3Rpb24K"}}"#;
-		let response = test.ws_send_text(request).await;
+	async fn submit_route_empty_transaction(json: &str) {
+		let route = super::submit_route(
+			Some(Arc::new(MockSubmitter {})),
+			submit_cache(),
+			RuntimeConfig::default(),
+			rate_limiter(),
+		);
+		let response = warp::test::request()
+			.method("POST")
+			.path("/v2/submit")
+			.body(json)
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+		assert_eq!(error_message(response.body()), "Transaction is empty.");
+	}
 
-		let WsResponse::DataTransactionSubmitted(response) =
-			serde_json::from_str(&response).unwrap()
-		else {
-			panic!("Invalid response");
-		};
-		let expected_request_id = to_uuid("fca2ff0c-7a26-42a2-a6f0-d0aeeaba8a9a");
-		assert_eq!(response.request_id, expected_request_id);
-		assert_eq!(response.message.index, 0);
+	#[test_case(r#"{"data":"dHJhbnNhY3Rpb24K"}"# ; "No errors in case of submitted data")]
+	#[test_case(r#"{"extrinsic":"dHJhbnNhY3Rpb24K"}"# ; "No errors in case of submitted extrinsic")]
+	#[tokio::test]
+	async fn submit_route_extrinsic(body: &str) {
+		let route = super::submit_route(
+			Some(Arc::new(MockSubmitter {})),
+			submit_cache(),
+			app_config(),
+			rate_limiter(),
+		);
+		let response = warp::test::request()
+			.method("POST")
+			.path("/v2/submit")
+			.body(body)
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::OK);
+		let response: SubmitResponse = serde_json::from_slice(response.body()).unwrap();
+		let _ = serde_json::to_string(&response).unwrap();
 	}
 
+	#[test_case(extrinsic(), 1, Ok(()) ; "Valid extrinsic")]
+	#[test_case(extrinsic()[..100].to_vec(), 1, Err("Extrinsic is malformed") ; "Truncated extrinsic")]
+	#[test_case(extrinsic(), 2, Err("Extrinsic app ID 1 does not match the configured app ID 2") ; "App ID mismatch")]
 	#[tokio::test]
-	async fn ws_route_submit_extrinsic() {
-		let submitter = Some(MockSubmitter {});
+	async fn submit_route_validate_extrinsic(
+		extrinsic: Vec<u8>,
+		app_id: u32,
+		expected: Result<(), &str>,
+	) {
+		let config = RuntimeConfig {
+			app_id: Some(app_id),
+			validate_extrinsics: true,
+			..Default::default()
+		};
+		let route = super::submit_route(
+			Some(Arc::new(MockSubmitter {})),
+			submit_cache(),
+			config,
+			rate_limiter(),
+		);
+		let body = serde_json::json!({ "extrinsic": Base64(extrinsic) });
+		let response = warp::test::request()
+			.method("POST")
+			.path("/v2/submit")
+			.body(body.to_string())
+			.reply(&route)
+			.await;
+		match expected {
+			Ok(()) => assert_eq!(response.status(), StatusCode::OK),
+			Err(message) => {
+				assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+				assert!(error_message(response.body()).starts_with(message));
+			},
+		}
+	}
+
+	#[tokio::test]
+	async fn submit_raw_route() {
+		use warp::Filter;
+
+		let submitter = Some(Arc::new(MockSubmitter {}));
+		let route = super::submit_raw_route(
+			submitter.clone(),
+			submit_cache(),
+			app_config(),
+			rate_limiter(),
+		)
+		.or(super::submit_route(
+			submitter,
+			submit_cache(),
+			app_config(),
+			rate_limiter(),
+		));
+
+		let raw_response = warp::test::request()
+			.method("POST")
+			.path("/v2/submit")
+			.header("content-type", "application/octet-stream")
+			.body(b"transaction\n".to_vec())
+			.reply(&route)
+			.await;
+		assert_eq!(raw_response.status(), StatusCode::OK);
+
+		let json_response = warp::test::request()
+			.method("POST")
+			.path("/v2/submit")
+			.body(r#"{"data":"dHJhbnNhY3Rpb24K"}"#)
+			.reply(&route)
+			.await;
+		assert_eq!(json_response.status(), StatusCode::OK);
+
+		let raw: SubmitResponse = serde_json::from_slice(raw_response.body()).unwrap();
+		let json: SubmitResponse = serde_json::from_slice(json_response.body()).unwrap();
+		assert_eq!(raw.hash, json.hash);
+	}
+
+	#[tokio::test]
+	async fn subscriptions_route() {
+		let clients = WsClients::default();
+		let route = super::subscriptions_route(
+			clients.clone(),
+			RuntimeConfig::default(),
+			Arc::new(Mutex::new(State::default())),
+		);
+
+		let body = r#"{"topics":["confidence-achieved","data-verified","header-verified"],"data_fields":["data","extrinsic"]}"#;
+		let response = warp::test::request()
+			.method("POST")
+			.body(body)
+			.path("/v2/subscriptions")
+			.reply(&route)
+			.await;
+
+		let SubscriptionId {
+			subscription_id,
+			subscription,
+		} = serde_json::from_slice(response.body()).unwrap();
+		assert!(uuid::Uuid::from_str(&subscription_id).is_ok());
+
+		let clients = clients.clients.read().await;
+		let client = clients.get(&subscription_id).unwrap();
+
+		let expected = Subscription {
+			topics: all_topics(),
+			data_fields: all_data_fields(),
+			..Default::default()
+		};
+		assert!(client.subscription == expected);
+		assert!(subscription == expected);
+	}
+
+	#[tokio::test]
+	async fn subscriptions_route_echo() {
+		let clients = WsClients::default();
+		let route = super::subscriptions_route(
+			clients.clone(),
+			RuntimeConfig::default(),
+			Arc::new(Mutex::new(State::default())),
+		);
+
+		let body = r#"{"topics":["header-verified"],"data_fields":[],"app_id":1}"#;
+		let response = warp::test::request()
+			.method("POST")
+			.body(body)
+			.path("/v2/subscriptions")
+			.reply(&route)
+			.await;
+
+		let response: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+		assert_eq!(
+			response["subscription"],
+			serde_json::json!({ "topics": ["header-verified"], "data_fields": [], "app_id": 1 })
+		);
+	}
+
+	#[test_case(r#","protocol_version":1"#, None ; "Supported version")]
+	#[test_case(r#","protocol_version":2"#, Some("Unsupported protocol version 2, supported versions are: 1") ; "Unsupported version")]
+	#[tokio::test]
+	async fn subscriptions_route_protocol_version(protocol_version: &str, expected: Option<&str>) {
+		let clients = WsClients::default();
+		let route = super::subscriptions_route(
+			clients.clone(),
+			RuntimeConfig::default(),
+			Arc::new(Mutex::new(State::default())),
+		);
+
+		let body =
+			format!(r#"{{"topics":["header-verified"],"data_fields":[]{protocol_version}}}"#);
+		let response = warp::test::request()
+			.method("POST")
+			.body(body)
+			.path("/v2/subscriptions")
+			.reply(&route)
+			.await;
+
+		match expected {
+			None => assert_eq!(response.status(), StatusCode::OK),
+			Some(expected) => {
+				assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+				assert_eq!(error_message(response.body()), expected);
+				assert!(clients.clients.read().await.is_empty());
+			},
+		}
+	}
+
+	struct MockSetup {
+		ws_client: warp::test::WsClient,
+		state: Arc<Mutex<State>>,
+		clients: WsClients,
+		client_uuid: String,
+		db: mem_db::MemoryDB,
+		metrics: ApiMetrics,
+	}
+
+	/// Web socket route setup, with configuration, submitter, cell fetcher and storage overrides.
+	struct MockSetupBuilder<S, F> {
+		config: RuntimeConfig,
+		submitter: Option<S>,
+		cell_fetcher: F,
+		subscription: Subscription,
+		clients: WsClients,
+		state: Arc<Mutex<State>>,
+		db: mem_db::MemoryDB,
+		metrics: ApiMetrics,
+	}
+
+	impl<S, F> MockSetupBuilder<S, F>
+	where
+		S: transactions::Submit + Clone + Send + Sync + 'static,
+		F: cells::Fetch + Send + Sync + 'static,
+	{
+		fn config(self, config: RuntimeConfig) -> Self {
+			Self { config, ..self }
+		}
+
+		fn submitter<T>(self, submitter: T) -> MockSetupBuilder<T, F> {
+			MockSetupBuilder {
+				config: self.config,
+				submitter: Some(submitter),
+				cell_fetcher: self.cell_fetcher,
+				subscription: self.subscription,
+				clients: self.clients,
+				state: self.state,
+				db: self.db,
+				metrics: self.metrics,
+			}
+		}
+
+		fn cell_fetcher<T>(self, cell_fetcher: T) -> MockSetupBuilder<S, T> {
+			MockSetupBuilder {
+				config: self.config,
+				submitter: self.submitter,
+				cell_fetcher,
+				subscription: self.subscription,
+				clients: self.clients,
+				state: self.state,
+				db: self.db,
+				metrics: self.metrics,
+			}
+		}
+
+		fn subscription(self, subscription: Subscription) -> Self {
+			Self {
+				subscription,
+				..self
+			}
+		}
+
+		fn clients(self, clients: WsClients) -> Self {
+			Self { clients, ..self }
+		}
+
+		fn state(self, state: Arc<Mutex<State>>) -> Self {
+			Self { state, ..self }
+		}
+
+		fn db(self, db: mem_db::MemoryDB) -> Self {
+			Self { db, ..self }
+		}
+
+		/// Web socket route, for the clients subscribed by the test.
+		fn route(self) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+			super::ws_route(
+				self.clients,
+				v1(),
+				self.config.clone(),
+				self.submitter.map(Arc::new),
+				submit_cache(),
+				Arc::new(self.cell_fetcher),
+				self.metrics,
+				RateLimiter::from(&self.config),
+				self.state,
+				self.db,
+			)
+		}
+
+		/// Subscribes new client, and connects it to the web socket route.
+		async fn build(self) -> MockSetup {
+			let client_uuid = uuid::Uuid::new_v4().to_string();
+			self.clients
+				.subscribe(&client_uuid, self.subscription.clone())
+				.await;
+			self.connect(&client_uuid).await
+		}
+
+		/// Connects client with the existing subscription to the web socket route.
+		async fn connect(self, subscription_id: &str) -> MockSetup {
+			let (state, clients, db, metrics) = (
+				self.state.clone(),
+				self.clients.clone(),
+				self.db.clone(),
+				self.metrics.clone(),
+			);
+			let ws_client = warp::test::ws()
+				.path(&format!("/v2/ws/{subscription_id}"))
+				.handshake(self.route())
+				.await
+				.expect("handshake");
+
+			MockSetup {
+				ws_client,
+				state,
+				clients,
+				client_uuid: subscription_id.to_string(),
+				db,
+				metrics,
+			}
+		}
+	}
+
+	impl MockSetup {
+		fn builder() -> MockSetupBuilder<MockSubmitter, MockCellFetcher> {
+			MockSetupBuilder {
+				config: RuntimeConfig::default(),
+				submitter: None,
+				cell_fetcher: MockCellFetcher {},
+				subscription: Subscription::default(),
+				clients: WsClients::default(),
+				state: Arc::new(Mutex::new(State::default())),
+				db: mem_db::MemoryDB::default(),
+				metrics: ApiMetrics::default(),
+			}
+		}
+
+		async fn new(config: RuntimeConfig, submitter: Option<MockSubmitter>) -> Self {
+			Self::new_with_subscription(config, submitter, Subscription::default()).await
+		}
+
+		async fn new_with_subscription(
+			config: RuntimeConfig,
+			submitter: Option<MockSubmitter>,
+			subscription: Subscription,
+		) -> Self {
+			let builder = MockSetup::builder()
+				.config(config)
+				.subscription(subscription);
+			match submitter {
+				Some(submitter) => builder.submitter(submitter).build().await,
+				None => builder.build().await,
+			}
+		}
+
+		async fn ws_send_text(&mut self, message: &str) -> String {
+			self.ws_client.send_text(message).await;
+			let message = self.ws_client.recv().await.unwrap();
+			message.to_str().unwrap().to_string()
+		}
+	}
+
+	#[tokio::test]
+	async fn ws_route_version() {
+		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
+		let request = r#"{"type":"version","request_id":"cae63fff-c4b8-4af9-b4fe-0605a5329aa0"}"#;
+		let response = test.ws_send_text(request).await;
+		assert_eq!(
+			format!(
+				r#"{{"topic":"version","request_id":"cae63fff-c4b8-4af9-b4fe-0605a5329aa0","message":{{"version":"v1.0.0","network_version":"nv1.0.0","network":"{NETWORK}"}}}}"#
+			),
+			response
+		);
+	}
+
+	#[tokio::test]
+	async fn ws_route_version_field_naming() {
+		let config = RuntimeConfig {
+			api_field_naming: FieldNaming::CamelCase,
+			..Default::default()
+		};
+		let mut test = MockSetup::new(config, None).await;
+		let request = r#"{"type":"version","request_id":"cae63fff-c4b8-4af9-b4fe-0605a5329aa0"}"#;
+		let response = test.ws_send_text(request).await;
+		let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+		assert_eq!(response["topic"], "version");
+		assert_eq!(
+			response["requestId"],
+			"cae63fff-c4b8-4af9-b4fe-0605a5329aa0"
+		);
+		assert_eq!(response["message"]["networkVersion"], "nv1.0.0");
+	}
+
+	#[tokio::test]
+	async fn ws_route_protocol_version() {
+		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
+
+		let request = r#"{"type":"version","request_id":"cae63fff-c4b8-4af9-b4fe-0605a5329aa0","protocol_version":1}"#;
+		let response = test.ws_send_text(request).await;
+		let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+		assert_eq!(response["topic"], "version");
+
+		// Unsupported version is reported even if request type is not known
+		let request = r#"{"type":"future-request","request_id":"cae63fff-c4b8-4af9-b4fe-0605a5329aa0","protocol_version":2}"#;
+		let response = test.ws_send_text(request).await;
+		let WsError::Error(error) = serde_json::from_str(&response).unwrap();
+		assert_eq!(error.error_code, ErrorCode::BadRequest);
+		assert_eq!(
+			error.request_id,
+			Some(to_uuid("cae63fff-c4b8-4af9-b4fe-0605a5329aa0"))
+		);
+		assert_eq!(
+			error.message,
+			"Unsupported protocol version 2, supported versions are: 1"
+		);
+	}
+
+	#[tokio::test]
+	async fn ws_route_ping() {
+		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
+		let request = r#"{"type":"ping","request_id":"a1b2c3d4-e5f6-4a7b-8c9d-0e1f2a3b4c5d","message":{"nonce":18446744073709551615}}"#;
+		let response = test.ws_send_text(request).await;
+
+		let WsResponse::Pong(response) = serde_json::from_str(&response).unwrap() else {
+			panic!("Invalid response");
+		};
+		assert_eq!(
+			response.request_id,
+			to_uuid("a1b2c3d4-e5f6-4a7b-8c9d-0e1f2a3b4c5d")
+		);
+		assert_eq!(response.message.nonce, u64::MAX);
+		assert!(response.message.timestamp > 0);
+	}
+
+	#[tokio::test]
+	async fn ws_route_status() {
+		let config = RuntimeConfig {
+			app_id: Some(1),
+			sync_start_block: Some(10),
+			block_matrix_partition: vec![Partition {
+				number: 1,
+				fraction: 10,
+			}],
+			..Default::default()
+		};
+
+		let mut test = MockSetup::new(config, None).await;
+
+		{
+			let mut state = test.state.lock().unwrap();
+			state.latest = 30;
+			state.confidence_achieved.set(20);
+			state.confidence_achieved.set(29);
+			state.data_verified.set(20);
+			state.data_verified.set(29);
+			state.synced.replace(false);
+			state.sync_confidence_achieved.set(10);
+			state.sync_confidence_achieved.set(19);
+			state.sync_data_verified.set(10);
+			state.sync_data_verified.set(18);
+		}
+
+		let gen_hash = H256::default();
+		let expected = format!(
+			r#"{{"topic":"status","request_id":"363c71fc-90f7-4276-a5b6-bec688bf01e2","message":{{"modes":["light","app","partition"],"app_id":1,"genesis_hash":"{:x?}","network":"{NETWORK}","blocks":{{"latest":30,"available":{{"first":20,"last":29}},"app_data":{{"first":20,"last":29}},"app_ranges":{{"1":{{"first":20,"last":29}}}},"historical_sync":{{"synced":false,"available":{{"first":10,"last":19}},"app_data":{{"first":10,"last":18}}}}}},"partition":"1/10"}}}}"#,
+			gen_hash
+		);
+
+		let status_request =
+			r#"{"type":"status","request_id":"363c71fc-90f7-4276-a5b6-bec688bf01e2"}"#;
+		let response = test.ws_send_text(status_request).await;
+		assert_eq!(expected, without_timestamp(response.as_bytes()));
+	}
+
+	#[tokio::test]
+	async fn ws_route_get_block_ranges() {
+		let config = RuntimeConfig {
+			app_id: Some(1),
+			sync_start_block: Some(10),
+			..Default::default()
+		};
+
+		let mut test = MockSetup::new(config, None).await;
+
+		{
+			let mut state = test.state.lock().unwrap();
+			state.latest = 30;
+			state.confidence_achieved.set(20);
+			state.confidence_achieved.set(29);
+			state.data_verified.set(20);
+			state.data_verified.set(28);
+			state.synced.replace(false);
+			state.sync_confidence_achieved.set(10);
+			state.sync_confidence_achieved.set(19);
+			state.sync_data_verified.set(10);
+			state.sync_data_verified.set(18);
+		}
+
+		let request = r#"{"type":"status","request_id":"363c71fc-90f7-4276-a5b6-bec688bf01e2"}"#;
+		let status: serde_json::Value =
+			serde_json::from_str(&test.ws_send_text(request).await).unwrap();
+
+		let request =
+			r#"{"type":"get-block-ranges","request_id":"5b1e7d2a-8c3f-4e6b-9a0d-2f4c6e8a1b3d"}"#;
+		let response: serde_json::Value =
+			serde_json::from_str(&test.ws_send_text(request).await).unwrap();
+
+		assert_eq!(response["topic"], "block-ranges");
+		let blocks = &status["message"]["blocks"];
+		let expected = serde_json::json!({
+			"available": blocks["available"],
+			"app_data": blocks["app_data"],
+			"historical_sync": blocks["historical_sync"],
+		});
+		assert_eq!(response["message"], expected);
+	}
+
+	#[test_case("",  "Failed to parse request" ; "Empty request")]
+	#[test_case("abcd",  "Failed to parse request" ; "Invalid json")]
+	#[test_case("{}",  "Failed to parse request" ; "Empty json")]
+	#[test_case(r#"{"type":"unknown","request_id":"11043443-7e4c-4485-a21c-304b457b6cc7","message":""}"#,  "Unknown request type `unknown`" ; "Wrong request type")]
+	#[tokio::test]
+	async fn ws_route_bad_request(request: &str, expected: &str) {
+		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
+		let response = test.ws_send_text(request).await;
+		assert!(response.contains(expected));
+	}
+
+	#[test_case("abcd", None, "Failed to parse request: Cannot parse json" ; "Malformed json")]
+	#[test_case(r#"{"type":"status"}"#, None, "Failed to parse request: missing field `request_id`" ; "Missing request ID")]
+	#[test_case(r#"{"type":"unknown","request_id":"11043443-7e4c-4485-a21c-304b457b6cc7"}"#, Some("11043443-7e4c-4485-a21c-304b457b6cc7"), "Unknown request type `unknown`" ; "Unknown request type")]
+	#[test_case(r#"{"type":"subscribe","request_id":"11043443-7e4c-4485-a21c-304b457b6cc7","message":{"topics":["unknown"],"data_fields":[]}}"#, Some("11043443-7e4c-4485-a21c-304b457b6cc7"), "Failed to parse request: unknown variant `unknown`" ; "Invalid message")]
+	#[tokio::test]
+	async fn ws_route_parse_errors(
+		request: &str,
+		expected_request_id: Option<&str>,
+		expected: &str,
+	) {
+		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
+		let response = test.ws_send_text(request).await;
+
+		let WsError::Error(error) = serde_json::from_str(&response).unwrap();
+		assert_eq!(error.error_code, ErrorCode::BadRequest);
+		assert_eq!(error.request_id, expected_request_id.map(to_uuid));
+		assert!(error.message.starts_with(expected));
+	}
+
+	#[tokio::test]
+	async fn ws_route_batch() {
+		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
+		let request = r#"[
+			{"type":"version","request_id":"cae63fff-c4b8-4af9-b4fe-0605a5329aa0"},
+			{"type":"unknown","request_id":"11043443-7e4c-4485-a21c-304b457b6cc7"}
+		]"#;
+		let response = test.ws_send_text(request).await;
+
+		let responses: Vec<serde_json::Value> = serde_json::from_str(&response).unwrap();
+		assert_eq!(responses.len(), 2);
+		assert_eq!(responses[0]["topic"], "version");
+		assert_eq!(
+			responses[0]["request_id"],
+			"cae63fff-c4b8-4af9-b4fe-0605a5329aa0"
+		);
+		assert_eq!(responses[0]["message"]["version"], "v1.0.0");
+
+		let WsError::Error(error) = serde_json::from_value(responses[1].clone()).unwrap();
+		assert_eq!(error.error_code, ErrorCode::BadRequest);
+		assert_eq!(
+			error.request_id,
+			Some(to_uuid("11043443-7e4c-4485-a21c-304b457b6cc7"))
+		);
+		assert_eq!(error.message, "Unknown request type `unknown`");
+	}
+
+	#[test_case("[]", "Batch is empty." ; "Empty batch")]
+	#[test_case("[abcd", "Failed to parse request: Cannot parse json" ; "Malformed batch")]
+	#[tokio::test]
+	async fn ws_route_batch_bad_request(request: &str, expected: &str) {
+		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
+		let response = test.ws_send_text(request).await;
+
+		let WsError::Error(error) = serde_json::from_str(&response).unwrap();
+		assert_eq!(error.error_code, ErrorCode::BadRequest);
+		assert_eq!(error.request_id, None);
+		assert!(error.message.starts_with(expected));
+	}
+
+	fn to_uuid(uuid: &str) -> Uuid {
+		Uuid::try_parse(uuid).unwrap()
+	}
+
+	#[test_case(r#"{"type":"submit","request_id":"16b24956-2e01-4ba8-bad5-456c561c87d7","message":{"data":""}}"#, false, Some("16b24956-2e01-4ba8-bad5-456c561c87d7"), "Submit is not configured" ; "No submitter")]
+	#[test_case(r#"{"type":"submit","request_id":"36bc1f28-e093-422f-964b-1cb1b3882baf","message":{"extrinsic":""}}"#, true, Some("36bc1f28-e093-422f-964b-1cb1b3882baf"), "Transaction is empty" ; "Empty extrinsic")]
+	#[test_case(r#"{"type":"submit","request_id":"cc60b2f3-d9ff-4c73-9632-d21d07f7b620","message":{"data":""}}"#, true, Some("cc60b2f3-d9ff-4c73-9632-d21d07f7b620"), "Transaction is empty" ; "Empty data")]
+	#[test_case(r#"{"type":"submit","request_id":"9181df86-22f0-42a1-a965-60adb9fc6bdc","message":{"extrinsic":"bad"}}"#, true, Some("9181df86-22f0-42a1-a965-60adb9fc6bdc"), "Failed to parse request" ; "Bad extrinsic")]
+	#[test_case(r#"{"type":"submit","request_id":"78cd7b7b-ba70-48e9-a1da-96b370db4d8f","message":{"data":"bad"}}"#, true, Some("78cd7b7b-ba70-48e9-a1da-96b370db4d8f"), "Failed to parse request" ; "Bad data")]
+	#[test_case(r#"{"type":"submit","request_id":"4e7a1c9d-3b2f-4d8e-a6c1-0f5b9e2d7a3c","message":{"data":"dHJhbnNhY3Rpb24K","expected_app_id":1}}"#, true, Some("4e7a1c9d-3b2f-4d8e-a6c1-0f5b9e2d7a3c"), "App ID is not configured" ; "Missing app ID")]
+	#[test_case(r#"{"type":"submit","request_id":"5b8e2d4f-7c1a-4e9b-b3d6-2a0f8c4e6d1b","message":{"data":"dHJhbnNhY3Rpb24K","app_id":2,"expected_app_id":2}}"#, true, Some("5b8e2d4f-7c1a-4e9b-b3d6-2a0f8c4e6d1b"), "Submitting data of app ID 2 is not allowed" ; "Not allowed app ID")]
+	#[tokio::test]
+	async fn ws_route_submit_bad_requests(
+		request: &str,
+		submitter: bool,
+		expected_request_id: Option<&str>,
+		expected: &str,
+	) {
+		let submitter = submitter.then_some(MockSubmitter {});
+		let expected_request_id = expected_request_id.map(to_uuid);
+		let mut test = MockSetup::new(RuntimeConfig::default(), submitter).await;
+		let response = test.ws_send_text(request).await;
+		let WsError::Error(error) = serde_json::from_str(&response).unwrap();
+		assert_eq!(error.error_code, ErrorCode::BadRequest);
+		assert_eq!(error.request_id, expected_request_id);
+		assert!(error.message.contains(expected));
+	}
+
+	#[tokio::test]
+	async fn ws_route_submit_invalid_base64() {
+		let submitter = Some(MockSubmitter {});
+		let mut test = MockSetup::new(RuntimeConfig::default(), submitter).await;
+
+		let request = r#"{"type":"submit","request_id":"fca2ff0c-7a26-42a2-a6f0-d0aeeaba8a9a","message":{"data":"dGVz:A=="}}"#;
+		let response = test.ws_send_text(request).await;
+
+		let WsError::Error(error) = serde_json::from_str(&response).unwrap();
+		assert_eq!(error.error_code, ErrorCode::BadRequest);
+		assert!(error
+			.message
+			.contains("Invalid base64 value: Invalid byte 58, offset 4."));
+	}
+
+	#[tokio::test]
+	async fn ws_route_message_too_large() {
+		let config = RuntimeConfig {
+			ws_max_message_size: 128,
+			..Default::default()
+		};
+		let mut test = MockSetup::new(config, Some(MockSubmitter {})).await;
+
+		let data = "A".repeat(128);
+		let request = format!(
+			r#"{{"type":"submit","request_id":"0e8a4c2b-6d1f-4a3e-9b5c-7d8e9f0a1b2c","message":{{"data":"{data}"}}}}"#
+		);
+		let response = test.ws_send_text(&request).await;
+
+		let WsError::Error(error) = serde_json::from_str(&response).unwrap();
+		assert_eq!(error.error_code, ErrorCode::BadRequest);
+		assert_eq!(error.request_id, None);
+		assert!(error.message.contains("exceeds the limit of 128 bytes"));
+
+		let message = test.ws_client.recv().await.unwrap();
+		let (code, reason) = message.close_frame().unwrap();
+		assert_eq!(code, 1009);
+		assert!(reason.contains("exceeds the limit of 128 bytes"));
+	}
+
+	#[tokio::test]
+	async fn ws_route_malformed_requests() {
+		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
+
+		// Counter of consecutive malformed requests is reset by a valid request
+		for request in ["garbage", "[]", r#"{"type":"version"}"#] {
+			let response = test.ws_send_text(request).await;
+			let WsError::Error(error) = serde_json::from_str(&response).unwrap();
+			assert_eq!(error.error_code, ErrorCode::BadRequest);
+			assert_eq!(error.request_id, None);
+
+			let request =
+				r#"{"type":"version","request_id":"cae63fff-c4b8-4af9-b4fe-0605a5329aa0"}"#;
+			let response = test.ws_send_text(request).await;
+			assert!(response.starts_with(r#"{"topic":"version""#));
+		}
+
+		for _ in 0..3 {
+			let response = test.ws_send_text("garbage").await;
+			let WsError::Error(error) = serde_json::from_str(&response).unwrap();
+			assert_eq!(error.error_code, ErrorCode::BadRequest);
+		}
+
+		let message = test.ws_client.recv().await.unwrap();
+		let (code, reason) = message.close_frame().unwrap();
+		assert_eq!(code, 1008);
+		assert_eq!(reason, "3 consecutive requests cannot be parsed");
+	}
+
+	#[tokio::test]
+	async fn ws_route_failed_requests_are_not_malformed() {
+		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
+
+		// Requests for unknown block are parsed, so they don't close the connection
+		for _ in 0..4 {
+			let request = r#"{"type":"get-row","request_id":"9a4f2e7c-3d1b-4c8a-b6e5-2f0a9d8c7b6e","message":{"block_number":1,"row":0}}"#;
+			let response = test.ws_send_text(request).await;
+			let WsError::Error(error) = serde_json::from_str(&response).unwrap();
+			assert!(error.request_id.is_some());
+		}
+
+		let request = r#"{"type":"version","request_id":"cae63fff-c4b8-4af9-b4fe-0605a5329aa0"}"#;
+		let response = test.ws_send_text(request).await;
+		assert!(response.starts_with(r#"{"topic":"version""#));
+	}
+
+	#[tokio::test]
+	async fn ws_route_malformed_requests_disabled() {
+		let config = RuntimeConfig {
+			ws_max_malformed_requests: 0,
+			..Default::default()
+		};
+		let mut test = MockSetup::new(config, None).await;
+
+		for _ in 0..4 {
+			let response = test.ws_send_text("garbage").await;
+			let WsError::Error(error) = serde_json::from_str(&response).unwrap();
+			assert_eq!(error.error_code, ErrorCode::BadRequest);
+		}
+
+		let request = r#"{"type":"version","request_id":"cae63fff-c4b8-4af9-b4fe-0605a5329aa0"}"#;
+		let response = test.ws_send_text(request).await;
+		assert!(response.starts_with(r#"{"topic":"version""#));
+	}
+
+	#[derive(Clone)]
+	struct SlowSubmitter {}
+
+	#[async_trait]
+	impl transactions::Submit for SlowSubmitter {
+		async fn submit(&self, _: Transaction) -> color_eyre::Result<SubmitResponse> {
+			tokio::time::sleep(Duration::from_secs(60)).await;
+			Err(color_eyre::eyre::eyre!("Transaction is not submitted"))
+		}
+	}
+
+	#[tokio::test]
+	async fn ws_route_submit_timeout() {
+		let config = RuntimeConfig {
+			app_id: Some(1),
+			ws_request_timeout: 1,
+			..Default::default()
+		};
+		let mut test = MockSetup::builder()
+			.config(config)
+			.submitter(SlowSubmitter {})
+			.build()
+			.await;
+
+		let request = r#"{"type":"submit","request_id":"5d1f7a0e-9c3b-4e2a-8f6d-1b2c3d4e5f60","message":{"data":"dHJhbnNhY3Rpb24K"}}"#;
+		let response = test.ws_send_text(request).await;
+
+		let WsError::Error(error) = serde_json::from_str(&response).unwrap();
+		assert_eq!(error.error_code, ErrorCode::ServiceUnavailable);
+		assert_eq!(
+			error.request_id,
+			Some(to_uuid("5d1f7a0e-9c3b-4e2a-8f6d-1b2c3d4e5f60"))
+		);
+	}
+
+	#[tokio::test]
+	async fn ws_route_submit_error_request_id() {
+		let mut test = MockSetup::builder()
+			.config(app_config())
+			.submitter(DryRunSubmitter {})
+			.build()
+			.await;
+
+		let request = r#"{"type":"submit","request_id":"3e7a9c1b-5d2f-4b8e-a6c0-9f1d2e3b4a5c","message":{"data":"dHJhbnNhY3Rpb24K"}}"#;
+		let response = test.ws_send_text(request).await;
+
+		let WsError::Error(error) = serde_json::from_str(&response).unwrap();
+		assert_eq!(error.error_code, ErrorCode::InternalServerError);
+		assert_eq!(
+			error.request_id,
+			Some(to_uuid("3e7a9c1b-5d2f-4b8e-a6c0-9f1d2e3b4a5c"))
+		);
+	}
+
+	#[tokio::test]
+	async fn ws_route_connection_limit() {
+		let config = RuntimeConfig {
+			ws_max_connections: 2,
+			..Default::default()
+		};
+		let client_uuid = uuid::Uuid::new_v4().to_string();
+		let clients = WsClients::default();
+		clients
+			.subscribe(&client_uuid, Subscription::default())
+			.await;
+		let route = MockSetup::builder()
+			.config(config)
+			.clients(clients.clone())
+			.route();
+		let path = format!("/v2/ws/{client_uuid}");
+
+		let mut ws_clients = vec![];
+		for _ in 0..2 {
+			let ws_client = warp::test::ws().path(&path).handshake(route.clone()).await;
+			ws_clients.push(ws_client.expect("handshake"));
+		}
+		assert_eq!(clients.connections(), 2);
+
+		let response = warp::test::request()
+			.path(&path)
+			.header("connection", "upgrade")
+			.header("upgrade", "websocket")
+			.header("sec-websocket-version", "13")
+			.header("sec-websocket-key", "dGhlIHNhbXBsZSBub25jZQ==")
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+		assert_eq!(response.headers()["retry-after"], "30");
+		assert_eq!(
+			error_message(response.body()),
+			"Maximum number of 2 connections reached"
+		);
+
+		drop(ws_clients.pop());
+		tokio::time::timeout(Duration::from_secs(5), async {
+			while clients.connections() > 1 {
+				tokio::time::sleep(Duration::from_millis(10)).await;
+			}
+		})
+		.await
+		.expect("connection is unregistered");
+
+		let ws_client = warp::test::ws().path(&path).handshake(route).await;
+		assert!(ws_client.is_ok());
+		assert_eq!(clients.connections(), 2);
+	}
+
+	#[tokio::test]
+	async fn ws_route_submit_data() {
+		let submitter = Some(MockSubmitter {});
+		let mut test = MockSetup::new(app_config(), submitter).await;
+
+		let request = r#"{"type":"submit","request_id":"fca2ff0c-7a26-42a2-a6f0-d0aeeaba8a9a","message":{"data":"dHJhbnNhY3Rpb24K"}}"#;
+		let response = test.ws_send_text(request).await;
+
+		let WsResponse::DataTransactionSubmitted(response) =
+			serde_json::from_str(&response).unwrap()
+		else {
+			panic!("Invalid response");
+		};
+		let expected_request_id = to_uuid("fca2ff0c-7a26-42a2-a6f0-d0aeeaba8a9a");
+		assert_eq!(response.request_id, expected_request_id);
+		assert_eq!(response.message.index, 0);
+	}
+
+	#[test_case(r#""# , Some(ErrorCode::Unauthorized) ; "No API key")]
+	#[test_case(r#","auth":"invalid""# , Some(ErrorCode::Unauthorized) ; "Invalid API key")]
+	#[test_case(r#","auth":"valid""# , None ; "Valid API key")]
+	#[tokio::test]
+	async fn ws_route_submit_authorization(auth: &str, error_code: Option<ErrorCode>) {
+		let config = RuntimeConfig {
+			app_id: Some(1),
+			api_keys: vec!["valid".into()],
+			..Default::default()
+		};
+		let mut test = MockSetup::new(config, Some(MockSubmitter {})).await;
+
+		let request = format!(
+			r#"{{"type":"submit","request_id":"fca2ff0c-7a26-42a2-a6f0-d0aeeaba8a9a"{auth},"message":{{"data":"dHJhbnNhY3Rpb24K"}}}}"#
+		);
+		let response = test.ws_send_text(&request).await;
+
+		match error_code {
+			Some(error_code) => {
+				let WsError::Error(error) = serde_json::from_str(&response).unwrap();
+				assert_eq!(error.error_code, error_code);
+				assert_eq!(
+					error.request_id,
+					Some(to_uuid("fca2ff0c-7a26-42a2-a6f0-d0aeeaba8a9a"))
+				);
+			},
+			None => {
+				let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+				assert_eq!(response["topic"], "data-transaction-submitted");
+			},
+		}
+	}
+
+	/// Configuration with regular and admin API keys.
+	fn admin_config() -> RuntimeConfig {
+		RuntimeConfig {
+			api_keys: vec![
+				"key".into(),
+				ApiKey {
+					key: "admin-key".to_string(),
+					submit_quota: None,
+					rate_limit_requests_per_second: None,
+					rate_limit_burst: None,
+					admin: true,
+				},
+			],
+			..Default::default()
+		}
+	}
+
+	#[test_case(r#""# ; "No API key")]
+	#[test_case(r#","auth":"key""# ; "Non-admin API key")]
+	#[tokio::test]
+	async fn ws_route_list_subscriptions_unauthorized(auth: &str) {
+		let mut test = MockSetup::new(admin_config(), None).await;
+
+		let request = format!(
+			r#"{{"type":"list-subscriptions","request_id":"9c1e5a3b-7d2f-4b6e-8a0c-4e6f8a2b1d3c"{auth}}}"#
+		);
+		let response = test.ws_send_text(&request).await;
+
+		let WsError::Error(error) = serde_json::from_str(&response).unwrap();
+		assert_eq!(error.error_code, ErrorCode::Unauthorized);
+	}
+
+	#[tokio::test]
+	async fn ws_route_list_subscriptions() {
+		let mut test = MockSetup::new(admin_config(), None).await;
+		let subscription = Subscription {
+			topics: HashSet::from([Topic::HeaderVerified]),
+			data_fields: HashSet::from([DataField::Data]),
+			..Default::default()
+		};
+		test.clients.subscribe("other", subscription.clone()).await;
+
+		let request = r#"{"type":"list-subscriptions","request_id":"9c1e5a3b-7d2f-4b6e-8a0c-4e6f8a2b1d3c","auth":"admin-key"}"#;
+		let response = test.ws_send_text(request).await;
+
+		let WsResponse::Subscriptions(response) = serde_json::from_str(&response).unwrap() else {
+			panic!("Invalid response");
+		};
+		let subscriptions = response
+			.message
+			.into_iter()
+			.map(|subscription| (subscription.subscription_id, subscription.subscription))
+			.collect::<Vec<_>>();
+		let expected = vec![
+			(test.client_uuid.clone(), Subscription::default()),
+			("other".to_string(), subscription),
+		];
+		assert_eq!(subscriptions, expected);
+
+		// Removed clients are not listed
+		test.clients.clients.write().await.shift_remove("other");
+		let response = test.ws_send_text(request).await;
+		let WsResponse::Subscriptions(response) = serde_json::from_str(&response).unwrap() else {
+			panic!("Invalid response");
+		};
+		assert_eq!(response.message.len(), 1);
+	}
+
+	#[tokio::test]
+	async fn ws_route_disconnect() {
+		let mut test = MockSetup::new(admin_config(), None).await;
+		let (sender, mut receiver) = tokio::sync::broadcast::channel(16);
+		test.clients
+			.subscribe("other", Subscription::default())
+			.await;
+		test.clients.set_sender("other", sender).await.unwrap();
+
+		let request = r#"{"type":"disconnect","request_id":"4a8c2e6f-1b3d-4f5a-9c7e-2d4f6a8b0c1e","auth":"admin-key","message":{"subscription_id":"other"}}"#;
+		let response = test.ws_send_text(request).await;
+
+		let WsResponse::Disconnected(response) = serde_json::from_str(&response).unwrap() else {
+			panic!("Invalid response");
+		};
+		assert_eq!(response.message.subscription_id, "other");
+		assert!(response.message.disconnected);
+		assert!(!test.clients.has_subscription("other").await);
+
+		let message = receiver.recv().await.unwrap();
+		let (code, reason) = message.close_frame().unwrap();
+		assert_eq!(code, 1008);
+		assert_eq!(reason, "Disconnected by admin");
+	}
+
+	#[tokio::test]
+	async fn ws_route_disconnect_not_found() {
+		let mut test = MockSetup::new(admin_config(), None).await;
+
+		let request = r#"{"type":"disconnect","request_id":"4a8c2e6f-1b3d-4f5a-9c7e-2d4f6a8b0c1e","auth":"admin-key","message":{"subscription_id":"unknown"}}"#;
+		let response = test.ws_send_text(request).await;
+
+		let WsResponse::Disconnected(response) = serde_json::from_str(&response).unwrap() else {
+			panic!("Invalid response");
+		};
+		assert_eq!(response.message.subscription_id, "unknown");
+		assert!(!response.message.disconnected);
+		assert!(test.clients.has_subscription(&test.client_uuid).await);
+	}
+
+	#[tokio::test]
+	async fn ws_route_disconnect_unauthorized() {
+		let mut test = MockSetup::new(admin_config(), None).await;
+
+		let request = format!(
+			r#"{{"type":"disconnect","request_id":"4a8c2e6f-1b3d-4f5a-9c7e-2d4f6a8b0c1e","auth":"key","message":{{"subscription_id":"{}"}}}}"#,
+			test.client_uuid
+		);
+		let response = test.ws_send_text(&request).await;
+
+		let WsError::Error(error) = serde_json::from_str(&response).unwrap();
+		assert_eq!(error.error_code, ErrorCode::Unauthorized);
+		assert!(test.clients.has_subscription(&test.client_uuid).await);
+	}
+
+	#[tokio::test]
+	async fn ws_route_submit_batch() {
+		let submitter = Some(MockSubmitter {});
+		let mut test = MockSetup::new(app_config(), submitter).await;
+
+		let request = r#"{"type":"submit-batch","request_id":"3f0c6a2e-8b1d-4e5f-a7c9-2d4e6f8a0b1c","message":[{"data":"dHJhbnNhY3Rpb24K"},{"extrinsic":""},{"extrinsic":"dHJhbnNhY3Rpb24K"}]}"#;
+		let response = test.ws_send_text(request).await;
+
+		let WsResponse::DataTransactionsSubmitted(response) =
+			serde_json::from_str(&response).unwrap()
+		else {
+			panic!("Invalid response");
+		};
+		let expected_request_id = to_uuid("3f0c6a2e-8b1d-4e5f-a7c9-2d4e6f8a0b1c");
+		assert_eq!(response.request_id, expected_request_id);
+		let SubmitBatchResponse(results) = response.message;
+		assert_mixed_batch(results);
+	}
+
+	#[tokio::test]
+	async fn ws_route_submit_batch_too_large() {
+		let config = RuntimeConfig {
+			max_batch_size: 2,
+			..app_config()
+		};
+		let mut test = MockSetup::new(config, Some(MockSubmitter {})).await;
+
+		let request = r#"{"type":"submit-batch","request_id":"3f0c6a2e-8b1d-4e5f-a7c9-2d4e6f8a0b1c","message":[{"data":"dHJhbnNhY3Rpb24K"},{"data":"dHJhbnNhY3Rpb24K"},{"data":"dHJhbnNhY3Rpb24K"}]}"#;
+		let response = test.ws_send_text(request).await;
+
+		let WsError::Error(error) = serde_json::from_str(&response).unwrap();
+		assert_eq!(error.error_code, ErrorCode::BadRequest);
+		assert_eq!(
+			error.message,
+			"Batch has 3 transactions, exceeding the limit of 2"
+		);
+	}
+
+	#[tokio::test]
+	async fn ws_route_submit_batch_app_id() {
+		let config = RuntimeConfig {
+			submit_app_ids: vec![2],
+			..Default::default()
+		};
+		let submitter = Some(MockSubmitter {});
+		let mut test = MockSetup::new(config, submitter).await;
+
+		let request = r#"{"type":"submit-batch","request_id":"3f0c6a2e-8b1d-4e5f-a7c9-2d4e6f8a0b1c","message":[{"data":"dHJhbnNhY3Rpb24K","app_id":2},{"data":"dHJhbnNhY3Rpb24K"}]}"#;
+		let response = test.ws_send_text(request).await;
+
+		let WsResponse::DataTransactionsSubmitted(response) =
+			serde_json::from_str(&response).unwrap()
+		else {
+			panic!("Invalid response");
+		};
+		let SubmitBatchResponse(results) = response.message;
+		assert_eq!(results.len(), 2);
+		let SubmitResult::Submitted(submitted) = &results[0] else {
+			panic!("Transaction with app ID is not submitted");
+		};
+		assert_eq!(submitted.app_id, Some(2));
+		let SubmitResult::Error(error) = &results[1] else {
+			panic!("Transaction without app ID is submitted");
+		};
+		assert_eq!(error.error_code, ErrorCode::BadRequest);
+		assert_eq!(
+			error.message,
+			"App ID is not configured, data transaction requires app_id"
+		);
+	}
+
+	#[tokio::test]
+	async fn ws_route_submit_batch_max_size() {
+		let config = RuntimeConfig {
+			max_submit_size: 12,
+			..app_config()
+		};
+		let mut test = MockSetup::new(config, Some(MockSubmitter {})).await;
+
+		let request = r#"{"type":"submit-batch","request_id":"3f0c6a2e-8b1d-4e5f-a7c9-2d4e6f8a0b1c","message":[{"data":"dHJhbnNhY3Rpb24K"},{"data":"dHJhbnNhY3Rpb24KCg=="}]}"#;
+		let response = test.ws_send_text(request).await;
+
+		let WsResponse::DataTransactionsSubmitted(response) =
+			serde_json::from_str(&response).unwrap()
+		else {
+			panic!("Invalid response");
+		};
+		let SubmitBatchResponse(results) = response.message;
+		assert_eq!(results.len(), 2);
+		assert!(matches!(results[0], SubmitResult::Submitted(_)));
+		let SubmitResult::Error(error) = &results[1] else {
+			panic!("Transaction over the limit is submitted");
+		};
+		assert_eq!(error.error_code, ErrorCode::BadRequest);
+		assert_eq!(
+			error.message,
+			"Transaction size 13 exceeds the limit of 12 bytes"
+		);
+	}
+
+	#[tokio::test]
+	async fn ws_route_submit_extrinsic() {
+		let submitter = Some(MockSubmitter {});
 		let mut test = MockSetup::new(RuntimeConfig::default(), submitter).await;
 
-		let request = r#"{"type":"submit","request_id":"fca2ff0c-7a26-42a2-a6f0-d0aeeaba8a9a","message":{"extrinsic":"dHJhbnNhY3Rpb24K"}}"#;
+		let request = r#"{"type":"submit","request_id":"fca2ff0c-7a26-42a2-a6f0-d0aeeaba8a9a","message":{"extrinsic":"dHJhbnNhY3Rpb24K"}}"#;
+		let response = test.ws_send_text(request).await;
+
+		let WsResponse::DataTransactionSubmitted(response) =
+			serde_json::from_str(&response).unwrap()
+		else {
+			panic!("Invalid response");
+		};
+		let expected_request_id = to_uuid("fca2ff0c-7a26-42a2-a6f0-d0aeeaba8a9a");
+		assert_eq!(response.request_id, expected_request_id);
+		assert_eq!(response.message.index, 0);
+	}
+
+	#[tokio::test]
+	async fn ws_route_submit_dry_run() {
+		let submitter = Some(MockSubmitter {});
+		let mut test = MockSetup::new(app_config(), submitter).await;
+
+		let request = r#"{"type":"submit","request_id":"fca2ff0c-7a26-42a2-a6f0-d0aeeaba8a9a","message":{"extrinsic":"dHJhbnNhY3Rpb24K","dry_run":true}}"#;
+		let response = test.ws_send_text(request).await;
+
+		let WsResponse::DataTransactionSubmitted(response) =
+			serde_json::from_str(&response).unwrap()
+		else {
+			panic!("Invalid response");
+		};
+		// Mock submitter responds with random block hash, so zeroed hash means it is not submitted
+		assert!(response.message.dry_run);
+		assert_eq!(response.message.block_hash, H256::zero());
+		assert_eq!(
+			response.message.hash,
+			H256::from(sp_core::blake2_256(b"transaction\n"))
+		);
+	}
+
+	fn all_subscription() -> Subscription {
+		Subscription {
+			topics: all_topics(),
+			data_fields: all_data_fields(),
+			..Default::default()
+		}
+	}
+
+	#[tokio::test]
+	async fn ws_route_unsubscribe_partial() {
+		let config = RuntimeConfig::default();
+		let mut test = MockSetup::new_with_subscription(config, None, all_subscription()).await;
+
+		let request = r#"{"type":"unsubscribe","request_id":"2b6b2b53-1dd5-4a8b-8bd3-2ab6bb1b6b8e","message":{"topics":["header-verified"],"data_fields":["data"]}}"#;
+		let response = test.ws_send_text(request).await;
+
+		let WsResponse::Subscription(response) = serde_json::from_str(&response).unwrap() else {
+			panic!("Invalid response");
+		};
+		let expected = Subscription {
+			topics: [Topic::ConfidenceAchieved, Topic::DataVerified].into(),
+			data_fields: [DataField::Extrinsic].into(),
+			..Default::default()
+		};
+		assert_eq!(
+			response.request_id,
+			to_uuid("2b6b2b53-1dd5-4a8b-8bd3-2ab6bb1b6b8e")
+		);
+		assert_eq!(response.message, expected);
+
+		let clients = test.clients.clients.read().await;
+		let client = clients.get(&test.client_uuid).unwrap();
+		assert_eq!(client.subscription, expected);
+	}
+
+	#[tokio::test]
+	async fn ws_route_unsubscribe_all() {
+		let config = RuntimeConfig::default();
+		let mut test = MockSetup::new_with_subscription(config, None, all_subscription()).await;
+
+		let request = r#"{"type":"unsubscribe","request_id":"5f1b8f2e-7c6e-4d1a-9a55-0c1f3b0e6a11","message":{"topics":["header-verified","confidence-achieved","data-verified"],"data_fields":["data","extrinsic"]}}"#;
+		let response = test.ws_send_text(request).await;
+
+		let WsResponse::Subscription(response) = serde_json::from_str(&response).unwrap() else {
+			panic!("Invalid response");
+		};
+		assert_eq!(response.message, Subscription::default());
+
+		let message = PublishMessage::HeaderVerified(Box::new(header().try_into().unwrap()));
+		let published = test
+			.clients
+			.publish(&Topic::HeaderVerified, message)
+			.await
+			.unwrap();
+		assert!(published.results.is_empty());
+
+		// Connection is kept alive after all topics are removed
+		let request = r#"{"type":"version","request_id":"cae63fff-c4b8-4af9-b4fe-0605a5329aa0"}"#;
+		let response = test.ws_send_text(request).await;
+		assert!(response.starts_with(r#"{"topic":"version""#));
+	}
+
+	async fn replay_setup(
+		config: RuntimeConfig,
+		from_block: u32,
+	) -> (WsClients, SubscriptionId, warp::test::WsClient) {
+		let clients = WsClients::default();
+		let state = Arc::new(Mutex::new(State::default()));
+		let db = mem_db::MemoryDB::default();
+		{
+			let mut state = state.lock().unwrap();
+			state.latest = 3;
+			state.header_verified = Some(BlockRange { first: 1, last: 3 });
+		}
+		for number in 1..=3 {
+			_ = db.put(Key::BlockHeader(number), DaHeader { number, ..header() });
+		}
+
+		let body = format!(
+			r#"{{"topics":["header-verified"],"data_fields":[],"from_block":{from_block}}}"#
+		);
+		let response = warp::test::request()
+			.method("POST")
+			.body(body)
+			.path("/v2/subscriptions")
+			.reply(&super::subscriptions_route(
+				clients.clone(),
+				config.clone(),
+				state.clone(),
+			))
+			.await;
+		let response: SubscriptionId = serde_json::from_slice(response.body()).unwrap();
+
+		let test = MockSetup::builder()
+			.config(config)
+			.clients(clients.clone())
+			.state(state)
+			.db(db)
+			.connect(&response.subscription_id)
+			.await;
+
+		(clients, response, test.ws_client)
+	}
+
+	async fn block_number(ws_client: &mut warp::test::WsClient) -> u64 {
+		let message = ws_client.recv().await.unwrap();
+		let message: serde_json::Value = serde_json::from_str(message.to_str().unwrap()).unwrap();
+		assert_eq!(message["topic"], "header-verified");
+		message["message"]["block_number"].as_u64().unwrap()
+	}
+
+	#[tokio::test]
+	async fn ws_route_replay_headers() {
+		let (clients, response, mut ws_client) = replay_setup(RuntimeConfig::default(), 2).await;
+		let subscription_id = response.subscription_id;
+		let expected = ReplayWindow {
+			first: 2,
+			last: 3,
+			truncated: false,
+		};
+		assert_eq!(response.replay, Some(expected));
+
+		let mut block_numbers = vec![];
+		block_numbers.push(block_number(&mut ws_client).await);
+		block_numbers.push(block_number(&mut ws_client).await);
+
+		// Live messages are published once sender is set, after replay
+		while clients.clients.read().await[&subscription_id]
+			.sender
+			.is_none()
+		{
+			tokio::time::sleep(Duration::from_millis(10)).await;
+		}
+		let live = DaHeader {
+			number: 4,
+			..header()
+		};
+		let message = PublishMessage::HeaderVerified(Box::new(live.try_into().unwrap()));
+		clients
+			.publish(&Topic::HeaderVerified, message)
+			.await
+			.unwrap();
+		block_numbers.push(block_number(&mut ws_client).await);
+
+		assert_eq!(block_numbers, vec![2, 3, 4]);
+	}
+
+	#[tokio::test]
+	async fn ws_route_replay_headers_truncated() {
+		let config = RuntimeConfig {
+			ws_replay_max_blocks: 2,
+			..Default::default()
+		};
+		let (_, response, mut ws_client) = replay_setup(config, 0).await;
+		let expected = ReplayWindow {
+			first: 1,
+			last: 2,
+			truncated: true,
+		};
+		assert_eq!(response.replay, Some(expected));
+
+		assert_eq!(block_number(&mut ws_client).await, 1);
+		assert_eq!(block_number(&mut ws_client).await, 2);
+	}
+
+	#[tokio::test]
+	async fn ws_route_replay_headers_buffer_size() {
+		let config = RuntimeConfig {
+			ws_buffer_size: 2,
+			..Default::default()
+		};
+		let (_, response, mut ws_client) = replay_setup(config, 1).await;
+		let expected = ReplayWindow {
+			first: 1,
+			last: 3,
+			truncated: false,
+		};
+		assert_eq!(response.replay, Some(expected));
+
+		// Only the last headers which fit into the client buffer are replayed
+		assert_eq!(block_number(&mut ws_client).await, 2);
+		assert_eq!(block_number(&mut ws_client).await, 3);
+	}
+
+	#[tokio::test]
+	async fn ws_route_replay_buffered() {
+		let clients = WsClients::new(2);
+		for number in 1..=3 {
+			let header = DaHeader { number, ..header() };
+			let message = PublishMessage::HeaderVerified(Box::new(header.try_into().unwrap()));
+			_ = clients.publish(&Topic::HeaderVerified, message).await;
+			let event = BlockEvent::Finalized {
+				block_number: number,
+				hash: H256::repeat_byte(number as u8),
+			};
+			_ = clients
+				.publish(&Topic::BlockFinalized, event.try_into().unwrap())
+				.await;
+		}
+
+		let body =
+			r#"{"topics":["header-verified","block-finalized"],"data_fields":[],"replay":true}"#;
+		let response = warp::test::request()
+			.method("POST")
+			.body(body)
+			.path("/v2/subscriptions")
+			.reply(&super::subscriptions_route(
+				clients.clone(),
+				RuntimeConfig::default(),
+				Arc::new(Mutex::new(State::default())),
+			))
+			.await;
+		let response: SubscriptionId = serde_json::from_slice(response.body()).unwrap();
+
+		let MockSetup { mut ws_client, .. } = MockSetup::builder()
+			.clients(clients.clone())
+			.connect(&response.subscription_id)
+			.await;
+
+		let mut replayed = vec![];
+		for _ in 0..4 {
+			let message = ws_client.recv().await.unwrap();
+			let message: serde_json::Value =
+				serde_json::from_str(message.to_str().unwrap()).unwrap();
+			replayed.push((
+				message["topic"].as_str().unwrap().to_string(),
+				message["message"]["block_number"].as_u64().unwrap(),
+				message["seq"].as_u64().unwrap(),
+			));
+		}
+
+		// Only the last two messages per topic are buffered, replayed in publish order
+		let expected = vec![
+			("header-verified".to_string(), 2, 1),
+			("block-finalized".to_string(), 2, 2),
+			("header-verified".to_string(), 3, 3),
+			("block-finalized".to_string(), 3, 4),
+		];
+		assert_eq!(replayed, expected);
+	}
+
+	#[tokio::test]
+	async fn subscriptions_route_replay_with_from_block() {
+		let body =
+			r#"{"topics":["header-verified"],"data_fields":[],"from_block":1,"replay":true}"#;
+		let response = warp::test::request()
+			.method("POST")
+			.body(body)
+			.path("/v2/subscriptions")
+			.reply(&super::subscriptions_route(
+				WsClients::new(2),
+				RuntimeConfig::default(),
+				Arc::new(Mutex::new(State::default())),
+			))
+			.await;
+		assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+		assert_eq!(
+			error_message(response.body()),
+			"Replay cannot be combined with from_block"
+		);
+	}
+
+	#[tokio::test]
+	async fn ws_route_subscribe_merge() {
+		let subscription = Subscription {
+			topics: [Topic::HeaderVerified].into(),
+			data_fields: [DataField::Data].into(),
+			..Default::default()
+		};
+		let config = RuntimeConfig::default();
+		let mut test = MockSetup::new_with_subscription(config, None, subscription).await;
+
+		let request = r#"{"type":"subscribe","request_id":"0d9c3c43-0b8b-4b5c-a1a4-5a7f7b0b9a3e","message":{"topics":["confidence-achieved","header-verified"],"data_fields":["extrinsic"]}}"#;
+		let response = test.ws_send_text(request).await;
+
+		let WsResponse::Subscription(response) = serde_json::from_str(&response).unwrap() else {
+			panic!("Invalid response");
+		};
+		let expected = Subscription {
+			topics: [Topic::HeaderVerified, Topic::ConfidenceAchieved].into(),
+			data_fields: all_data_fields(),
+			..Default::default()
+		};
+		assert_eq!(response.message, expected);
+
+		let clients = test.clients.clients.read().await;
+		let client = clients.get(&test.client_uuid).unwrap();
+		assert_eq!(client.subscription, expected);
+	}
+
+	#[tokio::test]
+	async fn ws_route_subscribe_limit() {
+		let subscription = Subscription {
+			topics: [Topic::HeaderVerified].into(),
+			app_ids: [1].into(),
+			..Default::default()
+		};
+		let config = RuntimeConfig {
+			ws_max_subscriptions: 3,
+			..Default::default()
+		};
+		let mut test = MockSetup::new_with_subscription(config, None, subscription.clone()).await;
+
+		let request = r#"{"type":"subscribe","request_id":"3e8c1f0a-6b2d-4c7e-9f5a-1d2b3c4e5f60","message":{"topics":["confidence-achieved"],"data_fields":[],"app_ids":[2]}}"#;
+		let response = test.ws_send_text(request).await;
+
+		let WsError::Error(error) = serde_json::from_str(&response).unwrap();
+		assert_eq!(error.error_code, ErrorCode::BadRequest);
+		assert_eq!(
+			error.message,
+			"Subscription exceeds the limit of 3 topics and application IDs"
+		);
+		{
+			let clients = test.clients.clients.read().await;
+			let client = clients.get(&test.client_uuid).unwrap();
+			assert_eq!(client.subscription, subscription);
+		}
+
+		let request = r#"{"type":"subscribe","request_id":"3e8c1f0a-6b2d-4c7e-9f5a-1d2b3c4e5f61","message":{"topics":["confidence-achieved"],"data_fields":[]}}"#;
+		let response = test.ws_send_text(request).await;
+
+		let WsResponse::Subscription(response) = serde_json::from_str(&response).unwrap() else {
+			panic!("Invalid response");
+		};
+		assert_eq!(
+			response.message.topics,
+			HashSet::from([Topic::HeaderVerified, Topic::ConfidenceAchieved])
+		);
+	}
+
+	#[tokio::test]
+	async fn ws_route_subscribe_unknown_subscription() {
+		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
+		test.clients
+			.clients
+			.write()
+			.await
+			.shift_remove(&test.client_uuid);
+
+		let request = r#"{"type":"subscribe","request_id":"8f0e5a9d-3c1b-4f5e-9a6d-2b7c8d9e0f1a","message":{"topics":["confidence-achieved"],"data_fields":[]}}"#;
+		let response = test.ws_send_text(request).await;
+
+		let WsError::Error(error) = serde_json::from_str(&response).unwrap();
+		assert_eq!(error.error_code, ErrorCode::NotFound);
+	}
+
+	#[tokio::test]
+	async fn heartbeat_cadence() {
+		let clients = WsClients::default();
+		let (sender, mut receiver) = tokio::sync::broadcast::channel(16);
+		clients.subscribe("1", Subscription::default()).await;
+		clients.set_sender("1", sender).await.unwrap();
+
+		let interval = Duration::from_millis(50);
+		tokio::spawn(super::heartbeat(clients.clone(), interval, u32::MAX));
+
+		let started = tokio::time::Instant::now();
+		for _ in 0..3 {
+			let message = receiver.recv().await.unwrap();
+			assert!(message.is_ping());
+		}
+		// First ping is sent immediately, following ones after each interval
+		assert!(started.elapsed() >= interval * 2);
+	}
+
+	#[tokio::test]
+	async fn shutdown_closes_clients() {
+		let clients = WsClients::default();
+		let mut receivers = vec![];
+		for subscription_id in ["1", "2"] {
+			let (sender, receiver) = tokio::sync::broadcast::channel(16);
+			clients
+				.subscribe(subscription_id, Subscription::default())
+				.await;
+			clients.set_sender(subscription_id, sender).await.unwrap();
+			receivers.push(receiver);
+		}
+		// Clients without established connection are removed without close frame
+		clients.subscribe("pending", Subscription::default()).await;
+
+		let shutdown = tokio::spawn(super::shutdown(
+			clients.clone(),
+			async {},
+			Duration::from_secs(1),
+		));
+
+		for mut receiver in receivers {
+			let message = receiver.recv().await.unwrap();
+			assert!(message.is_close());
+			assert_eq!(
+				message.close_frame(),
+				Some((1001, "Server is shutting down"))
+			);
+		}
+		shutdown.await.unwrap();
+		assert!(clients.clients.read().await.is_empty());
+		assert!(!clients.has_subscription("1").await);
+	}
+
+	#[tokio::test]
+	async fn ws_route_stats() {
+		let config = RuntimeConfig::default();
+		let mut test = MockSetup::new_with_subscription(config, None, all_subscription()).await;
+
+		let (sender, _receiver) = tokio::sync::broadcast::channel(16);
+		let subscription = Subscription {
+			topics: [Topic::HeaderVerified].into(),
+			..Default::default()
+		};
+		test.clients
+			.subscribe("connected", subscription.clone())
+			.await;
+		test.clients.set_sender("connected", sender).await.unwrap();
+		// Clients without established connection are not counted
+		test.clients.subscribe("pending", subscription).await;
+
+		let request = r#"{"type":"stats","request_id":"d6a0b1f4-5a8e-4f3a-8a0e-7f1c9b2d3e4f"}"#;
+		let response = test.ws_send_text(request).await;
+
+		let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+		assert_eq!(response["topic"], "stats");
+		let stats = &response["message"];
+		assert_eq!(stats["ws_clients"], 2);
+		assert_eq!(stats["topics"]["header-verified"], 2);
+		assert_eq!(stats["topics"]["confidence-achieved"], 1);
+		assert_eq!(stats["topics"]["data-verified"], 1);
+	}
+
+	#[test_case("", true ; "Default")]
+	#[test_case(r#","include_commitments":true"#, true ; "Included commitments")]
+	#[test_case(r#","include_commitments":false"#, false ; "Omitted commitments")]
+	#[tokio::test]
+	async fn ws_route_get_header(include_commitments: &str, expected_commitments: bool) {
+		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
+		{
+			let mut state = test.state.lock().unwrap();
+			state.latest = 1;
+			state.header_verified = Some(BlockRange::init(1));
+		}
+		_ = test.db.put(Key::BlockHeader(1), header());
+
+		let request = format!(
+			r#"{{"type":"get-header","request_id":"2b9e4a1c-7d3f-4e8a-9c6b-5f0d1e2a3b4c","message":{{"block_number":1{include_commitments}}}}}"#
+		);
+		let response = test.ws_send_text(&request).await;
+
+		let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+		assert_eq!(response["topic"], "header");
+		assert_eq!(
+			response["request_id"],
+			"2b9e4a1c-7d3f-4e8a-9c6b-5f0d1e2a3b4c"
+		);
+		assert_eq!(response["message"]["block_number"], 1);
+		assert_eq!(response["message"]["header"]["number"], 1);
+		let extension = &response["message"]["header"]["extension"];
+		assert_eq!(extension.get("commitments").is_some(), expected_commitments);
+	}
+
+	fn app_data_setup(state: &Arc<Mutex<State>>, db: &mem_db::MemoryDB) {
+		let mut state = state.lock().unwrap();
+		state.latest = 10;
+		state.header_verified = Some(BlockRange::init(5));
+		state.confidence_achieved = Some(BlockRange::init(5));
+		state.data_verified = Some(BlockRange::init(5));
+		_ = db.put(Key::AppData(1, 5), vec![extrinsic()]);
+	}
+
+	#[test_case(r#""app_id":1"# ; "Configured app ID")]
+	#[test_case(r#""app_id":null"# ; "Default app ID")]
+	#[tokio::test]
+	async fn ws_route_get_app_data(app_id: &str) {
+		let config = RuntimeConfig {
+			app_id: Some(1),
+			..Default::default()
+		};
+		let mut test = MockSetup::new(config, None).await;
+		app_data_setup(&test.state, &test.db);
+
+		let request = format!(
+			r#"{{"type":"get-app-data","request_id":"7c2e9b4a-1f3d-4a6e-8b5c-0d9e8f7a6b5c","message":{{"block_number":5,{app_id}}}}}"#
+		);
+		let response = test.ws_send_text(&request).await;
+
+		let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+		assert_eq!(response["topic"], "app-data");
+		assert_eq!(
+			response["request_id"],
+			"7c2e9b4a-1f3d-4a6e-8b5c-0d9e8f7a6b5c"
+		);
+		assert_eq!(
+			response["message"],
+			serde_json::json!({ "block_number": 5, "app_id": 1, "data": ["dGVzdAo="] })
+		);
+	}
+
+	#[test_case(Some(1), 6, 1 ; "Block data is not verified")]
+	#[test_case(Some(1), 5, 2 ; "Data of other app")]
+	#[test_case(None, 5, 1 ; "App mode is not active")]
+	#[tokio::test]
+	async fn ws_route_get_app_data_not_found(
+		config_app_id: Option<u32>,
+		block_number: u32,
+		app_id: u32,
+	) {
+		let config = RuntimeConfig {
+			app_id: config_app_id,
+			..Default::default()
+		};
+		let mut test = MockSetup::new(config, None).await;
+		app_data_setup(&test.state, &test.db);
+
+		let request = format!(
+			r#"{{"type":"get-app-data","request_id":"7c2e9b4a-1f3d-4a6e-8b5c-0d9e8f7a6b5c","message":{{"block_number":{block_number},"app_id":{app_id}}}}}"#
+		);
+		let response = test.ws_send_text(&request).await;
+
+		let WsError::Error(error) = serde_json::from_str(&response).unwrap();
+		assert_eq!(error.error_code, ErrorCode::NotFound);
+	}
+
+	#[test_case(0 ; "Block is unavailable")]
+	#[test_case(11 ; "Block is not yet produced")]
+	#[tokio::test]
+	async fn ws_route_get_header_not_found(block_number: u32) {
+		let config = RuntimeConfig {
+			sync_start_block: Some(1),
+			..Default::default()
+		};
+		let mut test = MockSetup::new(config, None).await;
+		{
+			let mut state = test.state.lock().unwrap();
+			state.latest = 10;
+			state.header_verified = Some(BlockRange::init(10));
+		}
+
+		let request = format!(
+			r#"{{"type":"get-header","request_id":"2b9e4a1c-7d3f-4e8a-9c6b-5f0d1e2a3b4c","message":{{"block_number":{block_number}}}}}"#
+		);
+		let response = test.ws_send_text(&request).await;
+
+		let WsError::Error(error) = serde_json::from_str(&response).unwrap();
+		assert_eq!(error.error_code, ErrorCode::NotFound);
+	}
+
+	#[tokio::test]
+	async fn ws_route_verify_genesis() {
+		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
+
+		let request = format!(
+			r#"{{"type":"verify-genesis","request_id":"6a1d2f3c-4b5e-4f60-8a7b-9c0d1e2f3a4b","message":{{"expected":"{:?}"}}}}"#,
+			H256::default()
+		);
+		let response = test.ws_send_text(&request).await;
+
+		let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+		assert_eq!(response["topic"], "genesis-verified");
+		assert_eq!(
+			response["message"]["genesis_hash"],
+			format!("{:?}", H256::default())
+		);
+	}
+
+	#[tokio::test]
+	async fn ws_route_verify_genesis_mismatch() {
+		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
+
+		let expected = H256::repeat_byte(1);
+		let request = format!(
+			r#"{{"type":"verify-genesis","request_id":"6a1d2f3c-4b5e-4f60-8a7b-9c0d1e2f3a4b","message":{{"expected":"{expected:?}"}}}}"#
+		);
+		let response = test.ws_send_text(&request).await;
+
+		let WsError::Error(error) = serde_json::from_str(&response).unwrap();
+		assert_eq!(error.error_code, ErrorCode::BadRequest);
+		assert_eq!(
+			error.request_id,
+			Some(Uuid::from_str("6a1d2f3c-4b5e-4f60-8a7b-9c0d1e2f3a4b").unwrap())
+		);
+		assert!(error.message.contains(&format!("{expected:?}")));
+		assert!(error.message.contains(&format!("{:?}", H256::default())));
+	}
+
+	/// Extrinsic of the test data transaction, with the given app ID.
+	/// App ID is the last signed extension, encoded as compact integer before the call.
+	fn extrinsic_with_app_id(app_id: u8) -> Vec<u8> {
+		let mut extrinsic = extrinsic();
+		let position = extrinsic.len() - 9;
+		extrinsic[position] = app_id << 2;
+		extrinsic
+	}
+
+	/// Fetcher of the given block extrinsics.
+	struct ExtrinsicsFetcher(Vec<Vec<u8>>);
+
+	#[async_trait]
+	impl cells::Fetch for ExtrinsicsFetcher {
+		async fn fetch(
+			&self,
+			block_hash: H256,
+			positions: &[Position],
+		) -> color_eyre::Result<Vec<Cell>> {
+			cells::Fetch::fetch(&MockCellFetcher {}, block_hash, positions).await
+		}
+
+		async fn fetch_data_proof(
+			&self,
+			block_hash: H256,
+			index: u32,
+		) -> color_eyre::Result<DataProof> {
+			cells::Fetch::fetch_data_proof(&MockCellFetcher {}, block_hash, index).await
+		}
+
+		async fn fetch_extrinsics(&self, _: H256) -> color_eyre::Result<Vec<Vec<u8>>> {
+			Ok(self.0.clone())
+		}
+
+		async fn fetch_verified(
+			&self,
+			block_number: u32,
+			block_hash: H256,
+			dimensions: Dimensions,
+			commitments: &[[u8; 48]],
+			positions: &[Position],
+		) -> color_eyre::Result<Vec<Cell>> {
+			let fetcher = MockCellFetcher {};
+			cells::Fetch::fetch_verified(
+				&fetcher,
+				block_number,
+				block_hash,
+				dimensions,
+				commitments,
+				positions,
+			)
+			.await
+		}
+	}
+
+	/// Submits data transactions as extrinsics with the transaction app ID, like the signed ones.
+	#[derive(Clone)]
+	struct SigningSubmitter {}
+
+	#[async_trait]
+	impl transactions::Submit for SigningSubmitter {
+		async fn submit(&self, transaction: Transaction) -> color_eyre::Result<SubmitResponse> {
+			let Transaction::Data {
+				app_id: Some(app_id),
+				..
+			} = transaction
+			else {
+				return Err(color_eyre::eyre::eyre!(
+					"Data transaction with app ID expected"
+				));
+			};
+			Ok(SubmitResponse {
+				block_number: 0,
+				block_hash: H256::random(),
+				hash: sp_core::blake2_256(&extrinsic_with_app_id(app_id as u8)).into(),
+				index: 0,
+				app_id: Some(app_id),
+				data_position: None,
+				dry_run: false,
+			})
+		}
+	}
+
+	async fn receive_included(test: &mut MockSetup, extrinsics: Vec<Vec<u8>>) -> serde_json::Value {
+		let (sender, receiver) = tokio::sync::broadcast::channel::<PublishMessage>(1);
+		tokio::spawn(super::publish_included(
+			receiver,
+			test.clients.clone(),
+			test.metrics.clone(),
+			ExtrinsicsFetcher(extrinsics),
+		));
+		sender
+			.send(header_verified_with_apps(serde_json::json!([])))
+			.unwrap();
+
+		let message = test.ws_client.recv().await.unwrap();
+		let message: serde_json::Value = serde_json::from_str(message.to_str().unwrap()).unwrap();
+		assert_eq!(message["topic"], "submitted-data-included");
+		assert_eq!(message["message"]["block_number"], 1);
+		message["message"]["transactions"].clone()
+	}
+
+	#[tokio::test]
+	async fn publish_submitted_data_included() {
+		let subscription = Subscription {
+			topics: [Topic::SubmittedDataIncluded].into(),
+			..Default::default()
+		};
+		// Without app ID, light client doesn't verify app data of any application
+		let mut test = MockSetup::new_with_subscription(
+			RuntimeConfig::default(),
+			Some(MockSubmitter {}),
+			subscription,
+		)
+		.await;
+
+		let request = format!(
+			r#"{{"type":"submit","request_id":"c5b2f7a4-3d1e-4b6a-9f8c-0e1d2c3b4a59","message":{{"extrinsic":"{}"}}}}"#,
+			String::from(Base64(extrinsic()))
+		);
+		let response = test.ws_send_text(&request).await;
+		let WsResponse::DataTransactionSubmitted(submitted) =
+			serde_json::from_str(&response).unwrap()
+		else {
+			panic!("Invalid response");
+		};
+
+		let transactions = receive_included(
+			&mut test,
+			vec![b"0".to_vec(), extrinsic_with_app_id(3), extrinsic()],
+		)
+		.await;
+		// Extrinsics which cannot be decoded are published without app ID
+		assert_eq!(transactions[0]["index"], 0);
+		assert!(transactions[0].get("app_id").is_none());
+		let transaction = &transactions[2];
+		assert_eq!(transaction["hash"], format!("{:?}", submitted.message.hash));
+		assert_eq!(transaction["index"], 2);
+		assert_eq!(transaction["app_id"], 1);
+		assert_eq!(transaction["data_position"], 0);
+	}
+
+	#[tokio::test]
+	async fn publish_submitted_data_included_app_id_override() {
+		let config = RuntimeConfig {
+			submit_app_ids: vec![2],
+			..app_config()
+		};
+		let subscription = Subscription {
+			topics: [Topic::SubmittedDataIncluded].into(),
+			..Default::default()
+		};
+		let mut test = MockSetup::builder()
+			.config(config)
+			.subscription(subscription)
+			.submitter(SigningSubmitter {})
+			.build()
+			.await;
+
+		let request = r#"{"type":"submit","request_id":"8e4b1c2d-5f6a-4b7c-9d8e-0f1a2b3c4d5e","message":{"data":"dGVzdAo=","app_id":2}}"#;
+		let response = test.ws_send_text(request).await;
+		let WsResponse::DataTransactionSubmitted(submitted) =
+			serde_json::from_str(&response).unwrap()
+		else {
+			panic!("Invalid response");
+		};
+
+		// Other transaction of the same application, with different data
+		let mut other = extrinsic_with_app_id(2);
+		*other.last_mut().unwrap() = b'!';
+		let extrinsics = vec![other, extrinsic(), extrinsic_with_app_id(2)];
+		let transactions = receive_included(&mut test, extrinsics).await;
+		assert_eq!(transactions[0]["app_id"], 2);
+		assert_eq!(transactions[0]["data_position"], 0);
+		assert_eq!(transactions[1]["app_id"], 1);
+		assert_eq!(transactions[1]["data_position"], 0);
+		let transaction = &transactions[2];
+		assert_eq!(transaction["hash"], format!("{:?}", submitted.message.hash));
+		assert_eq!(transaction["index"], 2);
+		assert_eq!(transaction["app_id"], 2);
+		assert_eq!(transaction["data_position"], 1);
+	}
+
+	#[tokio::test]
+	async fn publish_sync_progress() {
+		let subscription = Subscription {
+			topics: [Topic::SyncProgress].into(),
+			..Default::default()
+		};
+		let mut test =
+			MockSetup::new_with_subscription(RuntimeConfig::default(), None, subscription).await;
+		test.state.lock().unwrap().synced.replace(false);
+
+		tokio::spawn(super::publish_sync_progress(
+			RuntimeConfig::default(),
+			10..20,
+			test.state.clone(),
+			test.clients.clone(),
+			test.metrics.clone(),
+		));
+
+		async fn receive(ws_client: &mut warp::test::WsClient) -> serde_json::Value {
+			let message = ws_client.recv().await.unwrap();
+			let message: serde_json::Value =
+				serde_json::from_str(message.to_str().unwrap()).unwrap();
+			assert_eq!(message["topic"], "sync-progress");
+			assert_eq!(message["message"]["sync_range"]["first"], 10);
+			assert_eq!(message["message"]["sync_range"]["last"], 19);
+			message["message"].clone()
+		}
+
+		let message = receive(&mut test.ws_client).await;
+		assert_eq!(message["synced"], false);
+		assert!(message.get("available").is_none());
+		assert_eq!(message["percentage"], 0.0);
+
+		{
+			let mut state = test.state.lock().unwrap();
+			state.sync_confidence_achieved.set(10);
+			state.sync_confidence_achieved.set(14);
+		}
+		let message = receive(&mut test.ws_client).await;
+		assert_eq!(message["synced"], false);
+		assert_eq!(message["available"]["last"], 14);
+		assert_eq!(message["percentage"], 50.0);
+
+		{
+			let mut state = test.state.lock().unwrap();
+			state.sync_confidence_achieved.set(19);
+			state.synced.replace(true);
+		}
+		let message = receive(&mut test.ws_client).await;
+		assert_eq!(message["synced"], true);
+		assert_eq!(message["available"]["last"], 19);
+		assert_eq!(message["percentage"], 100.0);
+	}
+
+	fn header_verified_with_apps(app_cells: serde_json::Value) -> PublishMessage {
+		let header = serde_json::json!({
+			"block_number": 1,
+			"header": {
+				"hash": H256::default(),
+				"parent_hash": H256::default(),
+				"number": 1,
+				"state_root": H256::default(),
+				"extrinsics_root": H256::default(),
+				"extension": {
+					"extension_version": 3,
+					"rows": 1,
+					"cols": 1,
+					"data_root": H256::default(),
+					"commitments": [],
+					"app_lookup": { "size": 0, "index": [] },
+					"app_cells": app_cells
+				}
+			}
+		});
+		serde_json::from_value(serde_json::json!({ "topic": "header-verified", "message": header }))
+			.unwrap()
+	}
+
+	#[tokio::test]
+	async fn publish_app_registered_after_restart() {
+		let subscription = Subscription {
+			topics: [Topic::HeaderVerified, Topic::AppRegistered].into(),
+			..Default::default()
+		};
+		let mut test =
+			MockSetup::new_with_subscription(RuntimeConfig::default(), None, subscription).await;
+
+		async fn receive(ws_client: &mut warp::test::WsClient) -> serde_json::Value {
+			let message = ws_client.recv().await.unwrap();
+			serde_json::from_str(message.to_str().unwrap()).unwrap()
+		}
+
+		let (sender, receiver) = tokio::sync::broadcast::channel::<PublishMessage>(1);
+		let publish = tokio::spawn(super::publish(
+			Topic::HeaderVerified,
+			receiver,
+			test.clients.clone(),
+			test.metrics.clone(),
+			test.db.clone(),
+		));
+		sender
+			.send(header_verified_with_apps(serde_json::json!([[1, 4]])))
+			.unwrap();
+		assert_eq!(
+			receive(&mut test.ws_client).await["topic"],
+			"header-verified"
+		);
+		let message = receive(&mut test.ws_client).await;
+		assert_eq!(message["topic"], "app-registered");
+		assert_eq!(message["message"]["app_id"], 1);
+
+		// Publishing is restarted with the same database
+		drop(sender);
+		publish.await.unwrap();
+		let (sender, receiver) = tokio::sync::broadcast::channel::<PublishMessage>(1);
+		tokio::spawn(super::publish(
+			Topic::HeaderVerified,
+			receiver,
+			test.clients.clone(),
+			test.metrics.clone(),
+			test.db.clone(),
+		));
+		sender
+			.send(header_verified_with_apps(serde_json::json!([
+				[1, 4],
+				[2, 4]
+			])))
+			.unwrap();
+		assert_eq!(
+			receive(&mut test.ws_client).await["topic"],
+			"header-verified"
+		);
+		let message = receive(&mut test.ws_client).await;
+		assert_eq!(message["topic"], "app-registered");
+		assert_eq!(message["message"]["app_id"], 2);
+	}
+
+	#[tokio::test]
+	async fn publish_block_events() {
+		let subscription = Subscription {
+			topics: [Topic::BlockFinalized, Topic::BlockReorged].into(),
+			..Default::default()
+		};
+		let mut test =
+			MockSetup::new_with_subscription(RuntimeConfig::default(), None, subscription).await;
+
+		let (sender, receiver) = tokio::sync::broadcast::channel::<BlockEvent>(2);
+		tokio::spawn(super::publish(
+			Topic::BlockFinalized,
+			receiver,
+			test.clients.clone(),
+			test.metrics.clone(),
+			test.db.clone(),
+		));
+		sender
+			.send(BlockEvent::Finalized {
+				block_number: 10,
+				hash: H256::repeat_byte(1),
+			})
+			.unwrap();
+		sender
+			.send(BlockEvent::Reorged {
+				block_number: 10,
+				hash: H256::repeat_byte(2),
+			})
+			.unwrap();
+
+		let message = test.ws_client.recv().await.unwrap();
+		let message: serde_json::Value = serde_json::from_str(message.to_str().unwrap()).unwrap();
+		assert_eq!(message["topic"], "block-finalized");
+		assert_eq!(message["message"]["block_number"], 10);
+		assert_eq!(
+			message["message"]["hash"],
+			format!("{:?}", H256::repeat_byte(1))
+		);
+
+		let message = test.ws_client.recv().await.unwrap();
+		let message: serde_json::Value = serde_json::from_str(message.to_str().unwrap()).unwrap();
+		assert_eq!(message["topic"], "block-reorged");
+		assert_eq!(message["message"]["block_number"], 10);
+		assert_eq!(
+			message["message"]["hash"],
+			format!("{:?}", H256::repeat_byte(2))
+		);
+	}
+
+	async fn submit_with_key(submit_cache: &SubmitCache, idempotency_key: &str) -> SubmitResponse {
+		let route = super::submit_route(
+			Some(Arc::new(MockSubmitter {})),
+			submit_cache.clone(),
+			app_config(),
+			rate_limiter(),
+		);
+		let response = warp::test::request()
+			.method("POST")
+			.path("/v2/submit")
+			.header("idempotency-key", idempotency_key)
+			.body(r#"{"data":"dHJhbnNhY3Rpb24K"}"#)
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::OK);
+		serde_json::from_slice(response.body()).unwrap()
+	}
+
+	#[tokio::test]
+	async fn submit_route_idempotency_key() {
+		let submit_cache = SubmitCache::new(Duration::from_millis(200));
+
+		let key = "0f4a3c1e-8d2b-4e6f-9a1c-7b5d3e2f1a0b";
+		let first = submit_with_key(&submit_cache, key).await;
+
+		let duplicate = submit_with_key(&submit_cache, key).await;
+		assert_eq!(duplicate.block_hash, first.block_hash);
+
+		let other_key = "1a2b3c4d-5e6f-4a1b-8c2d-3e4f5a6b7c8d";
+		let other = submit_with_key(&submit_cache, other_key).await;
+		assert_ne!(other.block_hash, first.block_hash);
+
+		tokio::time::sleep(Duration::from_millis(300)).await;
+		let expired = submit_with_key(&submit_cache, key).await;
+		assert_ne!(expired.block_hash, first.block_hash);
+	}
+
+	/// Counts submitted transactions, and delays responses to overlap concurrent submits.
+	#[derive(Clone, Default)]
+	struct CountingSubmitter {
+		submitted: Arc<AtomicUsize>,
+	}
+
+	#[async_trait]
+	impl transactions::Submit for CountingSubmitter {
+		async fn submit(&self, transaction: Transaction) -> color_eyre::Result<SubmitResponse> {
+			self.submitted.fetch_add(1, Ordering::SeqCst);
+			tokio::time::sleep(Duration::from_millis(100)).await;
+			transactions::Submit::submit(&MockSubmitter {}, transaction).await
+		}
+	}
+
+	#[tokio::test]
+	async fn submit_idempotent_concurrent() {
+		let submitter = CountingSubmitter::default();
+		let submit_cache = submit_cache();
+		let key = Some(to_uuid("0f4a3c1e-8d2b-4e6f-9a1c-7b5d3e2f1a0b"));
+		let transaction = || Transaction::Data {
+			data: Base64(b"transaction".to_vec()),
+			app_id: Some(1),
+		};
+
+		let quota = rate_limiter().submit_quota(None);
+
+		let (first, second) = tokio::join!(
+			transactions::submit_idempotent(&submitter, &submit_cache, key, &quota, transaction()),
+			transactions::submit_idempotent(&submitter, &submit_cache, key, &quota, transaction()),
+		);
+		assert_eq!(submitter.submitted.load(Ordering::SeqCst), 1);
+		assert_eq!(first.unwrap().block_hash, second.unwrap().block_hash);
+	}
+
+	#[tokio::test]
+	async fn ws_route_submit_idempotency_key() {
+		let mut test = MockSetup::new(app_config(), Some(MockSubmitter {})).await;
+
+		let request = r#"{"type":"submit","request_id":"fca2ff0c-7a26-42a2-a6f0-d0aeeaba8a9a","idempotency_key":"0f4a3c1e-8d2b-4e6f-9a1c-7b5d3e2f1a0b","message":{"data":"dHJhbnNhY3Rpb24K"}}"#;
+		let first = test.ws_send_text(request).await;
+		let duplicate = test.ws_send_text(request).await;
+		assert_eq!(first, duplicate);
+	}
+
+	fn cells_setup(state: &Arc<Mutex<State>>, db: &mem_db::MemoryDB) {
+		{
+			let mut state = state.lock().unwrap();
+			state.latest = 1;
+			state.header_verified = Some(BlockRange::init(1));
+		}
+		let mut header = header();
+		header.extension = HeaderExtension::V3(v3::HeaderExtension {
+			commitment: KateCommitment {
+				rows: 1,
+				cols: 4,
+				commitment: vec![0u8; 2 * 48],
+				..Default::default()
+			},
+			app_lookup: CompactDataLookup {
+				size: 0,
+				index: vec![],
+			},
+		});
+		_ = db.put(Key::BlockHeader(1), header);
+	}
+
+	#[tokio::test]
+	async fn ws_route_get_cells() {
+		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
+		cells_setup(&test.state, &test.db);
+
+		let request = r#"{"type":"get-cells","request_id":"3e8d1c6a-5b2f-4d9e-a7c4-6f1b0e9d8c7a","message":{"block_number":1,"cells":[[0,0],[1,3]]}}"#;
+		let response = test.ws_send_text(request).await;
+
+		let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+		assert_eq!(response["topic"], "cells");
+		assert_eq!(
+			response["request_id"],
+			"3e8d1c6a-5b2f-4d9e-a7c4-6f1b0e9d8c7a"
+		);
+		assert_eq!(response["message"]["block_number"], 1);
+		let cells: Vec<types::Cell> =
+			serde_json::from_value(response["message"]["cells"].clone()).unwrap();
+		let positions = cells
+			.iter()
+			.map(|cell| (cell.row, cell.col))
+			.collect::<Vec<_>>();
+		assert_eq!(positions, vec![(0, 0), (1, 3)]);
+		assert!(cells.iter().all(|cell| cell.proof.0 == vec![0; 48]));
+		assert!(cells.iter().all(|cell| cell.data.0 == vec![1; 32]));
+	}
+
+	#[test_case("[[2,0]]", "Cell (2, 0) is outside of the 2x4 extended matrix" ; "Row out of bounds")]
+	#[test_case("[[0,0],[0,4]]", "Cell (0, 4) is outside of the 2x4 extended matrix" ; "Column out of bounds")]
+	#[test_case("[]", "No cells requested." ; "No cells")]
+	#[test_case("[[0,0],[0,1],[0,2]]", "Requested 3 cells, exceeding the limit of 2" ; "Too many cells")]
+	#[tokio::test]
+	async fn ws_route_get_cells_bad_request(cells: &str, expected: &str) {
+		let config = RuntimeConfig {
+			max_cells_per_request: 2,
+			..Default::default()
+		};
+		let mut test = MockSetup::new(config, None).await;
+		cells_setup(&test.state, &test.db);
+
+		let request = format!(
+			r#"{{"type":"get-cells","request_id":"3e8d1c6a-5b2f-4d9e-a7c4-6f1b0e9d8c7a","message":{{"block_number":1,"cells":{cells}}}}}"#
+		);
+		let response = test.ws_send_text(&request).await;
+
+		let WsError::Error(error) = serde_json::from_str(&response).unwrap();
+		assert_eq!(error.error_code, ErrorCode::BadRequest);
+		assert_eq!(error.message, expected);
+	}
+
+	fn data_proof_setup(state: &Arc<Mutex<State>>, db: &mem_db::MemoryDB, data_root: H256) {
+		cells_setup(state, db);
+		let mut header = db.get::<DaHeader>(Key::BlockHeader(1)).unwrap().unwrap();
+		header.extension = HeaderExtension::V3(v3::HeaderExtension {
+			commitment: KateCommitment {
+				rows: 1,
+				cols: 4,
+				data_root,
+				..Default::default()
+			},
+			app_lookup: CompactDataLookup {
+				size: 0,
+				index: vec![],
+			},
+		});
+		_ = db.put(Key::BlockHeader(1), header);
+	}
+
+	#[tokio::test]
+	async fn ws_route_get_data_proof() {
+		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
+		let expected = mock_data_proof(1);
+		data_proof_setup(&test.state, &test.db, expected.root);
+
+		let request = r#"{"type":"get-data-proof","request_id":"4c2e8a1f-6b3d-4e9a-8f7c-1d0b2a3e4f5c","message":{"block_number":1,"index":1}}"#;
+		let response = test.ws_send_text(request).await;
+
+		let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+		assert_eq!(response["topic"], "data-proof");
+		assert_eq!(
+			response["request_id"],
+			"4c2e8a1f-6b3d-4e9a-8f7c-1d0b2a3e4f5c"
+		);
+		let message: types::DataProofMessage =
+			serde_json::from_value(response["message"].clone()).unwrap();
+		assert_eq!(message.block_number, 1);
+		assert_eq!(message.data_root, expected.root);
+		assert_eq!(message.proof, expected.proof);
+		assert_eq!(message.number_of_leaves, 2);
+		assert_eq!(message.leaf_index, 1);
+		assert_eq!(message.leaf, expected.leaf);
+	}
+
+	#[test_case(H256::zero(), ErrorCode::NotFound ; "Block without data root")]
+	#[test_case(H256::repeat_byte(1), ErrorCode::InternalServerError ; "Proof of other data root")]
+	#[tokio::test]
+	async fn ws_route_get_data_proof_error(data_root: H256, error_code: ErrorCode) {
+		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
+		data_proof_setup(&test.state, &test.db, data_root);
+
+		let request = r#"{"type":"get-data-proof","request_id":"4c2e8a1f-6b3d-4e9a-8f7c-1d0b2a3e4f5c","message":{"block_number":1,"index":0}}"#;
+		let response = test.ws_send_text(request).await;
+
+		let WsError::Error(error) = serde_json::from_str(&response).unwrap();
+		assert_eq!(error.error_code, error_code);
+	}
+
+	#[test_case(false, None ; "Hidden cause")]
+	#[test_case(true, Some("Invalid data proof of the transaction 0 in block 1") ; "Exposed cause")]
+	#[tokio::test]
+	async fn ws_route_error_cause_chain(expose_error_causes: bool, expected: Option<&str>) {
+		let config = RuntimeConfig {
+			expose_error_causes,
+			..Default::default()
+		};
+		let mut test = MockSetup::new(config, None).await;
+		data_proof_setup(&test.state, &test.db, H256::repeat_byte(1));
+
+		let request = r#"{"type":"get-data-proof","request_id":"4c2e8a1f-6b3d-4e9a-8f7c-1d0b2a3e4f5c","message":{"block_number":1,"index":0}}"#;
+		let response = test.ws_send_text(request).await;
+
+		let WsError::Error(error) = serde_json::from_str(&response).unwrap();
+		assert_eq!(error.error_code, ErrorCode::InternalServerError);
+		assert_eq!(error.message, "Internal Server Error");
+		assert_eq!(error.cause_chain.as_deref(), expected);
+	}
+
+	fn verify_data_proof_request(proof: &DataProof) -> String {
+		let message = serde_json::json!({
+			"data_root": proof.root,
+			"proof": proof.proof,
+			"number_of_leaves": proof.number_of_leaves,
+			"leaf_index": proof.leaf_index,
+			"leaf": proof.leaf,
+		});
+		format!(
+			r#"{{"type":"verify-data-proof","request_id":"7d3f1b2a-9c4e-4a6b-8e5d-2c1f0a9b8e7d","message":{message}}}"#
+		)
+	}
+
+	#[test_case(mock_data_proof(1), true ; "Valid proof")]
+	#[test_case(DataProof { leaf: keccak_256(b"2").into(), ..mock_data_proof(1) }, false ; "Tampered leaf")]
+	#[test_case(DataProof { leaf_index: 0, ..mock_data_proof(1) }, false ; "Wrong index")]
+	#[tokio::test]
+	async fn ws_route_verify_data_proof(proof: DataProof, verified: bool) {
+		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
+
+		let response = test.ws_send_text(&verify_data_proof_request(&proof)).await;
+
+		let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+		assert_eq!(response["topic"], "data-proof-verified");
+		assert_eq!(
+			response["request_id"],
+			"7d3f1b2a-9c4e-4a6b-8e5d-2c1f0a9b8e7d"
+		);
+		assert_eq!(response["message"]["verified"], verified);
+	}
+
+	#[test_case(DataProof { leaf_index: 2, ..mock_data_proof(1) }, "Leaf index 2 is out of bounds of 2 leaves" ; "Index out of bounds")]
+	#[test_case(DataProof { proof: vec![], ..mock_data_proof(1) }, "Proof has 0 hashes, expected 1 for leaf index 1 of 2 leaves" ; "Missing proof hashes")]
+	#[tokio::test]
+	async fn ws_route_verify_data_proof_bad_request(proof: DataProof, expected: &str) {
+		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
+
+		let response = test.ws_send_text(&verify_data_proof_request(&proof)).await;
+
+		let WsError::Error(error) = serde_json::from_str(&response).unwrap();
+		assert_eq!(error.error_code, ErrorCode::BadRequest);
+		assert_eq!(error.message, expected);
+	}
+
+	#[tokio::test]
+	async fn ws_route_get_row() {
+		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
+		cells_setup(&test.state, &test.db);
+
+		let request = r#"{"type":"get-row","request_id":"9a4f2e7c-3d1b-4c8a-b6e5-2f0a9d8c7b6e","message":{"block_number":1,"row":1}}"#;
+		let response = test.ws_send_text(request).await;
+
+		let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+		assert_eq!(response["topic"], "row");
+		assert_eq!(
+			response["request_id"],
+			"9a4f2e7c-3d1b-4c8a-b6e5-2f0a9d8c7b6e"
+		);
+		assert_eq!(response["message"]["block_number"], 1);
+		assert_eq!(response["message"]["row"], 1);
+		let cells: Vec<types::Cell> =
+			serde_json::from_value(response["message"]["cells"].clone()).unwrap();
+		let positions = cells
+			.iter()
+			.map(|cell| (cell.row, cell.col))
+			.collect::<Vec<_>>();
+		assert_eq!(positions, vec![(1, 0), (1, 1), (1, 2), (1, 3)]);
+		assert!(cells.iter().all(|cell| cell.proof.0.len() == 48));
+	}
+
+	#[tokio::test]
+	async fn ws_route_get_row_out_of_range() {
+		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
+		cells_setup(&test.state, &test.db);
+
+		let request = r#"{"type":"get-row","request_id":"9a4f2e7c-3d1b-4c8a-b6e5-2f0a9d8c7b6e","message":{"block_number":1,"row":2}}"#;
+		let response = test.ws_send_text(request).await;
+
+		let WsError::Error(error) = serde_json::from_str(&response).unwrap();
+		assert_eq!(error.error_code, ErrorCode::BadRequest);
+		assert_eq!(error.message, "Row 2 is outside of the 2x4 extended matrix");
+	}
+
+	fn partition_config() -> RuntimeConfig {
+		RuntimeConfig {
+			block_matrix_partition: vec![Partition {
+				number: 1,
+				fraction: 2,
+			}],
+			..Default::default()
+		}
+	}
+
+	#[tokio::test]
+	async fn ws_route_reconstruct() {
+		let mut test = MockSetup::new(partition_config(), None).await;
+		cells_setup(&test.state, &test.db);
+
+		let request = r#"{"type":"reconstruct","request_id":"6b2d8f4a-1c3e-4a5b-9d7f-0e2c4a6b8d1f","message":{"block_number":1}}"#;
+		let response = test.ws_send_text(request).await;
+
+		let WsResponse::Reconstructed(response) = serde_json::from_str(&response).unwrap() else {
+			panic!("Invalid response");
+		};
+		assert_eq!(
+			response.request_id,
+			to_uuid("6b2d8f4a-1c3e-4a5b-9d7f-0e2c4a6b8d1f")
+		);
+		assert_eq!(response.message.block_number, 1);
+		assert_eq!(response.message.partition_cells, 4);
+		assert_eq!(response.message.columns, 4);
+	}
+
+	#[tokio::test]
+	async fn ws_route_reconstruct_not_partition_mode() {
+		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
+		cells_setup(&test.state, &test.db);
+
+		let request = r#"{"type":"reconstruct","request_id":"6b2d8f4a-1c3e-4a5b-9d7f-0e2c4a6b8d1f","message":{"block_number":1}}"#;
+		let response = test.ws_send_text(request).await;
+
+		let WsError::Error(error) = serde_json::from_str(&response).unwrap();
+		assert_eq!(error.error_code, ErrorCode::BadRequest);
+	}
+
+	/// Fetches cells which fail verification against the commitments.
+	struct UnverifiedCellFetcher {}
+
+	#[async_trait]
+	impl cells::Fetch for UnverifiedCellFetcher {
+		async fn fetch(&self, _: H256, positions: &[Position]) -> color_eyre::Result<Vec<Cell>> {
+			cells::Fetch::fetch(&MockCellFetcher {}, H256::zero(), positions).await
+		}
+
+		async fn fetch_data_proof(&self, _: H256, _: u32) -> color_eyre::Result<DataProof> {
+			Err(color_eyre::eyre::eyre!("Data proof is not available"))
+		}
+
+		async fn fetch_extrinsics(&self, _: H256) -> color_eyre::Result<Vec<Vec<u8>>> {
+			Err(color_eyre::eyre::eyre!("Extrinsics are not available"))
+		}
+
+		async fn fetch_verified(
+			&self,
+			_: u32,
+			_: H256,
+			_: Dimensions,
+			_: &[[u8; 48]],
+			_: &[Position],
+		) -> color_eyre::Result<Vec<Cell>> {
+			Ok(vec![])
+		}
+	}
+
+	#[tokio::test]
+	async fn ws_route_reconstruct_cells_unavailable() {
+		let state = Arc::new(Mutex::new(State::default()));
+		let db = mem_db::MemoryDB::default();
+		cells_setup(&state, &db);
+		let mut test = MockSetup::builder()
+			.config(partition_config())
+			.cell_fetcher(UnverifiedCellFetcher {})
+			.state(state)
+			.db(db)
+			.build()
+			.await;
+
+		let request = r#"{"type":"reconstruct","request_id":"6b2d8f4a-1c3e-4a5b-9d7f-0e2c4a6b8d1f","message":{"block_number":1}}"#;
 		let response = test.ws_send_text(request).await;
 
-		let WsResponse::DataTransactionSubmitted(response) =
-			serde_json::from_str(&response).unwrap()
-		else {
+		let WsError::Error(error) = serde_json::from_str(&response).unwrap();
+		assert_eq!(error.error_code, ErrorCode::ServiceUnavailable);
+		assert_eq!(
+			error.request_id,
+			Some(to_uuid("6b2d8f4a-1c3e-4a5b-9d7f-0e2c4a6b8d1f"))
+		);
+		assert_eq!(
+			error.message,
+			"Cannot reconstruct block 1, 4 more cells are needed"
+		);
+	}
+
+	/// Block with 2x4 extended matrix, with 8 cells already verified by the light client
+	fn confidence_setup(state: &Arc<Mutex<State>>, db: &mem_db::MemoryDB) {
+		cells_setup(state, db);
+		_ = db.put(Key::VerifiedCellCount(1), 8);
+	}
+
+	#[test_case("null", 8 ; "Configured target is already met")]
+	#[test_case("99.9", 10 ; "Target is met with additional sampling")]
+	#[test_case("99.9999", 14 ; "Sampling is capped")]
+	#[tokio::test]
+	async fn ws_route_get_confidence(confidence_target: &str, expected_cell_count: u32) {
+		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
+		confidence_setup(&test.state, &test.db);
+
+		let request = format!(
+			r#"{{"type":"get-confidence","request_id":"7d3b9e1a-2c4f-4a8b-9e6d-5f0c1b2a3d4e","message":{{"block_number":1,"confidence_target":{confidence_target}}}}}"#
+		);
+		let response = test.ws_send_text(&request).await;
+
+		let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+		assert_eq!(response["topic"], "confidence");
+		assert_eq!(response["message"]["block_number"], 1);
+		assert_eq!(response["message"]["cell_count"], expected_cell_count);
+		assert_eq!(
+			response["message"]["confidence"],
+			calculate_confidence(expected_cell_count)
+		);
+	}
+
+	#[test_case("0" ; "Zero target")]
+	#[test_case("100" ; "Full target")]
+	#[test_case("120" ; "Target above 100")]
+	#[test_case("-1" ; "Negative target")]
+	#[tokio::test]
+	async fn ws_route_get_confidence_invalid_target(confidence_target: &str) {
+		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
+		confidence_setup(&test.state, &test.db);
+
+		let request = format!(
+			r#"{{"type":"get-confidence","request_id":"7d3b9e1a-2c4f-4a8b-9e6d-5f0c1b2a3d4e","message":{{"block_number":1,"confidence_target":{confidence_target}}}}}"#
+		);
+		let response = test.ws_send_text(&request).await;
+
+		let WsError::Error(error) = serde_json::from_str(&response).unwrap();
+		assert_eq!(error.error_code, ErrorCode::BadRequest);
+		assert_eq!(
+			error.message,
+			"Confidence target must be greater than 0 and less than 100"
+		);
+	}
+
+	#[test_case(8, "99.9", 8 ; "All cells are already verified")]
+	#[test_case(4, "99.9999", 8 ; "Only unverified cells are sampled")]
+	#[tokio::test]
+	async fn ws_route_get_confidence_without_replacement(
+		verified_count: u32,
+		confidence_target: &str,
+		expected_cell_count: u32,
+	) {
+		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
+		confidence_setup(&test.state, &test.db);
+
+		// Extended matrix has 2 rows and 4 columns
+		let positions = (0..2u32)
+			.flat_map(|row| (0..4u16).map(move |col| (row, col)))
+			.take(verified_count as usize)
+			.collect::<Vec<_>>();
+		_ = test.db.put(Key::VerifiedCellCount(1), verified_count);
+		_ = test.db.put(Key::VerifiedCellPositions(1), positions);
+
+		let request = format!(
+			r#"{{"type":"get-confidence","request_id":"7d3b9e1a-2c4f-4a8b-9e6d-5f0c1b2a3d4e","message":{{"block_number":1,"confidence_target":{confidence_target}}}}}"#
+		);
+		let response = test.ws_send_text(&request).await;
+
+		let WsResponse::Confidence(response) = serde_json::from_str(&response).unwrap() else {
 			panic!("Invalid response");
 		};
-		let expected_request_id = to_uuid("fca2ff0c-7a26-42a2-a6f0-d0aeeaba8a9a");
-		assert_eq!(response.request_id, expected_request_id);
-		assert_eq!(response.message.index, 0);
+		assert_eq!(response.message.cell_count, expected_cell_count);
+		assert!(!response.message.cached);
+	}
+
+	#[test_case(Some(2), 2, true ; "Stored confidence is returned")]
+	#[test_case(None, 10, false ; "Confidence is recomputed")]
+	#[tokio::test]
+	async fn ws_route_get_confidence_stored(
+		verified_count: Option<u32>,
+		expected_cell_count: u32,
+		expected_cached: bool,
+	) {
+		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
+		confidence_setup(&test.state, &test.db);
+		match verified_count {
+			Some(count) => _ = test.db.put(Key::VerifiedCellCount(1), count),
+			None => _ = test.db.delete(Key::VerifiedCellCount(1)),
+		}
+
+		let request = r#"{"type":"get-confidence","request_id":"7d3b9e1a-2c4f-4a8b-9e6d-5f0c1b2a3d4e","message":{"block_number":1}}"#;
+		let response = test.ws_send_text(request).await;
+
+		let WsResponse::Confidence(response) = serde_json::from_str(&response).unwrap() else {
+			panic!("Invalid response");
+		};
+		assert_eq!(response.message.block_number, 1);
+		assert_eq!(response.message.cell_count, expected_cell_count);
+		assert_eq!(response.message.cached, expected_cached);
+		assert_eq!(
+			response.message.confidence,
+			calculate_confidence(expected_cell_count)
+		);
+	}
+
+	#[tokio::test]
+	async fn ws_route_get_confidence_not_found() {
+		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
+
+		let request = r#"{"type":"get-confidence","request_id":"7d3b9e1a-2c4f-4a8b-9e6d-5f0c1b2a3d4e","message":{"block_number":1}}"#;
+		let response = test.ws_send_text(request).await;
+
+		let WsError::Error(error) = serde_json::from_str(&response).unwrap();
+		assert_eq!(error.error_code, ErrorCode::NotFound);
+	}
+
+	#[test_case("null", &format!("0x{}", "0".repeat(96)) ; "Default encoding")]
+	#[test_case(r#""hex""#, &format!("0x{}", "0".repeat(96)) ; "Hex encoding")]
+	#[test_case(r#""base64""#, &"A".repeat(64) ; "Base64 encoding")]
+	#[tokio::test]
+	async fn ws_route_get_commitments(encoding: &str, expected_commitment: &str) {
+		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
+		confidence_setup(&test.state, &test.db);
+
+		let request = format!(
+			r#"{{"type":"get-commitments","request_id":"2f8c4e6a-9b1d-4c3e-8a5f-7d0b2e4c6a8f","message":{{"block_number":1,"encoding":{encoding}}}}}"#
+		);
+		let response = test.ws_send_text(&request).await;
+
+		let header = test
+			.db
+			.get::<DaHeader>(Key::BlockHeader(1))
+			.unwrap()
+			.unwrap();
+		let HeaderExtension::V3(extension) = header.extension;
+		let expected_count = extension.commitment.commitment.len() / 48;
+
+		let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+		assert_eq!(response["topic"], "commitments");
+		assert_eq!(
+			response["request_id"],
+			"2f8c4e6a-9b1d-4c3e-8a5f-7d0b2e4c6a8f"
+		);
+		assert_eq!(response["message"]["block_number"], 1);
+		assert_eq!(response["message"]["rows"], extension.commitment.rows);
+		assert_eq!(response["message"]["cols"], extension.commitment.cols);
+		let commitments = response["message"]["commitments"].as_array().unwrap();
+		assert_eq!(commitments.len(), expected_count);
+		assert!(commitments
+			.iter()
+			.all(|commitment| commitment == expected_commitment));
+	}
+
+	#[tokio::test]
+	async fn ws_route_get_commitments_not_found() {
+		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
+
+		let request = r#"{"type":"get-commitments","request_id":"2f8c4e6a-9b1d-4c3e-8a5f-7d0b2e4c6a8f","message":{"block_number":1}}"#;
+		let response = test.ws_send_text(request).await;
+
+		let WsError::Error(error) = serde_json::from_str(&response).unwrap();
+		assert_eq!(error.error_code, ErrorCode::NotFound);
+	}
+
+	#[tokio::test]
+	async fn ws_route_get_raw_header() {
+		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
+		confidence_setup(&test.state, &test.db);
+
+		let request = r#"{"type":"get-raw-header","request_id":"2f8c4e6a-9b1d-4c3e-8a5f-7d0b2e4c6a8f","message":{"block_number":1}}"#;
+		let response = test.ws_send_text(request).await;
+
+		let WsResponse::RawHeader(response) = serde_json::from_str(&response).unwrap() else {
+			panic!("Invalid response");
+		};
+		let expected = test
+			.db
+			.get::<DaHeader>(Key::BlockHeader(1))
+			.unwrap()
+			.unwrap();
+		let header = DaHeader::decode(&mut &response.message.header.0[..]).unwrap();
+		assert_eq!(response.message.block_number, 1);
+		assert_eq!(header.number, expected.number);
+		assert_eq!(header.encode(), expected.encode());
+		assert_eq!(response.message.header.0, expected.encode());
+		assert_eq!(
+			response.message.hash,
+			H256::from(sp_core::blake2_256(&expected.encode()))
+		);
+	}
+
+	#[tokio::test]
+	async fn ws_route_get_raw_header_not_found() {
+		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
+
+		let request = r#"{"type":"get-raw-header","request_id":"2f8c4e6a-9b1d-4c3e-8a5f-7d0b2e4c6a8f","message":{"block_number":1}}"#;
+		let response = test.ws_send_text(request).await;
+
+		let WsError::Error(error) = serde_json::from_str(&response).unwrap();
+		assert_eq!(error.error_code, ErrorCode::NotFound);
+	}
+
+	fn extrinsic_proof_setup(state: &Arc<Mutex<State>>, db: &mem_db::MemoryDB) {
+		confidence_setup(state, db);
+		let mut header = db.get::<DaHeader>(Key::BlockHeader(1)).unwrap().unwrap();
+		(header.extrinsics_root, _) = proof::extrinsic_proof(&mock_extrinsics(), 0).unwrap();
+		_ = db.put(Key::BlockHeader(1), header);
+	}
+
+	#[test_case(0 ; "First extrinsic")]
+	#[test_case(2 ; "Last extrinsic")]
+	#[tokio::test]
+	async fn ws_route_get_extrinsic_proof(index: u32) {
+		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
+		extrinsic_proof_setup(&test.state, &test.db);
+
+		let request = format!(
+			r#"{{"type":"get-extrinsic-proof","request_id":"4b7e2d9a-1c3f-4e5a-8b6d-0f9e8d7c6b5a","message":{{"block_number":1,"index":{index}}}}}"#
+		);
+		let response = test.ws_send_text(&request).await;
+
+		let WsResponse::ExtrinsicProof(response) = serde_json::from_str(&response).unwrap() else {
+			panic!("Invalid response");
+		};
+		let header = test
+			.db
+			.get::<DaHeader>(Key::BlockHeader(1))
+			.unwrap()
+			.unwrap();
+		let message = response.message;
+		assert_eq!(message.block_number, 1);
+		assert_eq!(message.index, index);
+		assert_eq!(message.extrinsics_root, header.extrinsics_root);
+		assert_eq!(message.extrinsic.0, mock_extrinsics()[index as usize]);
+		let proof: Vec<Vec<u8>> = message.proof.into_iter().map(|Base64(node)| node).collect();
+		assert!(proof::verify_extrinsic_proof(
+			header.extrinsics_root,
+			&proof,
+			index,
+			&message.extrinsic.0
+		));
+	}
+
+	#[test_case(1, 3, ErrorCode::BadRequest ; "Index out of range")]
+	#[test_case(2, 0, ErrorCode::NotFound ; "Block not found")]
+	#[tokio::test]
+	async fn ws_route_get_extrinsic_proof_error(
+		block_number: u32,
+		index: u32,
+		expected: ErrorCode,
+	) {
+		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
+		extrinsic_proof_setup(&test.state, &test.db);
+
+		let request = format!(
+			r#"{{"type":"get-extrinsic-proof","request_id":"4b7e2d9a-1c3f-4e5a-8b6d-0f9e8d7c6b5a","message":{{"block_number":{block_number},"index":{index}}}}}"#
+		);
+		let response = test.ws_send_text(&request).await;
+
+		let WsError::Error(error) = serde_json::from_str(&response).unwrap();
+		assert_eq!(error.error_code, expected);
+	}
+
+	#[tokio::test]
+	async fn ws_route_get_extrinsic_proof_invalid_root() {
+		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
+		confidence_setup(&test.state, &test.db);
+
+		let request = r#"{"type":"get-extrinsic-proof","request_id":"4b7e2d9a-1c3f-4e5a-8b6d-0f9e8d7c6b5a","message":{"block_number":1,"index":0}}"#;
+		let response = test.ws_send_text(request).await;
+
+		let WsError::Error(error) = serde_json::from_str(&response).unwrap();
+		assert_eq!(error.error_code, ErrorCode::InternalServerError);
+	}
+
+	#[derive(Clone, Default)]
+	struct LogCapture(Arc<Mutex<Vec<u8>>>);
+
+	impl std::io::Write for LogCapture {
+		fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+			self.0.lock().unwrap().extend_from_slice(buf);
+			Ok(buf.len())
+		}
+
+		fn flush(&mut self) -> std::io::Result<()> {
+			Ok(())
+		}
+	}
+
+	impl<'a> tracing_subscriber::fmt::MakeWriter<'a> for LogCapture {
+		type Writer = Self;
+
+		fn make_writer(&'a self) -> Self::Writer {
+			self.clone()
+		}
+	}
+
+	#[tokio::test]
+	async fn ws_route_request_logging() {
+		let logs = LogCapture::default();
+		let subscriber = tracing_subscriber::fmt()
+			.json()
+			.with_current_span(true)
+			.with_writer(logs.clone())
+			.finish();
+		let _guard = tracing::subscriber::set_default(subscriber);
+
+		let mut test = MockSetup::new(RuntimeConfig::default(), None).await;
+		let request = r#"{"type":"version","request_id":"cae63fff-c4b8-4af9-b4fe-0605a5329aa0"}"#;
+		test.ws_send_text(request).await;
+
+		let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+		let event = logs
+			.lines()
+			.filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+			.find(|event| event["fields"]["message"] == "Request handled")
+			.expect("Request handled event");
+
+		assert_eq!(event["fields"]["outcome"], "ok");
+		assert!(event["fields"]["elapsed_ms"].is_u64());
+		assert_eq!(event["span"]["name"], "ws_request");
+		assert_eq!(event["span"]["request_type"], "version");
+		assert_eq!(
+			event["span"]["request_id"],
+			"cae63fff-c4b8-4af9-b4fe-0605a5329aa0"
+		);
+	}
+
+	#[tokio::test]
+	async fn rate_limited_route() {
+		let state = Arc::new(Mutex::new(State::default()));
+		let config = RuntimeConfig {
+			rate_limit_requests_per_second: 0.001,
+			rate_limit_burst: 1,
+			api_keys: vec!["key".into()],
+			..Default::default()
+		};
+		let route = super::with_rate_limit(
+			RateLimiter::from(&config),
+			super::version_route(v1(), state),
+		)
+		.recover(super::handlers::handle_rejection);
+
+		let request = || warp::test::request().method("GET").path("/v2/version");
+		let response = request().reply(&route).await;
+		assert_eq!(response.status(), StatusCode::OK);
+
+		let response = request().reply(&route).await;
+		assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+		assert_eq!(response.headers()["retry-after"], "1000");
+		let error: Error = serde_json::from_slice(response.body()).unwrap();
+		assert_eq!(error.error_code, ErrorCode::TooManyRequests);
+
+		// Requests with configured API key have their own bucket
+		let response = request()
+			.header("authorization", "Bearer key")
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::OK);
+
+		// Unknown API keys are limited by IP address
+		let response = request()
+			.header("authorization", "Bearer unknown")
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+	}
+
+	#[tokio::test]
+	async fn submit_route_quota_exceeded() {
+		let config = RuntimeConfig {
+			app_id: Some(1),
+			api_keys: vec![
+				ApiKey {
+					key: "basic".to_string(),
+					submit_quota: Some(1),
+					rate_limit_requests_per_second: None,
+					rate_limit_burst: None,
+					admin: false,
+				},
+				"premium".into(),
+			],
+			..Default::default()
+		};
+		let route = super::submit_route(
+			Some(Arc::new(MockSubmitter {})),
+			submit_cache(),
+			config.clone(),
+			RateLimiter::from(&config),
+		)
+		.recover(super::handle_rejection);
+		let request = |api_key: &str| {
+			warp::test::request()
+				.method("POST")
+				.path("/v2/submit")
+				.header("authorization", format!("Bearer {api_key}"))
+				.body(r#"{"data":"dHJhbnNhY3Rpb24K"}"#)
+		};
+
+		let response = request("basic").reply(&route).await;
+		assert_eq!(response.status(), StatusCode::OK);
+
+		let response = request("basic").reply(&route).await;
+		assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+		assert_eq!(response.headers()["retry-after"], "86400");
+		let error: Error = serde_json::from_slice(response.body()).unwrap();
+		assert_eq!(error.error_code, ErrorCode::TooManyRequests);
+		assert_eq!(
+			error.message,
+			"Submit quota exceeded, quota resets in 86400 seconds"
+		);
+
+		// Other API keys are not affected
+		let response = request("premium").reply(&route).await;
+		assert_eq!(response.status(), StatusCode::OK);
+	}
+
+	fn quota_config(submit_quota: u32) -> RuntimeConfig {
+		RuntimeConfig {
+			app_id: Some(1),
+			api_keys: vec![ApiKey {
+				key: "basic".to_string(),
+				submit_quota: Some(submit_quota),
+				rate_limit_requests_per_second: None,
+				rate_limit_burst: None,
+				admin: false,
+			}],
+			..Default::default()
+		}
+	}
+
+	#[tokio::test]
+	async fn submit_route_quota_charged_per_submitted_transaction() {
+		let config = quota_config(1);
+		let route = super::submit_route(
+			Some(Arc::new(MockSubmitter {})),
+			submit_cache(),
+			config.clone(),
+			RateLimiter::from(&config),
+		)
+		.recover(super::handle_rejection);
+		let request = |body: &str, idempotency_key: &str| {
+			warp::test::request()
+				.method("POST")
+				.path("/v2/submit")
+				.header("authorization", "Bearer basic")
+				.header("idempotency-key", idempotency_key)
+				.body(body.to_string())
+		};
+		let first_key = "0f4a3c1e-8d2b-4e6f-9a1c-7b5d3e2f1a0b";
+		let second_key = "6a1e2c3d-4b5f-4a6e-8c7d-9e0f1a2b3c4d";
+
+		// Invalid transaction is not charged
+		let response = request(r#"{"data":""}"#, first_key).reply(&route).await;
+		assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+		let response = request(r#"{"data":"dHJhbnNhY3Rpb24K"}"#, first_key)
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::OK);
+
+		// Idempotent retry is served from cache and is not charged
+		let response = request(r#"{"data":"dHJhbnNhY3Rpb24K"}"#, first_key)
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::OK);
+
+		let response = request(r#"{"data":"dHJhbnNhY3Rpb24K"}"#, second_key)
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+	}
+
+	#[tokio::test]
+	async fn submit_batch_route_quota_exceeded() {
+		let config = quota_config(1);
+		let route = super::submit_batch_route(
+			Some(Arc::new(MockSubmitter {})),
+			config.clone(),
+			RateLimiter::from(&config),
+		);
+		let response = warp::test::request()
+			.method("POST")
+			.path("/v2/submit/batch")
+			.header("authorization", "Bearer basic")
+			.body(r#"[{"data":"dHJhbnNhY3Rpb24K"},{"data":""},{"data":"dHJhbnNhY3Rpb24K"}]"#)
+			.reply(&route)
+			.await;
+
+		assert_eq!(response.status(), StatusCode::OK);
+		let SubmitBatchResponse(results) = serde_json::from_slice(response.body()).unwrap();
+		assert_eq!(results.len(), 3);
+		assert!(matches!(&results[0], SubmitResult::Submitted(_)));
+		// Invalid transaction is not charged, so the quota is exceeded by the third one
+		let SubmitResult::Error(error) = &results[1] else {
+			panic!("Empty transaction is submitted");
+		};
+		assert_eq!(error.error_code, ErrorCode::BadRequest);
+		let SubmitResult::Error(error) = &results[2] else {
+			panic!("Transaction exceeding the quota is submitted");
+		};
+		assert_eq!(error.error_code, ErrorCode::TooManyRequests);
+	}
+
+	#[tokio::test]
+	async fn ws_route_rate_limited() {
+		let config = RuntimeConfig {
+			rate_limit_requests_per_second: 0.001,
+			rate_limit_burst: 2,
+			..Default::default()
+		};
+		let mut test = MockSetup::new(config, None).await;
+		let request = r#"{"type":"version","request_id":"cae63fff-c4b8-4af9-b4fe-0605a5329aa0"}"#;
+
+		for _ in 0..2 {
+			let response = test.ws_send_text(request).await;
+			let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+			assert_eq!(response["topic"], "version");
+		}
+
+		let response = test.ws_send_text(request).await;
+		let WsError::Error(error) = serde_json::from_str(&response).unwrap();
+		assert_eq!(error.error_code, ErrorCode::TooManyRequests);
+		assert_eq!(
+			error.request_id,
+			Some(uuid::uuid!("cae63fff-c4b8-4af9-b4fe-0605a5329aa0"))
+		);
+		assert_eq!(
+			error.message,
+			"Rate limit exceeded, retry after 1000 seconds"
+		);
+	}
+
+	#[tokio::test]
+	async fn ws_route_evict_lagging() {
+		let config = RuntimeConfig {
+			ws_buffer_size: 4,
+			ws_buffer_policy: WsBufferPolicy::Evict,
+			ws_buffer_grace_period: 0,
+			..Default::default()
+		};
+		let clients = WsClients::default();
+		let subscription_id = uuid::Uuid::new_v4().to_string();
+		let subscription = Subscription {
+			topics: HashSet::from([Topic::StatusChanged]),
+			..Default::default()
+		};
+		clients.subscribe(&subscription_id, subscription).await;
+		let setup = MockSetup::builder()
+			.config(config.clone())
+			.clients(clients.clone());
+		let metrics = setup.metrics.clone();
+		let route = setup.route();
+		let (address, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+		tokio::spawn(server);
+
+		// Client completes the handshake, but never reads the published messages
+		let mut stream = TcpStream::connect(address).await.unwrap();
+		let request = format!(
+			"GET /v2/ws/{subscription_id} HTTP/1.1\r\nHost: {address}\r\nConnection: Upgrade\r\nUpgrade: websocket\r\nSec-WebSocket-Version: 13\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n"
+		);
+		stream.write_all(request.as_bytes()).await.unwrap();
+		let mut response = [0u8; 1024];
+		let size = stream.read(&mut response).await.unwrap();
+		assert!(String::from_utf8_lossy(&response[..size]).starts_with("HTTP/1.1 101"));
+		while clients.clients.read().await[&subscription_id]
+			.sender
+			.is_none()
+		{
+			tokio::time::sleep(Duration::from_millis(10)).await;
+		}
+
+		tokio::spawn(super::evict_lagging(
+			clients.clone(),
+			Duration::from_millis(10),
+			config.ws_buffer_size,
+			Duration::ZERO,
+			metrics.clone(),
+		));
+
+		// Published messages fill the connection until the client buffer stays full
+		let padding = "0".repeat(64 * 1024);
+		let published = async {
+			while clients.has_subscription(&subscription_id).await {
+				let message =
+					PublishMessage::StatusChanged(serde_json::json!({ "padding": padding }));
+				_ = clients.publish(&Topic::StatusChanged, message).await;
+				tokio::time::sleep(Duration::from_millis(1)).await;
+			}
+		};
+		tokio::time::timeout(Duration::from_secs(10), published)
+			.await
+			.expect("Client is evicted");
+
+		// Connection is closed by the server after the messages already sent
+		let mut received = vec![];
+		tokio::time::timeout(Duration::from_secs(10), stream.read_to_end(&mut received))
+			.await
+			.expect("Connection is closed")
+			.unwrap();
 	}
 }
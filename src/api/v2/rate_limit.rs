@@ -0,0 +1,460 @@
+use std::{
+	collections::HashMap,
+	net::{IpAddr, Ipv4Addr, SocketAddr},
+	sync::Arc,
+	time::{Duration, Instant},
+};
+use tokio::sync::RwLock;
+
+use crate::types::RuntimeConfig;
+
+/// Maximum number of tracked clients, split between the current and the previous generation.
+const MAX_BUCKETS: usize = 10_000;
+
+/// Key by which requests are rate limited, configured API key if client sends it, otherwise client IP address.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub enum ClientKey {
+	ApiKey(String),
+	Ip(IpAddr),
+}
+
+/// Returns API key from the `Authorization: Bearer {api-key}` header value.
+pub fn bearer_api_key(authorization: &str) -> Option<&str> {
+	authorization
+		.strip_prefix("Bearer ")
+		.map(str::trim)
+		.filter(|api_key| !api_key.is_empty())
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Limit {
+	rate: f64,
+	burst: f64,
+}
+
+struct Bucket {
+	limit: Limit,
+	tokens: f64,
+	updated_at: Instant,
+}
+
+impl Bucket {
+	fn refill(&mut self, now: Instant) {
+		let elapsed = now.saturating_duration_since(self.updated_at).as_secs_f64();
+		self.tokens = (self.tokens + elapsed * self.limit.rate).min(self.limit.burst);
+		self.updated_at = now;
+	}
+}
+
+/// Buckets of the recently active clients. When the current generation is full, it replaces
+/// the previous one, so buckets of clients inactive for a whole generation are dropped.
+/// Used buckets are moved to the current generation, which approximates LRU eviction,
+/// without scanning all buckets when a new client arrives.
+struct Buckets {
+	generation_size: usize,
+	current: HashMap<ClientKey, Bucket>,
+	previous: HashMap<ClientKey, Bucket>,
+}
+
+impl Buckets {
+	fn new(generation_size: usize) -> Self {
+		Buckets {
+			generation_size: generation_size.max(1),
+			current: HashMap::new(),
+			previous: HashMap::new(),
+		}
+	}
+
+	fn get_or_insert(&mut self, key: &ClientKey, bucket: impl FnOnce() -> Bucket) -> &mut Bucket {
+		if !self.current.contains_key(key) {
+			let bucket = self.previous.remove(key).unwrap_or_else(bucket);
+			if self.current.len() >= self.generation_size {
+				self.previous = std::mem::take(&mut self.current);
+			}
+			self.current.insert(key.clone(), bucket);
+		}
+		self.current.get_mut(key).expect("Bucket is inserted")
+	}
+}
+
+struct Usage {
+	count: u32,
+	window_started_at: Instant,
+}
+
+/// Token bucket rate limiter. Each client can make `burst` requests at once,
+/// and bucket is refilled with `rate` requests per second.
+/// Configured API keys can have their own rate limits and submit quotas.
+#[derive(Clone)]
+pub struct RateLimiter {
+	limit: Limit,
+	/// Rate limits of the configured API keys, keys without own limit use the default one
+	key_limits: Arc<HashMap<String, Option<Limit>>>,
+	buckets: Arc<RwLock<Buckets>>,
+	quota_window: Duration,
+	quotas: Arc<HashMap<String, u32>>,
+	usage: Arc<RwLock<HashMap<String, Usage>>>,
+}
+
+impl RateLimiter {
+	/// Creates rate limiter, which is disabled if the rate is not positive.
+	pub fn new(rate: f64, burst: u32) -> Self {
+		RateLimiter {
+			limit: Limit {
+				rate,
+				burst: burst.max(1) as f64,
+			},
+			key_limits: Default::default(),
+			buckets: Arc::new(RwLock::new(Buckets::new(MAX_BUCKETS / 2))),
+			quota_window: Duration::from_secs(86400),
+			quotas: Default::default(),
+			usage: Default::default(),
+		}
+	}
+
+	/// Returns the key by which client is rate limited.
+	/// Unknown API keys are ignored, so clients cannot bypass the limit by changing keys.
+	pub fn client_key(
+		&self,
+		authorization: Option<String>,
+		remote: Option<SocketAddr>,
+	) -> ClientKey {
+		let api_key = authorization
+			.as_deref()
+			.and_then(bearer_api_key)
+			.filter(|api_key| self.key_limits.contains_key(*api_key));
+
+		match (api_key, remote) {
+			(Some(api_key), _) => ClientKey::ApiKey(api_key.to_string()),
+			(None, Some(remote)) => ClientKey::Ip(remote.ip()),
+			(None, None) => ClientKey::Ip(Ipv4Addr::UNSPECIFIED.into()),
+		}
+	}
+
+	fn limit(&self, key: &ClientKey) -> Option<Limit> {
+		let key_limit = match key {
+			ClientKey::ApiKey(api_key) => self.key_limits.get(api_key).copied().flatten(),
+			ClientKey::Ip(_) => None,
+		};
+		Some(key_limit.unwrap_or(self.limit)).filter(|limit| limit.rate > 0.0)
+	}
+
+	/// Takes one token from the client bucket.
+	/// Returns time after which the request can be retried, if the bucket is empty.
+	pub async fn acquire(&self, key: &ClientKey) -> Result<(), Duration> {
+		self.acquire_at(key, Instant::now()).await
+	}
+
+	async fn acquire_at(&self, key: &ClientKey, now: Instant) -> Result<(), Duration> {
+		let Some(limit) = self.limit(key) else {
+			return Ok(());
+		};
+
+		let mut buckets = self.buckets.write().await;
+		let bucket = buckets.get_or_insert(key, || Bucket {
+			limit,
+			tokens: limit.burst,
+			updated_at: now,
+		});
+		bucket.refill(now);
+
+		if bucket.tokens < 1.0 {
+			let retry_after = (1.0 - bucket.tokens) / limit.rate;
+			return Err(Duration::from_secs_f64(retry_after));
+		}
+		bucket.tokens -= 1.0;
+		Ok(())
+	}
+
+	/// Counts submitted transaction against the API key quota.
+	/// Returns time after which the quota is reset, if the quota is exceeded.
+	pub async fn consume_quota(&self, api_key: Option<&str>) -> Result<(), Duration> {
+		self.consume_quota_at(api_key, Instant::now()).await
+	}
+
+	async fn consume_quota_at(&self, api_key: Option<&str>, now: Instant) -> Result<(), Duration> {
+		let Some((api_key, &quota)) = api_key.and_then(|key| self.quotas.get_key_value(key)) else {
+			return Ok(());
+		};
+
+		let mut usage = self.usage.write().await;
+		let usage = usage.entry(api_key.clone()).or_insert(Usage {
+			count: 0,
+			window_started_at: now,
+		});
+		if now.saturating_duration_since(usage.window_started_at) >= self.quota_window {
+			usage.count = 0;
+			usage.window_started_at = now;
+		}
+
+		if usage.count >= quota {
+			let reset_at = usage.window_started_at + self.quota_window;
+			return Err(reset_at.saturating_duration_since(now));
+		}
+		usage.count += 1;
+		Ok(())
+	}
+
+	/// Binds submit quota to the API key.
+	pub fn submit_quota(&self, api_key: Option<&str>) -> SubmitQuota {
+		SubmitQuota {
+			limiter: self.clone(),
+			api_key: api_key.map(String::from),
+		}
+	}
+
+	/// Binds rate limiter to the client.
+	pub fn client(&self, key: ClientKey) -> ClientRateLimiter {
+		ClientRateLimiter {
+			limiter: self.clone(),
+			key,
+		}
+	}
+}
+
+impl From<&RuntimeConfig> for RateLimiter {
+	fn from(config: &RuntimeConfig) -> Self {
+		let limiter = RateLimiter::new(
+			config.rate_limit_requests_per_second,
+			config.rate_limit_burst,
+		);
+
+		let key_limits = config
+			.api_keys
+			.iter()
+			.map(|api_key| {
+				let limit = api_key.rate_limit_requests_per_second.map(|rate| Limit {
+					rate,
+					burst: api_key
+						.rate_limit_burst
+						.unwrap_or(config.rate_limit_burst)
+						.max(1) as f64,
+				});
+				(api_key.key.clone(), limit)
+			})
+			.collect();
+
+		let quotas = config
+			.api_keys
+			.iter()
+			.filter_map(|api_key| Some((api_key.key.clone(), api_key.submit_quota?)))
+			.collect();
+
+		RateLimiter {
+			key_limits: Arc::new(key_limits),
+			quota_window: Duration::from_secs(config.api_key_quota_window),
+			quotas: Arc::new(quotas),
+			..limiter
+		}
+	}
+}
+
+/// Rate limiter of a single client, used to limit requests on WebSocket connection.
+#[derive(Clone)]
+pub struct ClientRateLimiter {
+	limiter: RateLimiter,
+	key: ClientKey,
+}
+
+impl ClientRateLimiter {
+	pub async fn acquire(&self) -> Result<(), Duration> {
+		self.limiter.acquire(&self.key).await
+	}
+
+	pub fn submit_quota(&self, api_key: Option<&str>) -> SubmitQuota {
+		self.limiter.submit_quota(api_key)
+	}
+}
+
+/// Submit quota of the API key used by the client, which is charged per submitted transaction.
+#[derive(Clone)]
+pub struct SubmitQuota {
+	limiter: RateLimiter,
+	api_key: Option<String>,
+}
+
+impl SubmitQuota {
+	/// Counts submitted transaction against the API key quota.
+	/// Returns time after which the quota is reset, if the quota is exceeded.
+	pub async fn consume(&self) -> Result<(), Duration> {
+		self.limiter.consume_quota(self.api_key.as_deref()).await
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Bucket, Buckets, ClientKey, Limit, RateLimiter};
+	use crate::types::{ApiKey, RuntimeConfig};
+	use std::{
+		net::{IpAddr, Ipv4Addr, SocketAddr},
+		time::{Duration, Instant},
+	};
+
+	fn ip_key(last: u8) -> ClientKey {
+		ClientKey::Ip(IpAddr::V4(Ipv4Addr::new(10, 0, 0, last)))
+	}
+
+	fn api_key(key: &str) -> ClientKey {
+		ClientKey::ApiKey(key.to_string())
+	}
+
+	fn limiter_with_keys() -> RateLimiter {
+		let config = RuntimeConfig {
+			rate_limit_requests_per_second: 1.0,
+			rate_limit_burst: 1,
+			api_keys: vec![
+				ApiKey::from("default"),
+				ApiKey {
+					key: "premium".to_string(),
+					submit_quota: Some(2),
+					rate_limit_requests_per_second: Some(10.0),
+					rate_limit_burst: Some(3),
+					admin: false,
+				},
+				ApiKey {
+					key: "basic".to_string(),
+					submit_quota: Some(1),
+					rate_limit_requests_per_second: None,
+					rate_limit_burst: None,
+					admin: false,
+				},
+			],
+			api_key_quota_window: 60,
+			..Default::default()
+		};
+		RateLimiter::from(&config)
+	}
+
+	#[tokio::test]
+	async fn burst_allowance() {
+		let limiter = RateLimiter::new(1.0, 3);
+		let now = Instant::now();
+		for _ in 0..3 {
+			assert!(limiter.acquire_at(&ip_key(1), now).await.is_ok());
+		}
+		let retry_after = limiter.acquire_at(&ip_key(1), now).await.unwrap_err();
+		assert_eq!(retry_after, Duration::from_secs(1));
+
+		// Other clients have their own buckets
+		assert!(limiter.acquire_at(&ip_key(2), now).await.is_ok());
+	}
+
+	#[tokio::test]
+	async fn refill() {
+		let limiter = RateLimiter::new(2.0, 2);
+		let now = Instant::now();
+		assert!(limiter.acquire_at(&ip_key(1), now).await.is_ok());
+		assert!(limiter.acquire_at(&ip_key(1), now).await.is_ok());
+		assert!(limiter.acquire_at(&ip_key(1), now).await.is_err());
+
+		// Half a second refills one token
+		let now = now + Duration::from_millis(500);
+		assert!(limiter.acquire_at(&ip_key(1), now).await.is_ok());
+		assert!(limiter.acquire_at(&ip_key(1), now).await.is_err());
+
+		// Bucket is not refilled over the burst size
+		let now = now + Duration::from_secs(60);
+		assert!(limiter.acquire_at(&ip_key(1), now).await.is_ok());
+		assert!(limiter.acquire_at(&ip_key(1), now).await.is_ok());
+		assert!(limiter.acquire_at(&ip_key(1), now).await.is_err());
+	}
+
+	#[test]
+	fn buckets_generations() {
+		let mut buckets = Buckets::new(2);
+		let now = Instant::now();
+		let bucket = || Bucket {
+			limit: Limit {
+				rate: 1.0,
+				burst: 1.0,
+			},
+			tokens: 1.0,
+			updated_at: now,
+		};
+
+		buckets.get_or_insert(&ip_key(1), bucket).tokens = 0.0;
+		buckets.get_or_insert(&ip_key(2), bucket).tokens = 0.0;
+		// Full generation becomes the previous one
+		buckets.get_or_insert(&ip_key(3), bucket);
+		// Used bucket is moved to the current generation
+		assert_eq!(buckets.get_or_insert(&ip_key(1), bucket).tokens, 0.0);
+		buckets.get_or_insert(&ip_key(4), bucket);
+
+		// Bucket of the client inactive for a whole generation is dropped
+		assert_eq!(buckets.get_or_insert(&ip_key(1), bucket).tokens, 0.0);
+		assert_eq!(buckets.get_or_insert(&ip_key(2), bucket).tokens, 1.0);
+		assert!(buckets.current.len() + buckets.previous.len() <= 4);
+	}
+
+	#[tokio::test]
+	async fn disabled() {
+		let limiter = RateLimiter::new(0.0, 1);
+		let now = Instant::now();
+		for _ in 0..100 {
+			assert!(limiter.acquire_at(&ip_key(1), now).await.is_ok());
+		}
+	}
+
+	#[tokio::test]
+	async fn api_key_rate_limits() {
+		let limiter = limiter_with_keys();
+		let now = Instant::now();
+
+		// Key with its own limit
+		for _ in 0..3 {
+			assert!(limiter.acquire_at(&api_key("premium"), now).await.is_ok());
+		}
+		assert!(limiter.acquire_at(&api_key("premium"), now).await.is_err());
+
+		// Key without its own limit uses the default one
+		assert!(limiter.acquire_at(&api_key("default"), now).await.is_ok());
+		assert!(limiter.acquire_at(&api_key("default"), now).await.is_err());
+	}
+
+	#[tokio::test]
+	async fn api_key_quotas() {
+		let limiter = limiter_with_keys();
+		let now = Instant::now();
+
+		assert!(limiter.consume_quota_at(Some("basic"), now).await.is_ok());
+		let reset_in = limiter
+			.consume_quota_at(Some("basic"), now + Duration::from_secs(10))
+			.await
+			.unwrap_err();
+		assert_eq!(reset_in, Duration::from_secs(50));
+
+		// Other keys are not affected
+		assert!(limiter.consume_quota_at(Some("premium"), now).await.is_ok());
+		assert!(limiter.consume_quota_at(Some("premium"), now).await.is_ok());
+		assert!(limiter
+			.consume_quota_at(Some("premium"), now)
+			.await
+			.is_err());
+
+		// Keys without quota are not limited
+		for _ in 0..10 {
+			assert!(limiter.consume_quota_at(Some("default"), now).await.is_ok());
+			assert!(limiter.consume_quota_at(None, now).await.is_ok());
+		}
+
+		// Quota is reset after the window
+		let now = now + Duration::from_secs(60);
+		assert!(limiter.consume_quota_at(Some("basic"), now).await.is_ok());
+		assert!(limiter.consume_quota_at(Some("basic"), now).await.is_err());
+	}
+
+	#[test]
+	fn client_key() {
+		let limiter = limiter_with_keys();
+		let remote: SocketAddr = "10.0.0.1:1234".parse().unwrap();
+		let client_key = |authorization: Option<&str>| {
+			limiter.client_key(authorization.map(String::from), Some(remote))
+		};
+
+		assert_eq!(client_key(None), ip_key(1));
+		assert_eq!(client_key(Some("Bearer premium")), api_key("premium"));
+		assert_eq!(client_key(Some("Basic premium")), ip_key(1));
+		// Unknown API keys are limited by IP address
+		assert_eq!(client_key(Some("Bearer unknown")), ip_key(1));
+	}
+}
@@ -1,21 +1,176 @@
 use async_trait::async_trait;
-use avail_subxt::{api, primitives::AvailExtrinsicParams, AvailConfig};
-use color_eyre::Result;
-use sp_core::sr25519::Pair;
+use avail_subxt::{
+	api,
+	primitives::{AppUncheckedExtrinsic, AvailExtrinsicParams},
+	AvailConfig,
+};
+use codec::Decode;
+use color_eyre::{eyre::eyre, Result};
+use sp_core::{blake2_256, sr25519::Pair, H256};
 use subxt::tx::PairSigner;
+use tracing::warn;
+use uuid::Uuid;
 
-use super::types::{SubmitResponse, Transaction};
-use crate::network::rpc;
+use super::{
+	metrics::ApiMetrics,
+	rate_limit::SubmitQuota,
+	types::{Error, SubmitCache, SubmitResponse, SubmitResult, Transaction},
+};
+use crate::{network::rpc, types::RuntimeConfig};
 
 #[async_trait]
 pub trait Submit {
 	async fn submit(&self, transaction: Transaction) -> Result<SubmitResponse>;
 }
 
+/// Returns submit response with the hash of the extrinsic, without submitting it.
+/// Block hash, block number and index are zeroed, since the extrinsic is not included in a block.
+/// Data transactions are not supported, since signed extrinsic of the data transaction depends
+/// on the account nonce and mortality at the time of submit, so its hash cannot be predicted.
+pub fn dry_run(transaction: &Transaction) -> Result<SubmitResponse, String> {
+	let Transaction::Extrinsic(extrinsic) = transaction else {
+		return Err("Dry run is supported only for extrinsic transactions".to_string());
+	};
+	// Hash of the submitted extrinsic is the hash of its encoded bytes
+	Ok(SubmitResponse {
+		block_number: 0,
+		block_hash: H256::zero(),
+		hash: blake2_256(&extrinsic.0).into(),
+		index: 0,
+		app_id: None,
+		data_position: None,
+		dry_run: true,
+	})
+}
+
+/// Submits transaction, or returns cached response if the transaction
+/// with the same idempotency key is already submitted. Submit quota is charged
+/// only if the transaction is submitted, not for the cached responses.
+pub async fn submit_idempotent(
+	submitter: &impl Submit,
+	submit_cache: &SubmitCache,
+	idempotency_key: Option<Uuid>,
+	quota: &SubmitQuota,
+	transaction: Transaction,
+) -> Result<SubmitResponse, Error> {
+	let submit = || async {
+		if let Err(reset_in) = quota.consume().await {
+			return Err(Error::quota_exceeded(None, reset_in));
+		}
+		submitter
+			.submit(transaction)
+			.await
+			.map_err(Error::internal_server_error)
+	};
+
+	let Some(idempotency_key) = idempotency_key else {
+		return submit().await;
+	};
+	submit_cache.get_or_submit(idempotency_key, submit).await
+}
+
+/// Validates transaction before submit, and sets app ID of the data transaction to the configured one,
+/// unless it is overridden. Overridden app ID has to be allowed by the configuration.
+/// Expected app ID, if any, is compared to the app ID the transaction is submitted with.
+pub fn prepare(
+	transaction: Transaction,
+	expected_app_id: Option<u32>,
+	config: &RuntimeConfig,
+) -> Result<Transaction, String> {
+	if transaction.is_empty() {
+		return Err("Transaction is empty.".to_string());
+	}
+	transaction.check_size(config.max_submit_size)?;
+	if config.validate_extrinsics {
+		transaction.validate_extrinsic(config.app_id)?;
+	}
+	let transaction = transaction.with_default_app_id(config.app_id)?;
+	if let Transaction::Data {
+		app_id: Some(app_id),
+		..
+	} = &transaction
+	{
+		if !config.is_submit_allowed(*app_id) {
+			return Err(format!("Submitting data of app ID {app_id} is not allowed"));
+		}
+	}
+	let app_id = transaction.app_id();
+	match expected_app_id {
+		Some(expected) if Some(expected) != app_id => {
+			let actual = app_id.map_or("none".to_string(), |app_id| app_id.to_string());
+			Err(format!(
+				"App ID mismatch, expected {expected}, transaction app ID is {actual}"
+			))
+		},
+		_ => Ok(transaction),
+	}
+}
+
+/// Error message of the submit batch with more transactions than allowed.
+pub fn batch_size_exceeded(size: usize, max_size: usize) -> String {
+	format!("Batch has {size} transactions, exceeding the limit of {max_size}")
+}
+
+/// Submits transactions one by one, in the given order. Empty, invalid and failed transactions
+/// are reported as errors in the results, without aborting the rest of the batch.
+/// Data transactions without app ID are submitted with the configured app ID.
+/// Submit quota is charged per valid transaction, and transactions exceeding it are not submitted.
+pub async fn submit_batch(
+	submitter: &impl Submit,
+	config: &RuntimeConfig,
+	quota: &SubmitQuota,
+	transactions: Vec<Transaction>,
+) -> Vec<SubmitResult> {
+	let mut results = Vec::with_capacity(transactions.len());
+	for transaction in transactions {
+		let transaction = match prepare(transaction, None, config) {
+			Ok(transaction) => transaction,
+			Err(message) => {
+				results.push(SubmitResult::Error(Error::bad_request_unknown(&message)));
+				continue;
+			},
+		};
+		if let Err(reset_in) = quota.consume().await {
+			results.push(SubmitResult::Error(Error::quota_exceeded(None, reset_in)));
+			continue;
+		}
+		let result = match submitter.submit(transaction).await {
+			Ok(response) => SubmitResult::Submitted(response),
+			Err(error) => {
+				warn!("Batch transaction submit failed: {error:#}");
+				SubmitResult::Error(Error::internal_server_error(error))
+			},
+		};
+		results.push(result);
+	}
+	results
+}
+
+/// Returns app ID of the extrinsic at the given index, and its 0-based position
+/// within the app data of the block (extrinsics are grouped by app ID in block order).
+fn app_data_position(extrinsics: &[Vec<u8>], index: u32) -> Result<(u32, u32)> {
+	let app_id = |extrinsic: &Vec<u8>| -> Result<u32> {
+		let extrinsic = AppUncheckedExtrinsic::decode(&mut &extrinsic[..])?;
+		Ok(extrinsic.app_id().0)
+	};
+
+	let extrinsic = extrinsics
+		.get(index as usize)
+		.ok_or_else(|| eyre!("Extrinsic with index {index} not found"))?;
+	let submitted_app_id = app_id(extrinsic)?;
+
+	let mut position = 0;
+	for extrinsic in &extrinsics[..index as usize] {
+		if app_id(extrinsic)? == submitted_app_id {
+			position += 1;
+		}
+	}
+	Ok((submitted_app_id, position))
+}
+
 #[derive(Clone)]
 pub struct Submitter {
 	pub rpc_client: rpc::Client,
-	pub app_id: u32,
 	pub pair_signer: PairSigner<AvailConfig, Pair>,
 }
 
@@ -23,9 +178,11 @@ pub struct Submitter {
 impl Submit for Submitter {
 	async fn submit(&self, transaction: Transaction) -> Result<SubmitResponse> {
 		let ex_event = match transaction {
-			Transaction::Data(data) => {
+			Transaction::Data { data, app_id } => {
+				// App ID is set by the API handlers, either from the request or from the config
+				let app_id = app_id.ok_or_else(|| eyre!("App ID is not set"))?;
 				let extrinsic = api::tx().data_availability().submit_data(data.into());
-				let params = AvailExtrinsicParams::new_with_app_id(self.app_id.into());
+				let params = AvailExtrinsicParams::new_with_app_id(app_id.into());
 				self.rpc_client
 					.submit_signed_and_wait_for_finalized(&extrinsic, &self.pair_signer, params)
 					.await?
@@ -37,17 +194,52 @@ impl Submit for Submitter {
 			},
 		};
 
-		let block_number = self
-			.rpc_client
-			.get_header_by_hash(ex_event.block_hash())
-			.await?
-			.number;
+		let block_hash = ex_event.block_hash();
+		let index = ex_event.extrinsic_index();
+
+		let block_number = self.rpc_client.get_header_by_hash(block_hash).await?.number;
+
+		let (app_id, data_position) = match self.app_data_position(block_hash, index).await {
+			Ok((app_id, data_position)) => (Some(app_id), Some(data_position)),
+			Err(error) => {
+				warn!(
+					block_number,
+					index, "Cannot find app data position: {error:#}"
+				);
+				(None, None)
+			},
+		};
 
 		Ok(SubmitResponse {
 			block_number,
-			block_hash: ex_event.block_hash(),
+			block_hash,
 			hash: ex_event.extrinsic_hash(),
-			index: ex_event.extrinsic_index(),
+			index,
+			app_id,
+			data_position,
+			dry_run: false,
 		})
 	}
 }
+
+impl Submitter {
+	async fn app_data_position(&self, block_hash: H256, index: u32) -> Result<(u32, u32)> {
+		let extrinsics = self.rpc_client.get_block_extrinsics(block_hash).await?;
+		app_data_position(&extrinsics, index)
+	}
+}
+
+/// Counts submitted transactions in the API metrics.
+#[derive(Clone)]
+pub struct MeteredSubmitter<T> {
+	pub submitter: T,
+	pub metrics: ApiMetrics,
+}
+
+#[async_trait]
+impl<T: Submit + Send + Sync> Submit for MeteredSubmitter<T> {
+	async fn submit(&self, transaction: Transaction) -> Result<SubmitResponse> {
+		self.metrics.record_submit();
+		self.submitter.submit(transaction).await
+	}
+}
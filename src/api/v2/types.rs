@@ -1,23 +1,42 @@
-use avail_subxt::api::runtime_types::{
-	avail_core::{data_lookup::compact::CompactDataLookup, header::extension::HeaderExtension},
-	bounded_collections::bounded_vec::BoundedVec,
+use avail_subxt::{
+	api::runtime_types::{
+		avail_core::{
+			data_lookup::compact::CompactDataLookup, header::extension::HeaderExtension,
+			kate_commitment::v3::KateCommitment,
+		},
+		bounded_collections::bounded_vec::BoundedVec,
+	},
+	primitives::AppUncheckedExtrinsic,
 };
 use base64::{engine::general_purpose, DecodeError, Engine};
-use codec::Encode;
+use codec::{Decode, DecodeAll, Encode};
 use color_eyre::{
 	eyre::{eyre, WrapErr},
 	Report, Result,
 };
 use derive_more::From;
 use hyper::{http, StatusCode};
-use kate_recovery::{com::AppData, commitments, config, matrix::Partition};
+use indexmap::IndexMap;
+use kate_recovery::{com::AppData, config, matrix::Partition};
+use schemars::{gen::SchemaGenerator, JsonSchema};
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
-use sp_core::{blake2_256, H256};
+use serde_json::value::RawValue;
+use sp_core::{blake2_128, blake2_256, H256};
 use std::{
-	collections::{HashMap, HashSet},
-	sync::Arc,
+	collections::{HashMap, HashSet, VecDeque},
+	fmt,
+	future::Future,
+	ops::Range,
+	sync::{
+		atomic::{AtomicUsize, Ordering},
+		Arc, Mutex,
+	},
+	time::{Duration, Instant},
+};
+use tokio::{
+	sync::{broadcast, OnceCell, RwLock},
+	task::AbortHandle,
 };
-use tokio::sync::{mpsc::UnboundedSender, RwLock};
 use uuid::Uuid;
 use warp::{
 	ws::{self, Message},
@@ -25,11 +44,12 @@ use warp::{
 };
 
 use crate::{
-	network::rpc::Event as RpcEvent,
+	network::rpc::{BlockEvent, Event as RpcEvent},
 	types::{
-		self, block_matrix_partition_format, BlockVerified, OptionBlockRange, RuntimeConfig, State,
+		self, block_matrix_partitions_format, BlockVerified, FieldNaming, OptionBlockRange,
+		RuntimeConfig, State,
 	},
-	utils::decode_app_data,
+	utils::{self, decode_app_data, extended_partitions_positions},
 };
 
 #[derive(Debug)]
@@ -37,19 +57,40 @@ pub struct InternalServerError {}
 
 impl warp::reject::Reject for InternalServerError {}
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+/// Rejection of the rate limited request, with time after which the request can be retried.
+#[derive(Debug)]
+pub struct RateLimited(pub Duration);
+
+impl warp::reject::Reject for RateLimited {}
+
+/// Rejection of the request without valid API key.
+#[derive(Debug)]
+pub struct Unauthorized;
+
+impl warp::reject::Reject for Unauthorized {}
+
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
 pub struct Version {
 	pub version: String,
 	pub network_version: String,
+	pub network: String,
+}
+
+impl Version {
+	/// Sets network to the network of the currently connected node.
+	pub fn with_network(mut self, state: &State) -> Self {
+		self.network = state.connected_node.network();
+		self
+	}
 }
 
 impl Reply for Version {
 	fn into_response(self) -> warp::reply::Response {
-		warp::reply::json(&self).into_response()
+		json_reply(self)
 	}
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
 pub struct BlockRange {
 	pub first: u32,
 	pub last: u32,
@@ -64,7 +105,7 @@ impl From<&types::BlockRange> for BlockRange {
 	}
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct HistoricalSync {
 	pub synced: bool,
 	#[serde(skip_serializing_if = "Option::is_none")]
@@ -73,18 +114,51 @@ pub struct HistoricalSync {
 	pub app_data: Option<BlockRange>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct Blocks {
 	pub latest: u32,
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub available: Option<BlockRange>,
+	/// Confidence of the latest block in the available range
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub confidence: Option<f64>,
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub app_data: Option<BlockRange>,
+	/// Ranges of blocks with verified app data, per app ID
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub app_ranges: Option<HashMap<u32, BlockRange>>,
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub historical_sync: Option<HistoricalSync>,
 }
 
-#[derive(Serialize, Deserialize)]
+/// Block ranges reported in the status, which are cheaper to get than the full status
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct BlockRanges {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub available: Option<BlockRange>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub app_data: Option<BlockRange>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub historical_sync: Option<HistoricalSync>,
+}
+
+impl From<&State> for BlockRanges {
+	fn from(state: &State) -> Self {
+		let historical_sync = state.synced.map(|synced| HistoricalSync {
+			synced,
+			available: state.sync_confidence_achieved.as_ref().map(From::from),
+			app_data: state.sync_data_verified.as_ref().map(From::from),
+		});
+
+		BlockRanges {
+			available: state.confidence_achieved.as_ref().map(From::from),
+			app_data: state.data_verified.as_ref().map(From::from),
+			historical_sync,
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct Status {
 	pub modes: Vec<Mode>,
 	#[serde(skip_serializing_if = "Option::is_none")]
@@ -93,16 +167,275 @@ pub struct Status {
 	pub network: String,
 	pub blocks: Blocks,
 	#[serde(
-		skip_serializing_if = "Option::is_none",
-		with = "block_matrix_partition_format"
+		default,
+		skip_serializing_if = "Vec::is_empty",
+		with = "block_matrix_partitions_format"
 	)]
-	pub partition: Option<Partition>,
+	#[schemars(with = "String")]
+	pub partition: Vec<Partition>,
+	/// Number of extended matrix cells assigned to the partitions, for the latest block
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub partition_cells: Option<u32>,
+	/// Unix timestamp (in seconds) when the status is created
+	pub timestamp: u64,
+	/// Unix timestamp (in seconds) when the latest block number was updated
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub latest_block_at: Option<u64>,
+	/// Unix timestamp (in seconds) of the latest block, as set by the block author
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub chain_time: Option<u64>,
+	/// Difference in seconds between the status timestamp and the chain time
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub drift_seconds: Option<i64>,
+}
+
+/// Multi-word field names of the API messages, which are renamed according to the configured naming.
+/// Fields are listed explicitly, so keys of the data maps are not renamed.
+const RENAMED_FIELDS: &[&str] = &[
+	"app_cells",
+	"app_data",
+	"app_id",
+	"app_ids",
+	"app_lookup",
+	"app_ranges",
+	"block_hash",
+	"block_number",
+	"cause_chain",
+	"cell_count",
+	"cells_needed",
+	"chain_time",
+	"data_fields",
+	"data_position",
+	"data_root",
+	"data_transactions",
+	"drift_seconds",
+	"dry_run",
+	"error_code",
+	"extension_version",
+	"extrinsics_root",
+	"genesis_hash",
+	"historical_sync",
+	"include_commitments",
+	"latest_block_at",
+	"leaf_index",
+	"network_version",
+	"number_of_leaves",
+	"parent_hash",
+	"partition_cells",
+	"request_id",
+	"state_root",
+	"subscription_id",
+	"sync_range",
+	"ws_clients",
+];
+
+fn camel_case(field: &str) -> String {
+	let mut parts = field.split('_');
+	let first = parts.next().unwrap_or_default().to_string();
+	parts.fold(first, |mut field, part| {
+		let mut chars = part.chars();
+		if let Some(first) = chars.next() {
+			field.extend(first.to_uppercase());
+			field.push_str(chars.as_str());
+		}
+		field
+	})
+}
+
+/// Renames listed fields of the JSON objects, recursively, according to the given naming.
+pub fn rename_fields(value: serde_json::Value, naming: FieldNaming) -> serde_json::Value {
+	use serde_json::Value;
+
+	match (value, naming) {
+		(value, FieldNaming::SnakeCase) => value,
+		(Value::Object(object), FieldNaming::CamelCase) => Value::Object(
+			object
+				.into_iter()
+				.map(
+					|(field, value)| match RENAMED_FIELDS.contains(&field.as_str()) {
+						true => (camel_case(&field), rename_fields(value, naming)),
+						false => (field, rename_fields(value, naming)),
+					},
+				)
+				.collect(),
+		),
+		(Value::Array(values), FieldNaming::CamelCase) => Value::Array(
+			values
+				.into_iter()
+				.map(|value| rename_fields(value, naming))
+				.collect(),
+		),
+		(value, FieldNaming::CamelCase) => value,
+	}
+}
+
+/// Renames listed fields of the CBOR maps, recursively, according to the given naming.
+fn rename_cbor_fields(value: ciborium::Value, naming: FieldNaming) -> ciborium::Value {
+	use ciborium::Value;
+
+	match (value, naming) {
+		(value, FieldNaming::SnakeCase) => value,
+		(Value::Map(entries), FieldNaming::CamelCase) => Value::Map(
+			entries
+				.into_iter()
+				.map(|(field, value)| match field {
+					Value::Text(field) if RENAMED_FIELDS.contains(&field.as_str()) => (
+						Value::Text(camel_case(&field)),
+						rename_cbor_fields(value, naming),
+					),
+					field => (field, rename_cbor_fields(value, naming)),
+				})
+				.collect(),
+		),
+		(Value::Array(values), FieldNaming::CamelCase) => Value::Array(
+			values
+				.into_iter()
+				.map(|value| rename_cbor_fields(value, naming))
+				.collect(),
+		),
+		(value, FieldNaming::CamelCase) => value,
+	}
+}
+
+/// Encoder of the reply value to CBOR, kept in the extensions of the JSON response,
+/// so the value is encoded directly if client prefers CBOR, instead of parsing the JSON body.
+#[derive(Clone)]
+pub struct CborEncoder {
+	encode: Arc<dyn Fn() -> Result<Vec<u8>> + Send + Sync>,
+	/// Naming of the encoded fields, set if response fields are renamed
+	pub naming: FieldNaming,
+}
+
+impl CborEncoder {
+	pub fn new(value: impl Serialize + Send + Sync + 'static) -> Self {
+		CborEncoder {
+			encode: Arc::new(move || {
+				let mut cbor = vec![];
+				ciborium::into_writer(&value, &mut cbor)?;
+				Ok(cbor)
+			}),
+			naming: FieldNaming::default(),
+		}
+	}
+
+	pub fn encode(&self) -> Result<Vec<u8>> {
+		let cbor = (self.encode)()?;
+		if self.naming == FieldNaming::SnakeCase {
+			return Ok(cbor);
+		}
+		let value: ciborium::Value = ciborium::from_reader(&cbor[..])?;
+		let mut renamed = vec![];
+		ciborium::into_writer(&rename_cbor_fields(value, self.naming), &mut renamed)?;
+		Ok(renamed)
+	}
+}
+
+/// Creates JSON response of the reply value, which can be encoded to CBOR on content negotiation.
+pub fn json_reply(value: impl Serialize + Send + Sync + 'static) -> warp::reply::Response {
+	let mut response = warp::reply::json(&value).into_response();
+	response.extensions_mut().insert(CborEncoder::new(value));
+	response
+}
+
+/// Serializes message to JSON, with fields named according to the given naming.
+pub fn to_json<T: Serialize>(message: &T, naming: FieldNaming) -> serde_json::Result<String> {
+	match naming {
+		FieldNaming::SnakeCase => serde_json::to_string(message),
+		FieldNaming::CamelCase => serde_json::to_value(message)
+			.map(|value| rename_fields(value, naming))
+			.and_then(|value| serde_json::to_string(&value)),
+	}
+}
+
+/// Serializes message to raw JSON value, which can be embedded into another message as is.
+fn to_raw_json<T: Serialize>(
+	message: &T,
+	naming: FieldNaming,
+) -> serde_json::Result<Box<RawValue>> {
+	match naming {
+		FieldNaming::SnakeCase => serde_json::value::to_raw_value(message),
+		FieldNaming::CamelCase => serde_json::to_value(message)
+			.map(|value| rename_fields(value, naming))
+			.and_then(|value| serde_json::value::to_raw_value(&value)),
+	}
+}
+
+/// Creates JSON merge patch which transforms the previous value into the current one.
+/// Changed and added fields are set to the current values, removed fields are set to null.
+fn merge_patch(
+	previous: &serde_json::Value,
+	current: &serde_json::Value,
+) -> Option<serde_json::Value> {
+	use serde_json::Value;
+
+	let (Value::Object(previous), Value::Object(current)) = (previous, current) else {
+		return (previous != current).then(|| current.clone());
+	};
+
+	let mut patch = serde_json::Map::new();
+	for (key, value) in current {
+		let changed = match previous.get(key) {
+			Some(previous) => merge_patch(previous, value),
+			None => Some(value.clone()),
+		};
+		if let Some(changed) = changed {
+			patch.insert(key.clone(), changed);
+		}
+	}
+	for key in previous.keys().filter(|&key| !current.contains_key(key)) {
+		patch.insert(key.clone(), Value::Null);
+	}
+	(!patch.is_empty()).then_some(Value::Object(patch))
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
-#[serde(try_from = "String", into = "String")]
+/// Bytes, encoded as base64 string in JSON, and as byte string in binary formats (e.g. CBOR).
+#[derive(Clone, Debug, PartialEq)]
 pub struct Base64(pub Vec<u8>);
 
+impl Serialize for Base64 {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		if !serializer.is_human_readable() {
+			return serializer.serialize_bytes(&self.0);
+		}
+		serializer.serialize_str(&general_purpose::STANDARD.encode(&self.0))
+	}
+}
+
+impl<'de> Deserialize<'de> for Base64 {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		if !deserializer.is_human_readable() {
+			return deserializer.deserialize_byte_buf(BytesVisitor).map(Base64);
+		}
+		let value = String::deserialize(deserializer)?;
+		Base64::try_from(value).map_err(de::Error::custom)
+	}
+}
+
+/// Visitor of the byte strings of the binary formats.
+struct BytesVisitor;
+
+impl<'de> de::Visitor<'de> for BytesVisitor {
+	type Value = Vec<u8>;
+
+	fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		formatter.write_str("byte string")
+	}
+
+	fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<Self::Value, E> {
+		Ok(value.to_vec())
+	}
+
+	fn visit_byte_buf<E: de::Error>(self, value: Vec<u8>) -> Result<Self::Value, E> {
+		Ok(value)
+	}
+}
+
 impl From<Base64> for BoundedVec<u8> {
 	fn from(val: Base64) -> Self {
 		BoundedVec(val.0)
@@ -116,10 +449,16 @@ impl From<Base64> for Vec<u8> {
 }
 
 impl TryFrom<String> for Base64 {
-	type Error = DecodeError;
+	type Error = Report;
 
 	fn try_from(value: String) -> Result<Self, Self::Error> {
-		general_purpose::STANDARD.decode(value).map(Base64)
+		general_purpose::STANDARD
+			.decode(&value)
+			.or_else(|error: DecodeError| {
+				general_purpose::URL_SAFE.decode(&value).map_err(|_| error)
+			})
+			.map(Base64)
+			.map_err(|error| eyre!("Invalid base64 value: {error}"))
 	}
 }
 
@@ -129,20 +468,215 @@ impl From<Base64> for String {
 	}
 }
 
-#[derive(Clone, Debug, Deserialize)]
-#[serde(rename_all = "kebab-case")]
+/// Transaction to submit, e.g. `{"data":"..."}` or `{"data":"...","app_id":1}`.
+#[derive(Clone, Debug)]
 pub enum Transaction {
-	Data(Base64),
+	/// Data transaction, with optional app ID which overrides the configured one
+	Data {
+		data: Base64,
+		app_id: Option<u32>,
+	},
 	Extrinsic(Base64),
 }
 
 impl Transaction {
 	pub fn is_empty(&self) -> bool {
 		match self {
-			Transaction::Data(data) => data.0.is_empty(),
+			Transaction::Data { data, .. } => data.0.is_empty(),
 			Transaction::Extrinsic(data) => data.0.is_empty(),
 		}
 	}
+
+	/// Checks if the decoded transaction bytes fit into the given size limit.
+	pub fn check_size(&self, max_size: usize) -> Result<(), String> {
+		let size = match self {
+			Transaction::Data { data, .. } => data.0.len(),
+			Transaction::Extrinsic(data) => data.0.len(),
+		};
+		if size > max_size {
+			return Err(format!(
+				"Transaction size {size} exceeds the limit of {max_size} bytes"
+			));
+		}
+		Ok(())
+	}
+
+	/// Checks if the extrinsic is well formed and signed, and if its app ID matches the configured one.
+	/// Signature itself is verified by the node.
+	pub fn validate_extrinsic(&self, configured: Option<u32>) -> Result<(), String> {
+		let Transaction::Extrinsic(extrinsic) = self else {
+			return Ok(());
+		};
+		let extrinsic = AppUncheckedExtrinsic::decode_all(&mut &extrinsic.0[..])
+			.map_err(|error| format!("Extrinsic is malformed: {error}"))?;
+		if extrinsic.signature.is_none() {
+			return Err("Extrinsic is not signed".to_string());
+		}
+		let app_id = extrinsic.app_id().0;
+		match configured {
+			Some(configured) if configured != app_id => Err(format!(
+				"Extrinsic app ID {app_id} does not match the configured app ID {configured}"
+			)),
+			_ => Ok(()),
+		}
+	}
+
+	/// Returns app ID of the data transaction, or app ID of the extrinsic, if it can be decoded.
+	pub fn app_id(&self) -> Option<u32> {
+		match self {
+			Transaction::Data { app_id, .. } => *app_id,
+			Transaction::Extrinsic(extrinsic) => {
+				AppUncheckedExtrinsic::decode_all(&mut &extrinsic.0[..])
+					.ok()
+					.map(|extrinsic| extrinsic.app_id().0)
+			},
+		}
+	}
+
+	/// Sets app ID of the data transaction to the configured one, unless it is overridden.
+	/// Fails if the data transaction has no app ID and app ID is not configured.
+	pub fn with_default_app_id(self, configured: Option<u32>) -> Result<Self, String> {
+		match self {
+			Transaction::Data { data, app_id } => match app_id.or(configured) {
+				Some(app_id) => Ok(Transaction::Data {
+					data,
+					app_id: Some(app_id),
+				}),
+				None => {
+					Err("App ID is not configured, data transaction requires app_id".to_string())
+				},
+			},
+			extrinsic => Ok(extrinsic),
+		}
+	}
+}
+
+/// Transaction fields of the JSON object, which is deserialized by the enclosing visitor.
+#[derive(Default)]
+struct TransactionFields {
+	transaction: Option<Transaction>,
+	app_id: Option<u32>,
+}
+
+impl TransactionFields {
+	const VARIANTS: &'static [&'static str] = &["data", "extrinsic"];
+
+	/// Reads the value of the given transaction field, failing on unknown fields.
+	fn next_value<'de, A>(&mut self, key: &str, map: &mut A) -> Result<(), A::Error>
+	where
+		A: de::MapAccess<'de>,
+	{
+		let value = match key {
+			"app_id" => {
+				self.app_id = map.next_value()?;
+				return Ok(());
+			},
+			"data" => Transaction::Data {
+				data: map.next_value()?,
+				app_id: None,
+			},
+			"extrinsic" => Transaction::Extrinsic(map.next_value()?),
+			variant => return Err(de::Error::unknown_variant(variant, Self::VARIANTS)),
+		};
+		if self.transaction.replace(value).is_some() {
+			return Err(de::Error::custom("Expected a single transaction"));
+		}
+		Ok(())
+	}
+
+	fn into_transaction<E: de::Error>(self) -> Result<Transaction, E> {
+		match (self.transaction, self.app_id) {
+			(None, _) => Err(E::missing_field("data")),
+			(Some(Transaction::Data { data, .. }), app_id) => {
+				Ok(Transaction::Data { data, app_id })
+			},
+			(Some(Transaction::Extrinsic(_)), Some(_)) => {
+				Err(E::custom("App ID can be set only for data transactions"))
+			},
+			(Some(extrinsic), None) => Ok(extrinsic),
+		}
+	}
+}
+
+impl<'de> Deserialize<'de> for Transaction {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		struct TransactionVisitor;
+
+		impl<'de> de::Visitor<'de> for TransactionVisitor {
+			type Value = Transaction;
+
+			fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+				formatter.write_str("transaction with optional app ID")
+			}
+
+			fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+			where
+				A: de::MapAccess<'de>,
+			{
+				let mut fields = TransactionFields::default();
+				while let Some(key) = map.next_key::<String>()? {
+					fields.next_value(&key, &mut map)?;
+				}
+				fields.into_transaction()
+			}
+		}
+
+		deserializer.deserialize_map(TransactionVisitor)
+	}
+}
+
+/// Submit request is a transaction with optional app ID, which is expected to match the app ID
+/// the transaction is submitted with, e.g. `{"data":"...","expected_app_id":1}`.
+#[derive(Clone, Debug)]
+pub struct SubmitRequest {
+	pub transaction: Transaction,
+	pub expected_app_id: Option<u32>,
+	/// If set, transaction hash is computed, but the transaction is not submitted
+	pub dry_run: bool,
+}
+
+impl<'de> Deserialize<'de> for SubmitRequest {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		struct SubmitRequestVisitor;
+
+		impl<'de> de::Visitor<'de> for SubmitRequestVisitor {
+			type Value = SubmitRequest;
+
+			fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+				formatter.write_str("transaction with optional expected app ID")
+			}
+
+			fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+			where
+				A: de::MapAccess<'de>,
+			{
+				let mut fields = TransactionFields::default();
+				let mut expected_app_id = None;
+				let mut dry_run = false;
+				while let Some(key) = map.next_key::<String>()? {
+					match key.as_str() {
+						"expected_app_id" => expected_app_id = map.next_value()?,
+						"dry_run" => dry_run = map.next_value()?,
+						_ => fields.next_value(&key, &mut map)?,
+					}
+				}
+
+				Ok(SubmitRequest {
+					transaction: fields.into_transaction()?,
+					expected_app_id,
+					dry_run,
+				})
+			}
+		}
+
+		deserializer.deserialize_map(SubmitRequestVisitor)
+	}
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -151,98 +685,435 @@ pub struct SubmitResponse {
 	pub block_hash: H256,
 	pub hash: H256,
 	pub index: u32,
+	/// App ID under which the data is included
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub app_id: Option<u32>,
+	/// 0-based position of the transaction within the app data of the block
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub data_position: Option<u32>,
+	/// Set if the transaction is not submitted, and only its hash is computed
+	#[serde(default, skip_serializing_if = "std::ops::Not::not")]
+	pub dry_run: bool,
 }
 
 impl Reply for SubmitResponse {
 	fn into_response(self) -> warp::reply::Response {
-		warp::reply::json(&self).into_response()
+		json_reply(self)
+	}
+}
+
+/// Result of the single transaction submit within the batch.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SubmitResult {
+	Submitted(SubmitResponse),
+	Error(Error),
+}
+
+/// Batch submit results, in order of submitted transactions.
+#[derive(Serialize, Deserialize)]
+pub struct SubmitBatchResponse(pub Vec<SubmitResult>);
+
+impl Reply for SubmitBatchResponse {
+	fn into_response(self) -> warp::reply::Response {
+		json_reply(self)
+	}
+}
+
+/// Submit responses cached by idempotency key, used to prevent duplicate submits on retries.
+#[derive(Clone)]
+pub struct SubmitCache {
+	ttl: Duration,
+	responses: Arc<RwLock<HashMap<Uuid, (Instant, Arc<OnceCell<SubmitResponse>>)>>>,
+}
+
+impl SubmitCache {
+	pub fn new(ttl: Duration) -> Self {
+		SubmitCache {
+			ttl,
+			responses: Default::default(),
+		}
+	}
+
+	/// Returns cached response for the given idempotency key, if not expired, otherwise submits
+	/// the transaction and caches the response. Key is reserved before submit, so concurrent
+	/// submits with the same key wait for the first one. Failed submits are not cached.
+	pub async fn get_or_submit<F, Fut, E>(
+		&self,
+		idempotency_key: Uuid,
+		submit: F,
+	) -> Result<SubmitResponse, E>
+	where
+		F: FnOnce() -> Fut,
+		Fut: Future<Output = Result<SubmitResponse, E>>,
+	{
+		let response = {
+			let mut responses = self.responses.write().await;
+			// Entries referenced outside of the cache are still being submitted
+			responses.retain(|_, (cached_at, response)| {
+				cached_at.elapsed() < self.ttl || Arc::strong_count(response) > 1
+			});
+			let (_, response) = responses
+				.entry(idempotency_key)
+				.or_insert_with(|| (Instant::now(), Default::default()));
+			response.clone()
+		};
+		response.get_or_try_init(submit).await.cloned()
 	}
 }
 
 impl Status {
 	pub fn new(config: &RuntimeConfig, state: &State) -> Self {
-		let historical_sync = state.synced.map(|synced| HistoricalSync {
-			synced,
-			available: state.sync_confidence_achieved.as_ref().map(From::from),
-			app_data: state.sync_data_verified.as_ref().map(From::from),
-		});
+		let BlockRanges {
+			available,
+			app_data,
+			historical_sync,
+		} = state.into();
 
 		let blocks = Blocks {
 			latest: state.latest,
-			available: state.confidence_achieved.as_ref().map(From::from),
-			app_data: state.data_verified.as_ref().map(From::from),
+			available,
+			confidence: state.confidence,
+			app_data,
+			app_ranges: config
+				.app_id
+				.zip(state.data_verified.as_ref())
+				.map(|(app_id, range)| HashMap::from([(app_id, BlockRange::from(range))])),
 			historical_sync,
 		};
 
 		let node = state.connected_node.clone();
 
+		let partition_cells = state
+			.latest_dimensions
+			.filter(|_| config.is_fat_client())
+			.map(|dimensions| {
+				extended_partitions_positions(dimensions, &config.block_matrix_partition).len()
+					as u32
+			});
+
+		let timestamp = types::unix_timestamp();
+		let chain_time = state.latest_block_timestamp;
+
 		Status {
 			modes: config.into(),
 			app_id: config.app_id,
 			genesis_hash: format!("{:?}", node.genesis_hash),
 			network: node.network(),
 			blocks,
-			partition: config.block_matrix_partition,
+			partition: config.block_matrix_partition.clone(),
+			partition_cells,
+			timestamp,
+			latest_block_at: state.latest_block_at,
+			chain_time,
+			drift_seconds: chain_time.map(|chain_time| timestamp as i64 - chain_time as i64),
+		}
+	}
+
+	/// Returns status as JSON value, without the fields depending on the time the status is created.
+	fn without_timestamp(&self) -> serde_json::Value {
+		let mut status = serde_json::to_value(self).expect("Status is serializable");
+		if let Some(status) = status.as_object_mut() {
+			status.remove("timestamp");
+			status.remove("drift_seconds");
 		}
+		status
+	}
+
+	/// Returns status fields changed since the previous status, as JSON merge patch (RFC 7386),
+	/// or `None` if nothing has changed. Time the status is created is not compared.
+	pub fn changes(&self, previous: &Status) -> Option<serde_json::Value> {
+		merge_patch(&previous.without_timestamp(), &self.without_timestamp())
+	}
+
+	/// Weak entity tag of the status, which doesn't depend on the time the status is created,
+	/// and it is the same for all status encodings.
+	pub fn etag(&self) -> String {
+		let status = self.without_timestamp();
+		let hash = blake2_128(status.to_string().as_bytes());
+		format!(r#"W/"{}""#, hex::encode(hash))
 	}
 }
 
 #[derive(Serialize, Deserialize)]
-#[serde(rename_all = "kebab-case")]
-pub enum Mode {
-	Light,
-	App,
-	Partition,
+pub struct AppDataMessage {
+	pub block_number: u32,
+	pub app_id: u32,
+	/// Decoded data of the application data transactions, in block order
+	pub data: Vec<Base64>,
 }
 
-impl From<&RuntimeConfig> for Vec<Mode> {
-	fn from(value: &RuntimeConfig) -> Self {
-		let mut result: Vec<Mode> = vec![];
-		result.push(Mode::Light);
-		if value.app_id.is_some() {
-			result.push(Mode::App);
-		}
-		if value.block_matrix_partition.is_some() {
-			result.push(Mode::Partition)
+/// Cell of the extended data matrix, with its KZG proof
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct Cell {
+	pub row: u32,
+	pub col: u16,
+	pub data: Base64,
+	pub proof: Base64,
+}
+
+impl From<kate_recovery::data::Cell> for Cell {
+	fn from(cell: kate_recovery::data::Cell) -> Self {
+		let (proof, data) = cell.content.split_at(config::COMMITMENT_SIZE);
+		Cell {
+			row: cell.position.row,
+			col: cell.position.col,
+			data: Base64(data.to_vec()),
+			proof: Base64(proof.to_vec()),
 		}
-		result
 	}
 }
 
-impl Reply for Status {
-	fn into_response(self) -> warp::reply::Response {
-		warp::reply::json(&self).into_response()
-	}
+#[derive(Serialize, Deserialize)]
+pub struct CellsMessage {
+	pub block_number: u32,
+	pub cells: Vec<Cell>,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash)]
-#[serde(rename_all = "kebab-case")]
-pub enum Topic {
-	HeaderVerified,
-	ConfidenceAchieved,
-	DataVerified,
+#[derive(Serialize, Deserialize)]
+pub struct RowMessage {
+	pub block_number: u32,
+	pub row: u32,
+	/// All cells of the row, in column order
+	pub cells: Vec<Cell>,
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Eq, Hash)]
-#[serde(rename_all = "kebab-case")]
-pub enum DataField {
+/// Merkle proof of the data transaction inclusion under the data root of the verified block header
+#[derive(Serialize, Deserialize)]
+pub struct DataProofMessage {
+	pub block_number: u32,
+	pub data_root: H256,
+	/// Sibling hashes on the path from the leaf to the data root
+	pub proof: Vec<H256>,
+	pub number_of_leaves: u32,
+	pub leaf_index: u32,
+	/// Keccak-256 hash of the data transaction
+	pub leaf: H256,
+}
+
+/// Trie proof of the extrinsic inclusion under the extrinsics root of the verified block header
+#[derive(Serialize, Deserialize)]
+pub struct ExtrinsicProofMessage {
+	pub block_number: u32,
+	pub extrinsics_root: H256,
+	pub index: u32,
+	/// SCALE encoded extrinsic
+	pub extrinsic: Base64,
+	/// Trie nodes needed to verify the extrinsic, keyed by its SCALE compact encoded index
+	pub proof: Vec<Base64>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DataProofVerified {
+	pub verified: bool,
+}
+
+/// Echo of the ping request nonce, with the time when the request is handled
+#[derive(Serialize, Deserialize)]
+pub struct Pong {
+	pub nonce: u64,
+	/// Unix timestamp (in milliseconds) when the ping request is handled
+	pub timestamp: u64,
+}
+
+/// Confidence achieved by sampling the block cells
+#[derive(Serialize, Deserialize)]
+pub struct SampledConfidence {
+	pub block_number: u32,
+	pub confidence: f64,
+	/// Number of the verified cells the confidence is calculated from
+	pub cell_count: u32,
+	/// Set to `true` if confidence is stored by the light client, without additional sampling
+	pub cached: bool,
+}
+
+/// Outcome of the client disconnect request
+#[derive(Serialize, Deserialize)]
+pub struct Disconnected {
+	pub subscription_id: String,
+	/// Set to `false` if there is no client with the given subscription ID
+	pub disconnected: bool,
+}
+
+/// Outcome of the block data reconstruction from the partition cells
+#[derive(Serialize, Deserialize)]
+pub struct ReconstructMessage {
+	pub block_number: u32,
+	/// Number of cells in the configured partitions
+	pub partition_cells: u32,
+	/// Number of cells needed in addition to the partition cells to reconstruct the block
+	pub cells_needed: u32,
+	/// Number of reconstructed columns
+	pub columns: u32,
+}
+
+/// KZG commitments of the block, one per row of the extended matrix
+#[derive(Serialize, Deserialize)]
+pub struct CommitmentsMessage {
+	pub block_number: u32,
+	pub rows: u16,
+	pub cols: u16,
+	commitments: Vec<Commitment>,
+}
+
+/// SCALE encoded block header, for byte-exact verification
+#[derive(Serialize, Deserialize)]
+pub struct RawHeaderMessage {
+	pub block_number: u32,
+	pub hash: H256,
+	pub header: Base64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GenesisHash {
+	pub genesis_hash: H256,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum Mode {
+	Light,
+	App,
+	Partition,
+}
+
+impl From<&RuntimeConfig> for Vec<Mode> {
+	fn from(value: &RuntimeConfig) -> Self {
+		let mut result: Vec<Mode> = vec![];
+		result.push(Mode::Light);
+		if value.app_id.is_some() {
+			result.push(Mode::App);
+		}
+		if value.is_fat_client() {
+			result.push(Mode::Partition)
+		}
+		result
+	}
+}
+
+impl Reply for Status {
+	fn into_response(self) -> warp::reply::Response {
+		json_reply(self)
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum Topic {
+	HeaderVerified,
+	ConfidenceAchieved,
+	DataVerified,
+	SubmittedDataIncluded,
+	BlockFinalized,
+	BlockReorged,
+	StatusChanged,
+	AppRegistered,
+	SyncProgress,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum DataField {
 	Data,
 	Extrinsic,
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Default)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
 pub struct Subscription {
 	pub topics: HashSet<Topic>,
 	pub data_fields: HashSet<DataField>,
+	/// If set, only headers of blocks containing data of the application are published
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub app_id: Option<u32>,
+	/// If not empty, only data of the given applications is published
+	#[serde(default, skip_serializing_if = "HashSet::is_empty")]
+	pub app_ids: HashSet<u32>,
+	/// If set to `false`, commitments are omitted from the published headers (included by default)
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub include_commitments: Option<bool>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// Subscription request, with optional block number from which verified headers are replayed
+/// when client connects, before live messages.
+#[derive(Deserialize)]
+pub struct SubscriptionRequest {
+	#[serde(flatten)]
+	pub subscription: Subscription,
+	pub from_block: Option<u32>,
+	/// If set, the last published messages of the subscribed topics are replayed when client connects
+	#[serde(default)]
+	pub replay: bool,
+	/// Protocol version the client is implemented for, latest version is assumed if omitted
+	pub protocol_version: Option<u32>,
+}
+
+/// Versions of the web socket protocol supported by the light client.
+pub const PROTOCOL_VERSIONS: [u32; 1] = [1];
+
+/// Checks if the given protocol version, if any, is supported.
+pub fn check_protocol_version(protocol_version: Option<u32>) -> Result<(), String> {
+	match protocol_version {
+		Some(version) if !PROTOCOL_VERSIONS.contains(&version) => {
+			let supported = PROTOCOL_VERSIONS.map(|version| version.to_string());
+			Err(format!(
+				"Unsupported protocol version {version}, supported versions are: {}",
+				supported.join(", ")
+			))
+		},
+		_ => Ok(()),
+	}
+}
+
+impl Subscription {
+	/// Checks if commitments are included in the published headers.
+	fn includes_commitments(&self) -> bool {
+		self.include_commitments.unwrap_or(true)
+	}
+
+	/// Number of subscribed topics and application IDs.
+	fn size(&self) -> usize {
+		self.topics.len() + self.app_ids.len()
+	}
+
+	/// Merges given topics and data fields into the subscription (union of both).
+	fn merge(&mut self, subscription: Subscription) {
+		self.topics.extend(subscription.topics);
+		self.data_fields.extend(subscription.data_fields);
+		if subscription.app_id.is_some() {
+			self.app_id = subscription.app_id;
+		}
+		self.app_ids.extend(subscription.app_ids);
+		if subscription.include_commitments.is_some() {
+			self.include_commitments = subscription.include_commitments;
+		}
+	}
+
+	/// Removes given topics and data fields from the subscription.
+	fn remove(&mut self, subscription: &Subscription) {
+		self.topics
+			.retain(|topic| !subscription.topics.contains(topic));
+		self.data_fields
+			.retain(|field| !subscription.data_fields.contains(field));
+		if subscription.app_id.is_some() && subscription.app_id == self.app_id {
+			self.app_id = None;
+		}
+		self.app_ids
+			.retain(|app_id| !subscription.app_ids.contains(app_id));
+		if subscription.include_commitments.is_some()
+			&& subscription.include_commitments == self.include_commitments
+		{
+			self.include_commitments = None;
+		}
+	}
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct HeaderMessage {
 	block_number: u32,
 	header: Header,
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Debug, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub enum BlockStatus {
 	Unavailable,
@@ -301,7 +1172,7 @@ pub fn block_status(
 	Some(BlockStatus::Pending)
 }
 
-#[derive(Serialize, Deserialize, PartialEq)]
+#[derive(Serialize, Deserialize, PartialEq, JsonSchema)]
 pub struct Block {
 	pub status: BlockStatus,
 	pub confidence: Option<f64>,
@@ -315,7 +1186,7 @@ impl Block {
 
 impl Reply for Block {
 	fn into_response(self) -> warp::reply::Response {
-		warp::reply::json(&self).into_response()
+		json_reply(self)
 	}
 }
 
@@ -331,32 +1202,93 @@ impl TryFrom<avail_subxt::primitives::Header> for HeaderMessage {
 	}
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct Header {
+	#[schemars(with = "String")]
 	hash: H256,
+	#[schemars(with = "String")]
 	parent_hash: H256,
 	pub number: u32,
+	#[schemars(with = "String")]
 	state_root: H256,
+	#[schemars(with = "String")]
 	extrinsics_root: H256,
 	extension: Extension,
 }
 
+impl Header {
+	/// Sets encoding used to serialize header commitments.
+	pub fn with_commitment_encoding(mut self, encoding: CommitmentEncoding) -> Self {
+		for commitment in self.extension.commitments.iter_mut().flatten() {
+			commitment.encoding = encoding;
+		}
+		self
+	}
+}
+
+impl HeaderMessage {
+	/// Checks if the block contains data of the given application.
+	fn contains_app(&self, app_id: u32) -> bool {
+		let app_cells = &self.header.extension.app_cells;
+		app_cells
+			.iter()
+			.any(|&(id, cells)| id == app_id && cells > 0)
+	}
+
+	/// Sets encoding used to serialize header commitments.
+	pub fn with_commitment_encoding(mut self, encoding: CommitmentEncoding) -> Self {
+		self.header = self.header.with_commitment_encoding(encoding);
+		self
+	}
+
+	/// Omits commitments from the header, to reduce the message size.
+	pub fn omit_commitments(&mut self) {
+		self.header.extension.commitments = None;
+	}
+}
+
 impl Reply for Header {
 	fn into_response(self) -> warp::reply::Response {
-		warp::reply::json(&self).into_response()
+		json_reply(self)
 	}
 }
 
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum CommitmentEncoding {
+	#[default]
+	Hex,
+	Base64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct HeaderQuery {
+	pub commitments: Option<CommitmentEncoding>,
+}
+
 #[derive(Debug, Clone)]
-struct Commitment([u8; config::COMMITMENT_SIZE]);
+struct Commitment {
+	bytes: [u8; config::COMMITMENT_SIZE],
+	encoding: CommitmentEncoding,
+}
 
 impl Serialize for Commitment {
 	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
 	where
 		S: Serializer,
 	{
-		let hex_string = format!("0x{}", hex::encode(self.0));
-		serializer.serialize_str(&hex_string)
+		if !serializer.is_human_readable() {
+			return serializer.serialize_bytes(&self.bytes);
+		}
+		match self.encoding {
+			CommitmentEncoding::Hex => {
+				let hex_string = format!("0x{}", hex::encode(self.bytes));
+				serializer.serialize_str(&hex_string)
+			},
+			CommitmentEncoding::Base64 => {
+				serializer.serialize_str(&general_purpose::STANDARD.encode(self.bytes))
+			},
+		}
 	}
 }
 
@@ -369,9 +1301,35 @@ impl<'de> Deserialize<'de> for Commitment {
 		const HEX_ENCODED_BYTE_LEN: usize = 2;
 		const LEN: usize = (config::COMMITMENT_SIZE * HEX_ENCODED_BYTE_LEN) + PREFIX_0X_LEN;
 
+		if !deserializer.is_human_readable() {
+			let decoded = deserializer.deserialize_byte_buf(BytesVisitor)?;
+			let decoded_len = decoded.len();
+			let bytes: [u8; config::COMMITMENT_SIZE] = decoded.try_into().map_err(|_| {
+				de::Error::invalid_length(decoded_len, &"Expected vector of 48 bytes")
+			})?;
+			return Ok(Commitment {
+				bytes,
+				encoding: CommitmentEncoding::Hex,
+			});
+		}
+
 		let s = String::deserialize(deserializer)?;
 
-		if !s.starts_with("0x") || s.len() != LEN {
+		if !s.starts_with("0x") {
+			let decoded = general_purpose::STANDARD
+				.decode(&s)
+				.map_err(de::Error::custom)?;
+			let decoded_len = decoded.len();
+			let bytes: [u8; config::COMMITMENT_SIZE] = decoded.try_into().map_err(|_| {
+				de::Error::invalid_length(decoded_len, &"Expected vector of 48 bytes")
+			})?;
+			return Ok(Commitment {
+				bytes,
+				encoding: CommitmentEncoding::Base64,
+			});
+		}
+
+		if s.len() != LEN {
 			let message = "Expected a hex string of correct length with 0x prefix";
 			return Err(de::Error::custom(message));
 		}
@@ -382,17 +1340,45 @@ impl<'de> Deserialize<'de> for Commitment {
 			.try_into()
 			.map_err(|_| de::Error::invalid_length(decoded_len, &"Expected vector of 48 bytes"))?;
 
-		Ok(Commitment(bytes))
+		Ok(Commitment {
+			bytes,
+			encoding: CommitmentEncoding::Hex,
+		})
 	}
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 struct Extension {
+	/// Version of the header extension, which defines semantics of the extension fields
+	extension_version: u8,
 	rows: u16,
 	cols: u16,
+	#[schemars(with = "String")]
 	data_root: H256,
-	commitments: Vec<Commitment>,
+	/// Commitments are omitted if not requested
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	#[schemars(with = "Option<Vec<String>>")]
+	commitments: Option<Vec<Commitment>>,
+	#[schemars(with = "serde_json::Value")]
 	app_lookup: CompactDataLookup,
+	/// Number of matrix cells per application, as (app_id, cells) pairs
+	app_cells: Vec<(u32, u32)>,
+}
+
+fn app_cells(app_lookup: &CompactDataLookup) -> Vec<(u32, u32)> {
+	let ends = app_lookup
+		.index
+		.iter()
+		.skip(1)
+		.map(|item| item.start)
+		.chain([app_lookup.size]);
+
+	app_lookup
+		.index
+		.iter()
+		.zip(ends)
+		.map(|(item, end)| (item.app_id.0, end.saturating_sub(item.start)))
+		.collect()
 }
 
 impl TryFrom<avail_subxt::primitives::Header> for Header {
@@ -410,22 +1396,34 @@ impl TryFrom<avail_subxt::primitives::Header> for Header {
 	}
 }
 
+/// Decodes header commitments, rejecting commitments which don't fit into the extended matrix rows.
+/// Number of rows is capped to the configured limit when blocks are processed, so it is not capped here.
+fn header_commitments(commitment: &KateCommitment) -> Result<Vec<[u8; config::COMMITMENT_SIZE]>> {
+	let KateCommitment { rows, cols, .. } = *commitment;
+	utils::header_commitments(rows, cols, &commitment.commitment, u16::MAX)
+}
+
 impl TryFrom<HeaderExtension> for Extension {
 	type Error = Report;
 
 	fn try_from(value: HeaderExtension) -> Result<Self, Self::Error> {
 		match value {
 			HeaderExtension::V3(v3) => {
-				let commitments = commitments::from_slice(&v3.commitment.commitment)?
+				let commitments = header_commitments(&v3.commitment)?
 					.into_iter()
-					.map(Commitment)
+					.map(|bytes| Commitment {
+						bytes,
+						encoding: CommitmentEncoding::default(),
+					})
 					.collect::<Vec<_>>();
 
 				Ok(Extension {
+					extension_version: 3,
 					rows: v3.commitment.rows,
 					cols: v3.commitment.cols,
 					data_root: v3.commitment.data_root,
-					commitments,
+					commitments: Some(commitments),
+					app_cells: app_cells(&v3.app_lookup),
 					app_lookup: v3.app_lookup,
 				})
 			},
@@ -433,6 +1431,30 @@ impl TryFrom<HeaderExtension> for Extension {
 	}
 }
 
+impl CommitmentsMessage {
+	/// Creates commitments message from the header, without decoding the rest of the header.
+	pub fn new(
+		header: avail_subxt::primitives::Header,
+		encoding: CommitmentEncoding,
+	) -> Result<Self> {
+		match header.extension {
+			HeaderExtension::V3(v3) => {
+				let commitments = header_commitments(&v3.commitment)?
+					.into_iter()
+					.map(|bytes| Commitment { bytes, encoding })
+					.collect::<Vec<_>>();
+
+				Ok(CommitmentsMessage {
+					block_number: header.number,
+					rows: v3.commitment.rows,
+					cols: v3.commitment.cols,
+					commitments,
+				})
+			},
+		}
+	}
+}
+
 impl TryFrom<RpcEvent> for PublishMessage {
 	type Error = Report;
 
@@ -446,6 +1468,27 @@ impl TryFrom<RpcEvent> for PublishMessage {
 	}
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BlockMessage {
+	block_number: u32,
+	hash: H256,
+}
+
+impl TryFrom<BlockEvent> for PublishMessage {
+	type Error = Report;
+
+	fn try_from(value: BlockEvent) -> Result<Self, Self::Error> {
+		Ok(match value {
+			BlockEvent::Finalized { block_number, hash } => {
+				PublishMessage::BlockFinalized(BlockMessage { block_number, hash })
+			},
+			BlockEvent::Reorged { block_number, hash } => {
+				PublishMessage::BlockReorged(BlockMessage { block_number, hash })
+			},
+		})
+	}
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ConfidenceMessage {
 	block_number: u32,
@@ -494,13 +1537,55 @@ pub struct DataResponse {
 
 impl Reply for DataResponse {
 	fn into_response(self) -> warp::reply::Response {
-		warp::reply::json(&self).into_response()
+		json_reply(self)
+	}
+}
+
+/// Maximum number of headers returned in a single headers range response.
+pub const MAX_HEADERS_LIMIT: u32 = 100;
+
+#[derive(Serialize, Deserialize)]
+pub struct HeadersQuery {
+	pub first: u32,
+	pub last: u32,
+	pub limit: Option<u32>,
+	pub commitments: Option<CommitmentEncoding>,
+}
+
+impl HeadersQuery {
+	pub fn limit(&self) -> u32 {
+		self.limit
+			.unwrap_or(MAX_HEADERS_LIMIT)
+			.clamp(1, MAX_HEADERS_LIMIT)
+	}
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HeadersResponse {
+	pub headers: Vec<HeaderMessage>,
+	/// First block number of the next page, if the range was truncated by the limit.
+	pub next: Option<u32>,
+}
+
+impl Reply for HeadersResponse {
+	fn into_response(self) -> warp::reply::Response {
+		json_reply(self)
 	}
 }
 
+/// Returns the range of blocks with verified headers, including the synced ones.
+pub fn headers_range(state: &State) -> Option<types::BlockRange> {
+	let first = state.sync_header_verified.first();
+	let first = first.or_else(|| state.header_verified.first())?;
+	let last = state.header_verified.last();
+	let last = last.or_else(|| state.sync_header_verified.last())?;
+	Some(types::BlockRange { first, last })
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct DataMessage {
 	block_number: u32,
+	app_id: u32,
 	data_transactions: Vec<DataTransaction>,
 }
 
@@ -523,95 +1608,665 @@ impl TryFrom<Vec<u8>> for DataTransaction {
 	}
 }
 
+/// Projects data transactions down to the requested fields.
+/// If no fields are requested, transactions are left without payload.
 pub fn filter_fields(data_transactions: &mut [DataTransaction], fields: &HashSet<DataField>) {
 	if !fields.contains(&DataField::Extrinsic) {
 		for transaction in data_transactions.iter_mut() {
 			transaction.extrinsic = None
 		}
 	}
-	if !fields.contains(&DataField::Data) && fields.contains(&DataField::Extrinsic) {
+	if !fields.contains(&DataField::Data) {
 		for transaction in data_transactions.iter_mut() {
 			transaction.data = None
 		}
 	}
 }
 
-impl TryFrom<(u32, AppData)> for PublishMessage {
+impl TryFrom<(u32, u32, AppData)> for PublishMessage {
 	type Error = Report;
 
-	fn try_from((block_number, app_data): (u32, AppData)) -> Result<Self, Self::Error> {
+	fn try_from(
+		(app_id, block_number, app_data): (u32, u32, AppData),
+	) -> Result<Self, Self::Error> {
 		let data_transactions = app_data
 			.into_iter()
 			.map(TryFrom::try_from)
 			.collect::<Result<Vec<_>>>()?;
 		Ok(PublishMessage::DataVerified(DataMessage {
 			block_number,
+			app_id,
 			data_transactions,
 		}))
 	}
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IncludedTransaction {
+	hash: H256,
+	/// Index of the extrinsic in the block
+	index: u32,
+	/// App ID and data position are omitted if extrinsic cannot be decoded
+	#[serde(skip_serializing_if = "Option::is_none")]
+	app_id: Option<u32>,
+	/// 0-based position of the transaction within the app data of the block
+	#[serde(skip_serializing_if = "Option::is_none")]
+	data_position: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SubmittedDataMessage {
+	block_number: u32,
+	transactions: Vec<IncludedTransaction>,
+}
+
+impl SubmittedDataMessage {
+	/// Creates message with hashes of all block extrinsics, regardless of the application,
+	/// so clients can match them with hashes of the submitted transactions.
+	/// Positions within the app data are counted per app ID, in block order.
+	pub fn new(block_number: u32, extrinsics: &[Vec<u8>]) -> Self {
+		let mut positions = HashMap::<u32, u32>::new();
+		let transactions = extrinsics
+			.iter()
+			.zip(0..)
+			.map(|(extrinsic, index)| {
+				let app_id = AppUncheckedExtrinsic::decode(&mut &extrinsic[..])
+					.ok()
+					.map(|extrinsic| extrinsic.app_id().0);
+				let data_position = app_id.map(|app_id| {
+					let position = positions.entry(app_id).or_default();
+					*position += 1;
+					*position - 1
+				});
+				IncludedTransaction {
+					hash: blake2_256(extrinsic).into(),
+					index,
+					app_id,
+					data_position,
+				}
+			})
+			.collect();
+
+		SubmittedDataMessage {
+			block_number,
+			transactions,
+		}
+	}
+}
+
+/// Application whose data is seen for the first time since the light client started
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AppRegisteredMessage {
+	pub app_id: u32,
+	pub block_number: u32,
+}
+
+/// Progress of the historical sync
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SyncProgressMessage {
+	pub synced: bool,
+	/// Range of the blocks to sync
+	pub sync_range: BlockRange,
+	/// Range of the synced blocks with achieved confidence
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub available: Option<BlockRange>,
+	/// Percentage of the synced blocks in the sync range
+	pub percentage: f64,
+}
+
+impl SyncProgressMessage {
+	/// Creates sync progress from the state, if historical sync is started and the sync range is not empty.
+	pub fn new(sync_range: &Range<u32>, state: &State) -> Option<Self> {
+		let synced = state.synced?;
+		if sync_range.is_empty() {
+			return None;
+		}
+
+		let available = state
+			.sync_confidence_achieved
+			.as_ref()
+			.map(BlockRange::from);
+		// Blocks are synced in order, so all blocks up to the last synced one are processed
+		let total = sync_range.len();
+		let processed = available.as_ref().map_or(0, |range| {
+			(range.last + 1).saturating_sub(sync_range.start) as usize
+		});
+		let percentage = match synced {
+			true => 100.0,
+			false => 100.0 * processed.min(total) as f64 / total as f64,
+		};
+
+		Some(SyncProgressMessage {
+			synced,
+			sync_range: BlockRange {
+				first: sync_range.start,
+				last: sync_range.end - 1,
+			},
+			available,
+			percentage,
+		})
+	}
+}
+
 #[derive(Deserialize, Serialize, Clone)]
 #[serde(tag = "topic", content = "message", rename_all = "kebab-case")]
 pub enum PublishMessage {
 	HeaderVerified(Box<HeaderMessage>),
 	ConfidenceAchieved(ConfidenceMessage),
 	DataVerified(DataMessage),
+	SubmittedDataIncluded(SubmittedDataMessage),
+	BlockFinalized(BlockMessage),
+	BlockReorged(BlockMessage),
+	/// Status fields changed since the previously published status changes, as JSON merge patch
+	StatusChanged(serde_json::Value),
+	AppRegistered(AppRegisteredMessage),
+	SyncProgress(SyncProgressMessage),
 }
 
 impl PublishMessage {
-	fn apply_filter(&mut self, fields: &HashSet<DataField>) {
+	pub fn topic(&self) -> Topic {
+		match self {
+			PublishMessage::HeaderVerified(_) => Topic::HeaderVerified,
+			PublishMessage::ConfidenceAchieved(_) => Topic::ConfidenceAchieved,
+			PublishMessage::DataVerified(_) => Topic::DataVerified,
+			PublishMessage::SubmittedDataIncluded(_) => Topic::SubmittedDataIncluded,
+			PublishMessage::BlockFinalized(_) => Topic::BlockFinalized,
+			PublishMessage::BlockReorged(_) => Topic::BlockReorged,
+			PublishMessage::StatusChanged(_) => Topic::StatusChanged,
+			PublishMessage::AppRegistered(_) => Topic::AppRegistered,
+			PublishMessage::SyncProgress(_) => Topic::SyncProgress,
+		}
+	}
+
+	/// Serializes message content, without the topic.
+	fn content_json(&self, naming: FieldNaming) -> serde_json::Result<Box<RawValue>> {
+		match self {
+			PublishMessage::HeaderVerified(message) => to_raw_json(message, naming),
+			PublishMessage::ConfidenceAchieved(message) => to_raw_json(message, naming),
+			PublishMessage::DataVerified(message) => to_raw_json(message, naming),
+			PublishMessage::SubmittedDataIncluded(message) => to_raw_json(message, naming),
+			PublishMessage::BlockFinalized(message) => to_raw_json(message, naming),
+			PublishMessage::BlockReorged(message) => to_raw_json(message, naming),
+			PublishMessage::StatusChanged(message) => to_raw_json(message, naming),
+			PublishMessage::AppRegistered(message) => to_raw_json(message, naming),
+			PublishMessage::SyncProgress(message) => to_raw_json(message, naming),
+		}
+	}
+
+	fn apply_projection(&mut self, projection: Projection) {
 		match self {
+			PublishMessage::HeaderVerified(header) if !projection.commitments => {
+				header.omit_commitments()
+			},
 			PublishMessage::HeaderVerified(_) => (),
 			PublishMessage::ConfidenceAchieved(_) => (),
 			PublishMessage::DataVerified(data) => {
-				filter_fields(&mut data.data_transactions, fields)
+				filter_fields(&mut data.data_transactions, &projection.data_fields())
 			},
+			PublishMessage::SubmittedDataIncluded(_) => (),
+			PublishMessage::BlockFinalized(_) => (),
+			PublishMessage::BlockReorged(_) => (),
+			PublishMessage::StatusChanged(_) => (),
+			PublishMessage::AppRegistered(_) => (),
+			PublishMessage::SyncProgress(_) => (),
 		}
 	}
-}
 
-impl TryFrom<PublishMessage> for Message {
-	type Error = Report;
-	fn try_from(value: PublishMessage) -> Result<Self, Self::Error> {
-		serde_json::to_string(&value)
-			.map(ws::Message::text)
-			.wrap_err("Cannot serialize publish message")
+	/// Returns number and hash of the block with verified header.
+	pub fn verified_block(&self) -> Option<(u32, H256)> {
+		let PublishMessage::HeaderVerified(header) = self else {
+			return None;
+		};
+		Some((header.block_number, header.header.hash))
+	}
+
+	/// Creates messages for the applications whose data is contained in the verified block
+	/// for the first time, and adds them to the seen applications.
+	pub fn apps_registered(&self, seen_app_ids: &mut HashSet<u32>) -> Vec<PublishMessage> {
+		let PublishMessage::HeaderVerified(header) = self else {
+			return vec![];
+		};
+
+		header
+			.header
+			.extension
+			.app_cells
+			.iter()
+			.filter(|&&(app_id, cells)| cells > 0 && seen_app_ids.insert(app_id))
+			.map(|&(app_id, _)| {
+				PublishMessage::AppRegistered(AppRegisteredMessage {
+					app_id,
+					block_number: header.block_number,
+				})
+			})
+			.collect()
 	}
 }
 
-pub type Sender = UnboundedSender<Result<ws::Message, warp::Error>>;
+/// Subscription options which change the published message,
+/// so message is serialized once per projection, instead of once per client.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct Projection {
+	data: bool,
+	extrinsic: bool,
+	commitments: bool,
+}
 
-pub struct WsClient {
-	pub subscription: Subscription,
-	pub sender: Option<Sender>,
+impl Projection {
+	fn data_fields(&self) -> HashSet<DataField> {
+		let data = self.data.then_some(DataField::Data);
+		let extrinsic = self.extrinsic.then_some(DataField::Extrinsic);
+		data.into_iter().chain(extrinsic).collect()
+	}
 }
 
-impl WsClient {
-	pub fn new(subscription: Subscription) -> Self {
-		WsClient {
-			subscription,
-			sender: None,
+impl From<&Subscription> for Projection {
+	fn from(subscription: &Subscription) -> Self {
+		Projection {
+			data: subscription.data_fields.contains(&DataField::Data),
+			extrinsic: subscription.data_fields.contains(&DataField::Extrinsic),
+			commitments: subscription.includes_commitments(),
 		}
 	}
+}
 
-	fn is_subscribed(&self, topic: &Topic) -> bool {
-		self.subscription.topics.contains(topic)
-	}
+/// Publish message with the content serialized once, sent to each client with its own sequence number.
+struct SerializedMessage {
+	topic: Topic,
+	message: Box<RawValue>,
+}
 
-	fn sender_with_data_fields(&self) -> Option<(&Sender, &HashSet<DataField>)> {
-		self.sender
-			.as_ref()
-			.map(|sender| (sender, &self.subscription.data_fields))
+/// Publish message sent to the client, with the sequence number of the message sent to the client,
+/// so client can detect dropped messages.
+#[derive(Serialize)]
+struct SequencedMessage<'a> {
+	topic: &'a Topic,
+	message: &'a RawValue,
+	seq: u64,
+}
+
+impl SerializedMessage {
+	fn with_seq(&self, seq: u64) -> Result<ws::Message> {
+		let message = SequencedMessage {
+			topic: &self.topic,
+			message: &self.message,
+			seq,
+		};
+		serde_json::to_string(&message)
+			.map(ws::Message::text)
+			.wrap_err("Cannot serialize sequenced message")
 	}
 }
 
+/// Serializes publish message with the given projection applied.
+fn serialize_projection(
+	mut message: PublishMessage,
+	projection: Projection,
+	naming: FieldNaming,
+) -> Result<SerializedMessage> {
+	message.apply_projection(projection);
+	let content = message
+		.content_json(naming)
+		.wrap_err("Cannot serialize publish message")?;
+	Ok(SerializedMessage {
+		topic: message.topic(),
+		message: content,
+	})
+}
+
+/// Published messages and HTTP JSON responses larger than threshold (in bytes) are compressed,
+/// if client enabled compression.
+pub const COMPRESSION_THRESHOLD: usize = 1024;
+
+/// Marker of the binary message with zlib (deflate) compressed JSON text.
+pub const DEFLATE_MESSAGE_MARKER: u8 = 1;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Compression {
+	Deflate,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct WsQuery {
+	pub compression: Option<Compression>,
+}
+
+/// Compresses text message if it exceeds compression threshold.
+/// Compressed message is sent as binary message, prefixed with compression marker.
+fn compress(message: Message, compression: Option<Compression>) -> Message {
+	match compression {
+		Some(Compression::Deflate) if message.as_bytes().len() > COMPRESSION_THRESHOLD => {
+			let mut bytes = vec![DEFLATE_MESSAGE_MARKER];
+			bytes.extend(miniz_oxide::deflate::compress_to_vec_zlib(
+				message.as_bytes(),
+				6,
+			));
+			Message::binary(bytes)
+		},
+		_ => message,
+	}
+}
+
+pub type Sender = broadcast::Sender<ws::Message>;
+
+/// WebSocket close code sent to clients when server is shutting down.
+const GOING_AWAY: u16 = 1001;
+/// WebSocket close code sent to clients which violate the protocol.
+const POLICY_VIOLATION: u16 = 1008;
+/// WebSocket close code sent to clients which send requests exceeding the size limit.
+pub const MESSAGE_TOO_BIG: u16 = 1009;
+/// WebSocket close code sent to clients when the request cannot be handled because of server error.
+const INTERNAL_ERROR: u16 = 1011;
+/// WebSocket close code sent to clients when the service is temporarily unavailable.
+const TRY_AGAIN_LATER: u16 = 1013;
+
+pub struct WsClient {
+	pub subscription: Subscription,
+	pub sender: Option<Sender>,
+	/// Compression of the published messages, negotiated on connect.
+	pub compression: Option<Compression>,
+	/// Number of consecutive heartbeat pings without pong response.
+	pub missed_pongs: u32,
+	/// Number of messages dropped because client buffer was full.
+	pub dropped_messages: u64,
+	/// Block number from which verified headers are replayed on connect.
+	pub replay_from: Option<u32>,
+	/// If set, buffered messages are replayed on connect.
+	pub replay_buffered: bool,
+	/// Sequence number of the last message published to the client, reset on connect.
+	pub seq: u64,
+	/// Time since the client buffer is full, reset once the client takes buffered messages.
+	pub full_since: Option<Instant>,
+	/// Task forwarding buffered messages to the client connection, aborted on eviction.
+	pub forward: Option<AbortHandle>,
+}
+
+impl WsClient {
+	pub fn new(subscription: Subscription) -> Self {
+		WsClient {
+			subscription,
+			sender: None,
+			compression: None,
+			missed_pongs: 0,
+			dropped_messages: 0,
+			replay_from: None,
+			replay_buffered: false,
+			seq: 0,
+			full_since: None,
+			forward: None,
+		}
+	}
+
+	fn is_subscribed(&self, topic: &Topic) -> bool {
+		self.subscription.topics.contains(topic)
+	}
+
+	/// Headers of blocks without data of the subscribed application,
+	/// and data of applications which are not subscribed to, are skipped.
+	fn accepts(&self, message: &PublishMessage) -> bool {
+		let app_ids = &self.subscription.app_ids;
+		match (message, self.subscription.app_id) {
+			(PublishMessage::HeaderVerified(header), Some(app_id)) => header.contains_app(app_id),
+			(PublishMessage::DataVerified(data), _) => {
+				app_ids.is_empty() || app_ids.contains(&data.app_id)
+			},
+			_ => true,
+		}
+	}
+
+	/// Converts message to the ws message for the client, applying subscription filters,
+	/// next sequence number and compression.
+	fn ws_message(&mut self, message: PublishMessage, naming: FieldNaming) -> Result<ws::Message> {
+		self.seq += 1;
+		let message = serialize_projection(message, (&self.subscription).into(), naming)
+			.wrap_err("Cannot convert to ws message")?;
+		Ok(compress(message.with_seq(self.seq)?, self.compression))
+	}
+
+	/// Takes snapshot of the connected client, with the next sequence number,
+	/// so message can be sent to the client without holding the clients lock.
+	fn delivery(&mut self, subscription_id: &str) -> Option<Delivery> {
+		let sender = self.sender.clone()?;
+		self.seq += 1;
+		Some(Delivery {
+			subscription_id: subscription_id.to_string(),
+			projection: (&self.subscription).into(),
+			compression: self.compression,
+			seq: self.seq,
+			sender,
+		})
+	}
+}
+
+/// Publish message serialized once per distinct projection of the clients it is delivered to.
+/// Serialization errors are kept as text, since they are reported to each client of the projection.
+struct Serialized(HashMap<Projection, Result<SerializedMessage, String>>);
+
+impl Serialized {
+	fn new(message: &PublishMessage, deliveries: &[Delivery], naming: FieldNaming) -> Self {
+		let mut serialized = HashMap::new();
+		for delivery in deliveries {
+			serialized.entry(delivery.projection).or_insert_with(|| {
+				serialize_projection(message.clone(), delivery.projection, naming)
+					.map_err(|error| format!("{error:#}"))
+			});
+		}
+		Serialized(serialized)
+	}
+
+	/// Converts serialized message to the ws message for the client,
+	/// with the sequence number and compression of the client.
+	fn ws_message(&self, delivery: &Delivery) -> Result<ws::Message> {
+		match self.0.get(&delivery.projection) {
+			Some(Ok(message)) => Ok(compress(
+				message.with_seq(delivery.seq)?,
+				delivery.compression,
+			)),
+			Some(Err(error)) => Err(eyre!("Cannot convert to ws message: {error}")),
+			None => Err(eyre!("Message is not serialized for the client")),
+		}
+	}
+}
+
+/// Snapshot of the connected client the published message is delivered to.
+struct Delivery {
+	subscription_id: String,
+	projection: Projection,
+	compression: Option<Compression>,
+	seq: u64,
+	sender: Sender,
+}
+
+impl Delivery {
+	/// Sends message to the client. Returns the publish result, and whether the client is disconnected.
+	fn send(
+		&self,
+		topic: &Topic,
+		serialized: &Serialized,
+		expose_error_causes: bool,
+		naming: FieldNaming,
+	) -> Result<(Result<()>, bool)> {
+		// Client is notified if the message cannot be converted for it, instead of missing it silently
+		let (message, result) = match serialized.ws_message(self) {
+			Ok(message) => (message, Ok(())),
+			Err(error) => (
+				publish_error(topic, &error, expose_error_causes, naming)?,
+				Err(error),
+			),
+		};
+		if self.sender.send(message).is_err() {
+			let subscription_id = &self.subscription_id;
+			let error = eyre!("Send failed, client {subscription_id} is disconnected");
+			return Ok((Err(error), true));
+		}
+		Ok((result, false))
+	}
+}
+
+/// Sends message to the clients, split across the given number of worker tasks,
+/// so clients are not served one by one. Results are returned in the order of deliveries.
+async fn fan_out(
+	deliveries: Vec<Delivery>,
+	topic: &Topic,
+	serialized: Arc<Serialized>,
+	workers: usize,
+	expose_error_causes: bool,
+	naming: FieldNaming,
+) -> Result<Vec<(Result<()>, bool)>> {
+	let send_all = move |deliveries: Vec<Delivery>, topic: Topic, serialized: Arc<Serialized>| {
+		deliveries
+			.iter()
+			.map(|delivery| delivery.send(&topic, &serialized, expose_error_causes, naming))
+			.collect::<Result<Vec<_>>>()
+	};
+
+	if workers <= 1 || deliveries.len() <= 1 {
+		return send_all(deliveries, topic.clone(), serialized);
+	}
+
+	let chunk_size = deliveries.len().div_ceil(workers);
+	let mut deliveries = deliveries.into_iter();
+	let mut tasks = vec![];
+	loop {
+		let chunk = deliveries.by_ref().take(chunk_size).collect::<Vec<_>>();
+		if chunk.is_empty() {
+			break;
+		}
+		let (topic, serialized) = (topic.clone(), serialized.clone());
+		tasks.push(tokio::spawn(
+			async move { send_all(chunk, topic, serialized) },
+		));
+	}
+
+	let mut results = vec![];
+	for task in tasks {
+		results.extend(task.await.wrap_err("Publish worker failed")??);
+	}
+	Ok(results)
+}
+
+/// Configuration of the fan-out of the published messages to the clients.
+#[derive(Default, Clone, Copy)]
+struct FanOut {
+	workers: usize,
+	/// Include cause chains in the errors sent instead of the messages which cannot be published
+	expose_error_causes: bool,
+	field_naming: FieldNaming,
+}
+
+/// Last published messages per topic, kept up to the configured depth.
+/// Messages are numbered in publish order, so messages of different topics are replayed in order.
+#[derive(Default)]
+struct ReplayBuffer {
+	depth: usize,
+	published: u64,
+	messages: HashMap<Topic, VecDeque<(u64, PublishMessage)>>,
+}
+
+impl ReplayBuffer {
+	fn push(&mut self, topic: &Topic, message: PublishMessage) {
+		if self.depth == 0 {
+			return;
+		}
+		self.published += 1;
+		let messages = self.messages.entry(topic.clone()).or_default();
+		if messages.len() == self.depth {
+			messages.pop_front();
+		}
+		messages.push_back((self.published, message));
+	}
+
+	/// Returns buffered messages of the given topics, in publish order.
+	fn messages(&self, topics: &HashSet<Topic>) -> Vec<PublishMessage> {
+		let mut messages = topics
+			.iter()
+			.filter_map(|topic| self.messages.get(topic))
+			.flatten()
+			.collect::<Vec<_>>();
+		messages.sort_by_key(|(published, _)| *published);
+		messages
+			.into_iter()
+			.map(|(_, message)| message.clone())
+			.collect()
+	}
+}
+
+/// Subscribed clients, with the number of live web socket connections,
+/// and the last published messages which are replayed on request.
+/// Clients are kept in subscription order, so messages are published to older clients first.
 #[derive(Clone)]
-pub struct WsClients(pub Arc<RwLock<HashMap<String, WsClient>>>);
+pub struct WsClients {
+	pub clients: Arc<RwLock<IndexMap<String, WsClient>>>,
+	/// Number of live web socket connections.
+	connections: Arc<AtomicUsize>,
+	replay_buffer: Arc<Mutex<ReplayBuffer>>,
+	/// Publishing is serialized, so clients receive messages in the order of their sequence numbers.
+	publishing: Arc<tokio::sync::Mutex<()>>,
+	fan_out: FanOut,
+}
+
+/// Live web socket connection, which is unregistered when dropped.
+pub struct Connection(Arc<AtomicUsize>);
+
+impl Drop for Connection {
+	fn drop(&mut self) {
+		self.0.fetch_sub(1, Ordering::SeqCst);
+	}
+}
 
 impl WsClients {
+	/// Creates clients which keep the given number of the last published messages per topic.
+	pub fn new(replay_buffer_depth: usize) -> Self {
+		let replay_buffer = ReplayBuffer {
+			depth: replay_buffer_depth,
+			..Default::default()
+		};
+		Self {
+			clients: Arc::new(RwLock::new(IndexMap::new())),
+			connections: Arc::default(),
+			replay_buffer: Arc::new(Mutex::new(replay_buffer)),
+			publishing: Arc::default(),
+			fan_out: FanOut::default(),
+		}
+	}
+
+	/// Sets number of worker tasks which send published messages to the clients in parallel.
+	pub fn with_publish_workers(mut self, workers: usize) -> Self {
+		self.fan_out.workers = workers;
+		self
+	}
+
+	/// Sets whether cause chains are included in the errors sent to the clients
+	/// instead of the messages which cannot be published.
+	pub fn with_error_causes(mut self, expose_error_causes: bool) -> Self {
+		self.fan_out.expose_error_causes = expose_error_causes;
+		self
+	}
+
+	/// Sets naming of the fields in the messages published to the clients.
+	pub fn with_field_naming(mut self, field_naming: FieldNaming) -> Self {
+		self.fan_out.field_naming = field_naming;
+		self
+	}
+
+	/// Registers new web socket connection, unless the maximum number of connections is reached.
+	pub fn register_connection(&self, max_connections: usize) -> Option<Connection> {
+		self.connections
+			.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| {
+				(count < max_connections).then_some(count + 1)
+			})
+			.ok()?;
+		Some(Connection(self.connections.clone()))
+	}
+
+	/// Number of live web socket connections.
+	pub fn connections(&self) -> usize {
+		self.connections.load(Ordering::SeqCst)
+	}
+
 	pub async fn set_sender(&self, subscription_id: &str, sender: Sender) -> Result<()> {
-		let mut clients = self.0.write().await;
+		let mut clients = self.clients.write().await;
 		let Some(client) = clients.get_mut(subscription_id) else {
 			return Err(eyre!("Client is not subscribed"));
 		};
@@ -619,49 +2274,507 @@ impl WsClients {
 		Ok(())
 	}
 
-	pub async fn has_subscription(&self, subscription_id: &str) -> bool {
-		self.0.read().await.contains_key(subscription_id)
+	pub async fn set_compression(&self, subscription_id: &str, compression: Option<Compression>) {
+		if let Some(client) = self.clients.write().await.get_mut(subscription_id) {
+			client.compression = compression;
+		}
+	}
+
+	/// Resets sequence number of the published messages, since it is tracked per connection.
+	pub async fn reset_seq(&self, subscription_id: &str) {
+		if let Some(client) = self.clients.write().await.get_mut(subscription_id) {
+			client.seq = 0;
+		}
 	}
 
-	pub async fn subscribe(&self, subscription_id: &str, subscription: Subscription) {
-		let mut clients = self.0.write().await;
-		clients.insert(subscription_id.to_string(), WsClient::new(subscription));
+	pub async fn set_replay_from(&self, subscription_id: &str, from_block: Option<u32>) {
+		if let Some(client) = self.clients.write().await.get_mut(subscription_id) {
+			client.replay_from = from_block;
+		}
+	}
+
+	pub async fn set_replay_buffered(&self, subscription_id: &str, replay: bool) {
+		if let Some(client) = self.clients.write().await.get_mut(subscription_id) {
+			client.replay_buffered = replay;
+		}
 	}
 
-	pub async fn publish(&self, topic: &Topic, message: PublishMessage) -> Result<Vec<Result<()>>> {
-		let clients = self.0.read().await;
-		Ok(clients
+	/// Returns block from which verified headers are replayed to the client on connect, if any.
+	pub async fn replay_from(&self, subscription_id: &str) -> Option<u32> {
+		let clients = self.clients.read().await;
+		let client = clients.get(subscription_id)?;
+		client
+			.replay_from
+			.filter(|_| client.is_subscribed(&Topic::HeaderVerified))
+	}
+
+	/// Connects client to the given sender. Replayed headers, loaded before connecting,
+	/// and buffered messages if requested, are sent to the client before the sender is set.
+	/// Headers published after the replayed headers are loaded are taken from the replay buffer.
+	/// Replay and setting the sender are done while publishing is paused, so no message
+	/// is published in between, and live messages are sent after the replayed ones.
+	/// Replayed headers are de-duplicated by block number, and only the last messages
+	/// that fit into the client buffer of the given size are replayed.
+	/// Task forwarding the sender messages to the client is aborted if the client is evicted.
+	pub async fn connect(
+		&self,
+		subscription_id: &str,
+		sender: Sender,
+		forward: AbortHandle,
+		buffer_size: usize,
+		replayed: ReplayedHeaders,
+	) -> Result<()> {
+		let _publishing = self.publishing.lock().await;
+		let mut clients = self.clients.write().await;
+		let Some(client) = clients.get_mut(subscription_id) else {
+			return Err(eyre!("Client is not subscribed"));
+		};
+		client.seq = 0;
+
+		let replay_buffer = self
+			.replay_buffer
+			.lock()
+			.expect("Replay buffer lock can be acquired");
+		let mut messages = match client.replay_from.take() {
+			Some(_) if client.is_subscribed(&Topic::HeaderVerified) => {
+				let published = replay_buffer
+					.messages(&HashSet::from([Topic::HeaderVerified]))
+					.into_iter()
+					.filter(|message| match (message, replayed.loaded_until) {
+						(PublishMessage::HeaderVerified(header), Some(loaded_until)) => {
+							header.block_number > loaded_until
+						},
+						_ => false,
+					});
+				replayed.headers.into_iter().chain(published).collect()
+			},
+			_ => vec![],
+		};
+		if std::mem::take(&mut client.replay_buffered) {
+			messages.extend(replay_buffer.messages(&client.subscription.topics));
+		}
+		drop(replay_buffer);
+
+		let mut replayed_headers = HashSet::new();
+		messages.retain(|message| match message {
+			_ if !client.accepts(message) => false,
+			PublishMessage::HeaderVerified(header) => replayed_headers.insert(header.block_number),
+			_ => true,
+		});
+		let skipped = messages.len().saturating_sub(buffer_size);
+		send_replayed(
+			subscription_id,
+			client,
+			&sender,
+			messages.split_off(skipped),
+			self.fan_out.field_naming,
+		)?;
+
+		client.sender = Some(sender);
+		client.full_since = None;
+		client.forward = Some(forward);
+		Ok(())
+	}
+
+	/// Removes connected clients whose buffer of the given size stays full longer than
+	/// the grace period, and aborts forwarding of their messages, which closes the connection.
+	/// Client which doesn't read from the connection cannot take close frame from the full buffer,
+	/// so it is not sent. Returns subscription IDs of the evicted clients.
+	pub async fn evict_lagging(&self, buffer_size: usize, grace_period: Duration) -> Vec<String> {
+		let mut clients = self.clients.write().await;
+		let now = Instant::now();
+		let mut lagging = vec![];
+
+		for (subscription_id, client) in clients.iter_mut() {
+			let Some(sender) = client.sender.as_ref() else {
+				continue;
+			};
+			if sender.len() < buffer_size {
+				client.full_since = None;
+				continue;
+			}
+			let full_since = *client.full_since.get_or_insert(now);
+			if now.duration_since(full_since) >= grace_period {
+				lagging.push(subscription_id.clone());
+			}
+		}
+
+		for subscription_id in &lagging {
+			let forward = clients
+				.shift_remove(subscription_id)
+				.and_then(|client| client.forward);
+			if let Some(forward) = forward {
+				forward.abort();
+			}
+		}
+		lagging
+	}
+
+	/// Returns subscriptions of all clients, in subscription order.
+	pub async fn subscriptions(&self) -> Vec<SubscriptionId> {
+		let clients = self.clients.read().await;
+		clients
 			.iter()
-			.filter(|(_, client)| client.is_subscribed(topic))
-			.flat_map(|(_, client)| client.sender_with_data_fields())
-			.map(|(sender, data_fields)| {
-				let mut message = message.clone();
-				message.apply_filter(data_fields);
-				message
-					.try_into()
-					.wrap_err("Cannot convert to ws message")
-					.and_then(|message: warp::ws::Message| {
-						sender.send(Ok(message)).wrap_err("Send failed")
-					})
+			.map(|(subscription_id, client)| SubscriptionId {
+				subscription_id: subscription_id.clone(),
+				subscription: client.subscription.clone(),
+				replay: None,
+			})
+			.collect()
+	}
+
+	/// Sends close frame with `Policy Violation` code to the client, if connected,
+	/// and removes its subscription. Returns `false` if client is not subscribed.
+	pub async fn disconnect(&self, subscription_id: &str, reason: &str) -> bool {
+		let Some(client) = self.clients.write().await.shift_remove(subscription_id) else {
+			return false;
+		};
+		if let Some(sender) = client.sender {
+			let _ = sender.send(ws::Message::close_with(
+				POLICY_VIOLATION,
+				reason.to_string(),
+			));
+		}
+		true
+	}
+
+	pub async fn has_subscription(&self, subscription_id: &str) -> bool {
+		self.clients.read().await.contains_key(subscription_id)
+	}
+
+	/// Checks if any client is subscribed to the given topic.
+	pub async fn has_subscribers(&self, topic: &Topic) -> bool {
+		let clients = self.clients.read().await;
+		clients.values().any(|client| client.is_subscribed(topic))
+	}
+
+	/// Subscribes client and returns effective subscription.
+	pub async fn subscribe(
+		&self,
+		subscription_id: &str,
+		subscription: Subscription,
+	) -> Subscription {
+		let mut clients = self.clients.write().await;
+		let client = WsClient::new(subscription);
+		let subscription = client.subscription.clone();
+		clients.insert(subscription_id.to_string(), client);
+		subscription
+	}
+
+	pub async fn stats(&self) -> Stats {
+		let clients = self.clients.read().await;
+		let mut stats = Stats::default();
+		for client in clients.values().filter(|client| client.sender.is_some()) {
+			stats.ws_clients += 1;
+			for topic in &client.subscription.topics {
+				*stats.topics.entry(topic.clone()).or_default() += 1;
+			}
+		}
+		stats
+	}
+
+	/// Sends heartbeat ping to connected clients. Clients which missed `max_missed`
+	/// consecutive pongs are closed and removed. Returns number of removed clients.
+	pub async fn heartbeat(&self, max_missed: u32) -> usize {
+		let mut clients = self.clients.write().await;
+		let mut unresponsive = vec![];
+
+		for (subscription_id, client) in clients.iter_mut() {
+			let Some(sender) = client.sender.as_ref() else {
+				continue;
+			};
+			if client.missed_pongs >= max_missed {
+				let _ = sender.send(ws::Message::close());
+				unresponsive.push(subscription_id.clone());
+				continue;
+			}
+			if sender.send(ws::Message::ping(Vec::<u8>::new())).is_err() {
+				unresponsive.push(subscription_id.clone());
+				continue;
+			}
+			client.missed_pongs += 1;
+		}
+
+		for subscription_id in &unresponsive {
+			clients.shift_remove(subscription_id);
+		}
+		unresponsive.len()
+	}
+
+	/// Sends close frame with `Going Away` code to all connected clients and removes them,
+	/// so connections to existing subscriptions are not accepted anymore. Waits until close
+	/// frames are taken from the client buffers, or until timeout expires.
+	/// Returns the number of closed clients.
+	pub async fn shutdown(&self, timeout: Duration) -> usize {
+		let senders = self
+			.clients
+			.write()
+			.await
+			.drain(..)
+			.filter_map(|(_, client)| client.sender)
+			.filter(|sender| {
+				let close = ws::Message::close_with(GOING_AWAY, "Server is shutting down");
+				sender.send(close).is_ok()
 			})
-			.collect::<Vec<_>>())
+			.collect::<Vec<_>>();
+
+		let drained = async {
+			while senders.iter().any(|sender| !sender.is_empty()) {
+				tokio::time::sleep(Duration::from_millis(10)).await;
+			}
+		};
+		let _ = tokio::time::timeout(timeout, drained).await;
+		senders.len()
+	}
+
+	/// Resets missed pongs counter of the client.
+	pub async fn pong(&self, subscription_id: &str) {
+		if let Some(client) = self.clients.write().await.get_mut(subscription_id) {
+			client.missed_pongs = 0;
+		}
+	}
+
+	/// Adds to the number of messages dropped for the client and returns the total.
+	pub async fn add_dropped_messages(&self, subscription_id: &str, count: u64) -> Option<u64> {
+		let mut clients = self.clients.write().await;
+		let client = clients.get_mut(subscription_id)?;
+		client.dropped_messages += count;
+		Some(client.dropped_messages)
 	}
+
+	/// Merges topics and data fields into the client subscription and returns merged subscription.
+	/// Subscription is left unchanged if merged one exceeds the maximum number of topics and application IDs.
+	pub async fn update_subscription(
+		&self,
+		subscription_id: &str,
+		subscription: Subscription,
+		max_size: usize,
+	) -> Option<Result<Subscription>> {
+		let mut clients = self.clients.write().await;
+		let client = clients.get_mut(subscription_id)?;
+		let mut merged = client.subscription.clone();
+		merged.merge(subscription);
+		if merged.size() > max_size {
+			return Some(Err(eyre!(
+				"Subscription exceeds the limit of {max_size} topics and application IDs"
+			)));
+		}
+		client.subscription = merged.clone();
+		Some(Ok(merged))
+	}
+
+	/// Removes topics and data fields from the client subscription and returns remaining subscription.
+	/// Client without topics stays connected, but no messages are published to it.
+	pub async fn unsubscribe(
+		&self,
+		subscription_id: &str,
+		subscription: &Subscription,
+	) -> Option<Subscription> {
+		let mut clients = self.clients.write().await;
+		let client = clients.get_mut(subscription_id)?;
+		client.subscription.remove(subscription);
+		Some(client.subscription.clone())
+	}
+
+	/// Publishes message to all clients subscribed to the topic.
+	/// Clients are snapshotted under the clients lock, and the message is sent to them
+	/// after the lock is released. Clients with closed receivers are removed after publishing.
+	pub async fn publish(&self, topic: &Topic, message: PublishMessage) -> Result<Published> {
+		let FanOut {
+			workers,
+			expose_error_causes,
+			field_naming,
+		} = self.fan_out;
+		let _publishing = self.publishing.lock().await;
+
+		let locked_at = Instant::now();
+		let deliveries = {
+			let mut clients = self.clients.write().await;
+			clients
+				.iter_mut()
+				.filter(|(_, client)| client.is_subscribed(topic) && client.accepts(&message))
+				.filter_map(|(subscription_id, client)| client.delivery(subscription_id))
+				.collect::<Vec<_>>()
+		};
+		let mut lock_held = locked_at.elapsed();
+
+		// Senders identify the connections the message is sent to, since clients can
+		// reconnect or resubscribe while the message is sent
+		let connections = deliveries
+			.iter()
+			.map(|delivery| (delivery.subscription_id.clone(), delivery.sender.clone()))
+			.collect::<Vec<_>>();
+		let serialized = Arc::new(Serialized::new(&message, &deliveries, field_naming));
+		let serializations = serialized.0.len();
+		let sent = fan_out(
+			deliveries,
+			topic,
+			serialized,
+			workers,
+			expose_error_causes,
+			field_naming,
+		)
+		.await?;
+
+		let mut results = vec![];
+		let mut disconnected = vec![];
+		for (connection, (result, is_disconnected)) in connections.into_iter().zip(sent) {
+			if is_disconnected {
+				disconnected.push(connection);
+			}
+			results.push(result);
+		}
+
+		let mut pruned = 0;
+		if !disconnected.is_empty() {
+			let locked_at = Instant::now();
+			let mut clients = self.clients.write().await;
+			for (subscription_id, sender) in disconnected {
+				// Client is removed only if it is still on the disconnected connection
+				let is_same_connection = clients
+					.get(&subscription_id)
+					.and_then(|client| client.sender.as_ref())
+					.is_some_and(|current| current.same_channel(&sender));
+				if is_same_connection {
+					clients.shift_remove(&subscription_id);
+					pruned += 1;
+				}
+			}
+			lock_held += locked_at.elapsed();
+		}
+
+		self.replay_buffer
+			.lock()
+			.expect("Replay buffer lock can be acquired")
+			.push(topic, message);
+
+		Ok(Published {
+			results,
+			pruned,
+			lock_held,
+			serializations,
+		})
+	}
+}
+
+/// Sends replayed messages to the client, applying client filters and compression.
+fn send_replayed(
+	subscription_id: &str,
+	client: &mut WsClient,
+	sender: &Sender,
+	messages: Vec<PublishMessage>,
+	naming: FieldNaming,
+) -> Result<()> {
+	for message in messages
+		.into_iter()
+		.filter(|message| client.accepts(message))
+	{
+		let message = client.ws_message(message, naming)?;
+		sender
+			.send(message)
+			.map_err(|_| eyre!("Send failed, client {subscription_id} is disconnected"))?;
+	}
+	Ok(())
+}
+
+/// Connected clients statistics.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Stats {
+	/// Number of connected WebSocket clients
+	pub ws_clients: usize,
+	/// Number of connected clients subscribed to each topic
+	pub topics: HashMap<Topic, usize>,
+}
+
+/// Creates error message sent to the client instead of the message which cannot be published.
+/// Error cause is internal, so it is included only if error causes are exposed.
+fn publish_error(
+	topic: &Topic,
+	cause: &Report,
+	expose_error_causes: bool,
+	naming: FieldNaming,
+) -> Result<ws::Message> {
+	let error = Error::publish_failed(topic, cause);
+	let error = match expose_error_causes {
+		true => WsError::from(error.with_cause_chain()),
+		false => WsError::from(error),
+	};
+	to_json(&error, naming)
+		.map(ws::Message::text)
+		.wrap_err("Cannot serialize publish error")
+}
+
+/// Outcome of publishing message to the subscribed clients.
+pub struct Published {
+	pub results: Vec<Result<()>>,
+	/// Number of disconnected clients removed during publish.
+	pub pruned: usize,
+	/// Time the clients lock is held during publish.
+	pub lock_held: Duration,
+	/// Number of times the message is serialized, once per distinct client projection.
+	pub serializations: usize,
 }
 
+/// Messages are not buffered for replay by default.
 impl Default for WsClients {
 	fn default() -> Self {
-		Self(Arc::new(RwLock::new(HashMap::new())))
+		Self::new(0)
 	}
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct SubscriptionId {
 	pub subscription_id: String,
+	/// Effective subscription, used to detect unsupported topics
+	#[serde(default)]
+	pub subscription: Subscription,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub replay: Option<ReplayWindow>,
+}
+
+/// Range of verified headers replayed on connect.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ReplayWindow {
+	pub first: u32,
+	pub last: u32,
+	/// Replay is capped to the configured number of blocks,
+	/// remaining headers should be requested starting from `last + 1`
+	pub truncated: bool,
+}
+
+/// Verified headers replayed on connect, loaded before the client is connected.
+#[derive(Default)]
+pub struct ReplayedHeaders {
+	pub headers: Vec<PublishMessage>,
+	/// Block until which headers are loaded, if replay is not truncated.
+	/// Headers of the later blocks, published meanwhile, are replayed from the replay buffer.
+	pub loaded_until: Option<u32>,
+}
+
+/// Returns window of verified headers replayed from the given block, capped to `max_blocks`.
+pub fn replay_window(
+	from_block: u32,
+	header_verified: Option<&types::BlockRange>,
+	max_blocks: u32,
+) -> Option<ReplayWindow> {
+	let header_verified = header_verified?;
+	let first = from_block.max(header_verified.first);
+	if first > header_verified.last || max_blocks == 0 {
+		return None;
+	}
+	let last = header_verified
+		.last
+		.min(first.saturating_add(max_blocks - 1));
+	Some(ReplayWindow {
+		first,
+		last,
+		truncated: last < header_verified.last,
+	})
 }
 
 impl Reply for SubscriptionId {
 	fn into_response(self) -> warp::reply::Response {
-		warp::reply::json(&self).into_response()
+		json_reply(self)
 	}
 }
 
@@ -670,7 +2783,112 @@ impl Reply for SubscriptionId {
 pub enum Payload {
 	Version,
 	Status,
-	Submit(Transaction),
+	Stats,
+	/// Application level ping, echoed with the server timestamp
+	Ping {
+		nonce: u64,
+	},
+	Submit(SubmitRequest),
+	SubmitBatch(Vec<Transaction>),
+	Subscribe(Subscription),
+	Unsubscribe(Subscription),
+	GetBlockRanges,
+	/// Commitments are omitted from the header if `include_commitments` is set to `false`
+	GetHeader {
+		block_number: u32,
+		include_commitments: Option<bool>,
+	},
+	GetAppData {
+		block_number: u32,
+		app_id: Option<u32>,
+	},
+	/// Cells are requested as (row, col) positions of the extended matrix
+	GetCells {
+		block_number: u32,
+		cells: Vec<(u16, u16)>,
+	},
+	/// Row is requested by its index in the extended matrix
+	GetRow {
+		block_number: u32,
+		row: u16,
+	},
+	VerifyGenesis {
+		expected: H256,
+	},
+	/// Data transaction is requested by its index among the data transactions of the block
+	GetDataProof {
+		block_number: u32,
+		index: u32,
+	},
+	/// Extrinsic is requested by its index among all extrinsics of the block
+	GetExtrinsicProof {
+		block_number: u32,
+		index: u32,
+	},
+	/// Data proof has the same fields as the data proof response, so it can be verified independently
+	VerifyDataProof {
+		data_root: H256,
+		proof: Vec<H256>,
+		number_of_leaves: u32,
+		leaf_index: u32,
+		leaf: H256,
+	},
+	/// Stored confidence is returned if target is not set, otherwise additional cells are
+	/// sampled until the confidence target (percentage) is met, or until the sampling limit is reached
+	GetConfidence {
+		block_number: u32,
+		confidence_target: Option<f64>,
+	},
+	/// Block data is reconstructed from the partition cells and the additionally fetched cells
+	Reconstruct {
+		block_number: u32,
+	},
+	/// Commitments are encoded as hex strings if `encoding` is not set
+	GetCommitments {
+		block_number: u32,
+		encoding: Option<CommitmentEncoding>,
+	},
+	/// Header is returned SCALE encoded, as it is stored on the chain
+	GetRawHeader {
+		block_number: u32,
+	},
+	/// Admin request, lists subscriptions of all clients
+	ListSubscriptions,
+	/// Admin request, closes connection of the client and removes its subscription
+	Disconnect {
+		subscription_id: String,
+	},
+}
+
+impl Payload {
+	/// Returns request type as it is named in the request message.
+	pub fn request_type(&self) -> &'static str {
+		match self {
+			Payload::Version => "version",
+			Payload::Status => "status",
+			Payload::Stats => "stats",
+			Payload::Ping { .. } => "ping",
+			Payload::Submit(_) => "submit",
+			Payload::SubmitBatch(_) => "submit-batch",
+			Payload::Subscribe(_) => "subscribe",
+			Payload::Unsubscribe(_) => "unsubscribe",
+			Payload::GetBlockRanges => "get-block-ranges",
+			Payload::GetHeader { .. } => "get-header",
+			Payload::GetAppData { .. } => "get-app-data",
+			Payload::GetCells { .. } => "get-cells",
+			Payload::GetRow { .. } => "get-row",
+			Payload::VerifyGenesis { .. } => "verify-genesis",
+			Payload::GetDataProof { .. } => "get-data-proof",
+			Payload::GetExtrinsicProof { .. } => "get-extrinsic-proof",
+			Payload::VerifyDataProof { .. } => "verify-data-proof",
+			Payload::GetConfidence { .. } => "get-confidence",
+			Payload::Reconstruct { .. } => "reconstruct",
+			Payload::GetCommitments { .. } => "get-commitments",
+			Payload::GetRawHeader { .. } => "get-raw-header",
+			Payload::ListSubscriptions => "list-subscriptions",
+			Payload::Disconnect { .. } => "disconnect",
+		}
+	}
 }
 
 #[derive(Deserialize)]
@@ -678,6 +2896,12 @@ pub struct Request {
 	#[serde(flatten)]
 	pub payload: Payload,
 	pub request_id: Uuid,
+	/// Used to return cached response on repeated submit requests
+	pub idempotency_key: Option<Uuid>,
+	/// API key, required for submit requests if API keys are configured
+	pub auth: Option<String>,
+	/// Protocol version the request is made for, latest version is assumed if omitted
+	pub protocol_version: Option<u32>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -695,23 +2919,88 @@ impl<T> Response<T> {
 	}
 }
 
+/// Request type, ID and protocol version, parsed ahead of the payload
+/// to report unknown request types and unsupported protocol versions.
+#[derive(Deserialize)]
+struct RequestEnvelope {
+	#[serde(rename = "type")]
+	request_type: String,
+	request_id: Uuid,
+	protocol_version: Option<u32>,
+}
+
 impl TryFrom<ws::Message> for Request {
-	type Error = Report;
+	type Error = Error;
 
 	fn try_from(value: ws::Message) -> Result<Self, Self::Error> {
-		serde_json::from_slice(value.as_bytes()).wrap_err("Cannot parse json")
+		let json: serde_json::Value =
+			serde_json::from_slice(value.as_bytes()).map_err(|error| {
+				Error::bad_request_unknown(&format!(
+					"Failed to parse request: Cannot parse json: {error}"
+				))
+			})?;
+
+		let RequestEnvelope {
+			request_type,
+			request_id,
+			protocol_version,
+		} = RequestEnvelope::deserialize(&json).map_err(|error| {
+			Error::bad_request_unknown(&format!("Failed to parse request: {error}"))
+		})?;
+
+		// Payloads of unsupported versions may not be parsed, so version is checked first
+		check_protocol_version(protocol_version)
+			.map_err(|message| Error::bad_request(request_id, &message))?;
+
+		serde_json::from_value(json).map_err(|error| {
+			let unknown_variant = format!("unknown variant `{request_type}`");
+			if error.to_string().starts_with(&unknown_variant) {
+				let message = format!("Unknown request type `{request_type}`");
+				return Error::bad_request(request_id, &message);
+			}
+			Error::bad_request(request_id, &format!("Failed to parse request: {error}"))
+		})
 	}
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Debug, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub enum ErrorCode {
 	NotFound,
 	BadRequest,
 	InternalServerError,
+	TooManyRequests,
+	ServiceUnavailable,
+	Unauthorized,
 }
 
-#[derive(Serialize, Deserialize)]
+impl ErrorCode {
+	/// Returns error code as it is named in the error response.
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			ErrorCode::NotFound => "not-found",
+			ErrorCode::BadRequest => "bad-request",
+			ErrorCode::InternalServerError => "internal-server-error",
+			ErrorCode::TooManyRequests => "too-many-requests",
+			ErrorCode::ServiceUnavailable => "service-unavailable",
+			ErrorCode::Unauthorized => "unauthorized",
+		}
+	}
+
+	/// Returns WebSocket close code sent to the client when connection is closed because of the error.
+	pub fn close_code(&self) -> u16 {
+		match self {
+			ErrorCode::NotFound
+			| ErrorCode::BadRequest
+			| ErrorCode::TooManyRequests
+			| ErrorCode::Unauthorized => POLICY_VIOLATION,
+			ErrorCode::InternalServerError => INTERNAL_ERROR,
+			ErrorCode::ServiceUnavailable => TRY_AGAIN_LATER,
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct Error {
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub request_id: Option<Uuid>,
@@ -719,6 +3008,12 @@ pub struct Error {
 	pub cause: Option<Report>,
 	pub error_code: ErrorCode,
 	pub message: String,
+	/// Flattened chain of error causes, present only if exposing causes is enabled
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub cause_chain: Option<String>,
+	/// Additional HTTP response headers
+	#[serde(skip)]
+	pub headers: Vec<(String, String)>,
 }
 
 impl Error {
@@ -733,9 +3028,35 @@ impl Error {
 			cause,
 			error_code,
 			message: message.to_string(),
+			cause_chain: None,
+			headers: vec![],
 		}
 	}
 
+	/// Exposes error cause chain in the serialized error.
+	pub fn with_cause_chain(mut self) -> Self {
+		self.cause_chain = self.cause.as_ref().map(|cause| format!("{cause:#}"));
+		self
+	}
+
+	/// Sets request ID of the WebSocket request the error is produced for, unless it is already set.
+	pub fn with_request_id(mut self, request_id: Uuid) -> Self {
+		self.request_id.get_or_insert(request_id);
+		self
+	}
+
+	/// Adds HTTP response header.
+	pub fn with_header(mut self, name: &str, value: &str) -> Self {
+		self.headers.push((name.to_string(), value.to_string()));
+		self
+	}
+
+	/// Adds `Retry-After` HTTP response header, so clients can back off before retrying.
+	pub fn with_retry_after(self, retry_after: Duration) -> Self {
+		let seconds = retry_after.as_secs().to_string();
+		self.with_header(http::header::RETRY_AFTER.as_str(), &seconds)
+	}
+
 	pub fn not_found() -> Self {
 		Self::new(None, None, ErrorCode::NotFound, "Not Found")
 	}
@@ -749,6 +3070,74 @@ impl Error {
 		)
 	}
 
+	pub fn too_many_requests() -> Self {
+		Self::new(None, None, ErrorCode::TooManyRequests, "Too Many Requests")
+	}
+
+	pub fn unauthorized(request_id: Option<Uuid>) -> Self {
+		Self::new(request_id, None, ErrorCode::Unauthorized, "Unauthorized")
+			.with_header(http::header::WWW_AUTHENTICATE.as_str(), "Bearer")
+	}
+
+	/// Rate limit error, with retry period rounded up to whole seconds.
+	pub fn rate_limited(request_id: Option<Uuid>, retry_after: Duration) -> Self {
+		let seconds = retry_after.as_secs_f64().ceil() as u64;
+		let message = format!("Rate limit exceeded, retry after {seconds} seconds");
+		Self::new(request_id, None, ErrorCode::TooManyRequests, &message)
+			.with_retry_after(Duration::from_secs(seconds))
+	}
+
+	/// Submit quota error, with quota reset period rounded up to whole seconds.
+	pub fn quota_exceeded(request_id: Option<Uuid>, reset_in: Duration) -> Self {
+		let seconds = reset_in.as_secs_f64().ceil() as u64;
+		let message = format!("Submit quota exceeded, quota resets in {seconds} seconds");
+		Self::new(request_id, None, ErrorCode::TooManyRequests, &message)
+			.with_retry_after(Duration::from_secs(seconds))
+	}
+
+	/// Connection limit error, with retry period after which connections may be freed.
+	pub fn connection_limit_reached(max_connections: usize, retry_after: Duration) -> Self {
+		let message = format!("Maximum number of {max_connections} connections reached");
+		Self::new(None, None, ErrorCode::ServiceUnavailable, &message).with_retry_after(retry_after)
+	}
+
+	/// Error sent to the subscribed client when the published message cannot be converted for it.
+	pub fn publish_failed(topic: &Topic, cause: &Report) -> Self {
+		let message = format!("Cannot publish {topic:?} message");
+		let cause = eyre!("{cause:#}");
+		Self::new(None, Some(cause), ErrorCode::InternalServerError, &message)
+	}
+
+	pub fn service_unavailable(cause: Report) -> Self {
+		Self::new(
+			None,
+			Some(cause),
+			ErrorCode::ServiceUnavailable,
+			"Service Unavailable",
+		)
+	}
+
+	/// Reconstruction error, when there are not enough cells to reconstruct the block.
+	pub fn cells_unavailable(request_id: Uuid, block_number: u32, missing: usize) -> Self {
+		let message =
+			format!("Cannot reconstruct block {block_number}, {missing} more cells are needed");
+		Self::new(
+			Some(request_id),
+			None,
+			ErrorCode::ServiceUnavailable,
+			&message,
+		)
+	}
+
+	pub fn request_timeout(request_id: Uuid) -> Self {
+		Self::new(
+			Some(request_id),
+			None,
+			ErrorCode::ServiceUnavailable,
+			"Request timed out",
+		)
+	}
+
 	pub fn bad_request_unknown(message: &str) -> Self {
 		Self::new(None, None, ErrorCode::BadRequest, message)
 	}
@@ -762,107 +3151,855 @@ impl Error {
 			ErrorCode::NotFound => StatusCode::NOT_FOUND,
 			ErrorCode::BadRequest => StatusCode::BAD_REQUEST,
 			ErrorCode::InternalServerError => StatusCode::INTERNAL_SERVER_ERROR,
+			ErrorCode::TooManyRequests => StatusCode::TOO_MANY_REQUESTS,
+			ErrorCode::ServiceUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+			ErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
+		}
+	}
+}
+
+impl From<DecodeError> for Error {
+	fn from(error: DecodeError) -> Self {
+		Error::bad_request_unknown(&format!("Invalid base64 value: {error}"))
+	}
+}
+
+impl From<Report> for Error {
+	fn from(cause: Report) -> Self {
+		Error::internal_server_error(cause)
+	}
+}
+
+impl Reply for Error {
+	fn into_response(self) -> warp::reply::Response {
+		let mut builder = http::Response::builder()
+			.status(self.status())
+			.header(http::header::CONTENT_TYPE, "application/json");
+		for (name, value) in &self.headers {
+			builder = builder.header(name.as_str(), value.as_str());
+		}
+		let body = serde_json::to_string(&self).expect("Error is serializable");
+		builder
+			.extension(CborEncoder::new(self))
+			.body(body)
+			.expect("Can create error response")
+			.into_response()
+	}
+}
+
+impl From<Error> for String {
+	fn from(error: Error) -> Self {
+		serde_json::to_string(&error).expect("Error is serializable")
+	}
+}
+
+#[derive(Serialize, Deserialize, From)]
+#[serde(tag = "topic", rename_all = "kebab-case")]
+pub enum WsResponse {
+	Version(Response<Version>),
+	Status(Response<Status>),
+	Stats(Response<Stats>),
+	Pong(Response<Pong>),
+	BlockRanges(Response<BlockRanges>),
+	DataTransactionSubmitted(Response<SubmitResponse>),
+	DataTransactionsSubmitted(Response<SubmitBatchResponse>),
+	Subscription(Response<Subscription>),
+	Header(Response<HeaderMessage>),
+	AppData(Response<AppDataMessage>),
+	Cells(Response<CellsMessage>),
+	Row(Response<RowMessage>),
+	GenesisVerified(Response<GenesisHash>),
+	DataProof(Response<DataProofMessage>),
+	ExtrinsicProof(Response<ExtrinsicProofMessage>),
+	DataProofVerified(Response<DataProofVerified>),
+	Confidence(Response<SampledConfidence>),
+	Reconstructed(Response<ReconstructMessage>),
+	Commitments(Response<CommitmentsMessage>),
+	RawHeader(Response<RawHeaderMessage>),
+	Subscriptions(Response<Vec<SubscriptionId>>),
+	Disconnected(Response<Disconnected>),
+}
+
+#[derive(Serialize, Deserialize, From)]
+#[serde(tag = "topic", rename_all = "kebab-case")]
+pub enum WsError {
+	Error(Error),
+}
+
+/// Creates JSON schema document with definitions of the API response types.
+pub fn schema() -> serde_json::Value {
+	let mut generator = SchemaGenerator::default();
+	generator.subschema_for::<Version>();
+	generator.subschema_for::<Status>();
+	generator.subschema_for::<BlockRanges>();
+	generator.subschema_for::<Block>();
+	generator.subschema_for::<Header>();
+	generator.subschema_for::<HeaderMessage>();
+	generator.subschema_for::<Error>();
+
+	serde_json::json!({
+		"$schema": generator.settings().meta_schema,
+		"definitions": generator.take_definitions(),
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{
+		collections::{HashMap, HashSet},
+		sync::Arc,
+		time::{Duration, Instant},
+	};
+
+	use avail_subxt::api::runtime_types::avail_core::{
+		data_lookup::compact::{CompactDataLookup, DataLookupItem},
+		header::extension::{v3, HeaderExtension},
+		kate_commitment::v3::KateCommitment,
+		AppId,
+	};
+	use base64::{engine::general_purpose, Engine};
+	use color_eyre::eyre::eyre;
+	use sp_core::H256;
+	use tokio::{
+		sync::broadcast::{self, error::TryRecvError},
+		task::AbortHandle,
+	};
+
+	use crate::{
+		api::v2::types::{BlockStatus, Header, HeaderMessage, PublishMessage},
+		types::{FieldNaming, OptionBlockRange, RuntimeConfig, State},
+	};
+
+	use kate_recovery::{
+		config,
+		matrix::{Dimensions, Partition},
+	};
+	use test_case::test_case;
+
+	use super::{
+		block_status, filter_fields, Base64, BlockRange, Blocks, Commitment, CommitmentEncoding,
+		ConfidenceMessage, DataField, DataMessage, DataTransaction, Error, ErrorCode,
+		ReplayedHeaders, Status, SubmitResponse, Subscription, Topic, WsClients, WsError,
+	};
+	use super::{
+		compress, fan_out, rename_fields, Compression, Delivery, Projection, Serialized,
+		COMPRESSION_THRESHOLD, DEFLATE_MESSAGE_MARKER, RENAMED_FIELDS,
+	};
+	use warp::ws::Message;
+
+	fn subscription(topics: Vec<Topic>, fields: Vec<DataField>) -> Subscription {
+		Subscription {
+			topics: topics.into_iter().collect(),
+			data_fields: fields.into_iter().collect(),
+			..Default::default()
+		}
+	}
+
+	fn header_verified() -> PublishMessage {
+		header_verified_with_apps(vec![])
+	}
+
+	fn header_verified_with_apps(app_cells: Vec<(u32, u32)>) -> PublishMessage {
+		PublishMessage::HeaderVerified(Box::new(HeaderMessage {
+			block_number: 1,
+			header: Header {
+				hash: H256::default(),
+				parent_hash: H256::default(),
+				number: 1,
+				state_root: H256::default(),
+				extrinsics_root: H256::default(),
+				extension: super::Extension {
+					extension_version: 3,
+					rows: 1,
+					cols: 1,
+					data_root: H256::default(),
+					commitments: Some(vec![]),
+					app_lookup: CompactDataLookup {
+						size: 0,
+						index: vec![],
+					},
+					app_cells,
+				},
+			},
+		}))
+	}
+
+	fn confidence_achieved() -> PublishMessage {
+		PublishMessage::ConfidenceAchieved(ConfidenceMessage {
+			block_number: 1,
+			confidence: Some(1.0),
+		})
+	}
+
+	fn transaction_data() -> Option<Base64> {
+		Some(Base64(vec![0, 1, 2, 3, 4]))
+	}
+
+	fn data_verified() -> PublishMessage {
+		data_verified_with_app(1)
+	}
+
+	fn data_verified_with_app(app_id: u32) -> PublishMessage {
+		PublishMessage::DataVerified(DataMessage {
+			block_number: 1,
+			app_id,
+			data_transactions: vec![DataTransaction {
+				data: transaction_data(),
+				extrinsic: transaction_data(),
+			}],
+		})
+	}
+
+	#[test]
+	fn submit_response_serialization() {
+		let mut response = SubmitResponse {
+			block_number: 1,
+			block_hash: H256::zero(),
+			hash: H256::repeat_byte(1),
+			index: 2,
+			app_id: None,
+			data_position: None,
+			dry_run: false,
+		};
+		let zero = "0x0000000000000000000000000000000000000000000000000000000000000000";
+		let one = "0x0101010101010101010101010101010101010101010101010101010101010101";
+		assert_eq!(
+			serde_json::to_string(&response).unwrap(),
+			format!(r#"{{"block_number":1,"block_hash":"{zero}","hash":"{one}","index":2}}"#)
+		);
+
+		response.app_id = Some(3);
+		response.data_position = Some(0);
+		let json = serde_json::to_string(&response).unwrap();
+		assert_eq!(
+			json,
+			format!(
+				r#"{{"block_number":1,"block_hash":"{zero}","hash":"{one}","index":2,"app_id":3,"data_position":0}}"#
+			)
+		);
+		let response: SubmitResponse = serde_json::from_str(&json).unwrap();
+		assert_eq!(response.app_id, Some(3));
+		assert_eq!(response.data_position, Some(0));
+	}
+
+	#[test]
+	fn publish_message_confidence_achieved() {
+		let message = serde_json::to_string(&confidence_achieved()).unwrap();
+		assert_eq!(
+			message,
+			r#"{"topic":"confidence-achieved","message":{"block_number":1,"confidence":1.0}}"#
+		);
+	}
+
+	#[test]
+	fn publish_message_data_verified() {
+		let message = serde_json::to_string(&data_verified()).unwrap();
+		assert_eq!(
+			message,
+			r#"{"topic":"data-verified","message":{"block_number":1,"data_transactions":[{"data":"AAECAwQ=","extrinsic":"AAECAwQ="}]}}"#
+		);
+	}
+
+	#[test]
+	fn filter_fields_projection() {
+		let transactions = || {
+			vec![DataTransaction {
+				data: transaction_data(),
+				extrinsic: transaction_data(),
+			}]
+		};
+
+		let mut both = transactions();
+		filter_fields(&mut both, &[DataField::Data, DataField::Extrinsic].into());
+		assert!(both[0].data == transaction_data() && both[0].extrinsic == transaction_data());
+
+		let mut data = transactions();
+		filter_fields(&mut data, &[DataField::Data].into());
+		assert!(data[0].data == transaction_data() && data[0].extrinsic.is_none());
+
+		let mut extrinsic = transactions();
+		filter_fields(&mut extrinsic, &[DataField::Extrinsic].into());
+		assert!(extrinsic[0].data.is_none() && extrinsic[0].extrinsic == transaction_data());
+
+		let mut none = transactions();
+		filter_fields(&mut none, &HashSet::new());
+		assert!(none[0].data.is_none() && none[0].extrinsic.is_none());
+	}
+
+	fn header_verified_at(block_number: u32) -> PublishMessage {
+		let PublishMessage::HeaderVerified(mut header) = header_verified() else {
+			unreachable!();
+		};
+		header.block_number = block_number;
+		PublishMessage::HeaderVerified(header)
+	}
+
+	fn forward() -> AbortHandle {
+		tokio::spawn(async {}).abort_handle()
+	}
+
+	#[tokio::test]
+	async fn clients_connect_replay() {
+		let clients = WsClients::new(4);
+		for block_number in 3..=4 {
+			let message = header_verified_at(block_number);
+			_ = clients.publish(&Topic::HeaderVerified, message).await;
+		}
+		clients
+			.subscribe("1", subscription(vec![Topic::HeaderVerified], vec![]))
+			.await;
+		clients.set_replay_from("1", Some(1)).await;
+		clients.set_replay_buffered("1", true).await;
+
+		let (sender, mut receiver) = broadcast::channel(3);
+		let replayed = ReplayedHeaders {
+			headers: (1..=3).map(header_verified_at).collect(),
+			loaded_until: Some(3),
+		};
+		clients
+			.connect("1", sender, forward(), 3, replayed)
+			.await
+			.unwrap();
+		assert!(clients.clients.read().await["1"].sender.is_some());
+
+		// Header 3 is both loaded and buffered, and header 1 doesn't fit into the client buffer
+		let mut block_numbers = vec![];
+		while let Ok(message) = receiver.try_recv() {
+			let message: serde_json::Value = serde_json::from_slice(message.as_bytes()).unwrap();
+			block_numbers.push(message["message"]["block_number"].as_u64().unwrap());
+		}
+		assert_eq!(block_numbers, vec![2, 3, 4]);
+	}
+
+	#[tokio::test]
+	async fn clients_connect_replay_published_after_load() {
+		let clients = WsClients::new(4);
+		clients
+			.subscribe("1", subscription(vec![Topic::HeaderVerified], vec![]))
+			.await;
+		clients.set_replay_from("1", Some(1)).await;
+		assert_eq!(clients.replay_from("1").await, Some(1));
+
+		// Headers 1 and 2 are loaded, and headers 2 and 3 are published before client is connected
+		let replayed = ReplayedHeaders {
+			headers: (1..=2).map(header_verified_at).collect(),
+			loaded_until: Some(2),
+		};
+		for block_number in 2..=3 {
+			let message = header_verified_at(block_number);
+			_ = clients.publish(&Topic::HeaderVerified, message).await;
+		}
+
+		let (sender, mut receiver) = broadcast::channel(16);
+		clients
+			.connect("1", sender, forward(), 16, replayed)
+			.await
+			.unwrap();
+		assert_eq!(clients.replay_from("1").await, None);
+
+		let mut block_numbers = vec![];
+		while let Ok(message) = receiver.try_recv() {
+			let message: serde_json::Value = serde_json::from_slice(message.as_bytes()).unwrap();
+			block_numbers.push(message["message"]["block_number"].as_u64().unwrap());
+		}
+		assert_eq!(block_numbers, vec![1, 2, 3]);
+	}
+
+	#[tokio::test]
+	async fn clients_connect_concurrent_publish() {
+		let clients = WsClients::new(2);
+		_ = clients
+			.publish(&Topic::HeaderVerified, header_verified_at(1))
+			.await;
+		clients
+			.subscribe("1", subscription(vec![Topic::HeaderVerified], vec![]))
+			.await;
+		clients.set_replay_buffered("1", true).await;
+
+		let (sender, mut receiver) = broadcast::channel(16);
+		let (published, connected) = tokio::join!(
+			clients.publish(&Topic::HeaderVerified, header_verified_at(2)),
+			clients.connect("1", sender, forward(), 16, ReplayedHeaders::default()),
+		);
+		published.unwrap();
+		connected.unwrap();
+
+		// Message published during connect is either replayed or sent live, but not both
+		let mut received = vec![];
+		while let Ok(message) = receiver.try_recv() {
+			let message: serde_json::Value = serde_json::from_slice(message.as_bytes()).unwrap();
+			let block_number = message["message"]["block_number"].as_u64().unwrap();
+			received.push((block_number, message["seq"].as_u64().unwrap()));
+		}
+		assert_eq!(received, vec![(1, 1), (2, 2)]);
+	}
+
+	#[tokio::test]
+	async fn clients_evict_lagging() {
+		let clients = WsClients::default();
+		let grace_period = Duration::from_millis(50);
+		for subscription_id in ["1", "2"] {
+			clients
+				.subscribe(subscription_id, Subscription::default())
+				.await;
+		}
+		let (lagging, _lagging_receiver) = broadcast::channel(2);
+		let forward = tokio::spawn(std::future::pending::<()>());
+		clients
+			.connect(
+				"1",
+				lagging.clone(),
+				forward.abort_handle(),
+				2,
+				ReplayedHeaders::default(),
+			)
+			.await
+			.unwrap();
+		let (reading, mut receiver) = broadcast::channel(2);
+		clients.set_sender("2", reading.clone()).await.unwrap();
+
+		for _ in 0..3 {
+			lagging.send(Message::text("message")).unwrap();
+			reading.send(Message::text("message")).unwrap();
+		}
+		assert!(clients.evict_lagging(2, grace_period).await.is_empty());
+
+		// Client which takes the buffered messages within the grace period is not evicted
+		while !matches!(receiver.try_recv(), Err(TryRecvError::Empty)) {}
+		tokio::time::sleep(grace_period).await;
+		assert_eq!(clients.evict_lagging(2, grace_period).await, vec!["1"]);
+
+		assert!(!clients.has_subscription("1").await);
+		assert!(clients.has_subscription("2").await);
+		assert!(forward.await.unwrap_err().is_cancelled());
+	}
+
+	#[tokio::test]
+	async fn clients_publish_app_blocks() {
+		let clients = WsClients::default();
+		let (sender, mut receiver) = broadcast::channel(16);
+		let subscription = Subscription {
+			app_id: Some(1),
+			..subscription(vec![Topic::HeaderVerified], vec![])
+		};
+		clients.subscribe("1", subscription).await;
+		clients.set_sender("1", sender).await.unwrap();
+
+		for app_cells in [
+			vec![],
+			vec![(2, 4)],
+			vec![(1, 0), (2, 4)],
+			vec![(1, 4), (2, 4)],
+		] {
+			let message = header_verified_with_apps(app_cells);
+			let _ = clients.publish(&Topic::HeaderVerified, message).await;
+		}
+
+		let message = receiver.recv().await.unwrap();
+		let PublishMessage::HeaderVerified(header) =
+			serde_json::from_slice(message.as_bytes()).unwrap()
+		else {
+			panic!("Invalid message type");
+		};
+		assert!(header.contains_app(1));
+		assert!(receiver.try_recv().is_err());
+	}
+
+	#[test]
+	fn apps_registered() {
+		let mut seen_app_ids = HashSet::new();
+		let registered = |app_cells, seen_app_ids: &mut HashSet<u32>| {
+			header_verified_with_apps(app_cells)
+				.apps_registered(seen_app_ids)
+				.into_iter()
+				.map(|message| match message {
+					PublishMessage::AppRegistered(app) => app.app_id,
+					_ => panic!("Invalid message type"),
+				})
+				.collect::<Vec<_>>()
+		};
+
+		assert!(registered(vec![], &mut seen_app_ids).is_empty());
+		assert_eq!(registered(vec![(1, 0), (2, 4)], &mut seen_app_ids), vec![2]);
+		assert_eq!(registered(vec![(1, 4), (2, 4)], &mut seen_app_ids), vec![1]);
+		assert!(registered(vec![(1, 4), (2, 4)], &mut seen_app_ids).is_empty());
+		assert_eq!(registered(vec![(2, 4), (3, 4)], &mut seen_app_ids), vec![3]);
+		assert!(confidence_achieved()
+			.apps_registered(&mut seen_app_ids)
+			.is_empty());
+	}
+
+	#[test_case(vec![], vec![1, 2, 3] ; "All apps")]
+	#[test_case(vec![2], vec![2] ; "Single app")]
+	#[test_case(vec![1, 3], vec![1, 3] ; "Multiple apps")]
+	#[tokio::test]
+	async fn clients_publish_app_data(app_ids: Vec<u32>, expected: Vec<u32>) {
+		let clients = WsClients::default();
+		let (sender, mut receiver) = broadcast::channel(16);
+		let subscription = Subscription {
+			app_ids: app_ids.into_iter().collect(),
+			..subscription(vec![Topic::DataVerified], vec![])
+		};
+		clients.subscribe("1", subscription).await;
+		clients.set_sender("1", sender).await.unwrap();
+
+		for app_id in [1, 2, 3] {
+			let message = data_verified_with_app(app_id);
+			let _ = clients.publish(&Topic::DataVerified, message).await;
+		}
+
+		let mut received = vec![];
+		while let Ok(message) = receiver.try_recv() {
+			let PublishMessage::DataVerified(data) =
+				serde_json::from_slice(message.as_bytes()).unwrap()
+			else {
+				panic!("Invalid message type");
+			};
+			received.push(data.app_id);
+		}
+		assert_eq!(received, expected);
+	}
+
+	#[tokio::test]
+	async fn clients_publish_without_data_fields() {
+		let clients = WsClients::default();
+		let (sender, mut receiver) = broadcast::channel(16);
+		clients
+			.subscribe("1", subscription(vec![Topic::DataVerified], vec![]))
+			.await;
+		clients.set_sender("1", sender).await.unwrap();
+
+		let _ = clients.publish(&Topic::DataVerified, data_verified()).await;
+
+		let message = receiver.recv().await.unwrap();
+		assert_eq!(
+			message.to_str().unwrap(),
+			r#"{"topic":"data-verified","message":{"block_number":1,"app_id":1,"data_transactions":[{}]},"seq":1}"#
+		);
+	}
+
+	#[test_case(false, None ; "Cause is redacted")]
+	#[test_case(true, Some("Cannot convert to ws message: Invalid message") ; "Cause is exposed")]
+	#[tokio::test]
+	async fn publish_error_frame(expose_error_causes: bool, expected_cause: Option<&str>) {
+		let (sender, mut receiver) = broadcast::channel(1);
+		let projection = Projection {
+			data: false,
+			extrinsic: false,
+			commitments: false,
+		};
+		let delivery = Delivery {
+			subscription_id: "1".to_string(),
+			projection,
+			compression: None,
+			seq: 1,
+			sender,
+		};
+		// Message cannot be converted for the client projection
+		let serialized = Serialized([(projection, Err("Invalid message".to_string()))].into());
+
+		let sent = fan_out(
+			vec![delivery],
+			&Topic::HeaderVerified,
+			Arc::new(serialized),
+			1,
+			expose_error_causes,
+			FieldNaming::SnakeCase,
+		)
+		.await
+		.unwrap();
+		assert!(sent[0].0.is_err());
+
+		let message = receiver.recv().await.unwrap();
+		let WsError::Error(error) = serde_json::from_str(message.to_str().unwrap()).unwrap();
+		assert_eq!(error.error_code, ErrorCode::InternalServerError);
+		assert_eq!(error.message, "Cannot publish HeaderVerified message");
+		assert_eq!(error.cause_chain.as_deref(), expected_cause);
+	}
+
+	fn seq(message: &Message) -> u64 {
+		let message: serde_json::Value = serde_json::from_slice(message.as_bytes()).unwrap();
+		message["seq"].as_u64().unwrap()
+	}
+
+	#[tokio::test]
+	async fn clients_publish_sequence() {
+		let clients = WsClients::default();
+		let (sender, mut receiver) = broadcast::channel(2);
+		clients
+			.subscribe("1", subscription(vec![Topic::ConfidenceAchieved], vec![]))
+			.await;
+		clients.set_sender("1", sender).await.unwrap();
+
+		for _ in 0..2 {
+			let _ = clients
+				.publish(&Topic::ConfidenceAchieved, confidence_achieved())
+				.await;
 		}
+		assert_eq!(seq(&receiver.recv().await.unwrap()), 1);
+		assert_eq!(seq(&receiver.recv().await.unwrap()), 2);
+
+		// Messages not taken from the full buffer are dropped, which leaves a gap in sequence
+		for _ in 0..4 {
+			let _ = clients
+				.publish(&Topic::ConfidenceAchieved, confidence_achieved())
+				.await;
+		}
+		assert!(matches!(
+			receiver.recv().await,
+			Err(broadcast::error::RecvError::Lagged(2))
+		));
+		assert_eq!(seq(&receiver.recv().await.unwrap()), 5);
+		assert_eq!(seq(&receiver.recv().await.unwrap()), 6);
+
+		clients.reset_seq("1").await;
+		let _ = clients
+			.publish(&Topic::ConfidenceAchieved, confidence_achieved())
+			.await;
+		assert_eq!(seq(&receiver.recv().await.unwrap()), 1);
 	}
-}
 
-impl Reply for Error {
-	fn into_response(self) -> warp::reply::Response {
-		http::Response::builder()
-			.status(self.status())
-			.body(self.message.clone())
-			.expect("Can create error response")
-			.into_response()
+	#[test_case(FieldNaming::SnakeCase, "block_number" ; "Snake case")]
+	#[test_case(FieldNaming::CamelCase, "blockNumber" ; "Camel case")]
+	#[tokio::test]
+	async fn clients_publish_field_naming(naming: FieldNaming, expected_field: &str) {
+		let clients = WsClients::default().with_field_naming(naming);
+		let (sender, mut receiver) = broadcast::channel(16);
+		clients
+			.subscribe("1", subscription(vec![Topic::ConfidenceAchieved], vec![]))
+			.await;
+		clients.set_sender("1", sender).await.unwrap();
+
+		clients
+			.publish(&Topic::ConfidenceAchieved, confidence_achieved())
+			.await
+			.unwrap();
+
+		let message = receiver.recv().await.unwrap();
+		let message: serde_json::Value = serde_json::from_slice(message.as_bytes()).unwrap();
+		assert_eq!(message["topic"], "confidence-achieved");
+		assert_eq!(message["message"][expected_field], 1);
 	}
-}
 
-impl From<Error> for String {
-	fn from(error: Error) -> Self {
-		serde_json::to_string(&error).expect("Error is serializable")
+	#[tokio::test]
+	async fn clients_publish_prunes_disconnected() {
+		let clients = WsClients::default();
+		let subscription = || subscription(vec![Topic::ConfidenceAchieved], vec![]);
+		let (sender_1, receiver_1) = broadcast::channel(16);
+		let (sender_2, mut receiver_2) = broadcast::channel(16);
+		clients.subscribe("1", subscription()).await;
+		clients.subscribe("2", subscription()).await;
+		clients.set_sender("1", sender_1).await.unwrap();
+		clients.set_sender("2", sender_2).await.unwrap();
+		drop(receiver_1);
+
+		let published = clients
+			.publish(&Topic::ConfidenceAchieved, confidence_achieved())
+			.await
+			.unwrap();
+
+		assert_eq!(published.pruned, 1);
+		assert_eq!(published.results.iter().filter(|r| r.is_err()).count(), 1);
+		assert!(!clients.has_subscription("1").await);
+		assert!(clients.has_subscription("2").await);
+		assert!(receiver_2.recv().await.is_ok());
 	}
-}
-
-#[derive(Serialize, Deserialize, From)]
-#[serde(tag = "topic", rename_all = "kebab-case")]
-pub enum WsResponse {
-	Version(Response<Version>),
-	Status(Response<Status>),
-	DataTransactionSubmitted(Response<SubmitResponse>),
-}
 
-#[derive(Serialize, Deserialize, From)]
-#[serde(tag = "topic", rename_all = "kebab-case")]
-pub enum WsError {
-	Error(Error),
-}
+	#[test_case(0 ; "Without workers")]
+	#[test_case(2 ; "With workers")]
+	#[tokio::test]
+	async fn clients_publish_in_subscription_order(workers: usize) {
+		let clients = WsClients::default().with_publish_workers(workers);
+		let subscription = || subscription(vec![Topic::ConfidenceAchieved], vec![]);
+		for subscription_id in ["3", "1", "4", "2"] {
+			// Receiver is dropped, so publish error reports the client
+			let (sender, _) = broadcast::channel(16);
+			clients.subscribe(subscription_id, subscription()).await;
+			clients.set_sender(subscription_id, sender).await.unwrap();
+		}
+		clients.clients.write().await.shift_remove("4");
 
-#[cfg(test)]
-mod tests {
-	use std::time::Duration;
+		let published = clients
+			.publish(&Topic::ConfidenceAchieved, confidence_achieved())
+			.await
+			.unwrap();
 
-	use avail_subxt::api::runtime_types::avail_core::data_lookup::compact::CompactDataLookup;
-	use sp_core::H256;
-	use tokio::sync::mpsc;
+		let errors = published
+			.results
+			.into_iter()
+			.map(|result| result.unwrap_err().to_string())
+			.collect::<Vec<_>>();
+		assert_eq!(
+			errors,
+			vec![
+				"Send failed, client 3 is disconnected",
+				"Send failed, client 1 is disconnected",
+				"Send failed, client 2 is disconnected",
+			]
+		);
+	}
 
-	use crate::{
-		api::v2::types::{BlockStatus, Header, HeaderMessage, PublishMessage},
-		types::{OptionBlockRange, State},
-	};
+	#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+	async fn clients_publish_concurrent_reconnect() {
+		let clients = WsClients::default().with_publish_workers(4);
+		let mut receivers = vec![];
+		for subscription_id in 0..1000 {
+			let subscription_id = subscription_id.to_string();
+			let (sender, receiver) = broadcast::channel(16);
+			let subscription = subscription(vec![Topic::ConfidenceAchieved], vec![]);
+			clients.subscribe(&subscription_id, subscription).await;
+			clients.set_sender(&subscription_id, sender).await.unwrap();
+			receivers.push(receiver);
+		}
 
-	use super::{
-		block_status, Base64, ConfidenceMessage, DataField, DataMessage, DataTransaction,
-		Subscription, Topic, WsClients,
-	};
+		for _ in 0..20 {
+			// Client is disconnected, and reconnects while the message is published
+			let (sender, receiver) = broadcast::channel(16);
+			let subscription = subscription(vec![Topic::ConfidenceAchieved], vec![]);
+			clients.subscribe("x", subscription).await;
+			clients.set_sender("x", sender).await.unwrap();
+			drop(receiver);
+
+			let publishing = tokio::spawn({
+				let clients = clients.clone();
+				async move {
+					clients
+						.publish(&Topic::ConfidenceAchieved, confidence_achieved())
+						.await
+				}
+			});
+			let (sender, mut receiver) = broadcast::channel(16);
+			let reconnected = clients.set_sender("x", sender).await;
+			let published = publishing.await.unwrap().unwrap();
+
+			assert_eq!(published.results.len(), 1001);
+			assert!(published.pruned <= 1);
+			for receiver in receivers.iter_mut() {
+				assert!(receiver.recv().await.is_ok());
+			}
 
-	fn subscription(topics: Vec<Topic>, fields: Vec<DataField>) -> Subscription {
-		Subscription {
-			topics: topics.into_iter().collect(),
-			data_fields: fields.into_iter().collect(),
+			// Reconnected client is not pruned with the disconnected connection
+			if reconnected.is_ok() {
+				assert!(clients.has_subscription("x").await);
+				clients
+					.publish(&Topic::ConfidenceAchieved, confidence_achieved())
+					.await
+					.unwrap();
+				assert!(receiver.recv().await.is_ok());
+				for receiver in receivers.iter_mut() {
+					assert!(receiver.recv().await.is_ok());
+				}
+			} else {
+				assert_eq!(published.pruned, 1);
+			}
 		}
 	}
 
-	fn header_verified() -> PublishMessage {
-		PublishMessage::HeaderVerified(Box::new(HeaderMessage {
-			block_number: 1,
-			header: Header {
-				hash: H256::default(),
-				parent_hash: H256::default(),
-				number: 1,
-				state_root: H256::default(),
-				extrinsics_root: H256::default(),
-				extension: super::Extension {
-					rows: 1,
-					cols: 1,
-					data_root: H256::default(),
-					commitments: vec![],
-					app_lookup: CompactDataLookup {
-						size: 0,
-						index: vec![],
-					},
-				},
-			},
-		}))
-	}
-
-	fn confidence_achieved() -> PublishMessage {
-		PublishMessage::ConfidenceAchieved(ConfidenceMessage {
-			block_number: 1,
-			confidence: Some(1.0),
-		})
-	}
+	#[test_case(10 ; "Few clients")]
+	#[test_case(1000 ; "Many clients")]
+	#[tokio::test]
+	async fn clients_publish_serialized_once_per_projection(client_count: usize) {
+		let clients = WsClients::default().with_publish_workers(4);
+		let mut receivers = vec![];
+		for subscription_id in 0..client_count {
+			// Clients alternate between two projections of the data fields
+			let fields = match subscription_id % 2 {
+				0 => vec![DataField::Data],
+				_ => vec![DataField::Data, DataField::Extrinsic],
+			};
+			let subscription_id = subscription_id.to_string();
+			let (sender, receiver) = broadcast::channel(16);
+			let subscription = subscription(vec![Topic::DataVerified], fields);
+			clients.subscribe(&subscription_id, subscription).await;
+			clients.set_sender(&subscription_id, sender).await.unwrap();
+			receivers.push(receiver);
+		}
 
-	fn transaction_data() -> Option<Base64> {
-		Some(Base64(vec![0, 1, 2, 3, 4]))
+		let published = clients
+			.publish(&Topic::DataVerified, data_verified())
+			.await
+			.unwrap();
+
+		assert_eq!(published.serializations, 2);
+		assert!(published.results.iter().all(Result::is_ok));
+		for (index, receiver) in receivers.iter_mut().enumerate() {
+			let message = receiver.recv().await.unwrap();
+			let message: serde_json::Value = serde_json::from_slice(message.as_bytes()).unwrap();
+			let transaction = &message["message"]["data_transactions"][0];
+			assert_eq!(message["seq"], 1);
+			assert!(transaction.get("data").is_some());
+			assert_eq!(transaction.get("extrinsic").is_some(), index % 2 == 1);
+		}
 	}
 
-	fn data_verified() -> PublishMessage {
-		PublishMessage::DataVerified(DataMessage {
+	#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+	async fn clients_publish_lock_held_with_many_clients() {
+		let clients = WsClients::default().with_publish_workers(4);
+		let mut receivers = vec![];
+		for subscription_id in 0..2000 {
+			let subscription_id = subscription_id.to_string();
+			let (sender, receiver) = broadcast::channel(16);
+			let subscription = subscription(vec![Topic::DataVerified], vec![DataField::Data]);
+			clients.subscribe(&subscription_id, subscription).await;
+			clients.set_sender(&subscription_id, sender).await.unwrap();
+			clients
+				.set_compression(&subscription_id, Some(Compression::Deflate))
+				.await;
+			receivers.push(receiver);
+		}
+		// Message is compressed for each client, which is done after the clients lock is released
+		let data = (0..4 * COMPRESSION_THRESHOLD)
+			.map(|i| (i * 31 % 251) as u8)
+			.collect::<Vec<_>>();
+		let message = PublishMessage::DataVerified(DataMessage {
 			block_number: 1,
+			app_id: 1,
 			data_transactions: vec![DataTransaction {
-				data: transaction_data(),
-				extrinsic: transaction_data(),
+				data: Some(Base64(data)),
+				extrinsic: None,
 			}],
-		})
+		});
+
+		let started_at = Instant::now();
+		let published = clients
+			.publish(&Topic::DataVerified, message)
+			.await
+			.unwrap();
+		let elapsed = started_at.elapsed();
+
+		assert!(published.results.iter().all(Result::is_ok));
+		assert!(
+			published.lock_held < elapsed / 4,
+			"Lock is held for {:?} of {elapsed:?}",
+			published.lock_held
+		);
+		for receiver in receivers.iter_mut() {
+			assert!(receiver.recv().await.unwrap().is_binary());
+		}
+	}
+
+	#[tokio::test]
+	async fn clients_heartbeat() {
+		let clients = WsClients::default();
+		let (sender, mut receiver) = broadcast::channel(16);
+		clients.subscribe("1", Subscription::default()).await;
+		clients.set_sender("1", sender).await.unwrap();
+
+		// Pong resets the counter, so client survives any number of heartbeats
+		for _ in 0..3 {
+			assert_eq!(clients.heartbeat(2).await, 0);
+			assert!(receiver.recv().await.unwrap().is_ping());
+			clients.pong("1").await;
+		}
+
+		assert_eq!(clients.heartbeat(2).await, 0);
+		assert_eq!(clients.heartbeat(2).await, 0);
+		assert_eq!(clients.heartbeat(2).await, 1);
+		assert!(!clients.has_subscription("1").await);
+
+		assert!(receiver.recv().await.unwrap().is_ping());
+		assert!(receiver.recv().await.unwrap().is_ping());
+		assert!(receiver.recv().await.unwrap().is_close());
 	}
 
 	#[tokio::test]
@@ -876,8 +4013,8 @@ mod tests {
 			vec![Topic::ConfidenceAchieved, Topic::DataVerified],
 			vec![DataField::Data],
 		);
-		let (sender_1, mut receiver_1) = mpsc::unbounded_channel();
-		let (sender_2, mut receiver_2) = mpsc::unbounded_channel();
+		let (sender_1, mut receiver_1) = broadcast::channel(16);
+		let (sender_2, mut receiver_2) = broadcast::channel(16);
 		clients.subscribe("1", subscription_1).await;
 		clients.subscribe("2", subscription_2).await;
 		clients.set_sender("1", sender_1).await.unwrap();
@@ -894,22 +4031,22 @@ mod tests {
 		});
 
 		tokio::select! {
-			Some(message) = receiver_1.recv() => {
-				let message: PublishMessage = serde_json::from_slice(message.unwrap().as_bytes()).unwrap();
+			Ok(message) = receiver_1.recv() => {
+				let message: PublishMessage = serde_json::from_slice(message.as_bytes()).unwrap();
 				assert!(matches!(message, PublishMessage::HeaderVerified(_)));
 			},
 			_ = tokio::time::sleep(Duration::from_millis(100)) => panic!("Message isn't received"),
 		};
 		tokio::select! {
-			Some(message) = receiver_2.recv() => {
-				let message: PublishMessage = serde_json::from_slice(message.unwrap().as_bytes()).unwrap();
+			Ok(message) = receiver_2.recv() => {
+				let message: PublishMessage = serde_json::from_slice(message.as_bytes()).unwrap();
 				assert!(matches!(message, PublishMessage::ConfidenceAchieved(_)));
 			},
 			_ = tokio::time::sleep(Duration::from_millis(100)) => panic!("Message isn't received"),
 		};
 		tokio::select! {
-			Some(message) = receiver_1.recv() => {
-				let message: PublishMessage = serde_json::from_slice(message.unwrap().as_bytes()).unwrap();
+			Ok(message) = receiver_1.recv() => {
+				let message: PublishMessage = serde_json::from_slice(message.as_bytes()).unwrap();
 				let PublishMessage::DataVerified(data) = message else {
 					panic!("Invalid message type");
 				};
@@ -919,8 +4056,8 @@ mod tests {
 			_ = tokio::time::sleep(Duration::from_millis(100)) => panic!("Message isn't received"),
 		};
 		tokio::select! {
-			Some(message) = receiver_2.recv() => {
-				let message: PublishMessage = serde_json::from_slice(message.unwrap().as_bytes()).unwrap();
+			Ok(message) = receiver_2.recv() => {
+				let message: PublishMessage = serde_json::from_slice(message.as_bytes()).unwrap();
 				let PublishMessage::DataVerified(data) = message else {
 					panic!("Invalid message type");
 				};
@@ -1076,4 +4213,500 @@ mod tests {
 		assert_eq!(block_status(&Some(1), &state, 5), finished);
 		assert_ne!(block_status(&Some(1), &state, 6), finished);
 	}
+
+	#[test]
+	fn base64_standard_and_url_safe() {
+		let standard = Base64::try_from("+/+/dGVzdA==".to_string()).unwrap();
+		let url_safe = Base64::try_from("-_-_dGVzdA==".to_string()).unwrap();
+		assert_eq!(standard, url_safe);
+		assert_eq!(standard.0, [&[0xfb, 0xff, 0xbf][..], b"test"].concat());
+		assert_eq!(String::from(standard), "+/+/dGVzdA==");
+
+		assert!(Base64::try_from("+/-_".to_string()).is_err());
+	}
+
+	#[test]
+	fn extension_app_cells() {
+		let app_lookup = CompactDataLookup {
+			size: 10,
+			index: vec![
+				DataLookupItem {
+					app_id: AppId(1),
+					start: 0,
+				},
+				DataLookupItem {
+					app_id: AppId(3),
+					start: 4,
+				},
+			],
+		};
+		let extension = HeaderExtension::V3(v3::HeaderExtension {
+			commitment: KateCommitment::default(),
+			app_lookup,
+		});
+
+		let extension = super::Extension::try_from(extension).unwrap();
+		assert_eq!(extension.extension_version, 3);
+		assert_eq!(extension.app_cells, vec![(1, 4), (3, 6)]);
+		assert_eq!(extension.app_lookup.index.len(), 2);
+	}
+
+	#[test_case(2 * 48, true ; "Commitments of extended rows")]
+	#[test_case(3 * 48, false ; "Commitments exceed extended rows")]
+	#[test_case(1 << 20, false ; "Oversized commitments")]
+	fn extension_commitments_limit(commitments_len: usize, accepted: bool) {
+		let extension = HeaderExtension::V3(v3::HeaderExtension {
+			commitment: KateCommitment {
+				rows: 1,
+				cols: 4,
+				commitment: vec![0u8; commitments_len],
+				..Default::default()
+			},
+			app_lookup: CompactDataLookup {
+				size: 0,
+				index: vec![],
+			},
+		});
+
+		let extension = super::Extension::try_from(extension);
+		assert_eq!(extension.is_ok(), accepted);
+		if let Err(error) = extension {
+			assert_eq!(
+				error.to_string(),
+				format!("Header has {commitments_len} bytes of commitments, expected at most 96 for 1x4 matrix")
+			);
+		}
+	}
+
+	#[test_case(CommitmentEncoding::Hex, "0x0101" ; "Hex encoding")]
+	#[test_case(CommitmentEncoding::Base64, "AQEB" ; "Base64 encoding")]
+	fn commitment_round_trip(encoding: CommitmentEncoding, prefix: &str) {
+		let commitment = Commitment {
+			bytes: [1; config::COMMITMENT_SIZE],
+			encoding,
+		};
+
+		let serialized = serde_json::to_string(&commitment).unwrap();
+		assert!(serialized.starts_with(&format!(r#""{prefix}"#)));
+
+		let deserialized: Commitment = serde_json::from_str(&serialized).unwrap();
+		assert_eq!(deserialized.bytes, commitment.bytes);
+		assert_eq!(deserialized.encoding, encoding);
+	}
+
+	#[test]
+	fn commitment_base64_invalid_length() {
+		assert!(serde_json::from_str::<Commitment>(r#""AQEB""#).is_err());
+	}
+
+	#[test]
+	fn status_confidence() {
+		let config = RuntimeConfig::default();
+		let mut state = State::default();
+		state.confidence_achieved.set(1);
+
+		let status = serde_json::to_value(Status::new(&config, &state)).unwrap();
+		assert!(status["blocks"].get("confidence").is_none());
+
+		state.confidence = Some(93.75);
+		let status = serde_json::to_value(Status::new(&config, &state)).unwrap();
+		assert_eq!(status["blocks"]["confidence"], 93.75);
+		assert_eq!(status["blocks"]["available"]["last"], 1);
+	}
+
+	#[test]
+	fn status_changes() {
+		let config = RuntimeConfig::default();
+		let mut state = State::default();
+		state.latest = 1;
+		state.confidence_achieved.set(1);
+		state.confidence = Some(93.75);
+		let previous = Status::new(&config, &state);
+		assert!(Status::new(&config, &state).changes(&previous).is_none());
+
+		state.latest = 2;
+		state.confidence_achieved.set(2);
+		let current = Status::new(&config, &state);
+		assert_eq!(
+			current.changes(&previous),
+			Some(serde_json::json!({
+				"blocks": { "latest": 2, "available": { "last": 2 } }
+			}))
+		);
+
+		state.confidence = None;
+		state.latest_block_at = Some(1700000000);
+		assert_eq!(
+			Status::new(&config, &state).changes(&current),
+			Some(serde_json::json!({
+				"blocks": { "confidence": null },
+				"latest_block_at": 1700000000
+			}))
+		);
+	}
+
+	#[test]
+	fn status_app_ranges() {
+		let blocks = Blocks {
+			latest: 10,
+			available: None,
+			confidence: None,
+			app_data: None,
+			app_ranges: Some(HashMap::from([
+				(1, BlockRange { first: 1, last: 5 }),
+				(2, BlockRange { first: 3, last: 10 }),
+			])),
+			historical_sync: None,
+		};
+
+		let blocks = serde_json::to_value(blocks).unwrap();
+		assert_eq!(
+			blocks["app_ranges"],
+			serde_json::json!({
+				"1": { "first": 1, "last": 5 },
+				"2": { "first": 3, "last": 10 },
+			})
+		);
+
+		let config = RuntimeConfig {
+			app_id: Some(1),
+			..Default::default()
+		};
+		let mut state = State::default();
+		let status = serde_json::to_value(Status::new(&config, &state)).unwrap();
+		assert!(status["blocks"].get("app_ranges").is_none());
+
+		state.data_verified.set(4);
+		let status = serde_json::to_value(Status::new(&config, &state)).unwrap();
+		assert_eq!(
+			status["blocks"]["app_ranges"],
+			serde_json::json!({ "1": { "first": 4, "last": 4 } })
+		);
+	}
+
+	#[test]
+	fn status_timestamps() {
+		let config = RuntimeConfig::default();
+		let mut state = State::default();
+
+		let status = serde_json::to_value(Status::new(&config, &state)).unwrap();
+		assert!(status.get("latest_block_at").is_none());
+		let first = status["timestamp"].as_u64().unwrap();
+
+		state.set_latest(1);
+		let status = serde_json::to_value(Status::new(&config, &state)).unwrap();
+		let latest_block_at = status["latest_block_at"].as_u64().unwrap();
+		let second = status["timestamp"].as_u64().unwrap();
+
+		assert!(first <= latest_block_at);
+		assert!(latest_block_at <= second);
+	}
+
+	#[test_case(FieldNaming::SnakeCase, r#"{"app_ranges":{"1":[{"block_number":1}]},"latest":1,"topics":{"data_key":1}}"# ; "Snake case")]
+	#[test_case(FieldNaming::CamelCase, r#"{"appRanges":{"1":[{"blockNumber":1}]},"latest":1,"topics":{"data_key":1}}"# ; "Camel case")]
+	fn rename_fields_naming(naming: FieldNaming, expected: &str) {
+		let value = serde_json::json!({
+			"app_ranges": {"1": [{"block_number": 1}]},
+			"latest": 1,
+			"topics": {"data_key": 1}
+		});
+		assert_eq!(rename_fields(value, naming).to_string(), expected);
+	}
+
+	#[test]
+	fn rename_fields_schema() {
+		fn properties(value: &serde_json::Value, fields: &mut Vec<String>) {
+			match value {
+				serde_json::Value::Object(object) => {
+					if let Some(serde_json::Value::Object(properties)) = object.get("properties") {
+						fields.extend(properties.keys().cloned());
+					}
+					object.values().for_each(|value| properties(value, fields));
+				},
+				serde_json::Value::Array(values) => {
+					values.iter().for_each(|value| properties(value, fields))
+				},
+				_ => (),
+			}
+		}
+
+		let mut fields = vec![];
+		properties(&super::schema(), &mut fields);
+		for field in fields.iter().filter(|field| field.contains('_')) {
+			assert!(
+				RENAMED_FIELDS.contains(&field.as_str()),
+				"{field} is not renamed"
+			);
+		}
+	}
+
+	#[test]
+	fn status_chain_time_drift() {
+		let config = RuntimeConfig::default();
+		let mut state = State::default();
+
+		let status = serde_json::to_value(Status::new(&config, &state)).unwrap();
+		assert!(status.get("chain_time").is_none());
+		assert!(status.get("drift_seconds").is_none());
+
+		state.latest_block_timestamp = Some(1700000000);
+		let status = serde_json::to_value(Status::new(&config, &state)).unwrap();
+		let timestamp = status["timestamp"].as_i64().unwrap();
+		assert_eq!(status["chain_time"], 1700000000);
+		assert_eq!(status["drift_seconds"], timestamp - 1700000000);
+
+		// Chain time ahead of the server time results in negative drift
+		let chain_time = timestamp as u64 + 3600;
+		state.latest_block_timestamp = Some(chain_time);
+		let status = serde_json::to_value(Status::new(&config, &state)).unwrap();
+		assert!(status["drift_seconds"].as_i64().unwrap() <= -3599);
+	}
+
+	#[test]
+	fn status_partition_cells() {
+		let config = RuntimeConfig {
+			block_matrix_partition: vec![Partition {
+				number: 1,
+				fraction: 4,
+			}],
+			..Default::default()
+		};
+		let mut state = State::default();
+		let status = serde_json::to_value(Status::new(&config, &state)).unwrap();
+		assert!(status.get("partition_cells").is_none());
+
+		// Extended matrix of 2x4 block has 16 cells
+		state.latest_dimensions = Dimensions::new(2, 4);
+		let status = serde_json::to_value(Status::new(&config, &state)).unwrap();
+		assert_eq!(status["partition_cells"], 4);
+
+		let status = serde_json::to_value(Status::new(&RuntimeConfig::default(), &state)).unwrap();
+		assert!(status.get("partition_cells").is_none());
+	}
+
+	#[test_case(&[], None, None ; "No partitions")]
+	#[test_case(&[(1, 4)], Some(serde_json::json!("1/4")), Some(4) ; "Single partition")]
+	#[test_case(&[(1, 4), (2, 4), (1, 4)], Some(serde_json::json!(["1/4", "2/4", "1/4"])), Some(8) ; "Multiple partitions")]
+	fn status_partitions(
+		partitions: &[(u8, u8)],
+		expected: Option<serde_json::Value>,
+		expected_cells: Option<u32>,
+	) {
+		let config = RuntimeConfig {
+			block_matrix_partition: partitions
+				.iter()
+				.map(|&(number, fraction)| Partition { number, fraction })
+				.collect(),
+			..Default::default()
+		};
+		let state = State {
+			latest_dimensions: Dimensions::new(2, 4),
+			..Default::default()
+		};
+
+		let status = serde_json::to_value(Status::new(&config, &state)).unwrap();
+		assert_eq!(status.get("partition").cloned(), expected);
+		assert_eq!(
+			status
+				.get("partition_cells")
+				.and_then(|cells| cells.as_u64()),
+			expected_cells.map(u64::from)
+		);
+		let modes = status["modes"].as_array().unwrap();
+		assert_eq!(modes.contains(&"partition".into()), !partitions.is_empty());
+	}
+
+	#[test]
+	fn error_retry_after() {
+		let error = Error::bad_request_unknown("Too many requests")
+			.with_retry_after(Duration::from_secs(5))
+			.with_header("x-custom", "value");
+
+		let response = warp::Reply::into_response(error);
+		assert_eq!(response.headers()["retry-after"], "5");
+		assert_eq!(response.headers()["x-custom"], "value");
+	}
+
+	#[tokio::test]
+	async fn error_json_response() {
+		let response = warp::Reply::into_response(Error::bad_request_unknown("Invalid request"));
+		assert_eq!(response.status(), hyper::StatusCode::BAD_REQUEST);
+		assert_eq!(response.headers()["content-type"], "application/json");
+
+		let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+		let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+		assert_eq!(
+			body,
+			serde_json::json!({ "error_code": "bad-request", "message": "Invalid request" })
+		);
+	}
+
+	#[test]
+	fn error_with_request_id() {
+		let request_id = uuid::Uuid::new_v4();
+		let error = Error::not_found().with_request_id(request_id);
+		assert_eq!(error.request_id, Some(request_id));
+
+		let error =
+			Error::bad_request(request_id, "Bad request").with_request_id(uuid::Uuid::new_v4());
+		assert_eq!(error.request_id, Some(request_id));
+	}
+
+	#[test]
+	fn error_from_decode_error() {
+		let error = general_purpose::STANDARD.decode("invalid!").unwrap_err();
+		let error = Error::from(error);
+		assert_eq!(error.error_code, ErrorCode::BadRequest);
+		assert!(error.message.starts_with("Invalid base64 value: "));
+		assert!(error.cause.is_none());
+	}
+
+	#[test]
+	fn error_from_report() {
+		let error = Error::from(eyre!("Root cause").wrap_err("Outer cause"));
+		assert_eq!(error.error_code, ErrorCode::InternalServerError);
+		assert_eq!(error.message, "Internal Server Error");
+		let cause = error.cause.map(|cause| format!("{cause:#}"));
+		assert_eq!(cause.as_deref(), Some("Outer cause: Root cause"));
+	}
+
+	#[test]
+	fn error_cause_chain() {
+		let error = || Error::internal_server_error(eyre!("Root cause").wrap_err("Outer cause"));
+
+		let hidden = serde_json::to_value(error()).unwrap();
+		assert!(hidden.get("cause_chain").is_none());
+
+		let exposed = serde_json::to_value(error().with_cause_chain()).unwrap();
+		assert_eq!(exposed["cause_chain"], "Outer cause: Root cause");
+
+		let without_cause = serde_json::to_value(Error::not_found().with_cause_chain()).unwrap();
+		assert!(without_cause.get("cause_chain").is_none());
+	}
+
+	#[test_case(Error::not_found(), hyper::StatusCode::NOT_FOUND ; "Not found")]
+	#[test_case(Error::bad_request_unknown("Bad request"), hyper::StatusCode::BAD_REQUEST ; "Bad request")]
+	#[test_case(Error::internal_server_error(eyre!("Cause")), hyper::StatusCode::INTERNAL_SERVER_ERROR ; "Internal server error")]
+	#[test_case(Error::too_many_requests(), hyper::StatusCode::TOO_MANY_REQUESTS ; "Too many requests")]
+	#[test_case(Error::service_unavailable(eyre!("Cause")), hyper::StatusCode::SERVICE_UNAVAILABLE ; "Service unavailable")]
+	#[test_case(Error::unauthorized(None), hyper::StatusCode::UNAUTHORIZED ; "Unauthorized")]
+	fn error_status(error: Error, expected: hyper::StatusCode) {
+		assert_eq!(error.status(), expected);
+		assert_eq!(warp::Reply::into_response(error).status(), expected);
+	}
+
+	#[test_case(ErrorCode::NotFound, 1008 ; "Not found")]
+	#[test_case(ErrorCode::BadRequest, 1008 ; "Bad request")]
+	#[test_case(ErrorCode::InternalServerError, 1011 ; "Internal server error")]
+	#[test_case(ErrorCode::TooManyRequests, 1008 ; "Too many requests")]
+	#[test_case(ErrorCode::ServiceUnavailable, 1013 ; "Service unavailable")]
+	#[test_case(ErrorCode::Unauthorized, 1008 ; "Unauthorized")]
+	fn error_close_code(error_code: ErrorCode, expected: u16) {
+		assert_eq!(error_code.close_code(), expected);
+	}
+
+	fn decompress(message: &Message) -> String {
+		let (marker, compressed) = message.as_bytes().split_first().unwrap();
+		assert_eq!(*marker, DEFLATE_MESSAGE_MARKER);
+		let decompressed = miniz_oxide::inflate::decompress_to_vec_zlib(compressed).unwrap();
+		String::from_utf8(decompressed).unwrap()
+	}
+
+	#[test_case(COMPRESSION_THRESHOLD, Some(Compression::Deflate), false ; "Message at threshold")]
+	#[test_case(COMPRESSION_THRESHOLD + 1, None, false ; "Compression not enabled")]
+	#[test_case(COMPRESSION_THRESHOLD + 1, Some(Compression::Deflate), true ; "Message above threshold")]
+	fn compress_message(size: usize, compression: Option<Compression>, compressed: bool) {
+		let text = "a".repeat(size);
+		let message = compress(Message::text(&text), compression);
+
+		assert_eq!(message.is_binary(), compressed);
+		if compressed {
+			assert!(message.as_bytes().len() < size);
+			assert_eq!(decompress(&message), text);
+		} else {
+			assert_eq!(message.to_str().unwrap(), text);
+		}
+	}
+
+	#[test_case(None, true ; "Default")]
+	#[test_case(Some(true), true ; "Included commitments")]
+	#[test_case(Some(false), false ; "Omitted commitments")]
+	#[tokio::test]
+	async fn clients_publish_commitments(include_commitments: Option<bool>, expected: bool) {
+		let clients = WsClients::default();
+		let (sender, mut receiver) = broadcast::channel(16);
+		let subscription = Subscription {
+			include_commitments,
+			..subscription(vec![Topic::HeaderVerified], vec![])
+		};
+		clients.subscribe("1", subscription).await;
+		clients.set_sender("1", sender).await.unwrap();
+
+		let _ = clients
+			.publish(&Topic::HeaderVerified, header_verified())
+			.await;
+
+		let message = receiver.recv().await.unwrap();
+		let message: serde_json::Value = serde_json::from_slice(message.as_bytes()).unwrap();
+		let extension = &message["message"]["header"]["extension"];
+		assert_eq!(extension["rows"], 1);
+		assert_eq!(extension.get("commitments").is_some(), expected);
+	}
+
+	#[tokio::test]
+	async fn clients_publish_compressed() {
+		let clients = WsClients::default();
+		let (sender, mut receiver) = broadcast::channel(16);
+		clients
+			.subscribe("1", subscription(vec![Topic::HeaderVerified], vec![]))
+			.await;
+		clients.set_sender("1", sender).await.unwrap();
+		clients
+			.set_compression("1", Some(Compression::Deflate))
+			.await;
+
+		let app_cells = (0..200).map(|app_id| (app_id, 1)).collect();
+		let message = header_verified_with_apps(app_cells);
+		let expected = serde_json::to_string(&message).unwrap();
+		let _ = clients.publish(&Topic::HeaderVerified, message).await;
+
+		let message = receiver.recv().await.unwrap();
+		assert_eq!(decompress(&message), expected);
+	}
+
+	#[test]
+	fn header_message_cbor_round_trip() {
+		let PublishMessage::HeaderVerified(header) = header_verified_with_apps(vec![(1, 4)]) else {
+			panic!("Invalid message type");
+		};
+
+		let mut cbor = vec![];
+		ciborium::into_writer(&header, &mut cbor).unwrap();
+		let decoded: HeaderMessage = ciborium::from_reader(&cbor[..]).unwrap();
+
+		assert_eq!(
+			serde_json::to_value(&decoded).unwrap(),
+			serde_json::to_value(&header).unwrap()
+		);
+	}
+
+	#[test_case(CommitmentEncoding::Hex ; "Hex encoding")]
+	#[test_case(CommitmentEncoding::Base64 ; "Base64 encoding")]
+	fn commitment_cbor_bytes(encoding: CommitmentEncoding) {
+		let commitment = Commitment {
+			bytes: [7; config::COMMITMENT_SIZE],
+			encoding,
+		};
+
+		let mut cbor = vec![];
+		ciborium::into_writer(&commitment, &mut cbor).unwrap();
+		let value: ciborium::Value = ciborium::from_reader(&cbor[..]).unwrap();
+		assert_eq!(
+			value,
+			ciborium::Value::Bytes(vec![7; config::COMMITMENT_SIZE])
+		);
+
+		let decoded: Commitment = ciborium::from_reader(&cbor[..]).unwrap();
+		assert_eq!(decoded.bytes, commitment.bytes);
+	}
 }
@@ -1,20 +1,63 @@
 use super::{
+	cells,
+	metrics::ApiMetrics,
+	rate_limit::ClientRateLimiter,
 	transactions,
-	types::{Payload, Request, Response, Status, Version, WsClients, WsError, WsResponse},
+	types::{
+		block_status, replay_window, to_json, AppDataMessage, Base64, BlockRanges, BlockStatus,
+		CellsMessage, CommitmentsMessage, DataProofMessage, DataProofVerified, Disconnected,
+		ExtrinsicProofMessage, GenesisHash, HeaderMessage, Payload, Pong, PublishMessage,
+		RawHeaderMessage, ReconstructMessage, ReplayedHeaders, Request, Response, RowMessage,
+		SampledConfidence, Status, SubmitBatchResponse, SubmitCache, SubmitRequest, Version,
+		WsClients, WsError, WsResponse,
+	},
 };
 use crate::{
-	api::v2::types::{Error, Sender},
-	types::{RuntimeConfig, State},
+	api::v2::types::{Error, ErrorCode, MESSAGE_TOO_BIG},
+	data::{Database, Key},
+	network::rpc::{self, DataProof},
+	proof::{data_proof_len, extrinsic_proof, verify_data_proof},
+	types::{unix_timestamp_millis, FieldNaming, RuntimeConfig, State, WsBufferPolicy},
+	utils::{
+		calculate_confidence, decode_app_data, extended_partitions_positions, extract_kate,
+		header_commitments,
+	},
 };
-use color_eyre::{eyre::WrapErr, Result};
-use futures::{FutureExt, StreamExt};
+use avail_subxt::primitives;
+use codec::Encode;
+use color_eyre::{
+	eyre::{eyre, WrapErr},
+	Report, Result,
+};
+use futures::{stream, FutureExt, Stream, StreamExt};
+use kate_recovery::{
+	com::reconstruct_columns,
+	data::Cell,
+	matrix::{Dimensions, Position},
+};
+use rand::{thread_rng, Rng};
 use serde::Serialize;
-use std::sync::{Arc, Mutex};
-use tokio::sync::mpsc;
-use tokio_stream::wrappers::UnboundedReceiverStream;
-use tracing::{error, log::warn};
+use sp_core::{blake2_256, H256};
+use std::{
+	collections::{HashMap, HashSet},
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
+use tokio::sync::{
+	broadcast::{self, error::RecvError},
+	mpsc,
+};
+use tracing::{error, info, info_span, log::warn, Instrument};
+use uuid::Uuid;
 use warp::ws::{self, Message, WebSocket};
 
+/// Maximum number of cells sampled by a single confidence request,
+/// which limits retries of the cells failing verification
+const MAX_SAMPLED_CELLS: u32 = 2 * rpc::CELL_COUNT_99_99;
+
+/// Sender of the responses to the client requests, separate from the published messages.
+type ResponseSender = mpsc::Sender<Message>;
+
 #[allow(clippy::too_many_arguments)]
 pub async fn connect(
 	subscription_id: String,
@@ -23,94 +66,1209 @@ pub async fn connect(
 	version: Version,
 	config: RuntimeConfig,
 	submitter: Option<Arc<impl transactions::Submit + Clone + Send + Sync + 'static>>,
+	submit_cache: SubmitCache,
+	cell_fetcher: Arc<impl cells::Fetch + Send + Sync + 'static>,
+	metrics: ApiMetrics,
+	rate_limiter: ClientRateLimiter,
 	state: Arc<Mutex<State>>,
+	db: impl Database + Clone + Send + Sync + 'static,
 ) {
 	let (web_socket_sender, mut web_socket_receiver) = web_socket.split();
-	let (sender, receiver) = mpsc::unbounded_channel();
-	let receiver_stream = UnboundedReceiverStream::new(receiver);
+	let buffer_size = config.ws_buffer_size.max(1);
+	let (sender, receiver) = broadcast::channel(buffer_size);
+	// Responses to the client requests are not dropped when the published messages buffer is full,
+	// instead, handling of the requests waits until the client takes the previous responses
+	let (response_sender, response_receiver) = mpsc::channel(buffer_size);
+	let receiver_stream = client_messages(
+		subscription_id.clone(),
+		receiver,
+		response_receiver,
+		clients.clone(),
+		config.ws_buffer_policy,
+	);
+
+	let mut forward =
+		tokio::task::spawn(receiver_stream.forward(web_socket_sender).map(|result| {
+			if let Err(error) = result {
+				error!("Error sending web socket message: {error}");
+			}
+		}));
 
-	if let Err(error) = clients.set_sender(&subscription_id, sender.clone()).await {
-		error!("Cannot set sender: {error}");
+	// Headers are loaded before connecting, so publishing is not paused while reading the database
+	let replayed = match clients.replay_from(&subscription_id).await {
+		Some(from_block) => {
+			let (config, state, db) = (config.clone(), state.clone(), db.clone());
+			tokio::task::spawn_blocking(move || {
+				replay_headers(from_block, buffer_size, &config, &state, &db)
+			})
+			.await
+			.map_err(Report::from)
+			.and_then(|result| result)
+			.unwrap_or_else(|error| {
+				error!("Cannot replay headers: {error:#}");
+				ReplayedHeaders::default()
+			})
+		},
+		None => ReplayedHeaders::default(),
+	};
+	if let Err(error) = clients
+		.connect(
+			&subscription_id,
+			sender,
+			forward.abort_handle(),
+			buffer_size,
+			replayed,
+		)
+		.await
+	{
+		error!("Cannot connect client: {error:#}");
 		return;
 	};
 
-	tokio::task::spawn(receiver_stream.forward(web_socket_sender).map(|result| {
-		if let Err(error) = result {
-			error!("Error sending web socket message: {error}");
-		}
-	}));
-
-	fn send<T: Serialize>(sender: Sender, message: T) -> Result<()> {
-		let ws_message = serde_json::to_string(&message)
+	async fn send<T: Serialize>(
+		sender: &ResponseSender,
+		message: T,
+		naming: FieldNaming,
+	) -> Result<()> {
+		let ws_message = to_json(&message, naming)
 			.map(ws::Message::text)
 			.wrap_err("Failed to serialize message")?;
 
 		sender
-			.send(Ok(ws_message))
+			.send(ws_message)
+			.await
 			.wrap_err("Failed to send message")
 	}
 
-	while let Some(result) = web_socket_receiver.next().await {
+	// Number of consecutive requests which cannot be parsed
+	let mut malformed_requests = 0;
+
+	loop {
+		// Connection is closed once messages are not forwarded anymore, e.g. if client is evicted
+		let result = tokio::select! {
+			result = web_socket_receiver.next() => match result {
+				Some(result) => result,
+				None => break,
+			},
+			_ = &mut forward => break,
+		};
 		let message = match result {
 			Err(error) => {
 				error!("Error receiving client message: {error}");
 				continue;
 			},
+			Ok(message) if message.is_pong() => {
+				clients.pong(&subscription_id).await;
+				continue;
+			},
 			Ok(message) if !message.is_text() => continue,
 			Ok(message) => message,
 		};
 
-		let submitter = submitter.clone();
-		let state = state.clone();
+		// Requests are not parsed if they exceed the limit, and the connection is closed
+		let size = message.as_bytes().len();
+		if size > config.ws_max_message_size {
+			let reason = format!(
+				"Request size {size} exceeds the limit of {} bytes",
+				config.ws_max_message_size
+			);
+			let error = Error::bad_request_unknown(&reason);
+			if let Err(error) = send::<WsError>(
+				&response_sender,
+				logged(error, &metrics, config.expose_error_causes).into(),
+				config.api_field_naming,
+			)
+			.await
+			{
+				warn!("Error sending message: {error:#}");
+			}
+			close(&response_sender, MESSAGE_TOO_BIG, &reason).await;
+			break;
+		}
 
-		let send_result = match handle_request(message, &version, &config, submitter, state).await {
-			Ok(response) => send(sender.clone(), response),
-			Err(error) => {
-				if let Some(cause) = error.cause.as_ref() {
-					error!("Failed to handle request: {cause:#}");
+		let (send_result, malformed) = match batch_messages(&message) {
+			None => {
+				// Only requests which cannot be parsed are counted as malformed
+				let (result, malformed) = match Request::try_from(message) {
+					Ok(request) => {
+						let result = handle_request(
+							request,
+							&subscription_id,
+							&clients,
+							&version,
+							&config,
+							submitter.clone(),
+							&submit_cache,
+							cell_fetcher.as_ref(),
+							&metrics,
+							&rate_limiter,
+							state.clone(),
+							&db,
+						)
+						.await;
+						(result, false)
+					},
+					Err(error) => (Err(error), true),
+				};
+				let send_result = match result {
+					Ok(response) => send(&response_sender, response, config.api_field_naming).await,
+					Err(error) => {
+						send::<WsError>(
+							&response_sender,
+							logged(error, &metrics, config.expose_error_causes).into(),
+							config.api_field_naming,
+						)
+						.await
+					},
 				};
-				send::<WsError>(sender.clone(), error.into())
+				(send_result, malformed)
+			},
+			Some(Err(error)) => {
+				let send_result = send::<WsError>(
+					&response_sender,
+					logged(error, &metrics, config.expose_error_causes).into(),
+					config.api_field_naming,
+				)
+				.await;
+				(send_result, true)
+			},
+			Some(Ok(messages)) => {
+				let mut responses = Vec::with_capacity(messages.len());
+				let mut malformed = false;
+				for message in messages {
+					let request = match Request::try_from(message) {
+						Ok(request) => request,
+						Err(error) => {
+							malformed = true;
+							let error = logged(error, &metrics, config.expose_error_causes);
+							let response = to_json(&WsError::from(error), config.api_field_naming);
+							responses.push(response.wrap_err("Failed to serialize message"));
+							continue;
+						},
+					};
+					let result = handle_request(
+						request,
+						&subscription_id,
+						&clients,
+						&version,
+						&config,
+						submitter.clone(),
+						&submit_cache,
+						cell_fetcher.as_ref(),
+						&metrics,
+						&rate_limiter,
+						state.clone(),
+						&db,
+					)
+					.await;
+					let response = match result {
+						Ok(response) => to_json(&response, config.api_field_naming),
+						Err(error) => to_json(
+							&WsError::from(logged(error, &metrics, config.expose_error_causes)),
+							config.api_field_naming,
+						),
+					};
+					responses.push(response.wrap_err("Failed to serialize message"));
+				}
+				let send_result = match responses.into_iter().collect::<Result<Vec<_>>>() {
+					Ok(responses) => {
+						let ws_message = Message::text(format!("[{}]", responses.join(",")));
+						response_sender
+							.send(ws_message)
+							.await
+							.wrap_err("Failed to send message")
+					},
+					Err(error) => Err(error),
+				};
+				(send_result, malformed)
 			},
 		};
 
 		if let Err(error) = send_result {
 			warn!("Error sending message: {error:#}");
 		}
+
+		// Malformed requests limit of zero disables closing the connection
+		malformed_requests = if malformed { malformed_requests + 1 } else { 0 };
+		let max_malformed = config.ws_max_malformed_requests;
+		if max_malformed > 0 && malformed_requests >= max_malformed {
+			let reason = format!("{malformed_requests} consecutive requests cannot be parsed");
+			close(
+				&response_sender,
+				ErrorCode::BadRequest.close_code(),
+				&reason,
+			)
+			.await;
+			break;
+		}
+	}
+}
+
+/// Sends close frame to the client after the already sent responses.
+async fn close(sender: &ResponseSender, code: u16, reason: &str) {
+	info!("Closing client connection with code {code}: {reason}");
+	if sender
+		.send(Message::close_with(code, reason.to_string()))
+		.await
+		.is_err()
+	{
+		warn!("Cannot send close frame, client is disconnected");
+	}
+}
+
+/// Loads verified headers replayed to the client, starting from the requested block
+/// up to the latest verified header, capped to the configured number of blocks.
+/// Only the last headers of the window that fit into the client buffer are loaded.
+fn replay_headers(
+	from_block: u32,
+	buffer_size: usize,
+	config: &RuntimeConfig,
+	state: &Arc<Mutex<State>>,
+	db: &impl Database,
+) -> Result<ReplayedHeaders> {
+	let window = {
+		let state = state.lock().expect("State lock can be acquired");
+		replay_window(
+			from_block,
+			state.header_verified.as_ref(),
+			config.ws_replay_max_blocks,
+		)
+	};
+	let Some(window) = window else {
+		// Requested block is not verified yet, so it is replayed from the replay buffer once it is
+		return Ok(ReplayedHeaders {
+			headers: vec![],
+			loaded_until: Some(from_block.saturating_sub(1)),
+		});
+	};
+
+	let buffer_size = u32::try_from(buffer_size).unwrap_or(u32::MAX).max(1);
+	let first = window
+		.first
+		.max(window.last.saturating_sub(buffer_size - 1));
+	let headers = (first..=window.last)
+		.map(|block_number| db.get::<primitives::Header>(Key::BlockHeader(block_number)))
+		.filter_map(Result::transpose)
+		.map(|header| {
+			let header = HeaderMessage::try_from(header?)?;
+			Ok(PublishMessage::HeaderVerified(Box::new(header)))
+		})
+		.collect::<Result<_>>()?;
+
+	Ok(ReplayedHeaders {
+		headers,
+		loaded_until: (!window.truncated).then_some(window.last),
+	})
+}
+
+/// Logs the error cause and counts the error in the API metrics.
+fn logged(error: Error, metrics: &ApiMetrics, expose_error_causes: bool) -> Error {
+	metrics.record_error(&error.error_code);
+	if let Some(cause) = error.cause.as_ref() {
+		error!("Failed to handle request: {cause:#}");
+	};
+	match expose_error_causes {
+		true => error.with_cause_chain(),
+		false => error,
+	}
+}
+
+/// Splits JSON array frame into separate request messages, which are handled in order.
+/// Returns `None` if frame is not an array.
+fn batch_messages(message: &Message) -> Option<Result<Vec<Message>, Error>> {
+	let text = message.to_str().ok()?;
+	if !text.trim_start().starts_with('[') {
+		return None;
 	}
+
+	let messages = match serde_json::from_str::<Vec<serde_json::Value>>(text) {
+		Ok(requests) if requests.is_empty() => Err(Error::bad_request_unknown("Batch is empty.")),
+		Ok(requests) => Ok(requests
+			.iter()
+			.map(|request| Message::text(request.to_string()))
+			.collect()),
+		Err(error) => {
+			let message = format!("Failed to parse request: Cannot parse json: {error}");
+			Err(Error::bad_request_unknown(&message))
+		},
+	};
+	Some(messages)
+}
+
+/// Streams messages buffered for the client. When client falls behind and the buffer overflows,
+/// oldest messages are dropped and counted. With `Disconnect` policy, stream ends on overflow,
+/// which closes the client connection. Clients lagging with `Evict` policy are evicted
+/// by [`super::evict_lagging`], since overflow is not observed while client doesn't read.
+fn client_messages(
+	subscription_id: String,
+	receiver: broadcast::Receiver<Message>,
+	responses: mpsc::Receiver<Message>,
+	clients: WsClients,
+	policy: WsBufferPolicy,
+) -> impl Stream<Item = Result<Message, warp::Error>> {
+	stream::unfold(
+		(receiver, responses),
+		move |(mut receiver, mut responses)| {
+			let subscription_id = subscription_id.clone();
+			let clients = clients.clone();
+			async move {
+				loop {
+					// Responses are sent first, and their branch is disabled once all senders are dropped
+					let result = tokio::select! {
+						biased;
+						Some(message) = responses.recv() => Ok(message),
+						result = receiver.recv() => result,
+					};
+					match result {
+						Ok(message) => return Some((Ok(message), (receiver, responses))),
+						Err(RecvError::Closed) => return None,
+						Err(RecvError::Lagged(count)) => {
+							let total = clients
+								.add_dropped_messages(&subscription_id, count)
+								.await
+								.unwrap_or(count);
+							if policy == WsBufferPolicy::Disconnect {
+								warn!("Client {subscription_id} buffer is full, disconnecting (dropped messages: {total})");
+								return None;
+							}
+							warn!("Client {subscription_id} buffer is full, dropped {count} oldest messages (dropped messages: {total})");
+						},
+					}
+				}
+			}
+		},
+	)
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn handle_request(
-	message: Message,
+	request: Request,
+	subscription_id: &str,
+	clients: &WsClients,
 	version: &Version,
 	config: &RuntimeConfig,
 	submitter: Option<Arc<impl transactions::Submit>>,
+	submit_cache: &SubmitCache,
+	cell_fetcher: &impl cells::Fetch,
+	metrics: &ApiMetrics,
+	rate_limiter: &ClientRateLimiter,
 	state: Arc<Mutex<State>>,
+	db: &impl Database,
 ) -> Result<WsResponse, Error> {
-	let request = Request::try_from(message).map_err(|error| {
-		Error::bad_request_unknown(&format!("Failed to parse request: {error}"))
-	})?;
+	let request_id = request.request_id;
+	if let Err(retry_after) = rate_limiter.acquire().await {
+		return Err(Error::rate_limited(Some(request_id), retry_after));
+	}
+	let request_type = request.payload.request_type();
+	let span = info_span!("ws_request", subscription_id, request_type, %request_id);
+	let started = Instant::now();
+
+	// Request handling future is dropped on timeout, which cancels in-flight operation
+	let timeout = Duration::from_secs(config.ws_request_timeout);
+	let handle_payload = handle_payload(
+		request,
+		subscription_id,
+		clients,
+		version,
+		config,
+		submitter,
+		submit_cache,
+		cell_fetcher,
+		rate_limiter,
+		state,
+		db,
+	);
+	let result = tokio::time::timeout(timeout, handle_payload)
+		.instrument(span.clone())
+		.await
+		.unwrap_or_else(|_| Err(Error::request_timeout(request_id)))
+		.map_err(|error| error.with_request_id(request_id));
 
+	let elapsed = started.elapsed();
+	metrics.record_request(request_type, elapsed);
+	span.in_scope(|| log_outcome(&result, elapsed));
+	result
+}
+
+fn log_outcome<T>(result: &Result<T, Error>, elapsed: Duration) {
+	let elapsed_ms = elapsed.as_millis() as u64;
+	match result {
+		Ok(_) => info!(outcome = "ok", elapsed_ms, "Request handled"),
+		Err(error) => info!(
+			outcome = error.error_code.as_str(),
+			elapsed_ms, "Request handled"
+		),
+	}
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn handle_payload(
+	request: Request,
+	subscription_id: &str,
+	clients: &WsClients,
+	version: &Version,
+	config: &RuntimeConfig,
+	submitter: Option<Arc<impl transactions::Submit>>,
+	submit_cache: &SubmitCache,
+	cell_fetcher: &impl cells::Fetch,
+	rate_limiter: &ClientRateLimiter,
+	state: Arc<Mutex<State>>,
+	db: &impl Database,
+) -> Result<WsResponse, Error> {
 	let request_id = request.request_id;
+	let idempotency_key = request.idempotency_key;
+	let authorized = config.is_authorized(request.auth.as_deref());
 	match request.payload {
-		Payload::Version => Ok(Response::new(request_id, version.clone()).into()),
+		Payload::Version => {
+			let state = state.lock().expect("State lock can be acquired");
+			let version = version.clone().with_network(&state);
+			Ok(Response::new(request_id, version).into())
+		},
 		Payload::Status => {
 			let state = state.lock().expect("State lock can be acquired");
 			let status = Status::new(config, &state);
 			Ok(Response::new(request_id, status).into())
 		},
-		Payload::Submit(transaction) => {
+		Payload::Stats => Ok(Response::new(request_id, clients.stats().await).into()),
+		Payload::Ping { nonce } => {
+			let timestamp = unix_timestamp_millis();
+			Ok(Response::new(request_id, Pong { nonce, timestamp }).into())
+		},
+		Payload::GetBlockRanges => {
+			let state = state.lock().expect("State lock can be acquired");
+			Ok(Response::new(request_id, BlockRanges::from(&*state)).into())
+		},
+		Payload::Submit(submit_request) => {
+			let Some(submitter) = submitter else {
+				return Err(Error::bad_request(request_id, "Submit is not configured."));
+			};
+			if !authorized {
+				return Err(Error::unauthorized(Some(request_id)));
+			}
+			let SubmitRequest {
+				transaction,
+				expected_app_id,
+				dry_run,
+			} = submit_request;
+			let transaction = transactions::prepare(transaction, expected_app_id, config)
+				.map_err(|message| Error::bad_request(request_id, &message))?;
+
+			if dry_run {
+				return transactions::dry_run(&transaction)
+					.map(|response| Response::new(request_id, response).into())
+					.map_err(|message| Error::bad_request(request_id, &message));
+			}
+
+			let quota = rate_limiter.submit_quota(request.auth.as_deref());
+			transactions::submit_idempotent(
+				submitter.as_ref(),
+				submit_cache,
+				idempotency_key,
+				&quota,
+				transaction,
+			)
+			.await
+			.map(|response| Response::new(request_id, response).into())
+		},
+		Payload::SubmitBatch(transactions) => {
 			let Some(submitter) = submitter else {
 				return Err(Error::bad_request(request_id, "Submit is not configured."));
 			};
-			if transaction.is_empty() {
-				return Err(Error::bad_request(request_id, "Transaction is empty."));
+			if !authorized {
+				return Err(Error::unauthorized(Some(request_id)));
+			}
+			if transactions.is_empty() {
+				return Err(Error::bad_request(request_id, "Batch is empty."));
+			}
+			if transactions.len() > config.max_batch_size {
+				let message =
+					transactions::batch_size_exceeded(transactions.len(), config.max_batch_size);
+				return Err(Error::bad_request(request_id, &message));
 			}
 
-			submitter
-				.submit(transaction)
+			let quota = rate_limiter.submit_quota(request.auth.as_deref());
+			let results =
+				transactions::submit_batch(submitter.as_ref(), config, &quota, transactions).await;
+			Ok(Response::new(request_id, SubmitBatchResponse(results)).into())
+		},
+		Payload::Subscribe(subscription) => {
+			let max_size = config.ws_max_subscriptions;
+			match clients
+				.update_subscription(subscription_id, subscription, max_size)
 				.await
-				.map(|response| Response::new(request_id, response).into())
+			{
+				Some(Ok(subscription)) => Ok(Response::new(request_id, subscription).into()),
+				Some(Err(error)) => Err(Error::bad_request(request_id, &error.to_string())),
+				None => Err(Error::not_found()),
+			}
+		},
+		Payload::Unsubscribe(subscription) => clients
+			.unsubscribe(subscription_id, &subscription)
+			.await
+			.map(|subscription| Response::new(request_id, subscription).into())
+			.ok_or_else(Error::not_found),
+		Payload::GetHeader {
+			block_number,
+			include_commitments,
+		} => {
+			let header = verified_header(config, &state, db, block_number)?;
+			HeaderMessage::try_from(header)
+				.map(|mut header| {
+					if include_commitments == Some(false) {
+						header.omit_commitments();
+					}
+					Response::new(request_id, header).into()
+				})
 				.map_err(Error::internal_server_error)
 		},
+		Payload::GetAppData {
+			block_number,
+			app_id,
+		} => {
+			// Only data of the configured application is reconstructed and stored
+			let app_id = match (config.app_id, app_id) {
+				(Some(config_app_id), None) => config_app_id,
+				(Some(config_app_id), Some(app_id)) if app_id == config_app_id => app_id,
+				_ => return Err(Error::not_found()),
+			};
+
+			let block_status = {
+				let state = state.lock().expect("State lock can be acquired");
+				block_status(&config.sync_start_block, &state, block_number)
+			};
+
+			if block_status != Some(BlockStatus::Finished) {
+				return Err(Error::not_found());
+			}
+
+			let extrinsics = db
+				.get::<Vec<Vec<u8>>>(Key::AppData(app_id, block_number))
+				.map_err(Error::internal_server_error)?
+				.unwrap_or_default();
+
+			let data = extrinsics
+				.iter()
+				.filter_map(|extrinsic| decode_app_data(extrinsic).transpose())
+				.map(|data| data.map(Base64))
+				.collect::<Result<Vec<_>>>()
+				.map_err(Error::internal_server_error)?;
+
+			let message = AppDataMessage {
+				block_number,
+				app_id,
+				data,
+			};
+			Ok(Response::new(request_id, message).into())
+		},
+		Payload::GetCells {
+			block_number,
+			cells,
+		} => {
+			if cells.is_empty() {
+				return Err(Error::bad_request(request_id, "No cells requested."));
+			}
+			if cells.len() > config.max_cells_per_request {
+				let message = format!(
+					"Requested {} cells, exceeding the limit of {}",
+					cells.len(),
+					config.max_cells_per_request
+				);
+				return Err(Error::bad_request(request_id, &message));
+			}
+
+			let (block_hash, dimensions) =
+				block_matrix(config, &state, db, block_number, request_id)?;
+
+			let positions = cells
+				.into_iter()
+				.map(|(row, col)| Position {
+					row: row.into(),
+					col,
+				})
+				.collect::<Vec<_>>();
+
+			if let Some(position) = positions
+				.iter()
+				.find(|position| !is_in_matrix(dimensions, position))
+			{
+				let message = format!(
+					"Cell ({}, {}) is outside of the {}x{} extended matrix",
+					position.row,
+					position.col,
+					dimensions.extended_rows(),
+					dimensions.cols()
+				);
+				return Err(Error::bad_request(request_id, &message));
+			}
+
+			let cells = cell_fetcher
+				.fetch(block_hash, &positions)
+				.await
+				.map_err(Error::internal_server_error)?;
+
+			let message = CellsMessage {
+				block_number,
+				cells: cells.into_iter().map(Into::into).collect(),
+			};
+			Ok(Response::new(request_id, message).into())
+		},
+		Payload::GetRow { block_number, row } => {
+			let (block_hash, dimensions) =
+				block_matrix(config, &state, db, block_number, request_id)?;
+
+			let row = u32::from(row);
+			if row >= dimensions.extended_rows() {
+				let message = format!(
+					"Row {row} is outside of the {}x{} extended matrix",
+					dimensions.extended_rows(),
+					dimensions.cols()
+				);
+				return Err(Error::bad_request(request_id, &message));
+			}
+
+			let positions = dimensions.extended_rows_positions(&[row]);
+			let cells = cell_fetcher
+				.fetch(block_hash, &positions)
+				.await
+				.map_err(Error::internal_server_error)?;
+
+			let message = RowMessage {
+				block_number,
+				row,
+				cells: cells.into_iter().map(Into::into).collect(),
+			};
+			Ok(Response::new(request_id, message).into())
+		},
+		Payload::VerifyGenesis { expected } => {
+			let genesis_hash = state
+				.lock()
+				.expect("State lock can be acquired")
+				.connected_node
+				.genesis_hash;
+
+			if genesis_hash != expected {
+				let message = format!(
+					"Genesis hash mismatch, expected {expected:?}, connected node has {genesis_hash:?}"
+				);
+				return Err(Error::bad_request(request_id, &message));
+			}
+
+			Ok(Response::new(request_id, GenesisHash { genesis_hash }).into())
+		},
+		Payload::GetDataProof {
+			block_number,
+			index,
+		} => {
+			let header = verified_header(config, &state, db, block_number)?;
+			let (_, _, data_root, _) = extract_kate(&header.extension);
+			// Blocks without data transactions have no data root
+			if data_root.is_zero() {
+				return Err(Error::not_found());
+			}
+
+			let block_hash = Encode::using_encoded(&header, blake2_256).into();
+			let DataProof {
+				root,
+				proof,
+				number_of_leaves,
+				leaf_index,
+				leaf,
+			} = cell_fetcher
+				.fetch_data_proof(block_hash, index)
+				.await
+				.map_err(Error::internal_server_error)?;
+
+			// Proof is verified against the data root of the verified header
+			if root != data_root
+				|| leaf_index != index
+				|| !verify_data_proof(data_root, &proof, number_of_leaves, leaf_index, leaf)
+			{
+				let message = format!(
+					"Invalid data proof of the transaction {index} in block {block_number}"
+				);
+				return Err(Error::internal_server_error(eyre!(message)));
+			}
+
+			let message = DataProofMessage {
+				block_number,
+				data_root,
+				proof,
+				number_of_leaves,
+				leaf_index,
+				leaf,
+			};
+			Ok(Response::new(request_id, message).into())
+		},
+		Payload::GetExtrinsicProof {
+			block_number,
+			index,
+		} => {
+			let header = verified_header(config, &state, db, block_number)?;
+			let block_hash = Encode::using_encoded(&header, blake2_256).into();
+			let extrinsics = cell_fetcher
+				.fetch_extrinsics(block_hash)
+				.await
+				.map_err(Error::internal_server_error)?;
+
+			let Some(extrinsic) = extrinsics.get(index as usize).cloned() else {
+				let message = format!(
+					"Extrinsic index {index} is out of bounds of {} extrinsics in block {block_number}",
+					extrinsics.len()
+				);
+				return Err(Error::bad_request(request_id, &message));
+			};
+
+			let (extrinsics_root, proof) =
+				extrinsic_proof(&extrinsics, index).map_err(Error::internal_server_error)?;
+
+			// Extrinsics fetched from the node are verified against the verified header
+			if extrinsics_root != header.extrinsics_root {
+				let message = format!("Invalid extrinsics of block {block_number}");
+				return Err(Error::internal_server_error(eyre!(message)));
+			}
+
+			let message = ExtrinsicProofMessage {
+				block_number,
+				extrinsics_root,
+				index,
+				extrinsic: Base64(extrinsic),
+				proof: proof.into_iter().map(Base64).collect(),
+			};
+			Ok(Response::new(request_id, message).into())
+		},
+		Payload::VerifyDataProof {
+			data_root,
+			proof,
+			number_of_leaves,
+			leaf_index,
+			leaf,
+		} => {
+			if leaf_index >= number_of_leaves {
+				let message = format!(
+					"Leaf index {leaf_index} is out of bounds of {number_of_leaves} leaves"
+				);
+				return Err(Error::bad_request(request_id, &message));
+			}
+
+			let expected_len = data_proof_len(number_of_leaves, leaf_index);
+			if proof.len() != expected_len {
+				let message = format!(
+					"Proof has {} hashes, expected {expected_len} for leaf index {leaf_index} of {number_of_leaves} leaves",
+					proof.len()
+				);
+				return Err(Error::bad_request(request_id, &message));
+			}
+
+			let verified = verify_data_proof(data_root, &proof, number_of_leaves, leaf_index, leaf);
+			Ok(Response::new(request_id, DataProofVerified { verified }).into())
+		},
+		Payload::GetConfidence {
+			block_number,
+			confidence_target,
+		} => {
+			if let Some(target) = confidence_target {
+				if !(target > 0.0 && target < 100.0) {
+					let message = "Confidence target must be greater than 0 and less than 100";
+					return Err(Error::bad_request(request_id, message));
+				}
+			}
+
+			let header = verified_header(config, &state, db, block_number)?;
+
+			let verified_count = db
+				.get(Key::VerifiedCellCount(block_number))
+				.map_err(Error::internal_server_error)?;
+
+			if let (None, Some(cell_count)) = (confidence_target, verified_count) {
+				let message = SampledConfidence {
+					block_number,
+					confidence: calculate_confidence(cell_count),
+					cell_count,
+					cached: true,
+				};
+				return Ok(Response::new(request_id, message).into());
+			}
+
+			let (block_hash, dimensions, commitments) =
+				header_matrix(&header, config.max_block_rows, request_id)?;
+
+			// Cells verified by the light client are not sampled again
+			let verified_positions: Vec<(u32, u16)> = db
+				.get(Key::VerifiedCellPositions(block_number))
+				.map_err(Error::internal_server_error)?
+				.unwrap_or_default();
+			let verified_positions = verified_positions
+				.into_iter()
+				.map(|(row, col)| Position { row, col })
+				.collect::<HashSet<_>>();
+
+			// Any sampled cell gives 50% confidence,
+			// and required cell count is capped to the 99.99% confidence
+			let target = confidence_target.unwrap_or(config.confidence).max(50.0);
+			let required_count = rpc::cell_count_for_confidence(target);
+
+			let cell_count = sample_cells(
+				cell_fetcher,
+				block_number,
+				block_hash,
+				dimensions,
+				&commitments,
+				verified_count.unwrap_or(0),
+				verified_positions,
+				required_count,
+			)
+			.await
+			.map_err(Error::internal_server_error)?;
+
+			let message = SampledConfidence {
+				block_number,
+				confidence: calculate_confidence(cell_count),
+				cell_count,
+				cached: false,
+			};
+			Ok(Response::new(request_id, message).into())
+		},
+		Payload::Reconstruct { block_number } => {
+			if !config.is_fat_client() {
+				let message = "Reconstruct is available only in partition mode.";
+				return Err(Error::bad_request(request_id, message));
+			}
+
+			let header = verified_header(config, &state, db, block_number)?;
+			let (block_hash, dimensions, commitments) =
+				header_matrix(&header, config.max_block_rows, request_id)?;
+
+			let partition_positions =
+				extended_partitions_positions(dimensions, &config.block_matrix_partition);
+			let needed_positions = reconstruction_positions(dimensions, &partition_positions);
+			let positions = [partition_positions.as_slice(), &needed_positions].concat();
+
+			// Cells are fetched in batches, the same way as in the fat client,
+			// and only cells verified against the commitments are used for reconstruction
+			let max_cells = config.max_cells_per_rpc.unwrap_or(30).max(1);
+			let mut cells = Vec::with_capacity(positions.len());
+			for positions in positions.chunks(max_cells) {
+				let verified = cell_fetcher
+					.fetch_verified(
+						block_number,
+						block_hash,
+						dimensions,
+						&commitments,
+						positions,
+					)
+					.await
+					.map_err(Error::internal_server_error)?;
+				cells.extend(verified);
+			}
+
+			let missing = missing_cells(dimensions, &cells);
+			if missing > 0 {
+				return Err(Error::cells_unavailable(request_id, block_number, missing));
+			}
+
+			let columns = reconstruct_columns(dimensions, &cells)
+				.wrap_err("Failed to reconstruct block data")
+				.map_err(Error::internal_server_error)?;
+
+			let message = ReconstructMessage {
+				block_number,
+				partition_cells: partition_positions.len() as u32,
+				cells_needed: needed_positions.len() as u32,
+				columns: columns.len() as u32,
+			};
+			Ok(Response::new(request_id, message).into())
+		},
+		Payload::GetCommitments {
+			block_number,
+			encoding,
+		} => {
+			let header = verified_header(config, &state, db, block_number)?;
+			let message = CommitmentsMessage::new(header, encoding.unwrap_or_default())
+				.map_err(Error::internal_server_error)?;
+			Ok(Response::new(request_id, message).into())
+		},
+		Payload::GetRawHeader { block_number } => {
+			let header = verified_header(config, &state, db, block_number)?;
+			let encoded = header.encode();
+			let message = RawHeaderMessage {
+				block_number,
+				hash: blake2_256(&encoded).into(),
+				header: Base64(encoded),
+			};
+			Ok(Response::new(request_id, message).into())
+		},
+		Payload::ListSubscriptions => {
+			if !config.is_admin(request.auth.as_deref()) {
+				return Err(Error::unauthorized(Some(request_id)));
+			}
+			let subscriptions = clients.subscriptions().await;
+			Ok(Response::new(request_id, subscriptions).into())
+		},
+		Payload::Disconnect {
+			subscription_id: disconnected_id,
+		} => {
+			if !config.is_admin(request.auth.as_deref()) {
+				return Err(Error::unauthorized(Some(request_id)));
+			}
+			let disconnected = clients
+				.disconnect(&disconnected_id, "Disconnected by admin")
+				.await;
+			if disconnected {
+				info!(
+					subscription_id,
+					disconnected_id, "Client disconnected by admin"
+				);
+			}
+			let message = Disconnected {
+				subscription_id: disconnected_id,
+				disconnected,
+			};
+			Ok(Response::new(request_id, message).into())
+		},
+	}
+}
+
+/// Returns positions of the cells needed in addition to the partition cells to reconstruct
+/// the block, since each column is reconstructed from at least as many cells as there are rows.
+fn reconstruction_positions(
+	dimensions: Dimensions,
+	partition_positions: &[Position],
+) -> Vec<Position> {
+	let rows = usize::from(u16::from(dimensions.rows()));
+	let present = column_counts(partition_positions.iter());
+	let partition_positions = partition_positions.iter().collect::<HashSet<_>>();
+	(0..u16::from(dimensions.cols()))
+		.flat_map(|col| {
+			let present = present.get(&col).copied().unwrap_or(0);
+			(0..dimensions.extended_rows())
+				.map(|row| Position { row, col })
+				.filter(|position| !partition_positions.contains(position))
+				.take(rows.saturating_sub(present))
+				.collect::<Vec<_>>()
+		})
+		.collect()
+}
+
+/// Returns number of cells missing to reconstruct all columns of the block.
+fn missing_cells(dimensions: Dimensions, cells: &[Cell]) -> usize {
+	let rows = usize::from(u16::from(dimensions.rows()));
+	let present = column_counts(cells.iter().map(|cell| &cell.position));
+	(0..u16::from(dimensions.cols()))
+		.map(|col| rows.saturating_sub(present.get(&col).copied().unwrap_or(0)))
+		.sum()
+}
+
+/// Returns number of distinct positions per column.
+fn column_counts<'a>(positions: impl Iterator<Item = &'a Position>) -> HashMap<u16, usize> {
+	let mut counts = HashMap::new();
+	for position in positions.collect::<HashSet<_>>() {
+		*counts.entry(position.col).or_default() += 1;
+	}
+	counts
+}
+
+/// Samples random cells without replacement, skipping already verified positions,
+/// until the required number of cells is verified, the sampling limit is reached,
+/// or there are no cells left to sample. Returns number of the verified cells.
+#[allow(clippy::too_many_arguments)]
+async fn sample_cells(
+	cell_fetcher: &impl cells::Fetch,
+	block_number: u32,
+	block_hash: H256,
+	dimensions: Dimensions,
+	commitments: &[[u8; 48]],
+	verified_count: u32,
+	verified_positions: HashSet<Position>,
+	required_count: u32,
+) -> Result<u32> {
+	let mut verified_count = verified_count;
+	let mut sampled = verified_positions;
+	let mut sampled_count = 0;
+	while verified_count < required_count && sampled_count < MAX_SAMPLED_CELLS {
+		let count = (required_count - verified_count).min(MAX_SAMPLED_CELLS - sampled_count);
+		let positions = random_positions(dimensions, count, &sampled);
+		if positions.is_empty() {
+			break;
+		}
+		sampled.extend(positions.iter().copied());
+		let verified = cell_fetcher
+			.fetch_verified(
+				block_number,
+				block_hash,
+				dimensions,
+				commitments,
+				&positions,
+			)
+			.await?;
+
+		sampled_count += positions.len() as u32;
+		verified_count += verified.len() as u32;
+	}
+	Ok(verified_count)
+}
+
+/// Generates up to `count` distinct random positions in the extended matrix,
+/// which are not in the excluded positions.
+fn random_positions(
+	dimensions: Dimensions,
+	count: u32,
+	excluded: &HashSet<Position>,
+) -> Vec<Position> {
+	let excluded_count = excluded
+		.iter()
+		.filter(|position| is_in_matrix(dimensions, position))
+		.count();
+	let available = dimensions.extended_size() as usize - excluded_count;
+	let count = (count as usize).min(available);
+
+	let mut rng = thread_rng();
+	let mut positions = HashSet::new();
+	while positions.len() < count {
+		let row = rng.gen_range(0..dimensions.extended_rows());
+		let col = rng.gen_range(0..dimensions.cols().get());
+		let position = Position { row, col };
+		if !excluded.contains(&position) {
+			positions.insert(position);
+		}
+	}
+	positions.into_iter().collect()
+}
+
+fn is_in_matrix(dimensions: Dimensions, position: &Position) -> bool {
+	position.row < dimensions.extended_rows() && position.col < dimensions.cols().get()
+}
+
+/// Returns verified block header, or not found error if header is not verified yet.
+fn verified_header(
+	config: &RuntimeConfig,
+	state: &Arc<Mutex<State>>,
+	db: &impl Database,
+	block_number: u32,
+) -> Result<primitives::Header, Error> {
+	let block_status = {
+		let state = state.lock().expect("State lock can be acquired");
+		block_status(&config.sync_start_block, &state, block_number)
+	};
+
+	if matches!(
+		block_status,
+		None | Some(BlockStatus::Unavailable | BlockStatus::Pending | BlockStatus::VerifyingHeader)
+	) {
+		return Err(Error::not_found());
+	}
+
+	db.get::<primitives::Header>(Key::BlockHeader(block_number))
+		.and_then(|header| header.ok_or_else(|| eyre!("Header not found")))
+		.map_err(Error::internal_server_error)
+}
+
+/// Returns hash, matrix dimensions and commitments of the block with the given header.
+/// Blocks with more than `max_rows` rows are rejected.
+fn header_matrix(
+	header: &primitives::Header,
+	max_rows: u16,
+	request_id: Uuid,
+) -> Result<(H256, Dimensions, Vec<[u8; 48]>), Error> {
+	let block_hash = Encode::using_encoded(header, blake2_256).into();
+	let (rows, cols, _, commitment) = extract_kate(&header.extension);
+	let Some(dimensions) = Dimensions::new(rows, cols) else {
+		let message = format!("Block has invalid dimensions {rows}x{cols}");
+		return Err(Error::bad_request(request_id, &message));
+	};
+
+	let commitments = header_commitments(rows, cols, &commitment, max_rows)
+		.wrap_err("Failed to decode block commitments")
+		.map_err(Error::internal_server_error)?;
+	if commitments.len() != dimensions.extended_rows() as usize {
+		let message = format!(
+			"Block has {} commitments, expected {}",
+			commitments.len(),
+			dimensions.extended_rows()
+		);
+		return Err(Error::internal_server_error(eyre!(message)));
+	}
+
+	Ok((block_hash, dimensions, commitments))
+}
+
+/// Returns hash and matrix dimensions of the block with verified header.
+fn block_matrix(
+	config: &RuntimeConfig,
+	state: &Arc<Mutex<State>>,
+	db: &impl Database,
+	block_number: u32,
+	request_id: Uuid,
+) -> Result<(H256, Dimensions), Error> {
+	let header = verified_header(config, state, db, block_number)?;
+
+	let block_hash = Encode::using_encoded(&header, blake2_256).into();
+	let (rows, cols, _, _) = extract_kate(&header.extension);
+	let Some(dimensions) = Dimensions::new(rows, cols) else {
+		let message = format!("Block has invalid dimensions {rows}x{cols}");
+		return Err(Error::bad_request(request_id, &message));
+	};
+
+	Ok((block_hash, dimensions))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{client_messages, reconstruction_positions};
+	use crate::{
+		api::v2::types::{Subscription, WsClients},
+		types::WsBufferPolicy,
+	};
+	use futures::StreamExt;
+	use kate_recovery::matrix::{Dimensions, Position};
+	use test_case::test_case;
+	use tokio::sync::{broadcast, mpsc};
+	use warp::ws::Message;
+
+	// Responses are never dropped, regardless of the buffer policy
+	#[test_case(WsBufferPolicy::DropOldest, &["a", "b", "c", "3", "4"] ; "Oldest messages are dropped")]
+	#[test_case(WsBufferPolicy::Disconnect, &["a", "b", "c"] ; "Client is disconnected")]
+	#[tokio::test]
+	async fn client_messages_buffer_full(policy: WsBufferPolicy, expected: &[&str]) {
+		let clients = WsClients::default();
+		clients.subscribe("1", Subscription::default()).await;
+		let (sender, receiver) = broadcast::channel(2);
+		for message in ["1", "2", "3", "4"] {
+			sender.send(Message::text(message)).unwrap();
+		}
+		drop(sender);
+		let (response_sender, responses) = mpsc::channel(3);
+		for response in ["a", "b", "c"] {
+			response_sender.try_send(Message::text(response)).unwrap();
+		}
+		drop(response_sender);
+
+		let messages = client_messages(
+			"1".to_string(),
+			receiver,
+			responses,
+			clients.clone(),
+			policy,
+		)
+		.map(|message| message.unwrap().to_str().unwrap().to_string())
+		.collect::<Vec<_>>()
+		.await;
+
+		assert_eq!(messages, expected);
+		let clients = clients.clients.read().await;
+		assert_eq!(clients.get("1").unwrap().dropped_messages, 2);
+	}
+
+	#[test]
+	fn reconstruction_positions_complete_partition_columns() {
+		let dimensions = Dimensions::new(2, 3).unwrap();
+		let position = |row, col| Position { row, col };
+		// First column is complete, second one has a single cell, third one is empty
+		let partition_positions = [position(0, 0), position(3, 0), position(1, 1)];
+
+		let positions = reconstruction_positions(dimensions, &partition_positions);
+		assert_eq!(
+			positions,
+			vec![position(0, 1), position(0, 2), position(1, 2)]
+		);
 	}
 }
@@ -413,6 +413,7 @@ async fn process_block(
 /// * `app_id` - Application ID
 /// * `block_receive` - Channel used to receive header of verified block
 /// * `pp` - Public parameters (i.e. SRS) needed for proof verification
+/// * `data_verified_sender` - Channel used to send verified data, as (app_id, block_number, data)
 #[allow(clippy::too_many_arguments)]
 pub async fn run(
 	cfg: AppClientConfig,
@@ -424,7 +425,7 @@ pub async fn run(
 	pp: Arc<PublicParameters>,
 	state: Arc<Mutex<State>>,
 	sync_range: Range<u32>,
-	data_verified_sender: broadcast::Sender<(u32, AppData)>,
+	data_verified_sender: broadcast::Sender<(u32, u32, AppData)>,
 	shutdown: Controller<String>,
 ) {
 	info!("Starting for app {app_id}...");
@@ -482,7 +483,7 @@ pub async fn run(
 				},
 			};
 		set_data_verified_state(state.clone(), &sync_range, block_number);
-		if let Err(error) = data_verified_sender.send((block_number, data)) {
+		if let Err(error) = data_verified_sender.send((app_id.0, block_number, data)) {
 			error!("Cannot send data verified message: {error}");
 			let _ =
 				shutdown.trigger_shutdown(format!("Cannot send data verified message: {error:#}"));
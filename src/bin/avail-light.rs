@@ -11,7 +11,7 @@ use avail_light::{
 	sync_client::SyncClient,
 	sync_finality::SyncFinality,
 	telemetry::{self, otlp::MetricAttributes},
-	types::{CliOpts, IdentityConfig, LibP2PConfig, RuntimeConfig, State},
+	types::{CliOpts, IdentityConfig, LibP2PConfig, RuntimeConfig, State, WsBufferPolicy},
 };
 use clap::Parser;
 use color_eyre::{
@@ -25,6 +25,7 @@ use std::{
 	net::Ipv4Addr,
 	path::Path,
 	sync::{Arc, Mutex},
+	time::Duration,
 };
 use tokio::sync::{broadcast, mpsc, RwLock};
 use tracing::{error, info, metadata::ParseLevelError, trace, warn, Level, Subscriber};
@@ -129,20 +130,15 @@ async fn run(shutdown: Controller<String>) -> Result<()> {
 		origin: cfg.origin.clone(),
 		avail_address: identity_cfg.avail_address.clone(),
 		operating_mode: cfg.operation_mode.to_string(),
-		partition_size: cfg
-			.block_matrix_partition
-			.map(|_| {
-				format!(
-					"{}/{}",
-					cfg.block_matrix_partition
-						.expect("partition doesn't exist")
-						.number,
-					cfg.block_matrix_partition
-						.expect("partition doesn't exist")
-						.fraction
-				)
-			})
-			.unwrap_or("n/a".to_string()),
+		partition_size: if cfg.is_fat_client() {
+			cfg.block_matrix_partition
+				.iter()
+				.map(|partition| format!("{}/{}", partition.number, partition.fraction))
+				.collect::<Vec<_>>()
+				.join(",")
+		} else {
+			"n/a".to_string()
+		},
 	};
 
 	let ot_metrics = Arc::new(
@@ -217,13 +213,16 @@ async fn run(shutdown: Controller<String>) -> Result<()> {
 		cfg.retry_config.clone(),
 	)
 	.await?;
+	let rpc_subscriptions = rpc_subscriptions.with_header_hash_verification(cfg.verify_header_hash);
 
 	// Subscribing to RPC events before first event is published
 	let publish_rpc_event_receiver = rpc_events.subscribe();
+	let included_rpc_event_receiver = rpc_events.subscribe();
 	let first_header_rpc_event_receiver = rpc_events.subscribe();
 	let client_rpc_event_receiver = rpc_events.subscribe();
 	#[cfg(feature = "crawl")]
 	let crawler_rpc_event_receiver = rpc_events.subscribe();
+	let publish_block_event_receiver = rpc_subscriptions.block_events();
 
 	// spawn the RPC Network task for Event Loop to run in the background
 	// and shut it down, without delays
@@ -267,10 +266,23 @@ async fn run(shutdown: Controller<String>) -> Result<()> {
 		},
 	};
 
-	state.lock().unwrap().latest = block_header.number;
+	state.lock().unwrap().set_latest(block_header.number);
 	let sync_range = cfg.sync_range(block_header.number);
 
-	let ws_clients = api::v2::types::WsClients::default();
+	let ws_clients = api::v2::types::WsClients::new(cfg.ws_replay_buffer_depth)
+		.with_publish_workers(cfg.ws_publish_workers)
+		.with_error_causes(cfg.expose_error_causes)
+		.with_field_naming(cfg.api_field_naming);
+	let api_metrics = if cfg.api_metrics_enable {
+		let api_metrics = api::v2::metrics::ApiMetrics::new(ot_metrics.meter());
+		api::v2::metrics::ApiMetrics::observe_ws_clients(ot_metrics.meter(), ws_clients.clone())
+			.wrap_err("Unable to observe WebSocket clients")?;
+		api_metrics
+	} else {
+		api::v2::metrics::ApiMetrics::disabled()
+	};
+
+	let (block_tx, block_rx) = broadcast::channel::<avail_light::types::BlockVerified>(1 << 7);
 
 	// Spawn tokio task which runs one http server for handling RPC
 	let server = api::server::Server {
@@ -281,15 +293,17 @@ async fn run(shutdown: Controller<String>) -> Result<()> {
 		version: format!("v{}", clap::crate_version!()),
 		network_version: EXPECTED_SYSTEM_VERSION[0].to_string(),
 		node_client: rpc_client.clone(),
+		public_parameters: pp.clone(),
+		block_verified_sender: block_tx.clone(),
+		rpc_event_sender: rpc_events.clone(),
 		ws_clients: ws_clients.clone(),
+		api_metrics: api_metrics.clone(),
 		shutdown: shutdown.clone(),
 	};
-	tokio::task::spawn(shutdown.with_cancel(server.bind()));
-
-	let (block_tx, block_rx) = broadcast::channel::<avail_light::types::BlockVerified>(1 << 7);
+	tokio::task::spawn(shutdown.with_cancel(server.bind()?));
 
 	let data_rx = cfg.app_id.map(AppId).map(|app_id| {
-		let (data_tx, data_rx) = broadcast::channel::<(u32, AppData)>(1 << 7);
+		let (data_tx, data_rx) = broadcast::channel::<(u32, u32, AppData)>(1 << 7);
 		tokio::task::spawn(shutdown.with_cancel(avail_light::app_client::run(
 			(&cfg).into(),
 			db.clone(),
@@ -306,16 +320,66 @@ async fn run(shutdown: Controller<String>) -> Result<()> {
 		data_rx
 	});
 
+	tokio::task::spawn(shutdown.with_cancel(api::v2::heartbeat(
+		ws_clients.clone(),
+		Duration::from_secs(cfg.ws_heartbeat_interval),
+		cfg.ws_heartbeat_max_missed,
+	)));
+
+	if cfg.ws_buffer_policy == WsBufferPolicy::Evict {
+		tokio::task::spawn(shutdown.with_cancel(api::v2::evict_lagging(
+			ws_clients.clone(),
+			Duration::from_secs(1),
+			cfg.ws_buffer_size.max(1),
+			Duration::from_secs(cfg.ws_buffer_grace_period),
+			api_metrics.clone(),
+		)));
+	}
+
+	// Shutdown is delayed until clients receive close frames
+	tokio::task::spawn(shutdown.with_delay(api::v2::shutdown(
+		ws_clients.clone(),
+		shutdown.triggered_shutdown(),
+		Duration::from_secs(cfg.ws_shutdown_timeout),
+	))?);
+
 	tokio::task::spawn(shutdown.with_cancel(api::v2::publish(
 		api::v2::types::Topic::HeaderVerified,
 		publish_rpc_event_receiver,
 		ws_clients.clone(),
+		api_metrics.clone(),
+		db.clone(),
+	)));
+
+	tokio::task::spawn(shutdown.with_cancel(api::v2::publish_submitted_data(
+		included_rpc_event_receiver,
+		ws_clients.clone(),
+		api_metrics.clone(),
+		rpc_client.clone(),
+		pp.clone(),
+	)));
+
+	tokio::task::spawn(shutdown.with_cancel(api::v2::publish(
+		api::v2::types::Topic::BlockFinalized,
+		publish_block_event_receiver,
+		ws_clients.clone(),
+		api_metrics.clone(),
+		db.clone(),
+	)));
+
+	tokio::task::spawn(shutdown.with_cancel(api::v2::publish_status_changes(
+		cfg.clone(),
+		state.clone(),
+		ws_clients.clone(),
+		api_metrics.clone(),
 	)));
 
 	tokio::task::spawn(shutdown.with_cancel(api::v2::publish(
 		api::v2::types::Topic::ConfidenceAchieved,
 		block_tx.subscribe(),
 		ws_clients.clone(),
+		api_metrics.clone(),
+		db.clone(),
 	)));
 
 	if let Some(data_rx) = data_rx {
@@ -323,6 +387,8 @@ async fn run(shutdown: Controller<String>) -> Result<()> {
 			api::v2::types::Topic::DataVerified,
 			data_rx,
 			ws_clients,
+			api_metrics,
+			db.clone(),
 		)));
 	}
 
@@ -336,6 +402,7 @@ async fn run(shutdown: Controller<String>) -> Result<()> {
 			ot_metrics.clone(),
 			cfg.crawl.crawl_block_mode,
 			partition.unwrap_or(avail_light::crawl_client::ENTIRE_BLOCK),
+			cfg.max_block_rows,
 		)));
 	}
 
@@ -350,6 +417,13 @@ async fn run(shutdown: Controller<String>) -> Result<()> {
 
 	if cfg.sync_start_block.is_some() {
 		state.lock().unwrap().synced.replace(false);
+		tokio::task::spawn(shutdown.with_cancel(api::v2::publish_sync_progress(
+			cfg.clone(),
+			sync_range.clone(),
+			state.clone(),
+			ws_clients.clone(),
+			api_metrics.clone(),
+		)));
 		tokio::task::spawn(shutdown.with_cancel(avail_light::sync_client::run(
 			sync_client,
 			sync_network_client,
@@ -396,7 +470,7 @@ async fn run(shutdown: Controller<String>) -> Result<()> {
 		rpc_event_receiver: client_rpc_event_receiver,
 	};
 
-	if let Some(partition) = cfg.block_matrix_partition {
+	if cfg.is_fat_client() {
 		let fat_client = avail_light::fat_client::new(p2p_client.clone(), rpc_client.clone());
 
 		tokio::task::spawn(shutdown.with_cancel(avail_light::fat_client::run(
@@ -405,7 +479,7 @@ async fn run(shutdown: Controller<String>) -> Result<()> {
 			(&cfg).into(),
 			ot_metrics.clone(),
 			channels,
-			partition,
+			cfg.block_matrix_partition.clone(),
 			shutdown.clone(),
 		)));
 	} else {
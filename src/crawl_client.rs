@@ -60,6 +60,7 @@ pub async fn run(
 	metrics: Arc<impl Metrics>,
 	mode: CrawlMode,
 	partition: Partition,
+	max_block_rows: u16,
 ) {
 	info!("Starting crawl client...");
 
@@ -70,7 +71,7 @@ pub async fn run(
 		received_at,
 	}) = message_rx.recv().await
 	{
-		let block = match types::BlockVerified::try_from((header, None)) {
+		let block = match types::BlockVerified::try_from((header, None, max_block_rows)) {
 			Ok(block) => block,
 			Err(error) => {
 				error!("Header is not valid: {error}");
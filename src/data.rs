@@ -43,12 +43,17 @@ pub const STATE_CF: &str = "avail_light_state_cf";
 /// Sync finality checkpoint key name
 const FINALITY_SYNC_CHECKPOINT_KEY: &str = "finality_sync_checkpoint";
 
+/// Applications seen in the verified headers key name
+const SEEN_APP_IDS_KEY: &str = "seen_app_ids";
+
 #[derive(Clone)]
 pub enum Key {
 	AppData(u32, u32),
 	BlockHeader(u32),
 	VerifiedCellCount(u32),
+	VerifiedCellPositions(u32),
 	FinalitySyncCheckpoint,
+	SeenAppIds,
 }
 
 #[derive(Serialize, Deserialize, Debug, Decode, Encode)]
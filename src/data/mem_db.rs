@@ -1,5 +1,6 @@
 use crate::data::{
-	Database, Key, APP_DATA_CF, BLOCK_HEADER_CF, CONFIDENCE_FACTOR_CF, FINALITY_SYNC_CHECKPOINT_KEY,
+	Database, Key, APP_DATA_CF, BLOCK_HEADER_CF, CONFIDENCE_FACTOR_CF,
+	FINALITY_SYNC_CHECKPOINT_KEY, SEEN_APP_IDS_KEY,
 };
 use color_eyre::eyre::{eyre, Result};
 use serde::{Deserialize, Serialize};
@@ -65,7 +66,11 @@ impl From<Key> for HashMapKey {
 			Key::VerifiedCellCount(block_number) => {
 				HashMapKey(format!("{CONFIDENCE_FACTOR_CF}:{block_number}"))
 			},
+			Key::VerifiedCellPositions(block_number) => {
+				HashMapKey(format!("{CONFIDENCE_FACTOR_CF}:positions:{block_number}"))
+			},
 			Key::FinalitySyncCheckpoint => HashMapKey(FINALITY_SYNC_CHECKPOINT_KEY.to_string()),
+			Key::SeenAppIds => HashMapKey(SEEN_APP_IDS_KEY.to_string()),
 		}
 	}
 }
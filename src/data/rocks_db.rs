@@ -5,7 +5,7 @@ use rocksdb::{ColumnFamilyDescriptor, Options};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
-use super::FINALITY_SYNC_CHECKPOINT_KEY;
+use super::{FINALITY_SYNC_CHECKPOINT_KEY, SEEN_APP_IDS_KEY};
 
 #[derive(Clone)]
 pub struct RocksDB {
@@ -46,10 +46,15 @@ impl From<Key> for (Option<&'static str>, Vec<u8>) {
 				Some(CONFIDENCE_FACTOR_CF),
 				block_number.to_be_bytes().to_vec(),
 			),
+			Key::VerifiedCellPositions(block_number) => (
+				Some(CONFIDENCE_FACTOR_CF),
+				format!("positions:{block_number}").into_bytes(),
+			),
 			Key::FinalitySyncCheckpoint => (
 				Some(STATE_CF),
 				FINALITY_SYNC_CHECKPOINT_KEY.as_bytes().to_vec(),
 			),
+			Key::SeenAppIds => (Some(STATE_CF), SEEN_APP_IDS_KEY.as_bytes().to_vec()),
 		}
 	}
 }
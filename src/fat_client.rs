@@ -33,7 +33,7 @@ use crate::{
 	shutdown::Controller,
 	telemetry::{MetricCounter, MetricValue, Metrics},
 	types::{BlockVerified, ClientChannels, FatClientConfig},
-	utils::extract_kate,
+	utils::{extended_partitions_positions, extract_kate},
 };
 
 #[async_trait]
@@ -79,7 +79,7 @@ pub async fn process_block(
 	cfg: &FatClientConfig,
 	header: &Header,
 	received_at: Instant,
-	partition: Partition,
+	partitions: &[Partition],
 ) -> Result<()> {
 	metrics.count(MetricCounter::SessionBlock).await;
 	metrics
@@ -100,6 +100,15 @@ pub async fn process_block(
 		return Ok(());
 	};
 
+	if rows > cfg.max_block_rows {
+		let max_rows = cfg.max_block_rows;
+		error!(
+			block_number,
+			"Skipping block with {rows} rows, exceeding the limit of {max_rows}"
+		);
+		return Ok(());
+	}
+
 	if dimensions.cols().get() <= 2 {
 		error!(block_number, "More than 2 columns are required");
 		return Ok(());
@@ -117,14 +126,16 @@ pub async fn process_block(
 		.wrap_err("Fat Client failed to store Block Header")?;
 
 	// Fat client partition upload logic
-	let positions: Vec<Position> = dimensions
-		.iter_extended_partition_positions(&partition)
-		.collect();
-	let Partition { number, fraction } = partition;
+	let positions: Vec<Position> = extended_partitions_positions(dimensions, partitions);
+	let partitions = partitions
+		.iter()
+		.map(|Partition { number, fraction }| format!("{number}/{fraction}"))
+		.collect::<Vec<_>>()
+		.join(", ");
 	info!(
 		block_number,
 		"partition_cells_requested" = positions.len(),
-		"Fetching partition ({number}/{fraction}) from RPC",
+		"Fetching partitions ({partitions}) from RPC",
 	);
 
 	let begin = Instant::now();
@@ -193,7 +204,7 @@ pub async fn process_block(
 /// * `cfg` - Fat client configuration
 /// * `metrics` -  Metrics registry
 /// * `channels` - Communication channels
-/// * `partition` - Assigned fat client partition
+/// * `partitions` - Assigned fat client partitions
 /// * `shutdown` - Shutdown controller
 pub async fn run(
 	client: impl Client,
@@ -201,7 +212,7 @@ pub async fn run(
 	cfg: FatClientConfig,
 	metrics: Arc<impl Metrics>,
 	mut channels: ClientChannels,
-	partition: Partition,
+	partitions: Vec<Partition>,
 	shutdown: Controller<String>,
 ) {
 	info!("Starting fat client...");
@@ -238,7 +249,7 @@ pub async fn run(
 			&cfg,
 			&header,
 			received_at,
-			partition,
+			&partitions,
 		)
 		.await
 		{
@@ -247,7 +258,7 @@ pub async fn run(
 			return;
 		};
 
-		let Ok(client_msg) = BlockVerified::try_from((header, None)) else {
+		let Ok(client_msg) = BlockVerified::try_from((header, None, cfg.max_block_rows)) else {
 			error!("Cannot create message from header");
 			continue;
 		};
@@ -388,7 +399,7 @@ mod tests {
 			&FatClientConfig::from(&RuntimeConfig::default()),
 			&default_header(),
 			Instant::now(),
-			entire_block(),
+			&[entire_block()],
 		)
 		.await
 		.unwrap();
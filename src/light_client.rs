@@ -20,7 +20,7 @@
 use avail_subxt::{primitives::Header, utils::H256};
 use codec::Encode;
 use color_eyre::{eyre::WrapErr, Result};
-use kate_recovery::{commitments, matrix::Dimensions};
+use kate_recovery::matrix::Dimensions;
 use sp_core::blake2_256;
 use std::{
 	sync::{Arc, Mutex},
@@ -37,7 +37,7 @@ use crate::{
 	shutdown::Controller,
 	telemetry::{MetricCounter, MetricValue, Metrics},
 	types::{self, ClientChannels, LightClientConfig, OptionBlockRange, State},
-	utils::{calculate_confidence, extract_kate},
+	utils::{calculate_confidence, extract_kate, header_commitments},
 };
 
 pub async fn process_block(
@@ -76,7 +76,13 @@ pub async fn process_block(
 		return Ok(None);
 	}
 
-	let commitments = commitments::from_slice(&commitment)?;
+	let commitments = match header_commitments(rows, cols, &commitment, cfg.max_block_rows) {
+		Ok(commitments) => commitments,
+		Err(error) => {
+			error!(block_number, "Skipping block: {error:#}");
+			return Ok(None);
+		},
+	};
 	let cell_count = rpc::cell_count_for_confidence(cfg.confidence);
 	let positions = rpc::generate_random_cells(dimensions, cell_count);
 	info!(
@@ -133,9 +139,21 @@ pub async fn process_block(
 	db.put(Key::VerifiedCellCount(block_number), fetched.len() as u32)
 		.wrap_err("Light Client failed to store Confidence Factor")?;
 
-	state.lock().unwrap().confidence_achieved.set(block_number);
+	let verified_positions = fetched
+		.iter()
+		.map(|cell| (cell.position.row, cell.position.col))
+		.collect::<Vec<_>>();
+	db.put(Key::VerifiedCellPositions(block_number), verified_positions)
+		.wrap_err("Light Client failed to store verified cell positions")?;
 
 	let confidence = calculate_confidence(fetched.len() as u32);
+
+	{
+		let mut state = state.lock().unwrap();
+		state.confidence_achieved.set(block_number);
+		state.confidence = Some(confidence);
+	}
+
 	info!(
 		block_number,
 		"confidence" = confidence,
@@ -225,7 +243,9 @@ pub async fn run(
 			},
 		};
 
-		let Ok(client_msg) = types::BlockVerified::try_from((header, confidence)) else {
+		let Ok(client_msg) =
+			types::BlockVerified::try_from((header, confidence, cfg.max_block_rows))
+		else {
 			error!("Cannot create message from header");
 			continue;
 		};
@@ -30,7 +30,7 @@ use subscriptions::SubscriptionLoop;
 const CELL_SIZE: usize = 32;
 const PROOF_SIZE: usize = 48;
 pub const CELL_WITH_PROOF_SIZE: usize = CELL_SIZE + PROOF_SIZE;
-pub use subscriptions::Event;
+pub use subscriptions::{BlockEvent, Event};
 
 pub use client::Client;
 
@@ -69,6 +69,20 @@ pub struct FinalityProof {
 #[derive(Debug, Decode, Clone)]
 pub struct WrappedProof(pub FinalityProof);
 
+/// Merkle proof of the data transaction inclusion under the block data root.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DataProof {
+	/// Root of the data transactions Merkle tree
+	pub root: H256,
+	/// Sibling hashes on the path from the leaf to the root
+	pub proof: Vec<H256>,
+	pub number_of_leaves: u32,
+	pub leaf_index: u32,
+	/// Keccak-256 hash of the data transaction
+	pub leaf: H256,
+}
+
 impl<'de> Deserialize<'de> for WrappedProof {
 	fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
 	where
@@ -26,7 +26,7 @@ use tokio_retry::Retry;
 use tokio_stream::StreamExt;
 use tracing::{info, warn};
 
-use super::{Node, Nodes, Subscription, WrappedProof, CELL_WITH_PROOF_SIZE};
+use super::{DataProof, Node, Nodes, Subscription, WrappedProof, CELL_WITH_PROOF_SIZE};
 use crate::{
 	consts::ExpectedNodeVariant,
 	types::{RetryConfig, RuntimeVersion, State, DEV_FLAG_GENHASH},
@@ -284,6 +284,20 @@ impl Client {
 		Ok(header)
 	}
 
+	pub async fn get_block_extrinsics(&self, block_hash: H256) -> Result<Vec<Vec<u8>>> {
+		let block = self
+			.with_retries(|client| async move { client.rpc().block(Some(block_hash)).await })
+			.await?
+			.ok_or_else(|| eyre!("Block with hash: {:?} not found", block_hash))?;
+
+		Ok(block
+			.block
+			.extrinsics
+			.into_iter()
+			.map(|extrinsic| extrinsic.0)
+			.collect())
+	}
+
 	pub async fn get_validator_set_by_hash(&self, block_hash: H256) -> Result<Vec<Public>> {
 		let res = self
 			.with_retries(|client| async move {
@@ -362,6 +376,18 @@ impl Client {
 		Ok(proof)
 	}
 
+	pub async fn request_data_proof(&self, block_hash: H256, index: u32) -> Result<DataProof> {
+		let mut params = RpcParams::new();
+		params.push(index)?;
+		params.push(block_hash)?;
+
+		self.with_retries(|client| {
+			let params = params.clone();
+			async move { client.rpc().request("kate_queryDataProof", params).await }
+		})
+		.await
+	}
+
 	pub async fn get_system_version(&self) -> Result<String> {
 		let res = self
 			.with_retries(|client| async move { client.rpc().system_version().await })
@@ -400,6 +426,19 @@ impl Client {
 		Ok(res)
 	}
 
+	/// Fetches timestamp (in milliseconds) set by the author of the block.
+	pub async fn fetch_timestamp_at(&self, block_hash: H256) -> Result<u64> {
+		let res = self
+			.with_retries(|client| {
+				let timestamp_key = api::storage().timestamp().now();
+				async move { client.storage().at(block_hash).fetch(&timestamp_key).await }
+			})
+			.await?
+			.ok_or_else(|| eyre!("The timestamp should exist"))?;
+
+		Ok(res)
+	}
+
 	pub async fn get_current_set_id_by_block_number(&self, block_num: u32) -> Result<u64> {
 		let hash = self.get_block_hash(block_num).await?;
 		self.fetch_set_id_at(hash).await
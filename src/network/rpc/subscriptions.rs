@@ -1,17 +1,19 @@
 use avail_subxt::primitives::{grandpa::AuthorityId, Header};
 use codec::Encode;
 use color_eyre::{eyre::eyre, Result};
+use kate_recovery::matrix::Dimensions;
 use sp_core::{
 	blake2_256,
 	ed25519::{self, Public},
+	H256,
 };
 use std::{
 	sync::{Arc, Mutex},
 	time::Instant,
 };
-use tokio::sync::broadcast::Sender;
+use tokio::sync::broadcast::{self, Receiver, Sender};
 use tokio_stream::StreamExt;
-use tracing::{debug, info, trace};
+use tracing::{debug, error, info, trace, warn};
 
 use super::{Client, Subscription};
 use crate::{
@@ -19,7 +21,7 @@ use crate::{
 	data::{FinalitySyncCheckpoint, Key},
 	finality::{check_finality, ValidatorSet},
 	types::{GrandpaJustification, OptionBlockRange, State},
-	utils::filter_auth_set_changes,
+	utils::{extract_kate, filter_auth_set_changes},
 };
 
 #[derive(Clone, Debug)]
@@ -30,6 +32,76 @@ pub enum Event {
 	},
 }
 
+/// Finality notifications of the blocks received from the node.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BlockEvent {
+	/// Block is finalized and its header is verified
+	Finalized { block_number: u32, hash: H256 },
+	/// Block is not on the finalized chain, so it won't be finalized
+	Reorged { block_number: u32, hash: H256 },
+}
+
+fn header_hash(header: &Header) -> H256 {
+	Encode::using_encoded(header, blake2_256).into()
+}
+
+/// Checks if the computed header hash matches the block hash reported by the node.
+fn verify_header_hash(header: &Header, reported_hash: H256) -> Result<()> {
+	let hash = header_hash(header);
+	if hash != reported_hash {
+		return Err(eyre!(
+			"Hash {hash:?} of header {} doesn't match hash {reported_hash:?} reported by the node",
+			header.number
+		));
+	}
+	Ok(())
+}
+
+/// Updates the latest block timestamp (in milliseconds) if the block is still the latest one,
+/// so a late fetch doesn't override the newer block timestamp. If the fetch failed,
+/// timestamp is cleared instead of reporting timestamp of the older block.
+fn set_latest_block_timestamp(state: &mut State, block_number: u32, timestamp: Result<u64>) {
+	if state.latest != block_number {
+		return;
+	}
+	state.latest_block_timestamp = timestamp
+		.map(|timestamp| timestamp / 1000)
+		.map_err(|error| warn!("Cannot fetch timestamp of block {block_number}: {error:#}"))
+		.ok();
+}
+
+/// Removes unverified header with the given hash, and returns it with its receive time.
+fn take_unverified_header(
+	unverified_headers: &mut Vec<(Header, Instant, ValidatorSet)>,
+	hash: H256,
+) -> Option<(Header, Instant)> {
+	let position = unverified_headers
+		.iter()
+		.position(|(header, _, _)| header_hash(header) == hash)?;
+	let (header, received_at, _) = unverified_headers.swap_remove(position);
+	Some((header, received_at))
+}
+
+/// Removes unverified headers which are not on the chain finalized up to the given block number,
+/// and returns reorg events for them.
+fn remove_reorged_headers(
+	unverified_headers: &mut Vec<(Header, Instant, ValidatorSet)>,
+	finalized_block_number: u32,
+) -> Vec<BlockEvent> {
+	let mut reorged = vec![];
+	unverified_headers.retain(|(header, _, _)| {
+		if header.number > finalized_block_number {
+			return true;
+		}
+		reorged.push(BlockEvent::Reorged {
+			block_number: header.number,
+			hash: header_hash(header),
+		});
+		false
+	});
+	reorged
+}
+
 struct BlockData {
 	justifications: Vec<GrandpaJustification>,
 	unverified_headers: Vec<(Header, Instant, ValidatorSet)>,
@@ -41,9 +113,11 @@ struct BlockData {
 pub struct SubscriptionLoop<T: Database> {
 	rpc_client: Client,
 	event_sender: Sender<Event>,
+	block_event_sender: Sender<BlockEvent>,
 	state: Arc<Mutex<State>>,
 	db: T,
 	block_data: BlockData,
+	verify_header_hash: bool,
 }
 
 impl<T: Database> SubscriptionLoop<T> {
@@ -71,9 +145,12 @@ impl<T: Database> SubscriptionLoop<T> {
 			.get_header_by_hash(last_finalized_block_hash)
 			.await?;
 
+		let (block_event_sender, _) = broadcast::channel(1000);
+
 		Ok(Self {
 			rpc_client,
 			event_sender,
+			block_event_sender,
 			state,
 			db,
 			block_data: BlockData {
@@ -86,9 +163,26 @@ impl<T: Database> SubscriptionLoop<T> {
 				next_valset: None,
 				last_finalized_block_header: Some(last_finalized_block_header),
 			},
+			verify_header_hash: false,
 		})
 	}
 
+	/// Enables verification of the received header hashes against the block hashes reported by the node.
+	pub fn with_header_hash_verification(mut self, enabled: bool) -> Self {
+		self.verify_header_hash = enabled;
+		self
+	}
+
+	/// Subscribes to block finality notifications, which are sent after the header updates.
+	pub fn block_events(&self) -> Receiver<BlockEvent> {
+		self.block_event_sender.subscribe()
+	}
+
+	/// Sends block event, which is dropped if there are no subscribers.
+	fn send_block_event(&self, event: BlockEvent) {
+		let _ = self.block_event_sender.send(event);
+	}
+
 	pub async fn run(mut self) -> Result<()> {
 		// create subscriptions stream
 		let subscriptions = self.rpc_client.clone().subscription_stream().await;
@@ -110,7 +204,32 @@ impl<T: Database> SubscriptionLoop<T> {
 		match subscription {
 			Subscription::Header(header) => {
 				let received_at = Instant::now();
-				self.state.lock().unwrap().latest = header.clone().number;
+				if self.verify_header_hash {
+					let verified = self
+						.rpc_client
+						.get_block_hash(header.number)
+						.await
+						.and_then(|reported_hash| verify_header_hash(&header, reported_hash));
+					if let Err(error) = verified {
+						error!("Header {} discarded: {error:#}", header.number);
+						return;
+					}
+				}
+				let (rows, cols, _, _) = extract_kate(&header.extension);
+				{
+					let mut state = self.state.lock().unwrap();
+					state.set_latest(header.number);
+					state.latest_dimensions = Dimensions::new(rows, cols);
+				}
+
+				// Timestamp is fetched in the background, to avoid delaying header processing
+				let (rpc_client, state) = (self.rpc_client.clone(), self.state.clone());
+				let (block_number, block_hash) = (header.number, header_hash(&header));
+				tokio::spawn(async move {
+					let timestamp = rpc_client.fetch_timestamp_at(block_hash).await;
+					let mut state = state.lock().unwrap();
+					set_latest_block_timestamp(&mut state, block_number, timestamp);
+				});
 				info!("Header no.: {}", header.number);
 
 				// if new validator set becomes active, replace the current one
@@ -168,7 +287,7 @@ impl<T: Database> SubscriptionLoop<T> {
 				.block_data
 				.unverified_headers
 				.iter()
-				.map(|(h, _, _)| Encode::using_encoded(h, blake2_256).into())
+				.map(|(h, _, _)| header_hash(h))
 				.position(|hash| justification.commit.target_hash == hash)
 			{
 				// basically, pop it out of the collection
@@ -198,42 +317,59 @@ impl<T: Database> SubscriptionLoop<T> {
 						.unwrap();
 				}
 
-				// try and get get all the skipped blocks, if they exist
+				// try and get get all the skipped blocks, if they exist,
+				// following parent hashes back from the finalized block, so competing blocks are skipped
+				let mut skipped_headers = vec![];
 				if let Some(last_header) = self.block_data.last_finalized_block_header.as_ref() {
-					for bl_num in (last_header.number + 1)..header.number {
-						info!("Sending skipped block {bl_num}");
-						let (header, received_at) = match self
-							.block_data
-							.unverified_headers
-							.iter()
-							.position(|(h, _, _)| h.number == bl_num)
-						{
-							Some(pos) => {
-								info!("Fetching header from unverified headers");
-								let p = self.block_data.unverified_headers.swap_remove(pos);
-								(p.0, p.1)
+					let mut parent_hash = header.parent_hash;
+					for bl_num in ((last_header.number + 1)..header.number).rev() {
+						let (header, received_at) = match take_unverified_header(
+							&mut self.block_data.unverified_headers,
+							parent_hash,
+						) {
+							Some(unverified) => {
+								info!("Fetching header {bl_num} from unverified headers");
+								unverified
 							},
 							None => {
-								info!("Fetching header from RPC");
-								let a = self
+								info!("Fetching header {bl_num} from RPC");
+								let header = self
 									.rpc_client
-									.get_header_by_block_number(bl_num)
+									.get_header_by_hash(parent_hash)
 									.await
-									.unwrap()
-									.0;
-								(a, Instant::now())
+									.unwrap();
+								(header, Instant::now())
 							},
 						};
-						// send as output event
-						self.event_sender
-							.send(Event::HeaderUpdate {
-								header,
-								received_at,
-							})
-							.unwrap();
+						parent_hash = header.parent_hash;
+						skipped_headers.push((header, received_at));
 					}
 				}
 
+				for (header, received_at) in skipped_headers.into_iter().rev() {
+					info!("Sending skipped block {}", header.number);
+					let block_finalized = BlockEvent::Finalized {
+						block_number: header.number,
+						hash: header_hash(&header),
+					};
+					// send as output event
+					self.event_sender
+						.send(Event::HeaderUpdate {
+							header,
+							received_at,
+						})
+						.unwrap();
+					self.send_block_event(block_finalized);
+				}
+
+				// headers of the competing blocks which are not finalized are discarded
+				for event in
+					remove_reorged_headers(&mut self.block_data.unverified_headers, header.number)
+				{
+					info!("Discarding reorged block {event:?}");
+					self.send_block_event(event);
+				}
+
 				info!("Sending finalized block {}", header.number);
 				// reset Last Finalized Block Header
 				self.block_data.last_finalized_block_header = Some(header.clone());
@@ -244,12 +380,17 @@ impl<T: Database> SubscriptionLoop<T> {
 					.unwrap()
 					.header_verified
 					.set(header.number);
+				let block_finalized = BlockEvent::Finalized {
+					block_number: header.number,
+					hash: header_hash(&header),
+				};
 				self.event_sender
 					.send(Event::HeaderUpdate {
 						header,
 						received_at,
 					})
 					.unwrap();
+				self.send_block_event(block_finalized);
 			} else {
 				trace!("Matched pair of header/justification not found.");
 				self.block_data.justifications.push(justification);
@@ -258,3 +399,146 @@ impl<T: Database> SubscriptionLoop<T> {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::{
+		header_hash, remove_reorged_headers, set_latest_block_timestamp, take_unverified_header,
+		verify_header_hash, BlockEvent,
+	};
+	use crate::{finality::ValidatorSet, types::State};
+	use avail_subxt::{
+		api::runtime_types::avail_core::{
+			data_lookup::compact::CompactDataLookup,
+			header::extension::{v3, HeaderExtension},
+			kate_commitment::v3::KateCommitment,
+		},
+		primitives::Header,
+	};
+	use sp_core::H256;
+	use std::time::Instant;
+	use subxt::config::substrate::Digest;
+
+	fn header(number: u32, parent_hash: H256) -> Header {
+		Header {
+			parent_hash,
+			number,
+			state_root: H256::default(),
+			extrinsics_root: H256::default(),
+			extension: HeaderExtension::V3(v3::HeaderExtension {
+				commitment: KateCommitment::default(),
+				app_lookup: CompactDataLookup {
+					size: 0,
+					index: vec![],
+				},
+			}),
+			digest: Digest { logs: vec![] },
+		}
+	}
+
+	fn unverified(header: &Header) -> (Header, Instant, ValidatorSet) {
+		let valset = ValidatorSet {
+			set_id: 0,
+			validator_set: vec![],
+		};
+		(header.clone(), Instant::now(), valset)
+	}
+
+	#[test]
+	fn header_hash_verified() {
+		let header = header(10, H256::default());
+		assert!(verify_header_hash(&header, header_hash(&header)).is_ok());
+	}
+
+	#[test]
+	fn header_hash_mismatch() {
+		let reported = header(10, H256::default());
+		let mut tampered = reported.clone();
+		tampered.state_root = H256::repeat_byte(1);
+
+		let error = verify_header_hash(&tampered, header_hash(&reported)).unwrap_err();
+		assert!(error.to_string().contains("doesn't match"));
+	}
+
+	#[test]
+	fn finalized_block_without_reorg() {
+		let finalized = header(10, H256::default());
+		let next = header(11, header_hash(&finalized));
+		let mut unverified_headers = vec![unverified(&next)];
+
+		assert!(remove_reorged_headers(&mut unverified_headers, finalized.number).is_empty());
+		assert_eq!(unverified_headers.len(), 1);
+	}
+
+	#[test]
+	fn finalized_block_with_reorg() {
+		let finalized = header(10, H256::default());
+		let competing = header(10, H256::repeat_byte(1));
+		let stale = header(9, H256::repeat_byte(2));
+		let next = header(11, header_hash(&finalized));
+		let mut unverified_headers = vec![
+			unverified(&stale),
+			unverified(&competing),
+			unverified(&next),
+		];
+
+		let events = remove_reorged_headers(&mut unverified_headers, finalized.number);
+		assert_eq!(
+			events,
+			vec![
+				BlockEvent::Reorged {
+					block_number: 9,
+					hash: header_hash(&stale),
+				},
+				BlockEvent::Reorged {
+					block_number: 10,
+					hash: header_hash(&competing),
+				},
+			]
+		);
+		assert_eq!(unverified_headers.len(), 1);
+		assert_eq!(unverified_headers[0].0.number, 11);
+	}
+
+	#[test]
+	fn skipped_block_on_finalized_chain() {
+		let last_finalized = header(9, H256::default());
+		let skipped = header(10, header_hash(&last_finalized));
+		let competing = header(10, H256::repeat_byte(1));
+		let finalized = header(11, header_hash(&skipped));
+		let mut unverified_headers = vec![unverified(&competing), unverified(&skipped)];
+
+		// Skipped block is found by the parent hash, not by the block number
+		let (taken, _) =
+			take_unverified_header(&mut unverified_headers, finalized.parent_hash).unwrap();
+		assert_eq!(header_hash(&taken), header_hash(&skipped));
+		assert!(take_unverified_header(&mut unverified_headers, finalized.parent_hash).is_none());
+
+		let events = remove_reorged_headers(&mut unverified_headers, finalized.number);
+		assert_eq!(
+			events,
+			vec![BlockEvent::Reorged {
+				block_number: 10,
+				hash: header_hash(&competing),
+			}]
+		);
+	}
+
+	#[test]
+	fn latest_block_timestamp() {
+		let mut state = State::default();
+		state.set_latest(10);
+
+		set_latest_block_timestamp(&mut state, 10, Ok(1_700_000_000_000));
+		assert_eq!(state.latest_block_timestamp, Some(1_700_000_000));
+
+		// Late fetch of the older block doesn't override the timestamp
+		state.set_latest(11);
+		set_latest_block_timestamp(&mut state, 10, Ok(1_600_000_000_000));
+		assert_eq!(state.latest_block_timestamp, Some(1_700_000_000));
+
+		// Failed fetch clears the timestamp of the older block
+		set_latest_block_timestamp(&mut state, 11, Err(color_eyre::eyre::eyre!("error")));
+		assert_eq!(state.latest_block_timestamp, None);
+	}
+}
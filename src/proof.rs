@@ -1,6 +1,8 @@
-//! Parallelized proof verification
+//! Parallelized cell proof verification, data inclusion proof verification,
+//! and extrinsic inclusion proofs under the extrinsics root
 
-use color_eyre::eyre;
+use codec::{Compact, Encode};
+use color_eyre::eyre::{self, eyre};
 use dusk_plonk::commitment_scheme::kzg10::PublicParameters;
 use itertools::{Either, Itertools};
 use kate_recovery::{
@@ -8,6 +10,10 @@ use kate_recovery::{
 	matrix::{Dimensions, Position},
 	proof,
 };
+use sp_core::{keccak_256, Blake2Hasher, H256};
+use sp_trie::{
+	generate_trie_proof, verify_trie_proof, LayoutV0, MemoryDB, TrieDBMutBuilder, TrieMut,
+};
 use std::sync::Arc;
 use tokio::{task::JoinSet, time::Instant};
 use tracing::debug;
@@ -61,3 +67,206 @@ pub async fn verify(
 			false => Either::Right(position),
 		}))
 }
+
+fn hash_pair(left: &H256, right: &H256) -> H256 {
+	keccak_256(&[left.as_bytes(), right.as_bytes()].concat()).into()
+}
+
+/// Returns number of sibling hashes in the Merkle proof of the leaf,
+/// which is less than tree height if the path contains promoted nodes.
+pub fn data_proof_len(number_of_leaves: u32, leaf_index: u32) -> usize {
+	let mut len = 0;
+	let mut position = leaf_index;
+	let mut width = number_of_leaves;
+	while width > 1 {
+		if !(position % 2 == 0 && position + 1 == width) {
+			len += 1;
+		}
+		position /= 2;
+		width = (width + 1) / 2;
+	}
+	len
+}
+
+/// Verifies Merkle proof that the leaf (Keccak-256 hash of the data transaction) is included under the data root.
+/// Proof contains sibling hashes from the leaf towards the root. Last node of the tree level
+/// with odd number of nodes has no sibling, and it is promoted to the upper level.
+pub fn verify_data_proof(
+	root: H256,
+	proof: &[H256],
+	number_of_leaves: u32,
+	leaf_index: u32,
+	leaf: H256,
+) -> bool {
+	if leaf_index >= number_of_leaves {
+		return false;
+	}
+
+	let mut siblings = proof.iter();
+	let mut position = leaf_index;
+	let mut width = number_of_leaves;
+	let mut computed = leaf;
+	while width > 1 {
+		let is_promoted = position % 2 == 0 && position + 1 == width;
+		if !is_promoted {
+			let Some(sibling) = siblings.next() else {
+				return false;
+			};
+			computed = match position % 2 {
+				0 => hash_pair(&computed, sibling),
+				_ => hash_pair(sibling, &computed),
+			};
+		}
+		position /= 2;
+		width = (width + 1) / 2;
+	}
+
+	siblings.next().is_none() && computed == root
+}
+
+/// Trie layout used to calculate the extrinsics root of the block header
+type ExtrinsicsLayout = LayoutV0<Blake2Hasher>;
+
+/// Key of the extrinsic in the extrinsics trie, which is its SCALE compact encoded index.
+fn extrinsic_key(index: u32) -> Vec<u8> {
+	Compact(index).encode()
+}
+
+/// Builds trie of the block extrinsics, returning the trie nodes and the extrinsics root.
+fn extrinsics_trie(extrinsics: &[Vec<u8>]) -> eyre::Result<(MemoryDB<Blake2Hasher>, H256)> {
+	let mut db = MemoryDB::default();
+	let mut root = H256::default();
+	{
+		let mut trie = TrieDBMutBuilder::<ExtrinsicsLayout>::new(&mut db, &mut root).build();
+		for (index, extrinsic) in (0..).zip(extrinsics) {
+			trie.insert(&extrinsic_key(index), extrinsic)
+				.map_err(|error| eyre!("Cannot insert extrinsic {index}: {error}"))?;
+		}
+	}
+	Ok((db, root))
+}
+
+/// Generates proof that the extrinsic at the given index is included under the extrinsics root.
+/// Returns the extrinsics root and the proof trie nodes.
+pub fn extrinsic_proof(extrinsics: &[Vec<u8>], index: u32) -> eyre::Result<(H256, Vec<Vec<u8>>)> {
+	let (db, root) = extrinsics_trie(extrinsics)?;
+	let proof =
+		generate_trie_proof::<ExtrinsicsLayout, _, _, _>(&db, root, &[extrinsic_key(index)])
+			.map_err(|error| eyre!("Cannot generate proof of extrinsic {index}: {error}"))?;
+	Ok((root, proof))
+}
+
+/// Verifies proof that the extrinsic is included under the extrinsics root at the given index.
+pub fn verify_extrinsic_proof(root: H256, proof: &[Vec<u8>], index: u32, extrinsic: &[u8]) -> bool {
+	let items = [(extrinsic_key(index), Some(extrinsic))];
+	verify_trie_proof::<ExtrinsicsLayout, _, _, _>(&root, proof, &items).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{
+		data_proof_len, extrinsic_proof, extrinsics_trie, hash_pair, verify_data_proof,
+		verify_extrinsic_proof, ExtrinsicsLayout,
+	};
+	use sp_core::{keccak_256, H256};
+	use sp_trie::TrieConfiguration;
+
+	/// Builds Merkle tree of the data transactions, returning the root and proofs of all leaves.
+	fn data_proofs(data: &[&[u8]]) -> (H256, Vec<Vec<H256>>) {
+		let mut level: Vec<H256> = data.iter().map(|data| keccak_256(data).into()).collect();
+		let mut proofs = vec![vec![]; data.len()];
+		// Indices of the nodes on the current level, on the path from each leaf
+		let mut positions: Vec<usize> = (0..data.len()).collect();
+		while level.len() > 1 {
+			for (proof, position) in proofs.iter_mut().zip(positions.iter_mut()) {
+				let sibling = *position ^ 1;
+				if sibling < level.len() {
+					proof.push(level[sibling]);
+				}
+				*position /= 2;
+			}
+			level = level
+				.chunks(2)
+				.map(|pair| match pair {
+					[left, right] => hash_pair(left, right),
+					[node] => *node,
+					_ => unreachable!(),
+				})
+				.collect();
+		}
+		(level[0], proofs)
+	}
+
+	const DATA: [&[u8]; 5] = [b"0", b"1", b"2", b"3", b"4"];
+
+	#[test]
+	fn valid_data_proofs() {
+		let (root, proofs) = data_proofs(&DATA);
+		for (index, (data, proof)) in DATA.iter().zip(proofs).enumerate() {
+			let leaf = keccak_256(data).into();
+			assert!(verify_data_proof(root, &proof, 5, index as u32, leaf));
+		}
+
+		let (root, proofs) = data_proofs(&DATA[..1]);
+		assert!(verify_data_proof(
+			root,
+			&proofs[0],
+			1,
+			0,
+			keccak_256(DATA[0]).into()
+		));
+	}
+
+	#[test]
+	fn data_proof_lengths() {
+		let (_, proofs) = data_proofs(&DATA);
+		for (index, proof) in proofs.iter().enumerate() {
+			assert_eq!(data_proof_len(5, index as u32), proof.len());
+		}
+		assert_eq!(data_proof_len(1, 0), 0);
+	}
+
+	#[test]
+	fn invalid_data_proofs() {
+		let (root, proofs) = data_proofs(&DATA);
+		let leaf: H256 = keccak_256(DATA[1]).into();
+		assert!(!verify_data_proof(
+			root,
+			&proofs[1],
+			5,
+			1,
+			keccak_256(b"5").into()
+		));
+		assert!(!verify_data_proof(root, &proofs[1], 5, 0, leaf));
+		assert!(!verify_data_proof(root, &proofs[1], 5, 5, leaf));
+		assert!(!verify_data_proof(root, &proofs[1], 4, 1, leaf));
+		assert!(!verify_data_proof(root, &proofs[1][1..], 5, 1, leaf));
+		assert!(!verify_data_proof(H256::zero(), &proofs[1], 5, 1, leaf));
+	}
+
+	fn extrinsics() -> Vec<Vec<u8>> {
+		DATA.iter().map(|data| data.to_vec()).collect()
+	}
+
+	#[test]
+	fn extrinsics_root() {
+		let (_, root) = extrinsics_trie(&extrinsics()).unwrap();
+		assert_eq!(root, ExtrinsicsLayout::ordered_trie_root(extrinsics()));
+	}
+
+	#[test]
+	fn valid_extrinsic_proofs() {
+		for (index, extrinsic) in (0..).zip(extrinsics()) {
+			let (root, proof) = extrinsic_proof(&extrinsics(), index).unwrap();
+			assert!(verify_extrinsic_proof(root, &proof, index, &extrinsic));
+		}
+	}
+
+	#[test]
+	fn invalid_extrinsic_proofs() {
+		let (root, proof) = extrinsic_proof(&extrinsics(), 1).unwrap();
+		assert!(!verify_extrinsic_proof(root, &proof, 1, b"5"));
+		assert!(!verify_extrinsic_proof(root, &proof, 0, DATA[1]));
+		assert!(!verify_extrinsic_proof(H256::zero(), &proof, 1, DATA[1]));
+	}
+}
@@ -22,7 +22,7 @@ use crate::{
 		rpc::{self, Client as RpcClient},
 	},
 	types::{BlockVerified, OptionBlockRange, State, SyncClientConfig},
-	utils::{calculate_confidence, extract_app_lookup, extract_kate},
+	utils::{calculate_confidence, extract_app_lookup, extract_kate, header_commitments},
 };
 
 use async_trait::async_trait;
@@ -32,7 +32,7 @@ use color_eyre::{
 	eyre::{eyre, WrapErr},
 	Result,
 };
-use kate_recovery::{commitments, matrix::Dimensions};
+use kate_recovery::matrix::Dimensions;
 use mockall::automock;
 use sp_core::blake2_256;
 use std::{
@@ -128,7 +128,7 @@ async fn process_block(
 	let (rows, cols, _, commitment) = extract_kate(&header.extension);
 	let dimensions = Dimensions::new(rows, cols).ok_or_else(|| eyre!("Invalid dimensions"))?;
 
-	let commitments = commitments::from_slice(&commitment)?;
+	let commitments = header_commitments(rows, cols, &commitment, cfg.max_block_rows)?;
 
 	// now this is in `u64`
 	let cell_count = rpc::cell_count_for_confidence(cfg.confidence);
@@ -153,8 +153,8 @@ async fn process_block(
 	client.store_confidence(fetched.len().try_into()?, block_number)?;
 
 	let confidence = Some(calculate_confidence(fetched.len() as u32));
-	let client_msg =
-		BlockVerified::try_from((header, confidence)).wrap_err("converting to message failed")?;
+	let client_msg = BlockVerified::try_from((header, confidence, cfg.max_block_rows))
+		.wrap_err("converting to message failed")?;
 
 	if let Err(error) = block_verified_sender.send(client_msg) {
 		error!("Cannot send block verified message: {error}");
@@ -73,6 +73,11 @@ impl Metrics {
 		let mut m = self.attributes.multiaddress.write().await;
 		*m = multiaddr;
 	}
+
+	/// Meter used to register metrics of the other components, e.g. API metrics.
+	pub fn meter(&self) -> &Meter {
+		&self.meter
+	}
 }
 
 #[async_trait]
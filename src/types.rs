@@ -2,7 +2,7 @@
 
 use crate::network::p2p::MemoryStoreConfig;
 use crate::network::rpc::{Event, Node as RpcNode};
-use crate::utils::{extract_app_lookup, extract_kate};
+use crate::utils::{extract_app_lookup, extract_kate, header_commitments};
 use avail_core::DataLookup;
 use avail_subxt::{primitives::Header as DaHeader, utils::H256};
 use bip39::{Language, Mnemonic, MnemonicType};
@@ -12,10 +12,11 @@ use color_eyre::{
 	eyre::{eyre, WrapErr},
 	Report, Result,
 };
-use kate_recovery::{
-	commitments,
-	matrix::{Dimensions, Partition},
+use hyper::http::{
+	uri::{Authority, Scheme},
+	HeaderName, Method,
 };
+use kate_recovery::matrix::{Dimensions, Partition};
 use libp2p::kad::Mode as KadMode;
 use libp2p::{Multiaddr, PeerId};
 use serde::{de::Error, Deserialize, Serialize};
@@ -26,7 +27,8 @@ use std::fs;
 use std::num::{NonZeroU8, NonZeroUsize};
 use std::ops::Range;
 use std::str::FromStr;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use subtle::ConstantTimeEq;
 use subxt::ext::sp_core::{sr25519::Pair, Pair as _};
 use tokio::sync::broadcast;
 use tokio_retry::strategy::{jitter, ExponentialBackoff, FibonacciBackoff};
@@ -109,9 +111,12 @@ pub struct ClientChannels {
 	pub rpc_event_receiver: broadcast::Receiver<Event>,
 }
 
-impl TryFrom<(DaHeader, Option<f64>)> for BlockVerified {
+/// Converts header to the verified block message, rejecting blocks with more than the given number of rows.
+impl TryFrom<(DaHeader, Option<f64>, u16)> for BlockVerified {
 	type Error = Report;
-	fn try_from((header, confidence): (DaHeader, Option<f64>)) -> Result<Self, Self::Error> {
+	fn try_from(
+		(header, confidence, max_rows): (DaHeader, Option<f64>, u16),
+	) -> Result<Self, Self::Error> {
 		let hash: H256 = Encode::using_encoded(&header, blake2_256).into();
 		let enc_lookup = extract_app_lookup(&header.extension)
 			.map_err(|e| eyre!("Invalid DataLookup: {}", e))?
@@ -124,7 +129,7 @@ impl TryFrom<(DaHeader, Option<f64>)> for BlockVerified {
 			block_num: header.number,
 			dimensions: Dimensions::new(rows, cols).ok_or_else(|| eyre!("Invalid dimensions"))?,
 			lookup,
-			commitments: commitments::from_slice(&commitment)?,
+			commitments: header_commitments(rows, cols, &commitment, max_rows)?,
 			confidence,
 		})
 	}
@@ -169,6 +174,32 @@ impl TryFrom<String> for KademliaMode {
 	}
 }
 
+/// Policy applied when WebSocket client message buffer is full
+///
+/// * `DropOldest` - oldest buffered messages are dropped
+/// * `Disconnect` - client is disconnected
+/// * `Evict` - oldest buffered messages are dropped, client is disconnected if buffer stays full longer than grace period
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum WsBufferPolicy {
+	#[default]
+	DropOldest,
+	Disconnect,
+	Evict,
+}
+
+/// Naming of the fields in the API JSON responses and WebSocket messages
+///
+/// * `SnakeCase` - fields are named in snake case, e.g. `latest_block_at`
+/// * `CamelCase` - fields are named in camel case, e.g. `latestBlockAt`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum FieldNaming {
+	#[default]
+	SnakeCase,
+	CamelCase,
+}
+
 /// Client mode
 ///
 /// * `LightClient` - light client is running
@@ -212,15 +243,19 @@ pub mod block_matrix_partition_format {
 		D: Deserializer<'de>,
 	{
 		let s = String::deserialize(deserializer)?;
+		parse(&s).map_err(serde::de::Error::custom)
+	}
+
+	pub(crate) fn parse(s: &str) -> Result<Option<Partition>, String> {
 		if s.is_empty() || s.to_ascii_lowercase().contains("none") {
 			return Ok(None);
 		}
 		let parts = s.split('/').collect::<Vec<_>>();
 		if parts.len() != 2 {
-			return Err(serde::de::Error::custom(format!("Invalid value {s}")));
+			return Err(format!("Invalid value {s}"));
 		}
-		let number = parts[0].parse::<u8>().map_err(serde::de::Error::custom)?;
-		let fraction = parts[1].parse::<u8>().map_err(serde::de::Error::custom)?;
+		let number = parts[0].parse::<u8>().map_err(|error| error.to_string())?;
+		let fraction = parts[1].parse::<u8>().map_err(|error| error.to_string())?;
 		if number != 0 {
 			Ok(Some(Partition { number, fraction }))
 		} else {
@@ -228,6 +263,52 @@ pub mod block_matrix_partition_format {
 		}
 	}
 }
+
+/// Single partition is (de)serialized as a string (e.g. `1/20`),
+/// and multiple partitions as a list of strings (e.g. `["1/20", "2/20"]`).
+pub mod block_matrix_partitions_format {
+	use super::block_matrix_partition_format::parse;
+	use kate_recovery::matrix::Partition;
+	use serde::{self, Deserialize, Deserializer, Serializer};
+
+	#[derive(Deserialize)]
+	#[serde(untagged)]
+	enum Partitions {
+		Single(String),
+		Multiple(Vec<String>),
+	}
+
+	fn format(partition: &Partition) -> String {
+		let Partition { fraction, number } = partition;
+		format!("{number}/{fraction}")
+	}
+
+	pub fn serialize<S>(partitions: &[Partition], serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		match partitions {
+			[] => serializer.serialize_none(),
+			[partition] => serializer.serialize_str(&format(partition)),
+			partitions => serializer.collect_seq(partitions.iter().map(format)),
+		}
+	}
+
+	pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<Partition>, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		let values = match Partitions::deserialize(deserializer)? {
+			Partitions::Single(value) => vec![value],
+			Partitions::Multiple(values) => values,
+		};
+		values
+			.iter()
+			.filter_map(|value| parse(value).transpose())
+			.collect::<Result<Vec<_>, _>>()
+			.map_err(serde::de::Error::custom)
+	}
+}
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(try_from = "String")]
 pub struct CompactMultiaddress((PeerId, Multiaddr));
@@ -319,6 +400,79 @@ pub struct FibonacciConfig {
 	pub retries: usize,
 }
 
+/// API key with optional service level limits. Key without limits can be configured as a plain string.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(from = "ApiKeyFormat")]
+pub struct ApiKey {
+	pub key: String,
+	/// Number of transactions allowed to be submitted in each quota window
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub submit_quota: Option<u32>,
+	/// Number of API requests per second, overrides the default rate limit
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub rate_limit_requests_per_second: Option<f64>,
+	/// Number of API requests at once, overrides the default burst size
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub rate_limit_burst: Option<u32>,
+	/// Admin keys are allowed to make admin requests, e.g. to inspect subscriptions
+	#[serde(skip_serializing_if = "std::ops::Not::not")]
+	pub admin: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ApiKeyFormat {
+	Key(String),
+	WithLimits {
+		key: String,
+		submit_quota: Option<u32>,
+		rate_limit_requests_per_second: Option<f64>,
+		rate_limit_burst: Option<u32>,
+		#[serde(default)]
+		admin: bool,
+	},
+}
+
+impl From<ApiKeyFormat> for ApiKey {
+	fn from(value: ApiKeyFormat) -> Self {
+		match value {
+			ApiKeyFormat::Key(key) => ApiKey {
+				key,
+				submit_quota: None,
+				rate_limit_requests_per_second: None,
+				rate_limit_burst: None,
+				admin: false,
+			},
+			ApiKeyFormat::WithLimits {
+				key,
+				submit_quota,
+				rate_limit_requests_per_second,
+				rate_limit_burst,
+				admin,
+			} => ApiKey {
+				key,
+				submit_quota,
+				rate_limit_requests_per_second,
+				rate_limit_burst,
+				admin,
+			},
+		}
+	}
+}
+
+impl ApiKey {
+	/// Compares the given key in constant time, so the configured key cannot be guessed from response timing.
+	pub fn matches(&self, api_key: &str) -> bool {
+		self.key.as_bytes().ct_eq(api_key.as_bytes()).into()
+	}
+}
+
+impl From<&str> for ApiKey {
+	fn from(key: &str) -> Self {
+		ApiKeyFormat::Key(key.to_string()).into()
+	}
+}
+
 /// Representation of a configuration used by this project.
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(default)]
@@ -327,6 +481,73 @@ pub struct RuntimeConfig {
 	pub http_server_host: String,
 	/// Light client HTTP server port (default: 7000).
 	pub http_server_port: u16,
+	/// Path to the PEM encoded TLS certificate chain, HTTP server serves HTTPS and WSS if set, together with the key (default: None).
+	pub http_server_tls_cert: Option<String>,
+	/// Path to the PEM encoded TLS private key of the certificate (default: None).
+	pub http_server_tls_key: Option<String>,
+	/// Origins allowed to make cross-origin requests to the HTTP API, `*` allows any origin, API is same-origin only if empty (default: []).
+	pub cors_allowed_origins: Vec<String>,
+	/// Methods allowed in cross-origin requests (default: ["GET", "POST", "DELETE"]).
+	pub cors_allowed_methods: Vec<String>,
+	/// Headers allowed in cross-origin requests (default: ["content-type", "authorization"]).
+	pub cors_allowed_headers: Vec<String>,
+	/// Include cause chains of the errors in the API error responses, intended for development only (default: false).
+	pub expose_error_causes: bool,
+	/// Naming of the fields in the API JSON responses and WebSocket messages, `snake-case` or `camel-case` (default: snake-case).
+	pub api_field_naming: FieldNaming,
+	/// Interval in seconds between WebSocket heartbeat pings sent to connected clients, must be greater than 0 (default: 30).
+	pub ws_heartbeat_interval: u64,
+	/// Number of consecutive missed pongs after which WebSocket client is disconnected (default: 3).
+	pub ws_heartbeat_max_missed: u32,
+	/// Interval in seconds between status checks, changes are published to WebSocket clients subscribed to `status-changed` and `sync-progress` topics, must be greater than 0 (default: 1).
+	pub ws_status_changed_interval: u64,
+	/// Time in seconds to wait for WebSocket clients to receive close frame on shutdown (default: 5).
+	pub ws_shutdown_timeout: u64,
+	/// Maximum number of messages buffered for each WebSocket client (default: 1024).
+	pub ws_buffer_size: usize,
+	/// Policy applied when WebSocket client buffer is full, `drop-oldest`, `disconnect` or `evict` (default: drop-oldest).
+	pub ws_buffer_policy: WsBufferPolicy,
+	/// Time in seconds WebSocket client buffer can stay full before the client is evicted, if `ws_buffer_policy` is `evict` (default: 30).
+	pub ws_buffer_grace_period: u64,
+	/// Time in seconds after which WebSocket request is cancelled and timeout error is sent to the client (default: 180).
+	pub ws_request_timeout: u64,
+	/// Maximum size in bytes of the WebSocket request message (default: 2097152).
+	pub ws_max_message_size: usize,
+	/// Maximum number of consecutive WebSocket requests which cannot be parsed, after which the connection is closed. Set to 0 to keep connections with malformed requests open (default: 3).
+	pub ws_max_malformed_requests: u32,
+	/// Maximum number of verified headers replayed to the WebSocket client on connect (default: 100).
+	pub ws_replay_max_blocks: u32,
+	/// Number of the last published messages per topic, replayed to the WebSocket clients subscribed with replay (default: 10).
+	pub ws_replay_buffer_depth: usize,
+	/// Number of worker tasks sending published messages to the WebSocket clients in parallel (default: 4).
+	pub ws_publish_workers: usize,
+	/// Maximum number of topics and application IDs the WebSocket client can be subscribed to (default: 100).
+	pub ws_max_subscriptions: usize,
+	/// Maximum number of concurrent WebSocket connections, new connections are rejected when reached (default: 1024).
+	pub ws_max_connections: usize,
+	/// Time in seconds for which submit responses are cached by idempotency key (default: 3600).
+	pub idempotency_key_ttl: u64,
+	/// Maximum size in bytes of the submitted transaction, after base64 decoding (default: 524288).
+	pub max_submit_size: usize,
+	/// Maximum number of transactions in the submit batch (default: 100).
+	pub max_batch_size: usize,
+	/// Maximum number of cells requested by a single WebSocket `get-cells` request (default: 1024).
+	pub max_cells_per_request: usize,
+	/// API keys required to submit transactions, submit is not restricted if empty (default: []).
+	/// Each key can have its own submit quota and rate limit, and admin keys are allowed to make admin requests.
+	pub api_keys: Vec<ApiKey>,
+	/// Time in seconds after which API key submit quotas are reset (default: 86400).
+	pub api_key_quota_window: u64,
+	/// Number of API requests per second allowed for each client IP address or API key, rate limiting is disabled if 0 (default: 0).
+	pub rate_limit_requests_per_second: f64,
+	/// Number of API requests each client can make at once, before the rate limit applies (default: 20).
+	pub rate_limit_burst: u32,
+	/// Maximum time in seconds since the latest block for which the client is reported as ready (default: 120).
+	pub health_max_block_age: u64,
+	/// Maximum time in seconds for which block availability request waits for the block confidence (default: 30).
+	pub block_availability_timeout: u64,
+	/// Records API metrics with the telemetry meter, so they are exported with the other light client metrics (default: false).
+	pub api_metrics_enable: bool,
 	/// Secret key for libp2p keypair. Can be either set to `seed` or to `key`.
 	/// If set to seed, keypair will be generated from that seed.
 	/// If set to key, a valid ed25519 private key must be provided, else the client will fail
@@ -354,10 +575,16 @@ pub struct RuntimeConfig {
 	pub relays: Vec<MultiaddrConfig>,
 	/// WebSocket endpoint of full node for subscribing to latest header, etc (default: [ws://127.0.0.1:9944]).
 	pub full_node_ws: Vec<String>,
+	/// Cross-check hashes of the received headers with the block hashes reported by the node, mismatched headers are discarded (default: false).
+	pub verify_header_hash: bool,
+	/// Validate submitted extrinsics before broadcast, checking that they are well formed, signed, and have the configured app ID, if set (default: false).
+	pub validate_extrinsics: bool,
 	/// Genesis hash of the network to be connected to. Set to a string beginning with "DEV" to connect to any network.
 	pub genesis_hash: String,
 	/// ID of application used to start application client. If app_id is not set, or set to 0, application client is not started (default: 0).
 	pub app_id: Option<u32>,
+	/// Application IDs of data transactions which can be submitted in addition to the configured app_id. Submit is enabled only if app_id is configured or this list is not empty (default: []).
+	pub submit_app_ids: Vec<u32>,
 	/// Confidence threshold, used to calculate how many cells need to be sampled to achieve desired confidence (default: 92.0).
 	pub confidence: f64,
 	/// File system path where RocksDB used by light client, stores its data.
@@ -377,15 +604,17 @@ pub struct RuntimeConfig {
 	pub query_proof_rpc_parallel_tasks: usize,
 	/// Number of seconds to postpone block processing after block finalized message arrives (default: 0).
 	pub block_processing_delay: Option<u32>,
-	/// Fraction and number of the block matrix part to fetch (e.g. 2/20 means second 1/20 part of a matrix) (default: None)
-	#[serde(with = "block_matrix_partition_format")]
-	pub block_matrix_partition: Option<Partition>,
+	/// Fraction and number of the block matrix parts to fetch, as a single value or a list (e.g. 2/20 means second 1/20 part of a matrix) (default: None)
+	#[serde(with = "block_matrix_partitions_format")]
+	pub block_matrix_partition: Vec<Partition>,
 	/// Starting block of the syncing process. Omitting it will disable syncing. (default: None).
 	pub sync_start_block: Option<u32>,
 	/// Enable or disable synchronizing finality. If disabled, finality is assumed to be verified until the starting block at the point the LC is started and is only checked for new blocks. (default: true)
 	pub sync_finality_enable: bool,
 	/// Maximum number of cells per request for proof queries (default: 30).
 	pub max_cells_per_rpc: Option<usize>,
+	/// Maximum number of block matrix rows, headers of larger blocks are rejected (default: 1024).
+	pub max_block_rows: u16,
 	/// Threshold for the number of cells fetched via DHT for the app client (default: 5000)
 	pub threshold: usize,
 	/// Kademlia configuration - WARNING: Changing the default values might cause the peer to suffer poor performance!
@@ -447,7 +676,37 @@ pub struct RuntimeConfig {
 
 impl RuntimeConfig {
 	pub fn is_fat_client(&self) -> bool {
-		self.block_matrix_partition.is_some()
+		!self.block_matrix_partition.is_empty()
+	}
+
+	/// Checks if API key is allowed to submit transactions, which is not restricted if no API keys are configured.
+	pub fn is_authorized(&self, api_key: Option<&str>) -> bool {
+		self.api_keys.is_empty()
+			|| api_key.is_some_and(|api_key| {
+				// All keys are compared, so timing doesn't reveal which key is matched
+				self.api_keys
+					.iter()
+					.fold(false, |matched, key| key.matches(api_key) | matched)
+			})
+	}
+
+	/// Checks if submit is enabled, which requires configured app ID or allowed submit app IDs.
+	pub fn is_submit_enabled(&self) -> bool {
+		self.app_id.is_some() || !self.submit_app_ids.is_empty()
+	}
+
+	/// Checks if data transactions of the given app ID are allowed to be submitted.
+	pub fn is_submit_allowed(&self, app_id: u32) -> bool {
+		self.app_id == Some(app_id) || self.submit_app_ids.contains(&app_id)
+	}
+
+	/// Checks if API key is allowed to make admin requests, which are not allowed if no admin keys are configured.
+	pub fn is_admin(&self, api_key: Option<&str>) -> bool {
+		api_key.is_some_and(|api_key| {
+			self.api_keys.iter().fold(false, |matched, key| {
+				(key.matches(api_key) & key.admin) | matched
+			})
+		})
 	}
 }
 
@@ -457,6 +716,7 @@ pub struct Delay(pub Option<Duration>);
 pub struct LightClientConfig {
 	pub confidence: f64,
 	pub block_processing_delay: Delay,
+	pub max_block_rows: u16,
 }
 
 impl Delay {
@@ -476,6 +736,7 @@ impl From<&RuntimeConfig> for LightClientConfig {
 		LightClientConfig {
 			confidence: val.confidence,
 			block_processing_delay: Delay(block_processing_delay),
+			max_block_rows: val.max_block_rows,
 		}
 	}
 }
@@ -488,8 +749,9 @@ pub struct FatClientConfig {
 	pub dht_parallelization_limit: usize,
 	pub query_proof_rpc_parallel_tasks: usize,
 	pub block_processing_delay: Delay,
-	pub block_matrix_partition: Option<Partition>,
+	pub block_matrix_partition: Vec<Partition>,
 	pub max_cells_per_rpc: usize,
+	pub max_block_rows: u16,
 }
 
 impl From<&RuntimeConfig> for FatClientConfig {
@@ -505,8 +767,9 @@ impl From<&RuntimeConfig> for FatClientConfig {
 			dht_parallelization_limit: val.dht_parallelization_limit,
 			query_proof_rpc_parallel_tasks: val.query_proof_rpc_parallel_tasks,
 			block_processing_delay: Delay(block_processing_delay),
-			block_matrix_partition: val.block_matrix_partition,
+			block_matrix_partition: val.block_matrix_partition.clone(),
 			max_cells_per_rpc: val.max_cells_per_rpc.unwrap_or(30),
+			max_block_rows: val.max_block_rows,
 		}
 	}
 }
@@ -722,6 +985,7 @@ pub struct SyncClientConfig {
 	pub disable_rpc: bool,
 	pub dht_parallelization_limit: usize,
 	pub is_last_step: bool,
+	pub max_block_rows: u16,
 }
 
 impl From<&RuntimeConfig> for SyncClientConfig {
@@ -731,6 +995,7 @@ impl From<&RuntimeConfig> for SyncClientConfig {
 			disable_rpc: val.disable_rpc,
 			dht_parallelization_limit: val.dht_parallelization_limit,
 			is_last_step: val.app_id.is_none(),
+			max_block_rows: val.max_block_rows,
 		}
 	}
 }
@@ -756,6 +1021,39 @@ impl Default for RuntimeConfig {
 		RuntimeConfig {
 			http_server_host: "127.0.0.1".to_owned(),
 			http_server_port: 7000,
+			http_server_tls_cert: None,
+			http_server_tls_key: None,
+			cors_allowed_origins: vec![],
+			cors_allowed_methods: vec!["GET".to_string(), "POST".to_string(), "DELETE".to_string()],
+			cors_allowed_headers: vec!["content-type".to_string(), "authorization".to_string()],
+			expose_error_causes: false,
+			api_field_naming: FieldNaming::SnakeCase,
+			ws_heartbeat_interval: 30,
+			ws_heartbeat_max_missed: 3,
+			ws_status_changed_interval: 1,
+			ws_shutdown_timeout: 5,
+			ws_buffer_size: 1024,
+			ws_buffer_policy: WsBufferPolicy::DropOldest,
+			ws_buffer_grace_period: 30,
+			ws_request_timeout: 180,
+			ws_max_message_size: 2 * 1024 * 1024,
+			ws_max_malformed_requests: 3,
+			ws_replay_max_blocks: 100,
+			ws_replay_buffer_depth: 10,
+			ws_publish_workers: 4,
+			ws_max_subscriptions: 100,
+			ws_max_connections: 1024,
+			idempotency_key_ttl: 3600,
+			max_submit_size: 512 * 1024,
+			max_batch_size: 100,
+			max_cells_per_request: 1024,
+			api_keys: vec![],
+			api_key_quota_window: 86400,
+			rate_limit_requests_per_second: 0.0,
+			rate_limit_burst: 20,
+			health_max_block_age: 120,
+			block_availability_timeout: 30,
+			api_metrics_enable: false,
 			port: 37000,
 			ws_transport_enable: false,
 			secret_key: None,
@@ -768,8 +1066,11 @@ impl Default for RuntimeConfig {
 			bootstrap_period: 3600,
 			relays: Vec::new(),
 			full_node_ws: vec!["ws://127.0.0.1:9944".to_owned()],
+			verify_header_hash: false,
+			validate_extrinsics: false,
 			genesis_hash: "DEV".to_owned(),
 			app_id: None,
+			submit_app_ids: vec![],
 			confidence: 99.9,
 			avail_path: "avail_path".to_owned(),
 			log_level: "INFO".to_owned(),
@@ -779,10 +1080,11 @@ impl Default for RuntimeConfig {
 			dht_parallelization_limit: 20,
 			query_proof_rpc_parallel_tasks: 8,
 			block_processing_delay: Some(20),
-			block_matrix_partition: None,
+			block_matrix_partition: vec![],
 			sync_start_block: None,
 			sync_finality_enable: false,
 			max_cells_per_rpc: Some(30),
+			max_block_rows: 1024,
 			kad_record_ttl: 24 * 60 * 60,
 			threshold: 5000,
 			replication_factor: 5,
@@ -949,6 +1251,51 @@ impl RuntimeConfig {
 			})
 		}
 
+		self.validate()
+	}
+
+	/// Checks configuration values which cannot be used by the light client.
+	pub fn validate(&self) -> Result<()> {
+		if self.ws_heartbeat_interval == 0 {
+			return Err(eyre!("WebSocket heartbeat interval must be greater than 0"));
+		}
+
+		if self.ws_status_changed_interval == 0 {
+			return Err(eyre!(
+				"WebSocket status changed interval must be greater than 0"
+			));
+		}
+
+		if self.ws_request_timeout == 0 {
+			return Err(eyre!("WebSocket request timeout must be greater than 0"));
+		}
+
+		for method in &self.cors_allowed_methods {
+			if Method::from_bytes(method.as_bytes()).is_err() {
+				return Err(eyre!("Invalid CORS allowed method: {method}"));
+			}
+		}
+
+		for header in &self.cors_allowed_headers {
+			if HeaderName::from_bytes(header.as_bytes()).is_err() {
+				return Err(eyre!("Invalid CORS allowed header: {header}"));
+			}
+		}
+
+		// Origins are expected as `scheme://host[:port]`
+		for origin in self
+			.cors_allowed_origins
+			.iter()
+			.filter(|&origin| origin != "*")
+		{
+			let is_valid = origin.split_once("://").is_some_and(|(scheme, authority)| {
+				Scheme::from_str(scheme).is_ok() && Authority::from_str(authority).is_ok()
+			});
+			if !is_valid {
+				return Err(eyre!("Invalid CORS allowed origin: {origin}"));
+			}
+		}
+
 		Ok(())
 	}
 }
@@ -1012,6 +1359,8 @@ pub struct State {
 	pub latest: u32,
 	pub header_verified: Option<BlockRange>,
 	pub confidence_achieved: Option<BlockRange>,
+	/// Confidence of the latest block for which confidence is achieved
+	pub confidence: Option<f64>,
 	pub data_verified: Option<BlockRange>,
 	pub sync_latest: Option<u32>,
 	pub sync_header_verified: Option<BlockRange>,
@@ -1019,6 +1368,36 @@ pub struct State {
 	pub sync_data_verified: Option<BlockRange>,
 	pub finality_synced: bool,
 	pub connected_node: RpcNode,
+	/// Unix timestamp (in seconds) of the latest block number update
+	pub latest_block_at: Option<u64>,
+	/// Unix timestamp (in seconds) of the latest block, as set by the block author
+	pub latest_block_timestamp: Option<u64>,
+	/// Matrix dimensions of the latest block
+	pub latest_dimensions: Option<Dimensions>,
+}
+
+/// Returns current unix timestamp in seconds.
+pub fn unix_timestamp() -> u64 {
+	SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map(|duration| duration.as_secs())
+		.unwrap_or_default()
+}
+
+/// Returns current unix timestamp in milliseconds.
+pub fn unix_timestamp_millis() -> u64 {
+	SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map(|duration| duration.as_millis() as u64)
+		.unwrap_or_default()
+}
+
+impl State {
+	/// Sets latest block number and records the time of the update.
+	pub fn set_latest(&mut self, block_number: u32) {
+		self.latest = block_number;
+		self.latest_block_at = Some(unix_timestamp());
+	}
 }
 
 pub trait OptionBlockRange {
@@ -1107,3 +1486,98 @@ impl TimeToLive {
 		Instant::now().checked_add(self.0)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::{ApiKey, RuntimeConfig};
+	use serde_json::json;
+	use test_case::test_case;
+
+	#[test_case(json!(null), &[] ; "No partitions")]
+	#[test_case(json!("1/20"), &[(1, 20)] ; "Single partition")]
+	#[test_case(json!(["1/20", "3/20"]), &[(1, 20), (3, 20)] ; "Multiple partitions")]
+	fn block_matrix_partition(value: serde_json::Value, expected: &[(u8, u8)]) {
+		let config = match value {
+			serde_json::Value::Null => json!({}),
+			value => json!({ "block_matrix_partition": value }),
+		};
+
+		let config: RuntimeConfig = serde_json::from_value(config).unwrap();
+		let partitions = config
+			.block_matrix_partition
+			.iter()
+			.map(|partition| (partition.number, partition.fraction))
+			.collect::<Vec<_>>();
+		assert_eq!(partitions, expected);
+		assert_eq!(config.is_fat_client(), !expected.is_empty());
+	}
+
+	#[test_case(json!({}), None ; "Default configuration")]
+	#[test_case(json!({ "ws_heartbeat_interval": 0 }), Some("WebSocket heartbeat interval must be greater than 0") ; "Zero heartbeat interval")]
+	#[test_case(json!({ "ws_status_changed_interval": 0 }), Some("WebSocket status changed interval must be greater than 0") ; "Zero status changed interval")]
+	#[test_case(json!({ "ws_request_timeout": 0 }), Some("WebSocket request timeout must be greater than 0") ; "Zero request timeout")]
+	#[test_case(json!({ "cors_allowed_origins": ["*", "https://example.com", "http://localhost:3000"] }), None ; "Valid CORS origins")]
+	#[test_case(json!({ "cors_allowed_origins": ["example.com"] }), Some("Invalid CORS allowed origin: example.com") ; "CORS origin without scheme")]
+	#[test_case(json!({ "cors_allowed_origins": ["https://"] }), Some("Invalid CORS allowed origin: https://") ; "CORS origin without host")]
+	#[test_case(json!({ "cors_allowed_methods": ["GET", "NOT A METHOD"] }), Some("Invalid CORS allowed method: NOT A METHOD") ; "Invalid CORS method")]
+	#[test_case(json!({ "cors_allowed_headers": ["invalid header"] }), Some("Invalid CORS allowed header: invalid header") ; "Invalid CORS header")]
+	fn validate(config: serde_json::Value, expected_error: Option<&str>) {
+		let config: RuntimeConfig = serde_json::from_value(config).unwrap();
+		let error = config.validate().err().map(|error| error.to_string());
+		assert_eq!(error.as_deref(), expected_error);
+	}
+
+	#[test]
+	fn api_keys() {
+		let config = json!({
+			"api_keys": ["key", { "key": "other-key", "submit_quota": 10, "rate_limit_requests_per_second": 5 }, { "key": "admin-key", "admin": true }]
+		});
+		let config: RuntimeConfig = serde_json::from_value(config).unwrap();
+		assert_eq!(
+			config.api_keys,
+			vec![
+				ApiKey::from("key"),
+				ApiKey {
+					key: "other-key".to_string(),
+					submit_quota: Some(10),
+					rate_limit_requests_per_second: Some(5.0),
+					rate_limit_burst: None,
+					admin: false,
+				},
+				ApiKey {
+					key: "admin-key".to_string(),
+					submit_quota: None,
+					rate_limit_requests_per_second: None,
+					rate_limit_burst: None,
+					admin: true,
+				},
+			]
+		);
+		assert!(config.is_authorized(Some("other-key")));
+		assert!(!config.is_authorized(Some("unknown")));
+		assert!(!config.is_authorized(None));
+		assert!(config.is_admin(Some("admin-key")));
+		assert!(!config.is_admin(Some("other-key")));
+		assert!(!config.is_admin(None));
+	}
+
+	#[test]
+	fn submit_app_ids() {
+		let config = RuntimeConfig::default();
+		assert!(!config.is_submit_enabled());
+
+		let config: RuntimeConfig =
+			serde_json::from_value(json!({ "submit_app_ids": [2, 3] })).unwrap();
+		assert!(config.is_submit_enabled());
+		assert!(config.is_submit_allowed(2));
+		assert!(!config.is_submit_allowed(1));
+
+		let config = RuntimeConfig {
+			app_id: Some(1),
+			..Default::default()
+		};
+		assert!(config.is_submit_enabled());
+		assert!(config.is_submit_allowed(1));
+		assert!(!config.is_submit_allowed(2));
+	}
+}
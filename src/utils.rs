@@ -15,11 +15,16 @@ use avail_subxt::{
 	utils::H256,
 };
 use codec::Decode;
-use color_eyre::{eyre::WrapErr, Result};
+use color_eyre::{
+	eyre::{eyre, WrapErr},
+	Result,
+};
 use kate_recovery::{
+	commitments, config,
 	data::Cell,
-	matrix::{Dimensions, Position},
+	matrix::{Dimensions, Partition, Position},
 };
+use std::collections::HashSet;
 
 pub fn decode_app_data(data: &[u8]) -> Result<Option<Vec<u8>>> {
 	let extrisic: AppUncheckedExtrinsic =
@@ -50,6 +55,31 @@ pub(crate) fn extract_kate(extension: &HeaderExtension) -> (u16, u16, H256, Vec<
 	}
 }
 
+/// Decodes commitments from the header extension fields. Headers of blocks with more than
+/// `max_rows` rows, or with more commitments than rows of the extended matrix, are rejected
+/// before the commitments are decoded.
+pub(crate) fn header_commitments(
+	rows: u16,
+	cols: u16,
+	commitment: &[u8],
+	max_rows: u16,
+) -> Result<Vec<[u8; config::COMMITMENT_SIZE]>> {
+	if rows > max_rows {
+		return Err(eyre!(
+			"Block matrix has {rows} rows, exceeding the limit of {max_rows}"
+		));
+	}
+	let max_count = Dimensions::new(rows, cols).map_or(0, |dimensions| dimensions.extended_rows());
+	let max_len = max_count as usize * config::COMMITMENT_SIZE;
+	if commitment.len() > max_len {
+		return Err(eyre!(
+			"Header has {} bytes of commitments, expected at most {max_len} for {rows}x{cols} matrix",
+			commitment.len()
+		));
+	}
+	Ok(commitments::from_slice(commitment)?)
+}
+
 pub(crate) fn extract_app_lookup(
 	extension: &HeaderExtension,
 ) -> Result<DataLookup, DataLookupError> {
@@ -89,6 +119,19 @@ pub fn filter_auth_set_changes(header: &DaHeader) -> Vec<Vec<(AuthorityId, u64)>
 	new_auths
 }
 
+/// Returns extended matrix positions of all given partitions, without duplicates.
+pub(crate) fn extended_partitions_positions(
+	dimensions: Dimensions,
+	partitions: &[Partition],
+) -> Vec<Position> {
+	let mut unique = HashSet::new();
+	partitions
+		.iter()
+		.flat_map(|partition| dimensions.iter_extended_partition_positions(partition))
+		.filter(|position| unique.insert(*position))
+		.collect()
+}
+
 // TODO: Remove unused functions if not needed after next iteration
 
 #[allow(dead_code)]
@@ -113,11 +156,12 @@ fn diff_positions(positions: &[Position], cells: &[Cell]) -> Vec<Position> {
 
 #[cfg(test)]
 mod tests {
-	use super::{can_reconstruct, diff_positions};
+	use super::{can_reconstruct, diff_positions, header_commitments};
 	use kate_recovery::{
 		data::Cell,
 		matrix::{Dimensions, Position},
 	};
+	use test_case::test_case;
 
 	fn position(row: u32, col: u16) -> Position {
 		Position { row, col }
@@ -177,4 +221,15 @@ mod tests {
 		assert_eq!(diff_positions(&positions, &cells)[0], position(0, 0));
 		assert_eq!(diff_positions(&positions, &cells)[1], position(1, 1));
 	}
+
+	#[test_case(1, 2 * 48, None ; "Commitments of extended rows")]
+	#[test_case(1, 3 * 48, Some("Header has 144 bytes of commitments, expected at most 96 for 1x4 matrix") ; "Commitments exceed extended rows")]
+	#[test_case(4, 8 * 48, Some("Block matrix has 4 rows, exceeding the limit of 2") ; "Rows exceed the limit")]
+	fn header_commitments_limits(rows: u16, commitments_len: usize, error: Option<&str>) {
+		let result = header_commitments(rows, 4, &vec![0u8; commitments_len], 2);
+		assert_eq!(
+			result.err().map(|error| error.to_string()).as_deref(),
+			error
+		);
+	}
 }